@@ -0,0 +1,388 @@
+//! 操作の監査ログ - 誰がいつどのファイルに何をしたかを追記専用の JSON Lines で残す
+//!
+//! 各行は直前の行のハッシュを含むので、途中の行の書き換え・削除・入れ替えは `verify` で検出できる。
+//! ハッシュは FNV-1a で暗号学的な強度は無く、改ざんを防ぐのではなく気付くための仕組み。
+//! 件数または期間の上限に達したら `<名前>.<日時>.jsonl` に退避して新しいファイルに切り替える
+//! （新しいファイルの先頭行は退避したファイルの最終行のハッシュを引き継ぐ）
+
+use crate::hash::fnv1a_hex;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// 監査ログのパスを指定する環境変数
+pub const AUDIT_ENV: &str = "MILLSHEET_AUDIT_LOG";
+
+/// 既定の監査ログファイル名（実行ファイルと同じフォルダ）
+const DEFAULT_AUDIT_FILE: &str = "millsheet_audit.jsonl";
+
+/// 既定のローテーション件数
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// 既定のローテーション期間（日）
+pub const DEFAULT_MAX_AGE_DAYS: i64 = 90;
+
+/// 最初の行が参照する直前のハッシュ
+const GENESIS_HASH: &str = "0000000000000000";
+
+/// 記録する操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// 自動リネーム
+    Rename,
+    /// 確認待ちを確定してリネーム
+    Confirm,
+    /// リネームの取り消し
+    Revert,
+    /// 抽出値の手動修正
+    Edit,
+    /// 結果のエクスポート
+    Export,
+    /// タグ別のファイル移動
+    Move,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditAction::Rename => "リネーム",
+            AuditAction::Confirm => "確定",
+            AuditAction::Revert => "取り消し",
+            AuditAction::Edit => "手動修正",
+            AuditAction::Export => "エクスポート",
+            AuditAction::Move => "移動",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 監査ログの1行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// 記録日時（RFC 3339、ローカル時刻）
+    pub timestamp: String,
+    /// OSのログイン名
+    pub user: String,
+    /// 操作
+    pub action: AuditAction,
+    /// 対象のファイル（エクスポートは出力先）
+    pub target: Option<String>,
+    /// 操作の内容（リネーム前後の名前など）
+    pub detail: String,
+    /// 直前の行のハッシュ
+    pub prev_hash: String,
+    /// この行のハッシュ（hash 以外の項目から計算）
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// hash 以外の項目からハッシュを計算
+    fn compute_hash(&self) -> String {
+        let content = serde_json::json!([
+            self.timestamp,
+            self.user,
+            self.action,
+            self.target,
+            self.detail,
+            self.prev_hash,
+        ]);
+        fnv1a_hex(content.to_string().as_bytes())
+    }
+}
+
+/// ローテーションの条件（どちらかに達したら切り替える、None はその条件を使わない）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRotation {
+    /// 1ファイルの最大行数
+    pub max_entries: Option<usize>,
+    /// 1ファイルの先頭行からの最大日数
+    pub max_age_days: Option<i64>,
+}
+
+impl Default for AuditRotation {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(DEFAULT_MAX_ENTRIES),
+            max_age_days: Some(DEFAULT_MAX_AGE_DAYS),
+        }
+    }
+}
+
+/// 監査ログ（JSON Lines、追記のみ）
+pub struct AuditLog {
+    path: PathBuf,
+    rotation: AuditRotation,
+    /// 現在のファイルの行数
+    entries: usize,
+    /// 現在のファイルの先頭行の日時
+    started: Option<DateTime<Local>>,
+    /// 最終行のハッシュ
+    last_hash: String,
+}
+
+impl AuditLog {
+    /// 監査ログを開く（ファイルが無ければ最初の記録時に作成）
+    ///
+    /// 既存のファイルは末尾の行のハッシュを引き継ぐ。途中の改ざんは検査しないので `verify` を使う
+    pub fn open(path: impl Into<PathBuf>, rotation: AuditRotation) -> Result<Self> {
+        let path = path.into();
+        let entries = read_entries(&path)?;
+        Ok(Self {
+            rotation,
+            entries: entries.len(),
+            started: entries.first().and_then(|e| DateTime::parse_from_rfc3339(&e.timestamp).ok()).map(|t| t.with_timezone(&Local)),
+            last_hash: entries.last().map_or_else(|| GENESIS_HASH.to_string(), |e| e.hash.clone()),
+            path,
+        })
+    }
+    
+    /// 監査ログファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// 操作を1件追記（上限に達していれば先にローテーションする）
+    pub fn record(&mut self, action: AuditAction, target: Option<&Path>, detail: impl Into<String>) -> Result<()> {
+        let now = Local::now();
+        if self.needs_rotation(now) {
+            self.rotate(now)?;
+        }
+        
+        let mut entry = AuditEntry {
+            timestamp: now.to_rfc3339(),
+            user: current_user(),
+            action,
+            target: target.map(|p| p.display().to_string()),
+            detail: detail.into(),
+            prev_hash: self.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("監査ログを開けません: {:?}", self.path))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)
+            .with_context(|| format!("監査ログの書き込みに失敗: {:?}", self.path))?;
+        
+        self.entries += 1;
+        self.started.get_or_insert(now);
+        self.last_hash = entry.hash;
+        Ok(())
+    }
+    
+    /// 件数・期間の上限に達したか
+    fn needs_rotation(&self, now: DateTime<Local>) -> bool {
+        let by_count = self.rotation.max_entries.is_some_and(|max| self.entries >= max);
+        let by_age = self
+            .rotation
+            .max_age_days
+            .zip(self.started)
+            .is_some_and(|(days, started)| now - started >= chrono::Duration::days(days));
+        by_count || by_age
+    }
+    
+    /// 現在のファイルを `<名前>.<日時>.jsonl` に退避する（ハッシュの連鎖は引き継ぐ）
+    fn rotate(&mut self, now: DateTime<Local>) -> Result<()> {
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        let stamp = now.format("%Y%m%d-%H%M%S");
+        // 同じ秒に2回ローテーションしても退避済みのファイルを上書きしない
+        let rotated = (0..)
+            .map(|n| match n {
+                0 => self.path.with_file_name(format!("{}.{}.jsonl", stem, stamp)),
+                n => self.path.with_file_name(format!("{}.{}-{}.jsonl", stem, stamp, n)),
+            })
+            .find(|path| !path.exists())
+            .unwrap_or_default();
+        std::fs::rename(&self.path, &rotated)
+            .with_context(|| format!("監査ログのローテーションに失敗: {:?}", rotated))?;
+        tracing::info!("監査ログをローテーションしました: {}", rotated.display());
+        self.entries = 0;
+        self.started = None;
+        Ok(())
+    }
+}
+
+/// 監査ログのハッシュの連鎖を検査し、行数を返す（書き換え・削除・入れ替えがあればエラー）
+///
+/// ローテーション後のファイルは先頭行の prev_hash が退避したファイルの最終行を指すので、先頭行のつながりは検査しない
+pub fn verify(path: impl AsRef<Path>) -> Result<usize> {
+    let path = path.as_ref();
+    let entries = read_entries(path)?;
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.compute_hash() != entry.hash {
+            bail!("{} 行目の内容がハッシュと一致しません（書き換えの可能性）: {:?}", i + 1, path);
+        }
+        if i > 0 && entry.prev_hash != entries[i - 1].hash {
+            bail!("{} 行目の直前の行が記録と異なります（削除・挿入の可能性）: {:?}", i + 1, path);
+        }
+    }
+    Ok(entries.len())
+}
+
+/// 全行を読み込む（ファイルが無ければ空、壊れた行はエラー）
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("監査ログを開けません: {:?}", path)),
+    };
+    
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .with_context(|| format!("監査ログの {} 行目を読めません: {:?}", i + 1, path))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// OSのログイン名（取得できなければ "unknown"）
+pub fn current_user() -> String {
+    ["USERNAME", "USER", "LOGNAME"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok().filter(|name| !name.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// 監査ログのパスを決定（環境変数 > 実行ファイルと同じフォルダ）
+pub fn default_audit_path() -> PathBuf {
+    if let Ok(path) = std::env::var(AUDIT_ENV) {
+        return PathBuf::from(path);
+    }
+    
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_AUDIT_FILE)))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_AUDIT_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// テストごとの一時フォルダ
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("millsheet_audit_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    /// `count` 件の操作を記録した監査ログ（detail は 1 から順の番号）
+    fn write_log(path: &Path, rotation: AuditRotation, count: usize) -> AuditLog {
+        let mut log = AuditLog::open(path, rotation).unwrap();
+        for i in 1..=count {
+            log.record(AuditAction::Rename, Some(Path::new("in/a.pdf")), i.to_string()).unwrap();
+        }
+        log
+    }
+    
+    /// ファイルの行を書き換えて保存
+    fn edit_lines(path: &Path, edit: impl FnOnce(&mut Vec<String>)) {
+        let mut lines: Vec<String> = std::fs::read_to_string(path).unwrap().lines().map(String::from).collect();
+        edit(&mut lines);
+        std::fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+    
+    #[test]
+    fn verify_detects_tampering_deletion_and_reordering() {
+        let dir = temp_dir("verify");
+        let path = dir.join(DEFAULT_AUDIT_FILE);
+        write_log(&path, AuditRotation::default(), 3);
+        assert_eq!(verify(&path).unwrap(), 3);
+        let original = std::fs::read_to_string(&path).unwrap();
+        
+        // 2行目の内容を手で書き換える
+        edit_lines(&path, |lines| lines[1] = lines[1].replace(r#""detail":"2""#, r#""detail":"9""#));
+        assert!(verify(&path).unwrap_err().to_string().contains("2 行目の内容がハッシュと一致しません"));
+        
+        // 2行目を削除する
+        std::fs::write(&path, &original).unwrap();
+        edit_lines(&path, |lines| {
+            lines.remove(1);
+        });
+        assert!(verify(&path).unwrap_err().to_string().contains("2 行目の直前の行が記録と異なります"));
+        
+        // 2行目と3行目を入れ替える
+        std::fs::write(&path, &original).unwrap();
+        edit_lines(&path, |lines| lines.swap(1, 2));
+        assert!(verify(&path).is_err());
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn rotation_by_count_carries_hash_chain() {
+        let dir = temp_dir("count");
+        let path = dir.join(DEFAULT_AUDIT_FILE);
+        let rotation = AuditRotation { max_entries: Some(2), ..Default::default() };
+        write_log(&path, rotation, 5);
+        
+        // 2件ずつ退避し、現在のファイルには残りの1件
+        let files: Vec<PathBuf> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(files.len(), 3);
+        let mut counts: Vec<usize> = files.iter().map(|file| verify(file).unwrap()).collect();
+        counts.sort();
+        assert_eq!(counts, [1, 2, 2]);
+        assert_eq!(verify(&path).unwrap(), 1);
+        
+        // ファイルをまたいでも先頭からハッシュがつながる
+        let entries: Vec<AuditEntry> = files.iter().flat_map(|file| read_entries(file).unwrap()).collect();
+        let mut chain = Vec::new();
+        let mut prev = GENESIS_HASH.to_string();
+        while let Some(entry) = entries.iter().find(|e| e.prev_hash == prev) {
+            chain.push(entry.detail.as_str());
+            prev = entry.hash.clone();
+        }
+        assert_eq!(chain, ["1", "2", "3", "4", "5"]);
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn rotation_by_age_starts_from_first_entry() {
+        let dir = temp_dir("age");
+        let path = dir.join(DEFAULT_AUDIT_FILE);
+        let rotation = AuditRotation { max_entries: None, max_age_days: Some(1) };
+        write_log(&path, rotation, 1);
+        
+        // 先頭行を2日前の記録に書き換える（ハッシュも計算し直す）
+        let mut old: AuditEntry = serde_json::from_str(std::fs::read_to_string(&path).unwrap().trim()).unwrap();
+        old.timestamp = (Local::now() - chrono::Duration::days(2)).to_rfc3339();
+        old.hash = old.compute_hash();
+        std::fs::write(&path, serde_json::to_string(&old).unwrap() + "\n").unwrap();
+        
+        let mut log = AuditLog::open(&path, rotation).unwrap();
+        log.record(AuditAction::Export, None, "2").unwrap();
+        log.record(AuditAction::Export, None, "3").unwrap();
+        
+        // 古い行だけ退避し、新しいファイルは退避した最終行のハッシュを引き継ぐ
+        let rotated: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|file| *file != path)
+            .collect();
+        assert_eq!(rotated.len(), 1);
+        assert_eq!(verify(&rotated[0]).unwrap(), 1);
+        let current = read_entries(&path).unwrap();
+        assert_eq!(current.len(), 2);
+        assert_eq!(current[0].prev_hash, old.hash);
+        assert_eq!(verify(&path).unwrap(), 2);
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,297 @@
+//! ミルシートリネーマー CLI - パーサ開発用ツール
+//!
+//! 使い方:
+//!   millsheet-cli parse <textfile> [--mask]
+//!   millsheet-cli parse <textfile|folder>... --compare <old> <new> [--mask] [--sheet] [--materials <file>] [--jis] [--disable <field,...>]
+//!   millsheet-cli schema [info|export]
+//!   millsheet-cli history [--manufacturer <名前>] [--material <材質>] [--from <YYYY-MM-DD>] [--to <YYYY-MM-DD>] [--hash <ハッシュ>] [--db <パス>]
+//!
+//! `--compare` の <old>/<new> は辞書ファイルのパス、または組み込みルールのみを表す `builtin`。
+//! 辞書以外のオプションは旧ルール・新ルールの両方に同じように適用する
+
+use anyhow::{bail, Context, Result};
+use millsheet_renamer::audit::{self, default_audit_path};
+use millsheet_renamer::parser::dictionary::{default_dictionary_path, Dictionary};
+use millsheet_renamer::export::export_json_schema;
+use millsheet_renamer::history::{default_history_path, HistoryDb, HistoryQuery};
+use millsheet_renamer::parser::compare::{compare, CompareSummary, FieldChange};
+use millsheet_renamer::parser::dimensions::DimensionProfile;
+use millsheet_renamer::parser::material::MaterialMatcher;
+use millsheet_renamer::parser::{extract_field, FieldMatch, MillsheetInfo, ParseOptions, EXTRACT_FIELDS};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const USAGE: &str = "使い方: millsheet-cli parse <textfile> [--mask] [--sheet] [--materials <パターンファイル>] [--jis] [--disable <field,...>]
+       millsheet-cli parse <textfile|folder>... --compare <old> <new> [--mask] [--sheet] [--materials <パターンファイル>] [--jis] [--disable <field,...>]
+       millsheet-cli schema [info|export]
+       millsheet-cli history [--manufacturer <名前>] [--material <材質>] [--from <YYYY-MM-DD>] [--to <YYYY-MM-DD>] [--hash <ハッシュ>] [--db <パス>]
+       millsheet-cli audit verify [<ログファイル>]";
+
+/// 組み込みルールのみ（辞書なし）を表すルール指定
+const BUILTIN_RULES: &str = "builtin";
+
+fn main() -> Result<()> {
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+    dotenvy::dotenv().ok();
+    millsheet_renamer::parser::precompile_patterns();
+    
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("parse") => run_parse(&args[1..]),
+        Some("schema") => run_schema(&args[1..]),
+        Some("history") => run_history(&args[1..]),
+        Some("audit") => run_audit(&args[1..]),
+        _ => bail!("{}", USAGE),
+    }
+}
+
+/// テキストファイルを直接パースして結果と抽出根拠をJSONで出力
+fn run_parse(args: &[String]) -> Result<()> {
+    let mut paths = Vec::new();
+    let mut options = ParseOptions::default();
+    let mut compare_rules = None;
+    
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mask" => options.mask_used_tokens = true,
+            "--sheet" => options.dimension_profile = DimensionProfile::Sheet,
+            "--jis" => options.normalize_material_standard = true,
+            "--materials" => {
+                let Some(path) = args.next() else {
+                    bail!("--materials にはパターンファイルを指定してください\n{}", USAGE);
+                };
+                options.material_matcher = Some(Arc::new(MaterialMatcher::load(path)?));
+            }
+            "--disable" => {
+                let Some(fields) = args.next() else {
+                    bail!("--disable には無効にするフィールドを指定してください\n{}", USAGE);
+                };
+                for name in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+                    let Some(field) = extract_field(name) else {
+                        bail!("不明なフィールド: {}（{} のいずれか）", name, EXTRACT_FIELDS.join(", "));
+                    };
+                    options.disabled_fields.insert(field);
+                }
+            }
+            "--compare" => {
+                let (Some(old), Some(new)) = (args.next(), args.next()) else {
+                    bail!("--compare には旧ルールと新ルールを指定してください\n{}", USAGE);
+                };
+                compare_rules = Some((old.clone(), new.clone()));
+            }
+            _ if arg.starts_with("--") => bail!("不明な引数: {}\n{}", arg, USAGE),
+            _ => paths.push(arg.clone()),
+        }
+    }
+    
+    if let Some((old, new)) = compare_rules {
+        return run_compare(&paths, &old, &new, &options);
+    }
+    
+    let [path] = paths.as_slice() else {
+        bail!("{}", USAGE);
+    };
+    let path = path.clone();
+    
+    if let Some(dictionary_path) = default_dictionary_path() {
+        options.dictionary = Some(Arc::new(Dictionary::load(&dictionary_path)?));
+    }
+    
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("テキストファイルの読み込みに失敗: {}", path))?;
+    let (info, evidence) = MillsheetInfo::parse_detailed(&text, &options);
+    
+    let file_name = Path::new(&path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown.pdf");
+    
+    let output = json!({
+        "file": path,
+        "filename": info.generate_filename(file_name),
+        "fields": {
+            "date": field_json(&info.raw_text, &evidence.date, info.confidence.date),
+            "material": field_json(&info.raw_text, &evidence.material, info.confidence.material),
+            "dimensions": field_json(&info.raw_text, &evidence.dimensions, info.confidence.dimensions),
+            "manufacturer": field_json(&info.raw_text, &evidence.manufacturer, info.confidence.manufacturer),
+            "charge_no": field_json(&info.raw_text, &evidence.charge_no, info.confidence.charge_no),
+            "weight": field_json(&info.raw_text, &evidence.weight, info.confidence.weight),
+        },
+        "sub_materials": info.sub_materials,
+        "charge_nos": info.charge_nos,
+    });
+    
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// JSON Schema を出力（info: 解析結果、export: JSONエクスポート）
+fn run_schema(args: &[String]) -> Result<()> {
+    let schema = match args.first().map(String::as_str) {
+        None | Some("info") => MillsheetInfo::json_schema(),
+        Some("export") => export_json_schema(),
+        Some(other) => bail!("不明なスキーマ: {}\n{}", other, USAGE),
+    };
+    
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+/// 処理履歴を検索してJSONで出力（期間は処理日）
+fn run_history(args: &[String]) -> Result<()> {
+    let mut query = HistoryQuery::default();
+    let mut db_path = default_history_path();
+    
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            bail!("{} には値を指定してください\n{}", arg, USAGE);
+        };
+        let date = || {
+            chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .with_context(|| format!("日付は YYYY-MM-DD 形式で指定してください: {}", value))
+        };
+        match arg.as_str() {
+            "--manufacturer" => query.manufacturer = Some(value.clone()),
+            "--material" => query.material = Some(value.clone()),
+            "--from" => query.from = Some(date()?),
+            "--to" => query.to = Some(date()?),
+            "--hash" => query.content_hash = Some(value.clone()),
+            "--db" => db_path = PathBuf::from(value),
+            _ => bail!("不明な引数: {}\n{}", arg, USAGE),
+        }
+    }
+    
+    if !db_path.exists() {
+        bail!("履歴DBが見つかりません: {:?}", db_path);
+    }
+    let entries = HistoryDb::open(&db_path)?.search(&query)?;
+    println!("{}", serde_json::to_string_pretty(&entries)?);
+    Ok(())
+}
+
+/// 監査ログのハッシュの連鎖を検査（省略時は既定の監査ログ）
+fn run_audit(args: &[String]) -> Result<()> {
+    let path = match args {
+        [command] if command == "verify" => default_audit_path(),
+        [command, path] if command == "verify" => PathBuf::from(path),
+        _ => bail!("{}", USAGE),
+    };
+    
+    let count = audit::verify(&path)?;
+    println!("{} 行を検査しました。改ざんは見つかりませんでした: {}", count, path.display());
+    Ok(())
+}
+
+/// 旧ルールと新ルールで同じテキストを解析し、差分レポートをJSONで出力（辞書以外は `options` を共通で使う）
+fn run_compare(paths: &[String], old: &str, new: &str, options: &ParseOptions) -> Result<()> {
+    if paths.is_empty() {
+        bail!("{}", USAGE);
+    }
+    
+    let old_options = ParseOptions {
+        dictionary: load_rules(old)?,
+        ..options.clone()
+    };
+    let new_options = ParseOptions {
+        dictionary: load_rules(new)?,
+        ..options.clone()
+    };
+    
+    let mut summary = CompareSummary::default();
+    let mut samples = Vec::new();
+    
+    for file in collect_text_files(paths)? {
+        let text = std::fs::read_to_string(&file)
+            .with_context(|| format!("テキストファイルの読み込みに失敗: {:?}", file))?;
+        let diffs = compare(&text, &old_options, &new_options);
+        summary.add(&diffs);
+        
+        // 変化のあったフィールドだけを出力する
+        let changes: Vec<Value> = diffs
+            .iter()
+            .filter(|d| d.change != FieldChange::Unchanged)
+            .map(|d| json!({
+                "field": d.field,
+                "old": d.old,
+                "new": d.new,
+                "change": d.change.label(),
+            }))
+            .collect();
+        if !changes.is_empty() {
+            samples.push(json!({ "file": file, "changes": changes }));
+        }
+    }
+    
+    let fields: serde_json::Map<String, Value> = summary
+        .fields
+        .iter()
+        .map(|(field, counts)| {
+            (field.to_string(), json!({
+                "unchanged": counts.unchanged,
+                "improved": counts.improved,
+                "degraded": counts.degraded,
+                "changed": counts.changed,
+            }))
+        })
+        .collect();
+    
+    let output = json!({
+        "old": old,
+        "new": new,
+        "samples": summary.samples,
+        "has_regression": summary.has_regression(),
+        "summary": fields,
+        "diffs": samples,
+    });
+    
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// ルール指定（辞書ファイルのパスまたは builtin）を読み込む
+fn load_rules(spec: &str) -> Result<Option<Arc<Dictionary>>> {
+    if spec == BUILTIN_RULES {
+        return Ok(None);
+    }
+    Ok(Some(Arc::new(Dictionary::load(spec)?)))
+}
+
+/// 引数のファイルとフォルダ直下の .txt ファイルを列挙
+fn collect_text_files(paths: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if !path.is_dir() {
+            files.push(path);
+            continue;
+        }
+        
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(&path)
+            .with_context(|| format!("フォルダの読み込みに失敗: {:?}", path))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("txt")))
+            .collect();
+        entries.sort();
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+/// フィールド1件分のJSON（未抽出は null）
+fn field_json(text: &str, found: &Option<FieldMatch>, confidence: f32) -> Value {
+    let Some(m) = found else {
+        return Value::Null;
+    };
+    
+    json!({
+        "value": m.value,
+        "pattern": m.pattern,
+        "matched": text.get(m.span.clone()).unwrap_or_default(),
+        "start": m.span.start,
+        "end": m.span.end,
+        "inferred": m.inferred,
+        "confidence": confidence,
+    })
+}
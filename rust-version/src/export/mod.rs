@@ -0,0 +1,365 @@
+//! エクスポートモジュール - 処理結果をCSV/JSON/TSV/Markdown表/印刷用HTMLに整形
+
+pub mod report;
+
+use crate::parser::manufacturer::{normalize_corporate_form, CorporateFormStyle};
+use crate::processing::ProcessResult;
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::Path;
+
+/// エクスポート形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Tsv,
+    Markdown,
+    /// 印刷用HTML（受領記録としてブラウザで印刷・PDF保存する）
+    Html,
+}
+
+impl ExportFormat {
+    /// 全形式（ダイアログの選択肢順）
+    pub const ALL: [ExportFormat; 5] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Tsv,
+        ExportFormat::Markdown,
+        ExportFormat::Html,
+    ];
+    
+    /// 表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Tsv => "TSV",
+            ExportFormat::Markdown => "Markdown表",
+            ExportFormat::Html => "印刷用HTML",
+        }
+    }
+    
+    /// 拡張子
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// 列見出し（全形式で共通）
+const COLUMNS: [&str; 10] = [
+    "元ファイル名",
+    "新ファイル名",
+    "状態",
+    "エラー",
+    "発行日",
+    "材質",
+    "寸法",
+    "メーカー",
+    "チャージNo",
+    "重量",
+];
+
+/// JSON形式の1件分（フィールドは COLUMNS と同じ順序、未抽出は空文字）
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ExportRow {
+    /// 元ファイル名
+    pub original: String,
+    /// 新ファイル名
+    pub new_name: String,
+    /// 状態（成功/失敗）
+    pub status: String,
+    /// エラー
+    pub error: String,
+    /// 発行日 (YY-MM-DD形式)
+    pub date: String,
+    /// 材質
+    pub material: String,
+    /// 寸法
+    pub dimensions: String,
+    /// メーカー（法人格は統一表記）
+    pub manufacturer: String,
+    /// チャージNo
+    pub charge_no: String,
+    /// 重量 (2450kg, 12.3t など)
+    pub weight: String,
+}
+
+impl From<&ProcessResult> for ExportRow {
+    fn from(result: &ProcessResult) -> Self {
+        let [original, new_name, status, error, date, material, dimensions, manufacturer, charge_no, weight] =
+            row_values(result);
+        Self {
+            original,
+            new_name,
+            status,
+            error,
+            date,
+            material,
+            dimensions,
+            manufacturer,
+            charge_no,
+            weight,
+        }
+    }
+}
+
+/// JSONエクスポートの JSON Schema（ExportRow の配列）
+pub fn export_json_schema() -> serde_json::Value {
+    schemars::schema_for!(Vec<ExportRow>).to_value()
+}
+
+/// 1件分の列値を取り出す
+fn row_values(result: &ProcessResult) -> [String; 10] {
+    let parsed = result.parsed.as_ref();
+    let field = |f: fn(&crate::parser::MillsheetInfo) -> &Option<String>| {
+        parsed.and_then(|p| f(p).clone()).unwrap_or_default()
+    };
+    
+    [
+        result.original.clone(),
+        result.new_name.clone().unwrap_or_default(),
+        status_label(result).to_string(),
+        result.error.clone().unwrap_or_default(),
+        parsed.and_then(|p| p.date).map(|d| d.to_string()).unwrap_or_default(),
+        field(|p| &p.material),
+        field(|p| &p.dimensions),
+        // 集計で表記が揺れないよう法人格は統一表記にそろえる
+        normalize_corporate_form(&field(|p| &p.manufacturer), CorporateFormStyle::Unify),
+        field(|p| &p.charge_no),
+        field(|p| &p.weight),
+    ]
+}
+
+/// 状態の表示名
+pub(crate) fn status_label(result: &ProcessResult) -> &'static str {
+    if result.success {
+        "成功"
+    } else if result.pending_review {
+        "確認待ち"
+    } else {
+        "失敗"
+    }
+}
+
+/// クリップボードにコピーする表の既定の最大行数
+pub const CLIPBOARD_MAX_ROWS: usize = 50;
+
+/// 「元名 / 新名 / 状態」のMarkdown表（チャット・チケット貼り付け用）
+///
+/// `max_rows` を超える分は省略し、残り件数を表の下に注記する
+pub fn format_markdown_summary(results: &[ProcessResult], max_rows: usize) -> String {
+    let mut lines = vec![
+        "| 元名 | 新名 | 状態 |".to_string(),
+        "|---|---|---|".to_string(),
+    ];
+    
+    for result in results.iter().take(max_rows) {
+        lines.push(format!(
+            "| {} | {} | {} |",
+            escape_markdown(&result.original),
+            escape_markdown(result.new_name.as_deref().unwrap_or("")),
+            status_label(result),
+        ));
+    }
+    
+    if results.len() > max_rows {
+        // 表の直後に書くと最終行として解釈されるため空行を挟む
+        lines.push(String::new());
+        lines.push(format!("ほか {} 件（全 {} 件）", results.len() - max_rows, results.len()));
+    }
+    
+    lines.join("\n") + "\n"
+}
+
+/// 処理結果を指定形式の文字列に整形
+pub fn format_results(results: &[ProcessResult], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => format_delimited(results, ',', escape_csv),
+        ExportFormat::Tsv => format_delimited(results, '\t', escape_tsv),
+        ExportFormat::Json => format_json(results),
+        ExportFormat::Markdown => format_markdown(results),
+        ExportFormat::Html => report::format_print_report(results, chrono::Local::now().naive_local()),
+    }
+}
+
+/// 処理結果をファイルに保存
+pub fn export_results(results: &[ProcessResult], format: ExportFormat, path: impl AsRef<Path>) -> Result<()> {
+    let mut content = format_results(results, format);
+    
+    // Excelで文字化けしないようCSVにはBOMを付ける
+    if format == ExportFormat::Csv {
+        content.insert(0, '\u{feff}');
+    }
+    
+    std::fs::write(path.as_ref(), content)
+        .with_context(|| format!("エクスポートに失敗: {:?}", path.as_ref()))
+}
+
+/// 区切り文字形式 (CSV/TSV)
+fn format_delimited(results: &[ProcessResult], separator: char, escape: fn(&str) -> String) -> String {
+    let sep = separator.to_string();
+    let mut lines = vec![COLUMNS.iter().map(|c| escape(c)).collect::<Vec<_>>().join(&sep)];
+    
+    for result in results {
+        let row: Vec<String> = row_values(result).iter().map(|v| escape(v)).collect();
+        lines.push(row.join(&sep));
+    }
+    
+    lines.join("\r\n") + "\r\n"
+}
+
+/// CSVの値をエスケープ（カンマ・引用符・改行を含む場合はクォート）
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// TSVの値をエスケープ（タブ・改行は空白に置換）
+fn escape_tsv(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
+
+/// JSON形式
+fn format_json(results: &[ProcessResult]) -> String {
+    let rows: Vec<ExportRow> = results.iter().map(ExportRow::from).collect();
+    
+    serde_json::to_string_pretty(&rows).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Markdown表形式
+fn format_markdown(results: &[ProcessResult]) -> String {
+    let mut lines = vec![
+        format!("| {} |", COLUMNS.join(" | ")),
+        format!("|{}", "---|".repeat(COLUMNS.len())),
+    ];
+    
+    for result in results {
+        let row: Vec<String> = row_values(result).iter().map(|v| escape_markdown(v)).collect();
+        lines.push(format!("| {} |", row.join(" | ")));
+    }
+    
+    lines.join("\n") + "\n"
+}
+
+/// Markdown表セルのエスケープ（パイプと改行）
+pub fn escape_markdown(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\r', '\n'], "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::MillsheetInfo;
+    
+    /// 区切り文字・引用符・改行・パイプを含む日本語の値の結果
+    fn japanese_result() -> ProcessResult {
+        ProcessResult {
+            success: true,
+            original: "スキャン|0001.pdf".to_string(),
+            new_name: Some("24-03-15_SS400_日本製鉄.pdf".to_string()),
+            parsed: Some(MillsheetInfo {
+                material: Some("SS400".to_string()),
+                manufacturer: Some("日本製鉄, 東日本製鉄所".to_string()),
+                charge_no: Some("\"8K23456\"".to_string()),
+                weight: Some("2,450kg\n（正味）".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn csv_quotes_japanese_values_with_separators() {
+        let csv = format_results(&[japanese_result()], ExportFormat::Csv);
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], COLUMNS.join(","));
+        assert_eq!(
+            lines[1],
+            "スキャン|0001.pdf,24-03-15_SS400_日本製鉄.pdf,成功,,,SS400,,\"日本製鉄, 東日本製鉄所\",\"\"\"8K23456\"\"\",\"2,450kg\n（正味）\""
+        );
+    }
+    
+    #[test]
+    fn csv_file_starts_with_bom() {
+        let path = std::env::temp_dir().join(format!("millsheet_export_test_{}.csv", std::process::id()));
+        export_results(&[japanese_result()], ExportFormat::Csv, &path).unwrap();
+        let content = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+        let content = content.unwrap();
+        assert!(content.starts_with("\u{feff}元ファイル名,"));
+    }
+    
+    #[test]
+    fn tsv_replaces_tabs_and_newlines() {
+        assert_eq!(escape_tsv("日本製鉄\t東日本\r\n製鉄所"), "日本製鉄 東日本  製鉄所");
+        let tsv = format_results(&[japanese_result()], ExportFormat::Tsv);
+        assert_eq!(tsv.lines().nth(1).unwrap().split('\t').count(), COLUMNS.len());
+    }
+    
+    #[test]
+    fn markdown_escapes_pipes_and_newlines_in_japanese_values() {
+        assert_eq!(escape_markdown("A|B\\C\r\nD\nE"), "A\\|B\\\\C<br>D<br>E");
+        let markdown = format_results(&[japanese_result()], ExportFormat::Markdown);
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[2],
+            "| スキャン\\|0001.pdf | 24-03-15_SS400_日本製鉄.pdf | 成功 |  |  | SS400 |  | 日本製鉄, 東日本製鉄所 | \"8K23456\" | 2,450kg<br>（正味） |"
+        );
+    }
+    
+    #[test]
+    fn markdown_summary_escapes_and_notes_omitted_rows() {
+        let results = vec![japanese_result(); 3];
+        let summary = format_markdown_summary(&results, 2);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[2], "| スキャン\\|0001.pdf | 24-03-15_SS400_日本製鉄.pdf | 成功 |");
+        assert_eq!(lines[4], "");
+        assert_eq!(lines[5], "ほか 1 件（全 3 件）");
+    }
+    
+    #[test]
+    fn json_keeps_japanese_values_as_is() {
+        let json = format_results(&[japanese_result()], ExportFormat::Json);
+        let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(rows[0]["manufacturer"], "日本製鉄, 東日本製鉄所");
+        assert_eq!(rows[0]["weight"], "2,450kg\n（正味）");
+        assert!(json.contains("日本製鉄"));
+    }
+    
+    #[test]
+    fn json_rows_match_export_schema() {
+        let schema = export_json_schema();
+        assert_eq!(schema["type"], "array");
+        let row_schema = &schema["$defs"]["ExportRow"];
+        let properties = row_schema["properties"].as_object().unwrap();
+        let required = row_schema["required"].as_array().unwrap();
+        assert_eq!(properties.len(), COLUMNS.len());
+        assert_eq!(required.len(), COLUMNS.len());
+        
+        let rows: serde_json::Value = serde_json::from_str(&format_results(&[japanese_result()], ExportFormat::Json)).unwrap();
+        let row = rows[0].as_object().unwrap();
+        assert_eq!(row.keys().collect::<Vec<_>>(), properties.keys().collect::<Vec<_>>());
+        for (name, property) in properties {
+            assert_eq!(property["type"], "string", "{}", name);
+            assert!(property["description"].as_str().is_some_and(|d| !d.is_empty()), "{}", name);
+            assert!(row[name].is_string(), "{}", name);
+        }
+    }
+}
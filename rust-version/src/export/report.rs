@@ -0,0 +1,110 @@
+//! 印刷用レポート - 処理結果を受領記録としてブラウザで印刷・PDF保存できるHTMLに整形
+
+use crate::export::status_label;
+use crate::processing::ProcessResult;
+use chrono::NaiveDateTime;
+
+/// 印刷用のスタイル（A4縦、見出し行は各ページに繰り返し、行の途中で改ページしない）
+const REPORT_STYLE: &str = r#"
+@page { size: A4 portrait; margin: 15mm 12mm; }
+body { font-family: "Yu Gothic", "YuGothic", "Meiryo", "Hiragino Kaku Gothic ProN", "Noto Sans CJK JP", "Noto Sans JP", sans-serif; font-size: 10pt; color: #000; }
+h1 { font-size: 14pt; margin: 0 0 4mm; }
+.summary { margin: 0 0 4mm; }
+.summary td { padding: 0 6mm 0 0; }
+table.results { width: 100%; border-collapse: collapse; table-layout: fixed; }
+table.results th, table.results td { border: 1px solid #666; padding: 1.5mm 2mm; text-align: left; vertical-align: top; word-break: break-all; }
+table.results th { background: #eee; }
+table.results thead { display: table-header-group; }
+table.results tr { page-break-inside: avoid; break-inside: avoid; }
+col.no { width: 8%; }
+col.status { width: 12%; }
+.failure { color: #b00; }
+"#;
+
+/// 処理結果を印刷用のHTMLに整形（ヘッダに処理日時と件数、本文に元名→新名の表）
+pub fn format_print_report(results: &[ProcessResult], generated_at: NaiveDateTime) -> String {
+    let success = results.iter().filter(|r| r.success).count();
+    let pending = results.iter().filter(|r| r.pending_review).count();
+    let failure = results.len() - success - pending;
+    
+    let mut rows = String::new();
+    for (i, result) in results.iter().enumerate() {
+        let class = if result.success || result.pending_review { "" } else { r#" class="failure""# };
+        rows.push_str(&format!(
+            "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            class,
+            i + 1,
+            escape_html(&result.original),
+            escape_html(result.new_name.as_deref().unwrap_or("")),
+            status_label(result),
+        ));
+    }
+    
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="utf-8">
+<title>ミルシート処理結果 {date}</title>
+<style>{style}</style>
+</head>
+<body>
+<h1>ミルシート処理結果</h1>
+<table class="summary">
+<tr><td>処理日時</td><td>{date}</td></tr>
+<tr><td>件数</td><td>全 {total} 件（成功 {success} 件 / 確認待ち {pending} 件 / 失敗 {failure} 件）</td></tr>
+</table>
+<table class="results">
+<colgroup><col class="no"><col><col><col class="status"></colgroup>
+<thead><tr><th>No</th><th>元ファイル名</th><th>新ファイル名</th><th>状態</th></tr></thead>
+<tbody>
+{rows}</tbody>
+</table>
+</body>
+</html>
+"#,
+        date = generated_at.format("%Y-%m-%d %H:%M"),
+        style = REPORT_STYLE,
+        total = results.len(),
+        success = success,
+        pending = pending,
+        failure = failure,
+        rows = rows,
+    )
+}
+
+/// HTMLの特殊文字をエスケープ
+pub fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    
+    #[test]
+    fn print_report_has_header() {
+        let generated_at = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap().and_hms_opt(9, 30, 0).unwrap();
+        let html = format_print_report(&[], generated_at);
+        assert!(html.contains(r#"<meta charset="utf-8">"#));
+        assert!(html.contains("2025-04-01 09:30"));
+        assert!(html.contains("全 0 件"));
+    }
+    
+    #[test]
+    fn escape_html_escapes_markup_and_quotes() {
+        assert_eq!(escape_html(r#"<a&b>"c"'"#), "&lt;a&amp;b&gt;&quot;c&quot;&#39;");
+    }
+}
@@ -1,48 +1,287 @@
 //! メインアプリケーションウィンドウ
 
-use crate::parser::{get_unique_filename, MillsheetInfo};
-use crate::pdf::{cleanup_temp_image, convert_pdf_to_image};
-use crate::vision::VisionClient;
+use crate::audit::{default_audit_path, AuditAction, AuditLog, AuditRotation, DEFAULT_MAX_AGE_DAYS, DEFAULT_MAX_ENTRIES};
+use crate::export::{export_results, format_markdown_summary, ExportFormat, CLIPBOARD_MAX_ROWS};
+use crate::history::{default_history_path, HistoryDb};
+use crate::ocr::{create_backend, OcrBackend, OcrBackendKind};
+use crate::parser::date::DEFAULT_CENTURY_START;
+use crate::parser::dictionary::{default_dictionary_path, Dictionary};
+use crate::parser::dimensions::DimensionProfile;
+use crate::parser::manufacturer::CorporateFormStyle;
+use crate::parser::material::MaterialStyle;
+use crate::parser::{
+    field_label, sanitize_for_filename, ChargeNoOrder, DateFormat, DateSelection, FilenameRules, IssueDate, CONFIDENCE_DEFAULT,
+    CONFIDENCE_FIELDS, DEFAULT_REQUIRED_FIELDS, EXTRACT_FIELDS, FilenameOverflow, MillsheetInfo, SubdirRule,
+};
+use crate::parser::template::FilenameTemplate;
+use crate::pdf::{
+    diagnose, render_thumbnail, rendered_pixels, PdfDiagnosis, Thumbnail, CONVERT_DPI_CHOICES,
+    DEFAULT_CONVERT_DPI,
+};
+use crate::processing::pipeline::{PdfConverter, Pipeline};
+use crate::processing::queue::{JobQueue, PriorityMode, QueuedJob};
+use crate::processing::plan::next_sequence;
+use crate::processing::rescan::{format_rescan_list, rescan_candidates};
+use crate::processing::sequence::default_sequence_path;
+use crate::processing::tags::{collect_tags, export_by_tag, move_tagged, results_with_tag, PRESET_TAGS};
+use crate::processing::upload::upload_in_background;
+use crate::processing::{
+    confirm_pending, is_image_file, is_supported_input, preview_single_pdf, revert_rename, upload_processed,
+    warmup_with, CancelToken, OcrCache, PageMode, ProcessResult, ProcessingOptions, RenamePlan, SequenceCounter,
+    SequenceMode, TempImages, UploadMode, UploadOptions, UploadStatus, INPUT_EXTENSIONS,
+};
+use crate::sound::{NotificationSound, SoundNotifier};
+use crate::training::{default_training_path, CorrectionRecord, TrainingStore};
+use crate::vision::usage::{load_usage, UsageLog};
+use crate::watcher::{DictionaryEvent, DictionaryWatcher, FolderWatcher};
 use anyhow::Result;
 use eframe::egui;
 use egui::{CentralPanel, RichText, Vec2};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
-use super::theme::{dark_theme, Colors};
+use super::theme::{dark_theme, Colors, ConfidenceLevel};
 
-/// 処理結果
-#[derive(Clone)]
-pub struct ProcessResult {
-    pub success: bool,
-    pub original: String,
-    pub new_name: Option<String>,
-    pub error: Option<String>,
-    pub parsed: Option<MillsheetInfo>,
+/// 既定の確認待ち閾値
+const DEFAULT_REVIEW_THRESHOLD: f32 = 0.8;
+/// バッチ分割を有効にしたときの既定の1バッチのファイル数
+const DEFAULT_BATCH_SIZE: usize = 50;
+/// ページ分割を有効にしたときの既定の1ページの件数
+const DEFAULT_PAGE_SIZE: usize = 100;
+/// 設定画面で指定できるファイル名の最大文字数の上限（Windowsのパス長 260 文字に余裕を残す）
+const MAX_FILENAME_LEN_SETTING: usize = 200;
+
+/// 信頼度を「高」（緑）とみなす既定の下限
+const DEFAULT_CONFIDENCE_HIGH: f32 = 0.8;
+/// 信頼度を「中」（黄）とみなす既定の下限
+const DEFAULT_CONFIDENCE_MEDIUM: f32 = 0.4;
+
+/// フィールドをコピーしたことを知らせるツールチップの表示時間
+const COPY_FEEDBACK_DURATION: Duration = Duration::from_millis(1500);
+
+/// サムネイルを同時に生成する数（UIと本処理を圧迫しないよう少なめ）
+const THUMBNAIL_CONCURRENCY: usize = 2;
+/// グリッドに表示するサムネイルの大きさ
+const THUMBNAIL_DISPLAY_SIZE: f32 = 160.0;
+
+/// 「指定ページのみ」で入力できるページ番号の上限
+const MAX_TARGET_PAGE: usize = 999;
+
+/// 解像度の目安に使うA4の寸法（ポイント）
+const A4_WIDTH_PT: f32 = 595.0;
+const A4_HEIGHT_PT: f32 = 842.0;
+
+/// 処理待ちのファイル
+struct QueuedFile {
+    /// ファイルパス
+    path: PathBuf,
+    /// 事前診断の結果
+    diagnosis: PdfDiagnosis,
+    /// 暗号化PDFのパスワード
+    password: String,
+    /// 優先度（大きいほど先に処理）
+    priority: i32,
+    /// 処理対象から外すか
+    excluded: bool,
+}
+
+/// 処理待ちリストの表示方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueueView {
+    /// 1行1ファイルの一覧
+    List,
+    /// 1ページ目のサムネイルを並べたグリッド
+    Grid,
+}
+
+/// 処理待ちファイルのサムネイルの状態
+enum ThumbnailState {
+    /// 生成中
+    Loading,
+    /// 生成済み
+    Ready(egui::TextureHandle),
+    /// 生成できなかった
+    Failed,
+}
+
+/// サムネイルの生成結果
+type ThumbnailOutcome = (PathBuf, Result<Thumbnail>);
+
+/// アップロードの結果（アップロード前のファイルの場所, アップロード後の結果）
+type UploadOutcome = (PathBuf, ProcessResult);
+
+/// 試し処理の結果（リネーム計画、失敗時は失敗結果）
+type TrialOutcome = (PathBuf, Result<RenamePlan, ProcessResult>);
+
+/// 結果リストのタブ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResultTab {
+    /// すべての処理結果
+    All,
+    /// 確認待ち
+    Review,
 }
 
 /// アプリケーション状態
 pub struct MillsheetRenamerApp {
-    /// Vision APIクライアント
-    vision_client: Option<Arc<VisionClient>>,
+    /// OCRバックエンド
+    ocr_backend: Option<Arc<dyn OcrBackend>>,
+    /// 選択中のOCRバックエンドの種類
+    ocr_kind: OcrBackendKind,
     /// Tokioランタイム
     runtime: Runtime,
     /// 処理結果
     results: Vec<ProcessResult>,
+    /// リネーム後のファイルが存在するか（毎フレーム確かめないよう、結果が変わるか開くまで覚えておく）
+    output_exists: HashMap<PathBuf, bool>,
+    /// 表示中のタブ
+    result_tab: ResultTab,
+    /// タグの絞り込み（None ならすべて）
+    tag_filter: Option<String>,
+    /// 付け外しするタグの入力欄
+    tag_input: String,
+    /// 選択中の結果（results のインデックス）
+    selection: BTreeSet<usize>,
+    /// キーボード操作の現在行
+    cursor: Option<usize>,
+    /// Shift選択の起点
+    anchor: Option<usize>,
+    /// 次の描画で現在行までスクロールするか
+    scroll_to_cursor: bool,
+    /// 最後に値をコピーしたフィールド（結果のインデックス, フィールド名, コピーした時刻）
+    copied_field: Option<(usize, &'static str, Instant)>,
+    /// 結果一覧の1ページの件数（None ならページ分割しない）
+    page_size: Option<usize>,
+    /// 表示中のページ（0始まり）
+    page: usize,
+    /// 確認が必要な処理待ちファイル（暗号化・破損を含む場合のみ）
+    queue: Vec<QueuedFile>,
+    /// 処理待ちリストの表示方法
+    queue_view: QueueView,
+    /// 処理待ちファイルのサムネイル（グリッドに表示されたものから遅延生成）
+    thumbnails: HashMap<PathBuf, ThumbnailState>,
+    /// サムネイルの同時生成数の制限
+    thumbnail_limit: Arc<Semaphore>,
+    /// バックグラウンド処理が取り出す優先度付きキュー
+    job_queue: JobQueue,
     /// 処理中かどうか
     is_processing: bool,
+    /// 処理中のキャンセル要求（処理開始ごとに作り直す）
+    cancel_token: Option<CancelToken>,
+    /// キャンセルにより未処理となった件数（待機中の破棄分と、リネーム前に破棄した処理中の分）
+    cancelled_count: usize,
     /// 現在の処理ファイル
     current_file: Option<String>,
     /// 進捗
     progress: f32,
+    /// 処理対象のファイル数
+    total_files: usize,
     /// ステータスメッセージ
     status: String,
     /// エラーメッセージ
     error: Option<String>,
     /// 最後に処理したフォルダ
     last_folder: Option<PathBuf>,
+    /// 処理オプション
+    options: ProcessingOptions,
+    /// ファイル名テンプレートの入力欄
+    template_input: String,
+    /// ファイル名テンプレートの解析エラー
+    template_error: Option<String>,
+    /// 通し番号の採番方法
+    sequence_mode: SequenceMode,
+    /// 永続モードの通し番号の記録先
+    sequence_path: PathBuf,
+    /// 命名規則を検証するか
+    use_filename_rules: bool,
+    /// 命名規則の許可文字（正規表現の文字クラス、空なら検証しない）
+    rules_allowed_chars: String,
+    /// 命名規則の最大文字数（0なら検証しない）
+    rules_max_length: usize,
+    /// 命名規則の必須フィールド
+    rules_required_fields: BTreeSet<&'static str>,
+    /// エクスポートダイアログを表示中か
+    show_export_dialog: bool,
+    /// 選択中のエクスポート形式
+    export_format: ExportFormat,
+    /// 外部辞書ファイル
+    dictionary_path: Option<PathBuf>,
+    /// 辞書ファイルの変更を監視するか
+    watch_dictionary: bool,
+    /// 辞書ファイルの監視
+    dictionary_watcher: Option<DictionaryWatcher>,
+    /// 監視対象のフォルダ（ウォッチモード）
+    watch_folder: Option<PathBuf>,
+    /// フォルダの監視
+    folder_watcher: Option<FolderWatcher>,
+    /// 処理中に検出され、次のバッチを待っているPDF
+    watch_backlog: Vec<PathBuf>,
+    /// リネーム後に共有フォルダへアップロードするか
+    upload_enabled: bool,
+    /// アップロード先と方法（有効でアップロード先があれば `options.upload` に反映）
+    upload_settings: UploadOptions,
+    /// 現在のバッチの先頭（ウォッチモードでは結果を追記する）
+    batch_start: usize,
+    /// Vision API の使用量履歴
+    usage: UsageLog,
+    /// 「完全」バッジの必須フィールド
+    required_fields: BTreeSet<&'static str>,
+    /// 結果カードの色分け: 高（緑）とみなす信頼度の下限
+    confidence_high: f32,
+    /// 結果カードの色分け: 中（黄）とみなす信頼度の下限
+    confidence_medium: f32,
+    /// 処理結果を履歴DBに記録するか
+    record_history: bool,
+    /// 履歴DBのパス
+    history_path: PathBuf,
+    /// 開いている履歴DB（最初の記録時に開く）
+    history: Option<HistoryDb>,
+    /// 操作を監査ログに記録するか
+    record_audit: bool,
+    /// 監査ログのパス
+    audit_path: PathBuf,
+    /// 監査ログのローテーション条件
+    audit_rotation: AuditRotation,
+    /// 開いている監査ログ（最初の記録時に開く）
+    audit: Option<AuditLog>,
+    /// 処理完了時の通知音
+    sound: SoundNotifier,
+    /// 修正中の確認待ち結果（results のインデックスとフィールド別の入力値）
+    editing: Option<(usize, BTreeMap<&'static str, String>)>,
+    /// 手動修正を学習データとして蓄積するか（オプトイン）
+    collect_corrections: bool,
+    /// 学習データのOCRテキストから個人情報を伏せるか
+    anonymize_corrections: bool,
+    /// 学習データの保存先
+    training: TrainingStore,
+    /// 投入したファイルをすぐ処理せず処理待ちリストに置くか（試し処理用）
+    hold_queue: bool,
+    /// 「指定ページのみ」でOCRするページ（1始まり）
+    target_page: usize,
+    /// 試し処理中のファイル
+    trial_running: Option<PathBuf>,
+    /// 試し処理の結果
+    trial_result: Option<TrialOutcome>,
+    /// 試し処理の結果受信チャンネル
+    trial_rx: Receiver<TrialOutcome>,
+    /// 試し処理の結果送信チャンネル
+    trial_tx: Sender<TrialOutcome>,
+    /// サムネイルの受信チャンネル
+    thumbnail_rx: Receiver<ThumbnailOutcome>,
+    /// サムネイルの送信チャンネル
+    thumbnail_tx: Sender<ThumbnailOutcome>,
+    /// 再アップロードの結果受信チャンネル
+    upload_rx: Receiver<UploadOutcome>,
+    /// 再アップロードの結果送信チャンネル
+    upload_tx: Sender<UploadOutcome>,
+    /// ウォームアップの完了通知（実行中のみ Some）
+    warmup_rx: Option<Receiver<Result<()>>>,
     /// 結果受信チャンネル
     result_rx: Receiver<ProcessResult>,
     /// 結果送信チャンネル
@@ -52,91 +291,1972 @@ pub struct MillsheetRenamerApp {
 impl Default for MillsheetRenamerApp {
     fn default() -> Self {
         let (result_tx, result_rx) = channel();
+        let (trial_tx, trial_rx) = channel();
+        let (thumbnail_tx, thumbnail_rx) = channel();
+        let (upload_tx, upload_rx) = channel();
+        
+        // OCRバックエンドの初期化（既定は埋め込み認証情報を使う Google Vision）
+        let ocr_kind = OcrBackendKind::default();
+        let (ocr_backend, error) = match create_backend(ocr_kind) {
+            Ok(backend) => (Some(backend), None),
+            Err(e) => (None, Some(ocr_init_error(ocr_kind, &e))),
+        };
+        
+        // 外部辞書の読み込み
+        let dictionary_path = default_dictionary_path();
+        let mut options = ProcessingOptions {
+            ocr_cache: Some(OcrCache::default()),
+            sequence: Some(SequenceCounter::session()),
+            ..Default::default()
+        };
+        let mut status = "PDFファイルをドロップして開始".to_string();
+        if let Some(ref path) = dictionary_path {
+            match Dictionary::load(path) {
+                Ok(dictionary) => options.parse.dictionary = Some(Arc::new(dictionary)),
+                Err(e) => {
+                    tracing::error!("辞書の読み込みに失敗: {:#}", e);
+                    status = format!("辞書の読み込みに失敗: {:#}", e);
+                }
+            }
+        }
+        
+        let mut app = Self {
+            ocr_backend,
+            ocr_kind,
+            runtime: Runtime::new().expect("Tokioランタイムの作成に失敗"),
+            results: Vec::new(),
+            output_exists: HashMap::new(),
+            result_tab: ResultTab::All,
+            tag_filter: None,
+            tag_input: String::new(),
+            selection: BTreeSet::new(),
+            cursor: None,
+            anchor: None,
+            scroll_to_cursor: false,
+            copied_field: None,
+            page_size: None,
+            page: 0,
+            queue: Vec::new(),
+            queue_view: QueueView::List,
+            thumbnails: HashMap::new(),
+            thumbnail_limit: Arc::new(Semaphore::new(THUMBNAIL_CONCURRENCY)),
+            job_queue: JobQueue::new(),
+            is_processing: false,
+            cancel_token: None,
+            cancelled_count: 0,
+            current_file: None,
+            progress: 0.0,
+            total_files: 0,
+            status,
+            error,
+            last_folder: None,
+            options,
+            show_export_dialog: false,
+            export_format: ExportFormat::default(),
+            dictionary_path,
+            watch_dictionary: true,
+            dictionary_watcher: None,
+            hold_queue: false,
+            target_page: 1,
+            trial_running: None,
+            trial_result: None,
+            trial_rx,
+            trial_tx,
+            thumbnail_rx,
+            thumbnail_tx,
+            upload_rx,
+            upload_tx,
+            result_rx,
+            template_input: String::new(),
+            template_error: None,
+            sequence_mode: SequenceMode::default(),
+            sequence_path: default_sequence_path(),
+            use_filename_rules: false,
+            rules_allowed_chars: r"[A-Za-z0-9_.\-]".to_string(),
+            rules_max_length: 100,
+            rules_required_fields: BTreeSet::from(["date"]),
+            watch_folder: None,
+            folder_watcher: None,
+            watch_backlog: Vec::new(),
+            upload_enabled: false,
+            upload_settings: UploadOptions::new(PathBuf::new()),
+            batch_start: 0,
+            usage: load_usage(),
+            required_fields: DEFAULT_REQUIRED_FIELDS.into_iter().collect(),
+            confidence_high: DEFAULT_CONFIDENCE_HIGH,
+            confidence_medium: DEFAULT_CONFIDENCE_MEDIUM,
+            record_history: true,
+            history_path: default_history_path(),
+            history: None,
+            record_audit: true,
+            audit_path: default_audit_path(),
+            audit_rotation: AuditRotation::default(),
+            audit: None,
+            sound: SoundNotifier::default(),
+            editing: None,
+            collect_corrections: false,
+            anonymize_corrections: true,
+            training: TrainingStore::new(default_training_path()),
+            warmup_rx: None,
+            result_tx,
+        };
+        app.update_dictionary_watcher();
+        app.start_warmup();
+        app
+    }
+}
+
+impl MillsheetRenamerApp {
+    /// ファイルを診断し、問題がなければそのまま処理を開始
+    fn process_files(&mut self, files: Vec<PathBuf>) {
+        let pdf_files: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|p| is_supported_input(p))
+            .collect();
+        
+        if pdf_files.is_empty() {
+            self.status = "PDF・画像ファイルが見つかりません".to_string();
+            return;
+        }
+        
+        let queue: Vec<QueuedFile> = pdf_files
+            .into_iter()
+            .map(|path| {
+                // 画像ファイルはPDFとして診断できないので、そのまま処理できるものとして扱う
+                let diagnosis = if is_image_file(&path) {
+                    Ok(PdfDiagnosis::default())
+                } else {
+                    diagnose(&path)
+                };
+                let diagnosis = diagnosis.unwrap_or_else(|e| {
+                    tracing::warn!("PDFを診断できません: {:#}", e);
+                    PdfDiagnosis {
+                        corrupted: true,
+                        ..Default::default()
+                    }
+                });
+                QueuedFile {
+                    path,
+                    diagnosis,
+                    password: String::new(),
+                    priority: 0,
+                    excluded: false,
+                }
+            })
+            .collect();
+        
+        if !self.hold_queue && queue.iter().all(|f| f.diagnosis.is_ok()) {
+            self.start_processing(queue.into_iter().map(|f| QueuedJob::new(f.path, None, 0)).collect());
+        } else {
+            self.status = if self.hold_queue {
+                "処理待ちに追加しました（1ファイルだけ試してから処理を開始できます）".to_string()
+            } else {
+                "確認が必要なファイルがあります".to_string()
+            };
+            self.queue = queue;
+            self.thumbnails.clear();
+            self.trial_result = None;
+        }
+    }
+    
+    /// 処理待ちの1ファイルだけOCR・解析して結果をプレビュー（リネームしない）
+    fn start_trial(&mut self, path: PathBuf, password: Option<String>) {
+        let ocr_backend = self.ocr_backend.clone();
+        let options = self.options.clone();
+        let trial_tx = self.trial_tx.clone();
+        self.trial_running = Some(path.clone());
+        self.trial_result = None;
+        self.status = format!("試し処理中: {}", path.display());
+        
+        self.runtime.spawn(async move {
+            let outcome = preview_single_pdf(&path, password, ocr_backend.as_deref(), &options).await;
+            let _ = trial_tx.send((path, outcome));
+        });
+    }
+    
+    /// 試し処理の結果を受信
+    fn receive_trial(&mut self) {
+        while let Ok(outcome) = self.trial_rx.try_recv() {
+            self.trial_running = None;
+            self.usage = load_usage();
+            self.status = match outcome.1 {
+                Ok(_) => "試し処理が完了しました（リネームしていません）".to_string(),
+                Err(_) => "試し処理に失敗しました".to_string(),
+            };
+            self.trial_result = Some(outcome);
+        }
+    }
+    
+    /// 処理待ちファイルのサムネイル生成をバックグラウンドで開始（生成済み・生成中なら何もしない）
+    fn request_thumbnail(&mut self, path: &Path, password: Option<String>) {
+        if self.thumbnails.contains_key(path) {
+            return;
+        }
+        self.thumbnails.insert(path.to_path_buf(), ThumbnailState::Loading);
+        
+        let path = path.to_path_buf();
+        let limit = self.thumbnail_limit.clone();
+        let thumbnail_tx = self.thumbnail_tx.clone();
+        self.runtime.spawn(async move {
+            let Ok(_permit) = limit.acquire_owned().await else {
+                return;
+            };
+            let target = path.clone();
+            let outcome = tokio::task::spawn_blocking(move || render_thumbnail(&target, password.as_deref()))
+                .await
+                .unwrap_or_else(|e| Err(anyhow::anyhow!("サムネイル生成が中断されました: {}", e)));
+            let _ = thumbnail_tx.send((path, outcome));
+        });
+    }
+    
+    /// 生成できたサムネイルをテクスチャにする（処理待ちから外れたファイルの分は捨てる）
+    fn receive_thumbnails(&mut self, ctx: &egui::Context) {
+        while let Ok((path, outcome)) = self.thumbnail_rx.try_recv() {
+            if !self.thumbnails.contains_key(&path) {
+                continue;
+            }
+            let state = match outcome {
+                Ok(thumbnail) => {
+                    let image = egui::ColorImage::from_rgba_unmultiplied(
+                        [thumbnail.width as usize, thumbnail.height as usize],
+                        &thumbnail.rgba,
+                    );
+                    let name = format!("thumbnail:{}", path.display());
+                    ThumbnailState::Ready(ctx.load_texture(name, image, egui::TextureOptions::LINEAR))
+                }
+                Err(e) => {
+                    tracing::warn!("サムネイルを生成できません {}: {:#}", path.display(), e);
+                    ThumbnailState::Failed
+                }
+            };
+            self.thumbnails.insert(path, state);
+        }
+    }
+    
+    /// 結果のファイルをバックグラウンドでアップロード（確定したファイル・失敗分の再アップロード用）
+    fn start_uploads(&mut self, indices: &[usize]) {
+        let Some(options) = self.options.upload.clone() else {
+            self.status = "アップロード先が設定されていません".to_string();
+            return;
+        };
+        
+        for &i in indices {
+            let Some(result) = self.results.get_mut(i) else {
+                continue;
+            };
+            if !result.success || result.upload == Some(UploadStatus::Uploading) {
+                continue;
+            }
+            result.upload = Some(UploadStatus::Uploading);
+            
+            let path = result.current_path().to_path_buf();
+            let result = result.clone();
+            let options = options.clone();
+            let upload_tx = self.upload_tx.clone();
+            self.runtime.spawn(async move {
+                let uploaded = upload_in_background(result, options).await;
+                let _ = upload_tx.send((path, uploaded));
+            });
+        }
+    }
+    
+    /// アップロードの結果を反映（アップロード前の場所で結果を探す）
+    fn receive_uploads(&mut self) {
+        while let Ok((path, uploaded)) = self.upload_rx.try_recv() {
+            let Some(result) = self.results.iter_mut().find(|r| r.current_path() == path) else {
+                continue;
+            };
+            self.status = match uploaded.upload {
+                Some(UploadStatus::Failed(ref e)) => format!("アップロードに失敗: {}", e),
+                _ => format!("アップロードしました: {}", result.new_name.as_deref().unwrap_or(&result.original)),
+            };
+            result.source_path = uploaded.source_path;
+            result.new_path = uploaded.new_path;
+            result.upload = uploaded.upload;
+            self.output_exists.clear();
+        }
+    }
+    
+    /// アップロード中の結果があるか
+    fn uploads_running(&self) -> bool {
+        self.results.iter().any(|r| r.upload == Some(UploadStatus::Uploading))
+    }
+    
+    /// アップロードの設定を処理オプションに反映（アップロード先が未設定なら無効）
+    fn update_upload_options(&mut self) {
+        let has_dest = !self.upload_settings.dest_dir.as_os_str().is_empty();
+        self.options.upload = (self.upload_enabled && has_dest).then(|| self.upload_settings.clone());
+    }
+    
+    /// 生成待ちのサムネイルがあるか
+    fn thumbnails_loading(&self) -> bool {
+        self.thumbnails.values().any(|state| matches!(state, ThumbnailState::Loading))
+    }
+    
+    /// 試し処理の結果（提案名・抽出値・命名規則違反）を表示
+    fn show_trial_result(&self, ui: &mut egui::Ui) {
+        if let Some(ref path) = self.trial_running {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(RichText::new(format!("試し処理中: {}", path.file_name().unwrap_or_default().to_string_lossy()))
+                    .size(13.0)
+                    .color(Colors::TEXT_SECONDARY));
+            });
+            return;
+        }
+        let Some((ref path, ref outcome)) = self.trial_result else {
+            return;
+        };
+        
+        ui.separator();
+        ui.label(RichText::new(format!("試し処理の結果: {}", path.file_name().unwrap_or_default().to_string_lossy()))
+            .size(13.0)
+            .color(Colors::TEXT_PRIMARY));
+        match outcome {
+            Ok(plan) => {
+                ui.label(RichText::new(format!("→ {}（リネームしていません）", plan.new_name))
+                    .size(14.0)
+                    .color(Colors::TEXT_PRIMARY));
+                ui.horizontal_wrapped(|ui| {
+                    for field in CONFIDENCE_FIELDS.into_iter().filter(|f| plan.info.is_field_enabled(f)) {
+                        let confidence = plan.info.confidence.get(field).unwrap_or(0.0);
+                        let level = ConfidenceLevel::of(confidence, self.confidence_high, self.confidence_medium);
+                        let value = plan.info.field_value(field).unwrap_or_else(|| "未抽出".to_string());
+                        ui.label(RichText::new(format!("{} {}: {}", level.icon(), field_label(field), value))
+                            .size(12.0)
+                            .color(level.color()));
+                    }
+                });
+                for violation in &plan.violations {
+                    ui.label(RichText::new(format!("命名規則違反: {}", violation))
+                        .size(12.0)
+                        .color(Colors::WARNING));
+                }
+                ui.label(RichText::new("OCR結果はキャッシュ済みのため、本処理でこのファイルのAPIは再度呼び出しません")
+                    .size(11.0)
+                    .color(Colors::TEXT_SECONDARY));
+            }
+            Err(failure) => {
+                ui.label(RichText::new(format!("エラー: {}", failure.error.as_deref().unwrap_or("不明なエラー")))
+                    .size(13.0)
+                    .color(Colors::ERROR));
+            }
+        }
+    }
+    
+    /// 処理待ちリストの破損ファイルと除外したファイルを除いて処理を開始
+    fn start_queue(&mut self) {
+        self.thumbnails.clear();
+        let jobs: Vec<QueuedJob> = std::mem::take(&mut self.queue)
+            .into_iter()
+            .filter(|f| !f.diagnosis.corrupted && !f.excluded)
+            .map(|f| {
+                let password = (f.diagnosis.encrypted && !f.password.is_empty()).then_some(f.password);
+                QueuedJob::new(f.path, password, f.priority)
+            })
+            .collect();
         
-        // Vision クライアントの初期化（埋め込み認証情報を使用）
-        let vision_client = VisionClient::new().ok().map(Arc::new);
+        if jobs.is_empty() {
+            self.status = "処理できるファイルがありません".to_string();
+            return;
+        }
+        self.start_processing(jobs);
+    }
+    
+    /// ファイルを処理（暗号化PDFはパスワード付き、優先度の高い順に取り出す）
+    fn start_processing(&mut self, pdf_files: Vec<QueuedJob>) {
+        // 最初のファイルのフォルダを記録
+        if let Some(first) = pdf_files.first() {
+            self.last_folder = first.path.parent().map(|p| p.to_path_buf());
+        }
+        
+        // ウォッチモード中は前のバッチの結果を残して追記する
+        if self.folder_watcher.is_none() {
+            self.results.clear();
+            self.clear_selection();
+        }
+        self.output_exists.clear();
+        self.batch_start = self.results.len();
+        self.is_processing = true;
+        self.cancelled_count = 0;
+        self.progress = 0.0;
+        self.status = format!("{} 個のファイルを処理中...", pdf_files.len());
+        
+        self.total_files = pdf_files.len();
+        for job in pdf_files {
+            self.job_queue.push(job);
+        }
+        
+        let cancel = CancelToken::new();
+        self.cancel_token = Some(cancel.clone());
+        let job_queue = self.job_queue.clone();
+        let ocr_backend = self.ocr_backend.clone();
+        let temp_images = TempImages::default();
+        let options = Arc::new(ProcessingOptions {
+            cancel: Some(cancel.clone()),
+            temp_images: Some(temp_images.clone()),
+            ..self.options.clone()
+        });
+        let result_tx = self.result_tx.clone();
+        let concurrency = options.effective_concurrency();
+        let batch_size = options.batch_size.map(|n| n.max(1));
+        
+        // バックグラウンドで処理（同時実行数はセマフォで制限）
+        // 空きができてからキューを見るので、優先度の変更は次の取り出しから反映される
+        // バッチ分割時は1バッチ分を投入したら終わるのを待ち、このバッチの片付け漏れだけを消してから次へ進む
+        // （試し処理・サムネイル・タイムアウト後も走っている変換の一時フォルダには触れない）
+        self.runtime.spawn(async move {
+            let semaphore = Arc::new(Semaphore::new(concurrency));
+            let mut tasks = JoinSet::new();
+            let mut started = 0;
+            
+            loop {
+                if let Some(size) = batch_size
+                    && started > 0
+                    && started % size == 0
+                    && !tasks.is_empty()
+                {
+                    while tasks.join_next().await.is_some() {}
+                    let removed = temp_images.cleanup(&PdfConverter);
+                    tracing::info!("バッチ {} 件完了（片付け漏れの一時フォルダ {} 個を削除）", started, removed);
+                }
+                
+                let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                    break;
+                };
+                if cancel.is_cancelled() {
+                    break;
+                }
+                let Some(QueuedJob { path: pdf_path, password, .. }) = job_queue.pop() else {
+                    break;
+                };
+                let ocr_backend = ocr_backend.clone();
+                let options = options.clone();
+                let result_tx = result_tx.clone();
+                started += 1;
+                
+                // アップロードはリトライで待つことがあるので、同時実行数の枠を返してから行う
+                tasks.spawn(async move {
+                    let result = Pipeline::new(ocr_backend.as_deref()).process(&pdf_path, password, &options).await;
+                    drop(permit);
+                    let result = upload_processed(result, &options).await;
+                    let _ = result_tx.send(result);
+                });
+            }
+            
+            while tasks.join_next().await.is_some() {}
+            if batch_size.is_some() {
+                temp_images.cleanup(&PdfConverter);
+            }
+        });
+    }
+    
+    /// 処理をキャンセル（待機中のジョブは破棄し、処理済みの結果は残す）
+    fn cancel_processing(&mut self) {
+        let Some(ref cancel) = self.cancel_token else {
+            return;
+        };
+        cancel.cancel();
+        let discarded = self.job_queue.clear();
+        self.cancelled_count += discarded;
+        self.total_files = self.total_files.saturating_sub(discarded);
+        tracing::info!("処理をキャンセル: 待機中の {} 件を破棄", discarded);
+        self.update_progress();
+    }
+    
+    /// 結果を受信
+    fn receive_results(&mut self) {
+        while let Ok(result) = self.result_rx.try_recv() {
+            // リネーム前にキャンセルを検知した1件は結果に含めず未処理として数える
+            if result.is_cancelled() {
+                self.cancelled_count += 1;
+                self.total_files = self.total_files.saturating_sub(1);
+                self.update_progress();
+                continue;
+            }
+            
+            self.record_history(&result);
+            if result.success {
+                self.audit_rename(AuditAction::Rename, &result);
+            }
+            // 確認待ちも手作業が必要なので失敗と同じ音にする
+            self.sound.notify(if result.success {
+                NotificationSound::Success
+            } else {
+                NotificationSound::Failure
+            });
+            self.results.push(result);
+            self.output_exists.clear();
+            self.usage = load_usage();
+            self.update_progress();
+        }
+    }
+    
+    /// 受信済みの結果から進捗とステータスを更新（キャンセル時は処理済みの分で確定する）
+    fn update_progress(&mut self) {
+        if !self.is_processing {
+            return;
+        }
+        
+        let batch = &self.results[self.batch_start..];
+        let done = batch.len();
+        let success_count = batch.iter().filter(|r| r.success).count();
+        let pending_count = batch.iter().filter(|r| r.pending_review).count();
+        let fail_count = done - success_count - pending_count;
+        
+        self.progress = done as f32 / self.total_files.max(1) as f32;
+        
+        if done >= self.total_files {
+            // すべて完了
+            self.is_processing = false;
+            self.cancel_token = None;
+            self.status = if pending_count > 0 {
+                format!("完了: {} 件成功, {} 件確認待ち, {} 件失敗", success_count, pending_count, fail_count)
+            } else {
+                format!("完了: {} 件成功, {} 件失敗", success_count, fail_count)
+            };
+            if self.cancelled_count > 0 {
+                self.status = format!("{}（キャンセルにより未処理: {}件）", self.status, self.cancelled_count);
+            }
+        } else if self.cancel_token.as_ref().is_some_and(CancelToken::is_cancelled) {
+            self.status = format!("キャンセル中: 処理中の {} 件の終了を待っています", self.total_files - done);
+        } else {
+            self.status = format!("処理中: {}/{} 件", done, self.total_files);
+        }
+    }
+    
+    /// 操作を監査ログに記録（ログを開けなければ記録をやめ、操作は続ける）
+    fn audit(&mut self, action: AuditAction, target: Option<&Path>, detail: String) {
+        if !self.record_audit {
+            return;
+        }
+        
+        if self.audit.is_none() {
+            match AuditLog::open(&self.audit_path, self.audit_rotation) {
+                Ok(log) => self.audit = Some(log),
+                Err(e) => {
+                    tracing::error!("監査ログを開けません: {:#}", e);
+                    self.error = Some(format!("監査ログを開けないため記録を停止しました: {:#}", e));
+                    self.record_audit = false;
+                    return;
+                }
+            }
+        }
+        
+        if let Some(ref mut log) = self.audit
+            && let Err(e) = log.record(action, target, detail)
+        {
+            tracing::warn!("監査ログの記録に失敗: {:#}", e);
+            self.error = Some(format!("監査ログの記録に失敗: {:#}", e));
+        }
+    }
+    
+    /// リネーム（自動・確定）を監査ログに記録
+    fn audit_rename(&mut self, action: AuditAction, result: &ProcessResult) {
+        let detail = format!("{} → {}", result.original, result.new_name.as_deref().unwrap_or_default());
+        self.audit(action, Some(result.current_path()), detail);
+    }
+    
+    /// 処理結果を履歴DBに記録（DBを開けなければ記録をやめ、処理は続ける）
+    fn record_history(&mut self, result: &ProcessResult) {
+        if !self.record_history {
+            return;
+        }
+        
+        if self.history.is_none() {
+            match HistoryDb::open(&self.history_path) {
+                Ok(db) => self.history = Some(db),
+                Err(e) => {
+                    tracing::error!("履歴DBを開けません: {:#}", e);
+                    self.error = Some(format!("履歴DBを開けないため記録を停止しました: {:#}", e));
+                    self.record_history = false;
+                    return;
+                }
+            }
+        }
+        
+        if let Some(ref db) = self.history
+            && let Err(e) = db.record(result)
+        {
+            tracing::warn!("処理履歴の記録に失敗: {:#}", e);
+        }
+    }
+    
+    /// 処理待ちリスト（診断結果・パスワード入力）を表示
+    fn show_queue(&mut self, ui: &mut egui::Ui) {
+        let mut start = false;
+        let mut cancel = false;
+        let mut trial: Option<(PathBuf, Option<String>)> = None;
+        let trial_idle = self.trial_running.is_none() && self.ocr_backend.is_some();
+        
+        egui::Frame::new()
+            .fill(Colors::BG_CARD)
+            .corner_radius(12.0)
+            .inner_margin(12.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("処理待ち（破損ファイルは処理対象から外します）")
+                        .size(14.0)
+                        .color(Colors::TEXT_PRIMARY));
+                    ui.radio_value(&mut self.queue_view, QueueView::List, "一覧");
+                    ui.radio_value(&mut self.queue_view, QueueView::Grid, "サムネイル");
+                });
+                
+                if self.queue_view == QueueView::Grid {
+                    self.show_queue_grid(ui);
+                } else {
+                    for file in &mut self.queue {
+                        ui.horizontal(|ui| {
+                            let color = if file.diagnosis.is_ok() { Colors::SUCCESS } else { Colors::ERROR };
+                            ui.label(RichText::new(file.diagnosis.icon()).size(16.0).color(color));
+                            
+                            let name = file.path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                            let text = RichText::new(name).size(13.0).color(Colors::TEXT_PRIMARY);
+                            ui.label(if file.excluded { text.strikethrough() } else { text });
+                            ui.label(RichText::new(file.diagnosis.summary())
+                                .size(12.0)
+                                .color(Colors::TEXT_SECONDARY));
+                            
+                            if file.diagnosis.encrypted && !file.diagnosis.corrupted {
+                                let edited = ui.add(egui::TextEdit::singleline(&mut file.password)
+                                    .password(true)
+                                    .hint_text("パスワード")
+                                    .desired_width(140.0))
+                                    .changed();
+                                // パスワードを直したらサムネイルも作り直す
+                                if edited {
+                                    self.thumbnails.remove(&file.path);
+                                }
+                            }
+                            
+                            if !file.diagnosis.corrupted {
+                                ui.checkbox(&mut file.excluded, "除外");
+                                ui.add(egui::DragValue::new(&mut file.priority).prefix("優先度 "));
+                                if ui.add_enabled(trial_idle, egui::Button::new("🔍 このファイルだけ試す")).clicked() {
+                                    let password = (file.diagnosis.encrypted && !file.password.is_empty())
+                                        .then(|| file.password.clone());
+                                    trial = Some((file.path.clone(), password));
+                                }
+                            }
+                        });
+                    }
+                }
+                
+                self.show_trial_result(ui);
+                self.show_priority_mode(ui);
+                
+                let warming_up = self.warming_up();
+                ui.horizontal(|ui| {
+                    let label = if warming_up { "⏳ 準備中..." } else { "▶ 処理開始" };
+                    start = ui.add_enabled(!warming_up, egui::Button::new(label))
+                        .on_disabled_hover_text("Popplerの展開とOCRの認証を済ませています")
+                        .clicked();
+                    cancel = ui.button("キャンセル").clicked();
+                });
+            });
+        
+        if let Some((path, password)) = trial {
+            self.start_trial(path, password);
+        }
+        if start {
+            self.trial_result = None;
+            self.start_queue();
+        } else if cancel {
+            self.queue.clear();
+            self.thumbnails.clear();
+            self.trial_result = None;
+            self.status = "キャンセルしました".to_string();
+        }
+    }
+    
+    /// 処理待ちファイルを1ページ目のサムネイルで並べ、除外と優先度を設定する
+    ///
+    /// サムネイルは画面に入ったものから順に生成するので、大量のファイルでもUIは止まらない
+    fn show_queue_grid(&mut self, ui: &mut egui::Ui) {
+        let mut requests: Vec<(PathBuf, Option<String>)> = Vec::new();
+        
+        egui::ScrollArea::vertical().max_height(420.0).show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for file in &mut self.queue {
+                    ui.allocate_ui(Vec2::new(THUMBNAIL_DISPLAY_SIZE, THUMBNAIL_DISPLAY_SIZE + 72.0), |ui| {
+                        ui.vertical(|ui| {
+                            let (rect, _) = ui.allocate_exact_size(Vec2::splat(THUMBNAIL_DISPLAY_SIZE), egui::Sense::hover());
+                            ui.painter().rect_filled(rect, 6.0, Colors::BG_DARK);
+                            
+                            // 暗号化PDFはパスワードが入るまで、破損PDFはそもそも描けない
+                            let needs_password = file.diagnosis.encrypted && file.password.is_empty();
+                            let placeholder = match self.thumbnails.get(&file.path) {
+                                _ if file.diagnosis.corrupted || needs_password => Some(file.diagnosis.icon()),
+                                Some(ThumbnailState::Ready(texture)) => {
+                                    ui.put(rect, egui::Image::new(texture).max_size(rect.size()));
+                                    None
+                                }
+                                Some(ThumbnailState::Loading) => {
+                                    ui.put(rect, egui::Spinner::new());
+                                    None
+                                }
+                                Some(ThumbnailState::Failed) => Some("🚫"),
+                                None => {
+                                    if ui.is_rect_visible(rect) {
+                                        let password = file.diagnosis.encrypted.then(|| file.password.clone());
+                                        requests.push((file.path.clone(), password));
+                                    }
+                                    None
+                                }
+                            };
+                            if let Some(icon) = placeholder {
+                                ui.put(rect, egui::Label::new(RichText::new(icon).size(32.0).color(Colors::TEXT_SECONDARY)));
+                            }
+                            
+                            let name = file.path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                            let text = RichText::new(name).size(12.0).color(Colors::TEXT_PRIMARY);
+                            ui.add(egui::Label::new(if file.excluded { text.strikethrough() } else { text }).truncate())
+                                .on_hover_text(file.diagnosis.summary());
+                            
+                            if !file.diagnosis.corrupted {
+                                ui.horizontal(|ui| {
+                                    ui.checkbox(&mut file.excluded, "除外");
+                                    ui.add(egui::DragValue::new(&mut file.priority).prefix("優先度 "));
+                                });
+                            }
+                        });
+                    });
+                }
+            });
+        });
+        
+        for (path, password) in requests {
+            self.request_thumbnail(&path, password);
+        }
+    }
+    
+    /// 取り出し順の切り替え（処理中に変えた場合は次の取り出しから反映）
+    fn show_priority_mode(&self, ui: &mut egui::Ui) {
+        let mut mode = self.job_queue.mode();
+        ui.horizontal(|ui| {
+            ui.label("処理順:");
+            ui.radio_value(&mut mode, PriorityMode::Manual, "優先度の高い順");
+            ui.radio_value(&mut mode, PriorityMode::FileSize, "ファイルサイズの小さい順");
+        });
+        if mode != self.job_queue.mode() {
+            self.job_queue.set_mode(mode);
+        }
+    }
+    
+    /// 処理中に待機しているファイルと優先度を表示
+    fn show_waiting_jobs(&self, ui: &mut egui::Ui) {
+        let jobs = self.job_queue.snapshot();
+        if jobs.is_empty() {
+            return;
+        }
+        
+        egui::CollapsingHeader::new(RichText::new(format!("待機中 {} 件", jobs.len())).color(Colors::TEXT_SECONDARY))
+            .default_open(false)
+            .show(ui, |ui| {
+                self.show_priority_mode(ui);
+                
+                egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                    for job in jobs {
+                        ui.horizontal(|ui| {
+                            let mut priority = job.priority;
+                            if ui.add(egui::DragValue::new(&mut priority).prefix("優先度 ")).changed() {
+                                self.job_queue.set_priority(&job.path, priority);
+                            }
+                            
+                            let name = job.path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                            ui.label(RichText::new(name).size(13.0).color(Colors::TEXT_PRIMARY));
+                            ui.label(RichText::new(format!("{} KB", job.size.div_ceil(1024)))
+                                .size(12.0)
+                                .color(Colors::TEXT_SECONDARY));
+                        });
+                    }
+                });
+            });
+    }
+    
+    /// 表示中のタブに含まれる結果のインデックス
+    fn visible_indices(&self) -> Vec<usize> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| self.is_visible(r))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    
+    /// 表示中のページに載る結果のインデックス（ページ分割しないときは表示中の全件）
+    fn page_indices(&mut self) -> Vec<usize> {
+        let visible = self.visible_indices();
+        let Some(size) = self.page_size else {
+            return visible;
+        };
+        self.page = self.page.min(visible.len().saturating_sub(1) / size);
+        visible.into_iter().skip(self.page * size).take(size).collect()
+    }
+    
+    /// 結果が載っているページに切り替える
+    fn show_page_of(&mut self, index: usize) {
+        if let Some(size) = self.page_size
+            && let Some(position) = self.visible_indices().iter().position(|&i| i == index)
+        {
+            self.page = position / size;
+        }
+    }
+    
+    /// ページ送り（ページ分割していて2ページ以上あるときだけ表示）
+    fn show_pager(&mut self, ui: &mut egui::Ui) {
+        let Some(size) = self.page_size else {
+            return;
+        };
+        let total = self.visible_indices().len();
+        let pages = total.div_ceil(size);
+        if pages <= 1 {
+            return;
+        }
+        
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.page > 0, egui::Button::new("⏮")).clicked() {
+                self.page = 0;
+            }
+            if ui.add_enabled(self.page > 0, egui::Button::new("◀ 前へ")).clicked() {
+                self.page -= 1;
+            }
+            let first = self.page * size + 1;
+            let last = ((self.page + 1) * size).min(total);
+            ui.label(RichText::new(format!("{} / {} ページ（{}〜{} 件目 / {} 件）", self.page + 1, pages, first, last, total))
+                .size(13.0)
+                .color(Colors::TEXT_SECONDARY));
+            if ui.add_enabled(self.page + 1 < pages, egui::Button::new("次へ ▶")).clicked() {
+                self.page += 1;
+            }
+            if ui.add_enabled(self.page + 1 < pages, egui::Button::new("⏭")).clicked() {
+                self.page = pages - 1;
+            }
+        });
+    }
+    
+    /// 表示中のタブとタグの絞り込みに含まれるか
+    fn is_visible(&self, result: &ProcessResult) -> bool {
+        (self.result_tab == ResultTab::All || result.pending_review)
+            && self.tag_filter.as_ref().is_none_or(|tag| result.has_tag(tag))
+    }
+    
+    /// 選択中の結果にタグを付ける/外す
+    fn tag_selection(&mut self, add: bool) {
+        let tag = self.tag_input.trim().to_string();
+        for &i in &self.selection {
+            if let Some(result) = self.results.get_mut(i) {
+                if add {
+                    result.add_tag(&tag);
+                } else {
+                    result.remove_tag(&tag);
+                }
+            }
+        }
+        self.status = format!(
+            "{} 件{}「{}」",
+            self.selection.len(),
+            if add { "にタグを付けました: " } else { "からタグを外しました: " },
+            tag
+        );
+        if !add && self.tag_filter.as_deref() == Some(tag.as_str()) && !self.results.iter().any(|r| r.has_tag(&tag)) {
+            self.tag_filter = None;
+        }
+    }
+    
+    /// 絞り込み中のタグの結果を保存
+    fn export_tag(&mut self, tag: &str) {
+        let format = self.export_format;
+        let mut dialog = rfd::FileDialog::new()
+            .add_filter(format.label(), &[format.extension()])
+            .set_file_name(format!("millsheet_results_{}.{}", sanitize_for_filename(tag), format.extension()));
+        if let Some(ref folder) = self.last_folder {
+            dialog = dialog.set_directory(folder);
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+        let results = results_with_tag(&self.results, tag);
+        self.status = match export_results(&results, format, &path) {
+            Ok(()) => {
+                self.audit(AuditAction::Export, Some(&path), format!("タグ「{}」の {} 件 ({})", tag, results.len(), format.label()));
+                format!("タグ「{}」をエクスポートしました: {}", tag, path.display())
+            }
+            Err(e) => format!("エクスポートエラー: {}", e),
+        };
+    }
+    
+    /// タグごとのファイルに分けてフォルダへ保存
+    fn export_all_tags(&mut self) {
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        self.status = match export_by_tag(&self.results, self.export_format, &dir) {
+            Ok(paths) => {
+                for path in &paths {
+                    self.audit(AuditAction::Export, Some(path), format!("タグ別 ({})", self.export_format.label()));
+                }
+                format!("{} 個のタグを書き出しました: {}", paths.len(), dir.display())
+            }
+            Err(e) => format!("エクスポートエラー: {:#}", e),
+        };
+    }
+    
+    /// 絞り込み中のタグの結果のファイルを「選んだフォルダ/タグ名」へ移動
+    fn move_tag(&mut self, tag: &str) {
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        self.output_exists.clear();
+        match move_tagged(&mut self.results, tag, &dir) {
+            Ok(count) => {
+                self.audit(AuditAction::Move, Some(&dir), format!("タグ「{}」の {} 件", tag, count));
+                self.status = format!("タグ「{}」の {} 件を移動しました: {}", tag, count, dir.display());
+            }
+            Err(e) => self.error = Some(format!("移動に失敗: {:#}", e)),
+        }
+    }
+    
+    /// タグの付け外し・絞り込み・タグ別の一括操作
+    fn show_tag_bar(&mut self, ui: &mut egui::Ui) {
+        let tags = collect_tags(&self.results);
+        if self.tag_filter.as_ref().is_some_and(|tag| !tags.contains(tag)) {
+            self.tag_filter = None;
+        }
+        
+        ui.horizontal(|ui| {
+            let filter_label = self.tag_filter.clone().unwrap_or_else(|| "すべて".to_string());
+            let before = self.tag_filter.clone();
+            egui::ComboBox::from_label("タグ")
+                .selected_text(filter_label)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.tag_filter, None, "すべて");
+                    for tag in &tags {
+                        ui.selectable_value(&mut self.tag_filter, Some(tag.clone()), tag);
+                    }
+                });
+            if self.tag_filter != before {
+                self.clear_selection();
+                self.page = 0;
+            }
+            
+            ui.separator();
+            ui.add(egui::TextEdit::singleline(&mut self.tag_input)
+                .hint_text("タグ名")
+                .desired_width(100.0));
+            for preset in PRESET_TAGS {
+                if ui.small_button(preset).clicked() {
+                    self.tag_input = preset.to_string();
+                }
+            }
+            let can_tag = !self.selection.is_empty() && !self.tag_input.trim().is_empty();
+            if ui.add_enabled(can_tag, egui::Button::new("🏷 選択中に付ける")).clicked() {
+                self.tag_selection(true);
+            }
+            if ui.add_enabled(can_tag, egui::Button::new("選択中から外す")).clicked() {
+                self.tag_selection(false);
+            }
+        });
+        
+        if tags.is_empty() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            if let Some(tag) = self.tag_filter.clone() {
+                if ui.add_enabled(!self.is_processing, egui::Button::new(format!("📤 「{}」をエクスポート", tag))).clicked() {
+                    self.export_tag(&tag);
+                }
+                if ui.add_enabled(!self.is_processing, egui::Button::new(format!("📁 「{}」をフォルダへ移動", tag))).clicked() {
+                    self.move_tag(&tag);
+                }
+            }
+            if ui.add_enabled(!self.is_processing, egui::Button::new(format!("📤 タグ別にエクスポート（{}）", self.export_format.label()))).clicked() {
+                self.export_all_tags();
+            }
+        });
+    }
+    
+    /// 選択を解除
+    fn clear_selection(&mut self) {
+        self.selection.clear();
+        self.cursor = None;
+        self.anchor = None;
+    }
+    
+    /// 行を選択（Ctrl: 追加/解除、Shift: 起点からの範囲）
+    fn select(&mut self, index: usize, modifiers: egui::Modifiers) {
+        if modifiers.shift && let Some(anchor) = self.anchor {
+            let visible = self.visible_indices();
+            let from = visible.iter().position(|&i| i == anchor);
+            let to = visible.iter().position(|&i| i == index);
+            if let (Some(from), Some(to)) = (from, to) {
+                if !modifiers.command {
+                    self.selection.clear();
+                }
+                self.selection.extend(&visible[from.min(to)..=from.max(to)]);
+            }
+        } else if modifiers.command {
+            if !self.selection.remove(&index) {
+                self.selection.insert(index);
+            }
+            self.anchor = Some(index);
+        } else {
+            self.selection = BTreeSet::from([index]);
+            self.anchor = Some(index);
+        }
+        self.cursor = Some(index);
+    }
+    
+    /// 結果リストのキー操作（↑↓: 選択、Enter: 確定、Delete: 取り消し）
+    fn handle_result_keys(&mut self, ctx: &egui::Context) {
+        if self.is_processing || ctx.wants_keyboard_input() || self.results.is_empty() {
+            return;
+        }
+        
+        let (up, down, enter, delete, modifiers) = ctx.input(|i| (
+            i.key_pressed(egui::Key::ArrowUp),
+            i.key_pressed(egui::Key::ArrowDown),
+            i.key_pressed(egui::Key::Enter),
+            i.key_pressed(egui::Key::Delete),
+            i.modifiers,
+        ));
+        
+        if up || down {
+            let visible = self.visible_indices();
+            let position = self.cursor.and_then(|c| visible.iter().position(|&i| i == c));
+            let next = match (position, down) {
+                (None, true) => visible.first(),
+                (None, false) => visible.last(),
+                (Some(p), true) => visible.get((p + 1).min(visible.len() - 1)),
+                (Some(p), false) => visible.get(p.saturating_sub(1)),
+            };
+            if let Some(&next) = next {
+                // Ctrl+矢印は現在行だけ動かし、選択は変えない
+                if modifiers.command && !modifiers.shift {
+                    self.cursor = Some(next);
+                } else {
+                    self.select(next, egui::Modifiers { command: false, ..modifiers });
+                }
+                self.show_page_of(next);
+                self.scroll_to_cursor = true;
+            }
+        }
+        
+        if enter && !self.selection.is_empty() {
+            let indices: Vec<usize> = self.selection.iter().copied().collect();
+            self.confirm_results(&indices);
+        }
+        
+        if delete && !self.selection.is_empty() {
+            let indices: Vec<usize> = self.selection.iter().copied().collect();
+            self.revert_results(&indices);
+        }
+    }
+    
+    /// リネームを取り消して確認待ちに戻す
+    fn revert_results(&mut self, indices: &[usize]) {
+        let mut reverted = 0;
+        let mut errors = Vec::new();
+        let mut audits = Vec::new();
+        for &i in indices {
+            let Some(result) = self.results.get_mut(i) else {
+                continue;
+            };
+            if !result.success {
+                continue;
+            }
+            match revert_rename(result) {
+                Ok(pending) => {
+                    let detail = format!("{} → {}", result.new_name.as_deref().unwrap_or_default(), pending.original);
+                    audits.push((pending.source_path.clone(), detail));
+                    *result = pending;
+                    reverted += 1;
+                }
+                Err(e) => errors.push(format!("{:#}", e)),
+            }
+        }
+        
+        self.output_exists.clear();
+        for (path, detail) in audits {
+            self.audit(AuditAction::Revert, Some(&path), detail);
+        }
+        self.status = match errors.first() {
+            Some(e) => format!("{} 件取り消し, {} 件失敗: {}", reverted, errors.len(), e),
+            None => format!("{} 件のリネームを取り消しました", reverted),
+        };
+    }
+    
+    /// 確認待ちの件数
+    fn pending_count(&self) -> usize {
+        self.results.iter().filter(|r| r.pending_review).count()
+    }
+    
+    /// 確認待ちを確定してリネーム
+    fn confirm_results(&mut self, indices: &[usize]) {
+        let mut confirmed = 0;
+        let mut failed = 0;
+        let mut uploads = Vec::new();
+        for &i in indices {
+            let Some(result) = self.results.get_mut(i) else {
+                continue;
+            };
+            if !result.pending_review {
+                continue;
+            }
+            *result = confirm_pending(result, &self.options);
+            let result = result.clone();
+            self.record_history(&result);
+            if result.success {
+                self.audit_rename(AuditAction::Confirm, &result);
+                uploads.push(i);
+                confirmed += 1;
+            } else {
+                failed += 1;
+            }
+        }
+        
+        self.output_exists.clear();
+        self.status = if failed > 0 {
+            format!("{} 件確定, {} 件失敗", confirmed, failed)
+        } else {
+            format!("{} 件確定しました", confirmed)
+        };
+        if self.options.upload.is_some() && !uploads.is_empty() {
+            self.start_uploads(&uploads);
+        }
+    }
+    
+    /// 確認待ちの結果の修正を開始（入力欄に現在の値を入れる）
+    fn start_edit(&mut self, index: usize) {
+        let Some(parsed) = self.results.get(index).and_then(|r| r.parsed.as_ref()) else {
+            return;
+        };
+        let values = CONFIDENCE_FIELDS
+            .into_iter()
+            .map(|field| {
+                let value = match field {
+                    "date" => parsed.date.map(|d| d.to_string()),
+                    _ => parsed.field_value(field),
+                };
+                (field, value.unwrap_or_default())
+            })
+            .collect();
+        self.editing = Some((index, values));
+    }
+    
+    /// 修正を適用して提案名を作り直す（学習データの蓄積が有効なら記録）
+    fn apply_edit(&mut self) {
+        let Some((index, values)) = self.editing.take() else {
+            return;
+        };
+        let Some(result) = self.results.get_mut(index) else {
+            return;
+        };
+        let Some(extracted) = result.parsed.clone() else {
+            return;
+        };
+        
+        let input = |field: &str| values.get(field).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let century = self.options.parse.date_century.unwrap_or(DEFAULT_CENTURY_START);
+        let date = match input("date").map(|d| IssueDate::parse_in_century(&d, century)).transpose() {
+            Ok(date) => date,
+            Err(e) => {
+                self.error = Some(format!("{:#}", e));
+                self.editing = Some((index, values));
+                return;
+            }
+        };
+        
+        let mut corrected = extracted.clone();
+        corrected.date = date;
+        corrected.material = input("material");
+        corrected.dimensions = input("dimensions");
+        corrected.manufacturer = input("manufacturer");
+        corrected.charge_no = input("charge_no");
+        if corrected.charge_no != extracted.charge_no {
+            corrected.charge_nos = corrected.charge_no.iter().cloned().collect();
+        }
+        if corrected.material != extracted.material {
+            corrected.material_spec = None;
+        }
+        if corrected.manufacturer != extracted.manufacturer {
+            corrected.manufacturer_matched_term = None;
+        }
+        if corrected.dimensions != extracted.dimensions {
+            corrected.dimensions_spec = corrected
+                .dimensions
+                .as_deref()
+                .and_then(crate::parser::dimensions::extract_dimensions_structured);
+        }
+        // 手で確かめた値は通常の抽出と同じ信頼度にする
+        let confidence = &mut corrected.confidence;
+        for (slot, filled) in [
+            (&mut confidence.date, corrected.date.is_some()),
+            (&mut confidence.material, corrected.material.is_some()),
+            (&mut confidence.dimensions, corrected.dimensions.is_some()),
+            (&mut confidence.manufacturer, corrected.manufacturer.is_some()),
+            (&mut confidence.charge_no, corrected.charge_no.is_some()),
+            (&mut confidence.weight, corrected.weight.is_some()),
+        ] {
+            *slot = if filled { CONFIDENCE_DEFAULT } else { 0.0 };
+        }
+        
+        result.new_name = Some(match self.options.filename_template {
+            Some(ref template) => {
+                let seq = if template.uses_sequence() { next_sequence(&self.options) } else { None };
+                template.render_with_sequence(&corrected, &result.original, seq)
+            }
+            None => corrected.generate_filename_with(&result.original, &self.options.filename),
+        });
+        let changes: Vec<String> = CONFIDENCE_FIELDS
+            .into_iter()
+            .filter_map(|field| {
+                let value = |info: &MillsheetInfo| match field {
+                    "date" => info.date.map(|d| d.to_string()),
+                    _ => info.field_value(field),
+                };
+                let (before, after) = (value(&extracted), value(&corrected));
+                (before != after).then(|| {
+                    format!("{}: {} → {}", field_label(field), before.unwrap_or_default(), after.unwrap_or_default())
+                })
+            })
+            .collect();
+        let audit_target = result.source_path.clone();
+        
+        if self.collect_corrections
+            && let Some(record) = CorrectionRecord::new(&result.original, &extracted, &corrected, self.anonymize_corrections)
+            && let Err(e) = self.training.append(&record)
+        {
+            tracing::warn!("学習データの記録に失敗: {:#}", e);
+            self.error = Some(format!("学習データの記録に失敗: {:#}", e));
+        }
+        result.parsed = Some(corrected);
+        if !changes.is_empty() {
+            self.audit(AuditAction::Edit, Some(&audit_target), changes.join(" / "));
+        }
+    }
+    
+    /// 学習データをテストケースとしてフォルダに書き出す
+    fn export_training_cases(&mut self) {
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        match self.training.export_cases(&dir) {
+            Ok(count) => self.status = format!("{} 件の学習データを書き出しました: {}", count, dir.display()),
+            Err(e) => self.error = Some(format!("学習データの書き出しに失敗: {:#}", e)),
+        }
+    }
+    
+    /// 設定に合わせて辞書ファイルの監視を開始/停止
+    fn update_dictionary_watcher(&mut self) {
+        if !self.watch_dictionary {
+            self.dictionary_watcher = None;
+            return;
+        }
+        
+        let Some(ref path) = self.dictionary_path else {
+            return;
+        };
+        if self.dictionary_watcher.as_ref().is_some_and(|w| w.path() == path) {
+            return;
+        }
+        
+        match DictionaryWatcher::start(path) {
+            Ok(watcher) => self.dictionary_watcher = Some(watcher),
+            Err(e) => {
+                tracing::error!("辞書ファイルの監視を開始できません: {:#}", e);
+                self.status = format!("辞書ファイルの監視を開始できません: {:#}", e);
+            }
+        }
+    }
+    
+    /// 入力欄からファイル名テンプレートを設定（不正なら以前の設定を保持）
+    fn update_filename_template(&mut self) {
+        if self.template_input.trim().is_empty() {
+            self.options.filename_template = None;
+            self.template_error = None;
+            return;
+        }
+        
+        match FilenameTemplate::parse(self.template_input.trim()) {
+            Ok(template) => {
+                let unknown = template.unknown_fields();
+                self.template_error = (!unknown.is_empty())
+                    .then(|| format!("不明なフィールド（常に空）: {}", unknown.join(", ")));
+                self.options.filename_template = Some(template);
+            }
+            Err(e) => self.template_error = Some(format!("{:#}", e)),
+        }
+    }
+    
+    /// 採番方法に合わせて通し番号のカウンタを作り直す（永続モードで開けなければセッションモードに戻す）
+    fn update_sequence_counter(&mut self) {
+        match SequenceCounter::open(self.sequence_mode, &self.sequence_path) {
+            Ok(counter) => self.options.sequence = Some(counter),
+            Err(e) => {
+                tracing::error!("通し番号ファイルを開けません: {:#}", e);
+                self.error = Some(format!("通し番号ファイルを開けません: {:#}", e));
+                self.sequence_mode = SequenceMode::Session;
+                self.options.sequence = Some(SequenceCounter::session());
+            }
+        }
+    }
+    
+    /// 命名規則の入力内容を処理オプションに反映
+    fn update_filename_rules(&mut self) {
+        self.options.filename_rules = self.use_filename_rules.then(|| FilenameRules {
+            allowed_chars: Some(self.rules_allowed_chars.trim().to_string()).filter(|s| !s.is_empty()),
+            max_length: (self.rules_max_length > 0).then_some(self.rules_max_length),
+            required_fields: self.rules_required_fields.iter().map(|f| f.to_string()).collect(),
+        });
+    }
+    
+    /// ウォッチモードの開始/停止
+    fn toggle_folder_watch(&mut self) {
+        if self.folder_watcher.take().is_some() {
+            self.watch_backlog.clear();
+            self.status = "フォルダの監視を停止しました".to_string();
+            return;
+        }
+        
+        let Some(ref folder) = self.watch_folder else {
+            return;
+        };
+        match FolderWatcher::start(folder) {
+            Ok(watcher) => {
+                self.status = format!("フォルダを監視中: {}", folder.display());
+                self.folder_watcher = Some(watcher);
+            }
+            Err(e) => {
+                tracing::error!("フォルダの監視を開始できません: {:#}", e);
+                self.status = format!("フォルダの監視を開始できません: {:#}", e);
+            }
+        }
+    }
+    
+    /// 監視フォルダに追加されたPDFをキューに入れ、手が空いていれば処理を開始
+    fn receive_watched_files(&mut self) {
+        let Some(ref watcher) = self.folder_watcher else {
+            return;
+        };
+        
+        for path in watcher.poll() {
+            // リネーム後のファイルや処理済みのファイルも新規として通知されるため除外する
+            let known = self.results
+                .iter()
+                .any(|r| r.new_path.as_ref() == Some(&path) || r.source_path == path);
+            if !known && !self.watch_backlog.contains(&path) {
+                self.watch_backlog.push(path);
+            }
+        }
+        
+        if !self.watch_backlog.is_empty() && !self.is_processing && self.queue.is_empty() {
+            let files = std::mem::take(&mut self.watch_backlog);
+            self.process_files(files);
+        }
+    }
+    
+    /// 辞書の再読み込みを反映
+    fn receive_dictionary_events(&mut self) {
+        let Some(event) = self.dictionary_watcher.as_ref().and_then(|w| w.poll()) else {
+            return;
+        };
+        
+        match event {
+            DictionaryEvent::Reloaded(dictionary) => {
+                self.options.parse.dictionary = Some(dictionary);
+                self.status = "辞書を更新しました".to_string();
+            }
+            DictionaryEvent::Failed(e) => {
+                self.status = format!("辞書の更新に失敗しました（以前の辞書を使用）: {}", e);
+            }
+        }
+    }
+    
+    /// 設定パネルを表示
+    fn show_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(RichText::new("⚙ 設定").color(Colors::TEXT_SECONDARY))
+            .default_open(false)
+            .show(ui, |ui| {
+                let dictionary_label = match self.dictionary_path {
+                    Some(ref path) => format!("辞書ファイル: {}", path.display()),
+                    None => "辞書ファイル: なし".to_string(),
+                };
+                ui.label(RichText::new(dictionary_label)
+                    .size(12.0)
+                    .color(Colors::TEXT_SECONDARY));
+                
+                if ui.add_enabled(
+                    self.dictionary_path.is_some(),
+                    egui::Checkbox::new(&mut self.watch_dictionary, "辞書ファイルの変更を監視"),
+                ).changed() {
+                    self.update_dictionary_watcher();
+                }
+                
+                let mut strip_corporate_form = self.options.parse.corporate_form == CorporateFormStyle::Strip;
+                if ui.checkbox(&mut strip_corporate_form, "ファイル名のメーカー名から法人格（株式会社・㈱など）を除去").changed() {
+                    self.options.parse.corporate_form = if strip_corporate_form {
+                        CorporateFormStyle::Strip
+                    } else {
+                        CorporateFormStyle::Keep
+                    };
+                }
+                
+                ui.horizontal(|ui| {
+                    ui.label("材質の処理記号（+BA, -Z27 など）:");
+                    let style = &mut self.options.parse.material_style;
+                    ui.radio_value(style, MaterialStyle::Base, "付けない");
+                    ui.radio_value(style, MaterialStyle::Combined, "結合 (SUS304+BA)");
+                    ui.radio_value(style, MaterialStyle::Separated, "分離 (SUS304_BA)");
+                });
+                ui.checkbox(
+                    &mut self.options.parse.normalize_material_standard,
+                    "海外規格の材質記号をJIS記号に読み替える（A36 → SS400, Q235B → SS400 など）",
+                );
+                
+                ui.horizontal(|ui| {
+                    ui.label("寸法の判定:");
+                    for profile in DimensionProfile::ALL {
+                        ui.radio_value(&mut self.options.parse.dimension_profile, profile, profile.label());
+                    }
+                });
+                
+                // 誤抽出の多いフィールドは抽出自体をやめ、ファイル名からも外す
+                ui.horizontal(|ui| {
+                    ui.label("抽出するフィールド:");
+                    for field in EXTRACT_FIELDS {
+                        let mut enabled = self.options.parse.is_enabled(field);
+                        if ui.checkbox(&mut enabled, field_label(field)).changed() {
+                            if enabled {
+                                self.options.parse.disabled_fields.remove(field);
+                            } else {
+                                self.options.parse.disabled_fields.insert(field);
+                            }
+                        }
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("発行日の書式:");
+                    for format in DateFormat::ALL {
+                        ui.radio_value(&mut self.options.parse.date_format, format, format.label());
+                    }
+                });
+                
+                if self.options.parse.date_format.is_two_digit_year() {
+                    ui.horizontal(|ui| {
+                        let mut fixed = self.options.parse.date_century.is_some();
+                        if ui.checkbox(&mut fixed, "2桁の年の世紀を固定（範囲外の年は4桁で出力）").changed() {
+                            self.options.parse.date_century = fixed.then_some(DEFAULT_CENTURY_START);
+                        }
+                        if let Some(century) = &mut self.options.parse.date_century {
+                            ui.add(egui::DragValue::new(century).range(1900..=2100).speed(100));
+                            *century -= *century % 100;
+                            ui.label(format!("〜{}年", *century + 99));
+                        }
+                    });
+                }
+                
+                let mut latest_date = self.options.parse.date_selection == DateSelection::Latest;
+                if ui.checkbox(&mut latest_date, "ラベルのない日付が複数あれば最も新しいものを採用（未来の日付は除外）").changed() {
+                    self.options.parse.date_selection = if latest_date {
+                        DateSelection::Latest
+                    } else {
+                        DateSelection::First
+                    };
+                }
+                
+                let mut ascending_charge_nos = self.options.parse.charge_no_order == ChargeNoOrder::Ascending;
+                if ui.checkbox(&mut ascending_charge_nos, "複数のチャージ番号は昇順に並べる（ファイル名には先頭2件まで）").changed() {
+                    self.options.parse.charge_no_order = if ascending_charge_nos {
+                        ChargeNoOrder::Ascending
+                    } else {
+                        ChargeNoOrder::Appearance
+                    };
+                }
+                
+                ui.horizontal(|ui| {
+                    ui.label("ファイル名テンプレート:");
+                    let edit = ui.add(egui::TextEdit::singleline(&mut self.template_input)
+                        .hint_text("空欄で既定 例: {date}_{material}_{charge_no|'NA'}")
+                        .desired_width(320.0));
+                    if edit.changed() {
+                        self.update_filename_template();
+                    }
+                });
+                if let Some(ref e) = self.template_error {
+                    ui.label(RichText::new(e).size(12.0).color(Colors::ERROR));
+                }
+                ui.horizontal(|ui| {
+                    ui.label("通し番号 {seq}:");
+                    let mut changed = false;
+                    for mode in [SequenceMode::Session, SequenceMode::Persistent] {
+                        changed |= ui.radio_value(&mut self.sequence_mode, mode, mode.label()).changed();
+                    }
+                    ui.add_enabled_ui(self.sequence_mode == SequenceMode::Persistent, |ui| {
+                        ui.label(RichText::new(self.sequence_path.display().to_string())
+                            .size(12.0)
+                            .color(Colors::TEXT_SECONDARY));
+                        if ui.button("場所を変更").clicked()
+                            && let Some(path) = rfd::FileDialog::new()
+                                .add_filter("テキスト", &["txt"])
+                                .set_file_name("millsheet_sequence.txt")
+                                .save_file()
+                        {
+                            self.sequence_path = path;
+                            changed = true;
+                        }
+                    });
+                    if changed {
+                        self.update_sequence_counter();
+                    }
+                });
+                
+                ui.add_enabled_ui(self.options.filename_template.is_none(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("既定の命名の区切り文字:");
+                        let separator = &mut self.options.filename.separator;
+                        ui.radio_value(separator, '_', "アンダースコア (_)");
+                        ui.radio_value(separator, '-', "ハイフン (-)");
+                        ui.radio_value(separator, ' ', "スペース");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("ファイル名の最大文字数:");
+                        ui.add(egui::DragValue::new(&mut self.options.filename.max_len).range(20..=MAX_FILENAME_LEN_SETTING));
+                        ui.radio_value(&mut self.options.filename.overflow, FilenameOverflow::DropFields, "超えた項目ごと省く");
+                        ui.radio_value(&mut self.options.filename.overflow, FilenameOverflow::Truncate, "文字数で切る");
+                    });
+                    ui.checkbox(&mut self.options.filename.mark_unextracted, "何も抽出できなかったファイル名に _renamed を付ける");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("リネーム後にサブフォルダへ振り分け:");
+                    for rule in SubdirRule::ALL {
+                        ui.radio_value(&mut self.options.subdir_rule, rule, rule.label());
+                    }
+                });
+                
+                // リネーム後のアップロード（共有フォルダへコピー / 移動、失敗時はリトライ）
+                let mut upload_changed = false;
+                ui.horizontal(|ui| {
+                    upload_changed |= ui.checkbox(&mut self.upload_enabled, "リネーム後に共有フォルダへアップロード").changed();
+                    let dest = &self.upload_settings.dest_dir;
+                    let dest_label = if dest.as_os_str().is_empty() {
+                        "アップロード先: 未設定".to_string()
+                    } else {
+                        format!("アップロード先: {}", dest.display())
+                    };
+                    ui.label(RichText::new(dest_label)
+                        .size(12.0)
+                        .color(Colors::TEXT_SECONDARY));
+                    if ui.button("選択").clicked()
+                        && let Some(folder) = rfd::FileDialog::new().pick_folder()
+                    {
+                        self.upload_settings.dest_dir = folder;
+                        upload_changed = true;
+                    }
+                });
+                ui.add_enabled_ui(self.upload_enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        for mode in UploadMode::ALL {
+                            upload_changed |= ui.radio_value(&mut self.upload_settings.mode, mode, mode.label()).changed();
+                        }
+                        ui.label("失敗時のリトライ:");
+                        upload_changed |= ui.add(egui::DragValue::new(&mut self.upload_settings.retries)
+                            .range(0..=10)
+                            .suffix(" 回")).changed();
+                    });
+                });
+                if upload_changed {
+                    self.update_upload_options();
+                }
+                
+                // 命名規則（違反したファイルはリネームせず、理由を結果に表示）
+                let mut rules_changed = ui.checkbox(&mut self.use_filename_rules, "ファイル名を命名規則で検証").changed();
+                ui.add_enabled_ui(self.use_filename_rules, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("許可文字:");
+                        rules_changed |= ui.add(egui::TextEdit::singleline(&mut self.rules_allowed_chars)
+                            .hint_text(r"空欄で制限なし 例: [A-Za-z0-9_.\-]")
+                            .desired_width(180.0)).changed();
+                        ui.label("最大文字数:");
+                        rules_changed |= ui.add(egui::DragValue::new(&mut self.rules_max_length)
+                            .range(0..=255)
+                            .suffix(" (0で制限なし)")).changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("必須項目:");
+                        for field in CONFIDENCE_FIELDS {
+                            let mut required = self.rules_required_fields.contains(field);
+                            if ui.checkbox(&mut required, field_label(field)).changed() {
+                                if required {
+                                    self.rules_required_fields.insert(field);
+                                } else {
+                                    self.rules_required_fields.remove(field);
+                                }
+                                rules_changed = true;
+                            }
+                        }
+                    });
+                    ui.checkbox(&mut self.options.allow_rule_violations, "違反してもリネームする（ログに記録）");
+                });
+                if rules_changed {
+                    self.update_filename_rules();
+                }
+                
+                // ウォッチモード（スキャナの出力フォルダを監視して自動処理）
+                ui.horizontal(|ui| {
+                    let folder_label = match self.watch_folder {
+                        Some(ref folder) => format!("監視フォルダ: {}", folder.display()),
+                        None => "監視フォルダ: 未設定".to_string(),
+                    };
+                    ui.label(RichText::new(folder_label)
+                        .size(12.0)
+                        .color(Colors::TEXT_SECONDARY));
+                    
+                    let watching = self.folder_watcher.is_some();
+                    if ui.add_enabled(!watching, egui::Button::new("選択")).clicked()
+                        && let Some(folder) = rfd::FileDialog::new().pick_folder()
+                    {
+                        self.watch_folder = Some(folder);
+                    }
+                    
+                    let label = if watching { "⏹ 監視を停止" } else { "👁 監視を開始" };
+                    if ui.add_enabled(self.watch_folder.is_some(), egui::Button::new(label)).clicked() {
+                        self.toggle_folder_watch();
+                    }
+                });
+                
+                // OCRバックエンド（Azure は AZURE_VISION_ENDPOINT / AZURE_VISION_KEY で認証）
+                ui.add_enabled_ui(!self.is_processing, |ui| {
+                    ui.checkbox(&mut self.hold_queue, "投入したファイルをすぐ処理せず処理待ちに置く（1ファイルだけ試してから処理）");
+                    ui.horizontal(|ui| {
+                        ui.label("OCRするページ:");
+                        for mode in PageMode::ALL {
+                            ui.radio_value(&mut self.options.page_mode, mode, mode.label());
+                        }
+                        let page_selected = matches!(self.options.page_mode, PageMode::Page(_));
+                        let page_radio = ui.radio(page_selected, PageMode::Page(self.target_page).label());
+                        let page_changed = ui.add(egui::DragValue::new(&mut self.target_page)
+                            .range(1..=MAX_TARGET_PAGE)
+                            .suffix(" ページ目")).changed();
+                        if page_radio.clicked() || (page_selected && page_changed) {
+                            self.options.page_mode = PageMode::Page(self.target_page);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("変換の解像度:");
+                        let mut dpi = self.options.convert_dpi.unwrap_or(DEFAULT_CONVERT_DPI);
+                        for choice in CONVERT_DPI_CHOICES {
+                            let (width, height) = rendered_pixels(A4_WIDTH_PT, A4_HEIGHT_PT, choice);
+                            ui.radio_value(&mut dpi, choice, format!("{} DPI", choice))
+                                .on_hover_text(format!("A4で {}×{} ピクセル", width, height));
+                        }
+                        self.options.convert_dpi = (dpi != DEFAULT_CONVERT_DPI).then_some(dpi);
+                    });
+                    ui.checkbox(
+                        &mut self.options.preserve_layout,
+                        "見た目の行を再構成したテキストでも解析し、抽出できた項目が多い方を使う（Google Vision のみ）",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("OCR:");
+                        let mut kind = self.ocr_kind;
+                        for option in OcrBackendKind::ALL {
+                            ui.radio_value(&mut kind, option, option.label());
+                        }
+                        if kind != self.ocr_kind {
+                            self.ocr_kind = kind;
+                            self.reconnect_ocr();
+                        }
+                    });
+                });
+                
+                // Vision API の使用量（課金管理用）
+                let usage = self.usage.current_month();
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("Vision API 今月: {} 回 / {} ページ", usage.calls, usage.pages))
+                        .size(12.0)
+                        .color(Colors::TEXT_SECONDARY));
+                    if ui.button("使用量をエクスポート").clicked() {
+                        self.export_usage();
+                    }
+                });
+                
+                // 結果カードの「完全」バッジに必要なフィールド
+                ui.horizontal(|ui| {
+                    ui.label("「完全」の必須項目:");
+                    for field in CONFIDENCE_FIELDS {
+                        let mut required = self.required_fields.contains(field);
+                        if ui.checkbox(&mut required, field_label(field)).changed() {
+                            if required {
+                                self.required_fields.insert(field);
+                            } else {
+                                self.required_fields.remove(field);
+                            }
+                        }
+                    }
+                });
+                
+                // 結果カードのフィールドの色分け（緑→黄→赤）
+                ui.horizontal(|ui| {
+                    ui.label("信頼度の色分け:");
+                    ui.add(egui::Slider::new(&mut self.confidence_high, 0.0..=1.0).text("以上で緑"));
+                    ui.add(egui::Slider::new(&mut self.confidence_medium, 0.0..=1.0).text("以上で黄"));
+                    self.confidence_medium = self.confidence_medium.min(self.confidence_high);
+                });
+                
+                // 処理履歴（CLIの history コマンドで検索できる）
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.record_history, "処理履歴をDBに記録");
+                    ui.label(RichText::new(self.history_path.display().to_string())
+                        .size(12.0)
+                        .color(Colors::TEXT_SECONDARY));
+                    if ui.button("場所を変更").clicked()
+                        && let Some(path) = rfd::FileDialog::new()
+                            .add_filter("SQLite", &["db", "sqlite"])
+                            .set_file_name("millsheet_history.db")
+                            .save_file()
+                    {
+                        self.history_path = path;
+                        self.history = None;
+                    }
+                });
+                
+                // 監査ログ（リネーム・取り消し・手動修正・エクスポート・移動を追記）
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.record_audit, "操作を監査ログに記録");
+                    ui.label(RichText::new(self.audit_path.display().to_string())
+                        .size(12.0)
+                        .color(Colors::TEXT_SECONDARY));
+                    if ui.button("場所を変更").clicked()
+                        && let Some(path) = rfd::FileDialog::new()
+                            .add_filter("JSON Lines", &["jsonl"])
+                            .set_file_name("millsheet_audit.jsonl")
+                            .save_file()
+                    {
+                        self.audit_path = path;
+                        self.audit = None;
+                    }
+                });
+                // 件数・期間のどちらかに達したら新しいファイルに切り替える
+                ui.horizontal(|ui| {
+                    let rotation = &mut self.audit_rotation;
+                    let mut by_count = rotation.max_entries.is_some();
+                    let mut by_age = rotation.max_age_days.is_some();
+                    let mut changed = false;
+                    
+                    ui.label("ローテーション:");
+                    if ui.checkbox(&mut by_count, "件数").changed() {
+                        rotation.max_entries = by_count.then_some(DEFAULT_MAX_ENTRIES);
+                        changed = true;
+                    }
+                    if let Some(ref mut entries) = rotation.max_entries {
+                        changed |= ui.add(egui::DragValue::new(entries).range(100..=1_000_000).suffix(" 件")).changed();
+                    }
+                    if ui.checkbox(&mut by_age, "期間").changed() {
+                        rotation.max_age_days = by_age.then_some(DEFAULT_MAX_AGE_DAYS);
+                        changed = true;
+                    }
+                    if let Some(ref mut days) = rotation.max_age_days {
+                        changed |= ui.add(egui::DragValue::new(days).range(1..=3650).suffix(" 日")).changed();
+                    }
+                    
+                    if changed {
+                        self.audit = None;
+                    }
+                });
+                
+                // 処理完了の通知音（ハンズフリーでのスキャン向け）
+                ui.horizontal(|ui| {
+                    let settings = &mut self.sound.settings;
+                    ui.checkbox(&mut settings.enabled, "処理完了時に通知音を鳴らす");
+                    ui.add_enabled_ui(settings.enabled, |ui| {
+                        ui.checkbox(&mut settings.on_success, "成功時も鳴らす");
+                        let mut seconds = settings.min_interval.as_secs_f32();
+                        if ui.add(egui::Slider::new(&mut seconds, 0.0..=10.0).text("最短間隔 (秒)")).changed() {
+                            settings.min_interval = Duration::from_secs_f32(seconds);
+                        }
+                    });
+                });
+                
+                // 確認待ちでの手動修正を学習データとして蓄積（オプトイン）
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.collect_corrections, "手動修正を学習データとして蓄積");
+                    ui.add_enabled(
+                        self.collect_corrections,
+                        egui::Checkbox::new(&mut self.anonymize_corrections, "個人情報（氏名・電話・メール）を伏せる"),
+                    );
+                    if ui.button("テストケースとして書き出し").clicked() {
+                        self.export_training_cases();
+                    }
+                });
+                ui.label(RichText::new(format!("学習データ: {}", self.training.path().display()))
+                    .size(12.0)
+                    .color(Colors::TEXT_SECONDARY));
+                
+                // 件数が多いときは結果一覧をページに分ける（エクスポートは常に全件）
+                let mut use_pages = self.page_size.is_some();
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut use_pages, "結果一覧をページ分割して表示する").changed() {
+                        self.page_size = use_pages.then_some(DEFAULT_PAGE_SIZE);
+                        self.page = 0;
+                    }
+                    if let Some(ref mut size) = self.page_size
+                        && ui.add(egui::DragValue::new(size).range(10..=1000).suffix(" 件/ページ")).changed()
+                    {
+                        self.page = 0;
+                    }
+                });
+                
+                // 総合信頼度が閾値未満のファイルは確認待ちにする
+                let mut use_review = self.options.review_threshold.is_some();
+                ui.add_enabled_ui(!self.is_processing, |ui| {
+                    if ui.checkbox(&mut use_review, "信頼度の低いファイルは確認待ちにする").changed() {
+                        self.options.review_threshold = use_review.then_some(DEFAULT_REVIEW_THRESHOLD);
+                    }
+                    if let Some(ref mut threshold) = self.options.review_threshold {
+                        ui.add(egui::Slider::new(threshold, 0.0..=1.0).text("閾値"));
+                    }
+                    
+                    // 大量のファイルは分割して処理し、バッチごとに一時ファイルを片付ける
+                    let mut use_batches = self.options.batch_size.is_some();
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut use_batches, "ファイルをバッチに分けて処理する").changed() {
+                            self.options.batch_size = use_batches.then_some(DEFAULT_BATCH_SIZE);
+                        }
+                        if let Some(ref mut size) = self.options.batch_size {
+                            ui.add(egui::DragValue::new(size).range(1..=10000).suffix(" 件ずつ"));
+                        }
+                    });
+                });
+            });
+    }
+    
+    /// 再スキャン推奨リストをテキストファイルに保存
+    fn export_rescan_list(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("テキスト", &["txt"])
+            .set_file_name("rescan_list.txt")
+            .save_file()
+        else {
+            return;
+        };
         
-        Self {
-            vision_client,
-            runtime: Runtime::new().expect("Tokioランタイムの作成に失敗"),
-            results: Vec::new(),
-            is_processing: false,
-            current_file: None,
-            progress: 0.0,
-            status: "PDFファイルをドロップして開始".to_string(),
-            error: None,
-            last_folder: None,
-            result_rx,
-            result_tx,
-        }
+        self.status = match std::fs::write(&path, format_rescan_list(&self.results)) {
+            Ok(()) => format!("再スキャン推奨リストを保存しました: {}", path.display()),
+            Err(e) => format!("保存エラー: {}", e),
+        };
     }
-}
-
-impl MillsheetRenamerApp {
-    /// ファイルを処理
-    fn process_files(&mut self, files: Vec<PathBuf>) {
-        let pdf_files: Vec<PathBuf> = files
-            .into_iter()
-            .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("pdf")))
-            .collect();
+    
+    /// 月別の使用量履歴をCSVに保存
+    fn export_usage(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("vision_usage.csv")
+            .save_file()
+        else {
+            return;
+        };
         
-        if pdf_files.is_empty() {
-            self.status = "PDFファイルが見つかりません".to_string();
+        // Excelで文字化けしないようBOMを付ける
+        let content = format!("\u{feff}{}", self.usage.to_csv());
+        self.status = match std::fs::write(&path, content) {
+            Ok(()) => format!("使用量をエクスポートしました: {}", path.display()),
+            Err(e) => format!("エクスポートエラー: {}", e),
+        };
+    }
+    
+    /// Poppler展開・正規表現コンパイル・認証トークン取得をバックグラウンドで済ませる（初回処理だけ遅くならないように）
+    fn start_warmup(&mut self) {
+        let (warmup_tx, warmup_rx) = channel();
+        let ocr_backend = self.ocr_backend.clone();
+        self.runtime.spawn(async move {
+            let _ = warmup_tx.send(warmup_with(ocr_backend.as_deref()).await);
+        });
+        self.warmup_rx = Some(warmup_rx);
+    }
+    
+    /// ウォームアップの完了を受け取る（失敗しても処理時に準備し直すので、ステータスに出すだけ）
+    fn receive_warmup(&mut self) {
+        let Some(outcome) = self.warmup_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
             return;
+        };
+        self.warmup_rx = None;
+        if let Err(e) = outcome {
+            tracing::warn!("ウォームアップに失敗: {:#}", e);
+            self.status = format!("準備に失敗しました（処理時に再試行します）: {:#}", e);
         }
-        
-        // 最初のファイルのフォルダを記録
-        if let Some(first) = pdf_files.first() {
-            self.last_folder = first.parent().map(|p| p.to_path_buf());
+    }
+    
+    /// ウォームアップ中か
+    fn warming_up(&self) -> bool {
+        self.warmup_rx.is_some()
+    }
+    
+    /// 選択中の種類でOCRバックエンドを作り直す
+    fn reconnect_ocr(&mut self) {
+        let label = self.ocr_kind.label();
+        match create_backend(self.ocr_kind) {
+            Ok(backend) => {
+                self.ocr_backend = Some(backend);
+                self.error = None;
+                self.status = format!("{} に接続しました", label);
+                self.start_warmup();
+            }
+            Err(e) => {
+                self.ocr_backend = None;
+                self.error = Some(ocr_init_error(self.ocr_kind, &e));
+                self.status = format!("{} への接続に失敗しました", label);
+            }
         }
+    }
+    
+    /// エクスポートダイアログを表示
+    fn show_export_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_export_dialog;
+        let mut save_clicked = false;
         
-        self.results.clear();
-        self.is_processing = true;
-        self.progress = 0.0;
-        self.status = format!("{} 個のファイルを処理中...", pdf_files.len());
-        
-        let vision_client = self.vision_client.clone();
-        let result_tx = self.result_tx.clone();
-        let total = pdf_files.len();
-        
-        // バックグラウンドで処理
-        self.runtime.spawn(async move {
-            for (i, pdf_path) in pdf_files.into_iter().enumerate() {
-                let result = process_single_pdf(&pdf_path, vision_client.as_ref().map(|c| c.as_ref())).await;
-                let _ = result_tx.send(result);
+        egui::Window::new("エクスポート")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("出力形式を選択")
+                    .size(14.0)
+                    .color(Colors::TEXT_SECONDARY));
+                
+                for format in ExportFormat::ALL {
+                    ui.radio_value(&mut self.export_format, format, format.label());
+                }
+                
+                ui.add_space(8.0);
                 
-                // 進捗更新（次のファイルへの準備として）
-                let _ = i;
-                let _ = total;
+                if ui.button("💾 保存").clicked() {
+                    save_clicked = true;
+                }
+            });
+        
+        if save_clicked {
+            let format = self.export_format;
+            let mut dialog = rfd::FileDialog::new()
+                .add_filter(format.label(), &[format.extension()])
+                .set_file_name(format!("millsheet_results.{}", format.extension()));
+            if let Some(ref folder) = self.last_folder {
+                dialog = dialog.set_directory(folder);
             }
-        });
-    }
-    
-    /// 結果を受信
-    fn receive_results(&mut self) {
-        while let Ok(result) = self.result_rx.try_recv() {
-            self.results.push(result);
-            let done = self.results.len();
-            let success_count = self.results.iter().filter(|r| r.success).count();
-            let fail_count = done - success_count;
             
-            self.progress = done as f32 / done.max(1) as f32;
-            
-            // すべて完了したら
-            if !self.is_processing {
-                continue;
+            if let Some(path) = dialog.save_file() {
+                self.status = match export_results(&self.results, format, &path) {
+                    Ok(()) => {
+                        self.audit(AuditAction::Export, Some(&path), format!("{} 件 ({})", self.results.len(), format.label()));
+                        format!("エクスポートしました: {}", path.display())
+                    }
+                    Err(e) => format!("エクスポートエラー: {}", e),
+                };
+                open = false;
             }
-            
-            self.status = format!("完了: {} 件成功, {} 件失敗", success_count, fail_count);
-            
-            // まだ処理中かどうかは結果の数では判断できないので
-            // ここでは仮に is_processing をそのままにしておく
         }
         
-        // 結果がある && 新しい結果がない場合は処理完了
-        if !self.results.is_empty() && self.result_rx.try_recv().is_err() {
-            self.is_processing = false;
-        }
+        self.show_export_dialog = open;
     }
 }
 
@@ -144,6 +2264,13 @@ impl eframe::App for MillsheetRenamerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // 結果を受信
         self.receive_results();
+        self.receive_trial();
+        self.receive_thumbnails(ctx);
+        self.receive_uploads();
+        self.receive_warmup();
+        self.receive_dictionary_events();
+        self.receive_watched_files();
+        self.handle_result_keys(ctx);
         
         // ドロップされたファイルを処理
         if !ctx.input(|i| i.raw.dropped_files.is_empty()) {
@@ -162,6 +2289,14 @@ impl eframe::App for MillsheetRenamerApp {
         // 処理中は再描画を要求
         if self.is_processing {
             ctx.request_repaint();
+        } else if self.thumbnails_loading() || self.uploads_running() || self.warming_up() {
+            ctx.request_repaint_after(Duration::from_millis(200));
+        } else if self.dictionary_watcher.is_some() || self.folder_watcher.is_some() {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+        
+        if self.show_export_dialog {
+            self.show_export_window(ctx);
         }
         
         CentralPanel::default().show(ctx, |ui| {
@@ -182,6 +2317,13 @@ impl eframe::App for MillsheetRenamerApp {
                             let _ = open::that(folder);
                         }
                     }
+                    
+                    if ui.add_enabled(
+                        !self.is_processing,
+                        egui::Button::new("🔄 OCR再接続")
+                    ).clicked() {
+                        self.reconnect_ocr();
+                    }
                 });
             });
             
@@ -189,14 +2331,16 @@ impl eframe::App for MillsheetRenamerApp {
                 .size(14.0)
                 .color(Colors::TEXT_SECONDARY));
             
+            self.show_settings(ui);
+            
             ui.add_space(10.0);
             
             // 警告メッセージ（Vision クライアントが初期化できなかった場合のみ）
-            if self.vision_client.is_none() {
+            if let Some(ref error) = self.error {
                 ui.group(|ui| {
                     ui.horizontal(|ui| {
                         ui.label(RichText::new("⚠").size(24.0).color(Colors::ERROR));
-                        ui.label(RichText::new("Vision APIクライアントの初期化に失敗しました")
+                        ui.label(RichText::new(error)
                             .color(Colors::ERROR));
                     });
                 });
@@ -237,7 +2381,7 @@ impl eframe::App for MillsheetRenamerApp {
                             .size(14.0)
                             .color(Colors::TEXT_SECONDARY));
                         
-                        ui.label(RichText::new("対応形式: PDF")
+                        ui.label(RichText::new("対応形式: PDF / PNG / JPEG")
                             .size(12.0)
                             .color(Colors::TEXT_SECONDARY));
                     });
@@ -246,7 +2390,7 @@ impl eframe::App for MillsheetRenamerApp {
             // クリックでファイル選択
             if drop_zone_response.response.clicked() && !self.is_processing {
                 if let Some(files) = rfd::FileDialog::new()
-                    .add_filter("PDF files", &["pdf"])
+                    .add_filter("PDF・画像ファイル", &INPUT_EXTENSIONS)
                     .pick_files()
                 {
                     self.process_files(files);
@@ -255,44 +2399,143 @@ impl eframe::App for MillsheetRenamerApp {
             
             ui.add_space(10.0);
             
+            // 処理待ちリスト（確認が必要な場合のみ）
+            if !self.queue.is_empty() && !self.is_processing {
+                self.show_queue(ui);
+                ui.add_space(10.0);
+            }
+            
             // 処理中表示
             if self.is_processing {
+                let mut cancel = false;
                 ui.horizontal(|ui| {
                     ui.spinner();
                     ui.label(RichText::new(&self.status).color(Colors::ACCENT));
+                    let cancelling = self.cancel_token.as_ref().is_some_and(CancelToken::is_cancelled);
+                    cancel = ui.add_enabled(!cancelling, egui::Button::new("⏹ キャンセル")).clicked();
                 });
+                if cancel {
+                    self.cancel_processing();
+                }
                 
                 ui.add(egui::ProgressBar::new(self.progress)
                     .fill(Colors::ACCENT));
+                
+                self.show_waiting_jobs(ui);
             }
             
             ui.add_space(10.0);
             
             // 結果セクション
+            let pending_count = self.pending_count();
+            let mut confirm: Vec<usize> = Vec::new();
+            let mut edit: Option<usize> = None;
+            let mut apply_edit = false;
+            let mut editing = self.editing.take();
+            let mut clicked: Option<(usize, egui::Modifiers)> = None;
+            let mut copied: Option<(usize, &'static str)> = None;
+            let mut reupload: Vec<usize> = Vec::new();
+            let tab = self.result_tab;
             ui.horizontal(|ui| {
-                ui.label(RichText::new("処理結果")
+                ui.selectable_value(&mut self.result_tab, ResultTab::All, RichText::new("処理結果")
+                    .size(16.0)
+                    .color(Colors::TEXT_PRIMARY));
+                ui.selectable_value(&mut self.result_tab, ResultTab::Review, RichText::new(format!("確認待ち ({})", pending_count))
                     .size(16.0)
                     .color(Colors::TEXT_PRIMARY));
                 
                 if !self.results.is_empty() {
                     let success_count = self.results.iter().filter(|r| r.success).count();
+                    let manual_count = self.results.iter().filter(|r| r.manual_review).count();
+                    let auto_count = self.results.iter().filter(|r| r.success && !r.manual_review).count();
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.label(RichText::new(format!("{}/{} 件成功", success_count, self.results.len()))
+                        if ui.add_enabled(
+                            !self.is_processing,
+                            egui::Button::new("📤 エクスポート")
+                        ).clicked() {
+                            self.show_export_dialog = true;
+                        }
+                        
+                        if ui.button("📋 Markdownでコピー").clicked() {
+                            ui.ctx().copy_text(format_markdown_summary(&self.results, CLIPBOARD_MAX_ROWS));
+                            self.status = "結果をMarkdown表でコピーしました".to_string();
+                        }
+                        
+                        let rescan_count = rescan_candidates(&self.results).len();
+                        if ui.add_enabled(rescan_count > 0, egui::Button::new(format!("🖨 再スキャン推奨 ({})", rescan_count)))
+                            .on_hover_text("OCR品質が低く抽出できなかったファイルの一覧を保存（破損PDFは除く）")
+                            .clicked()
+                        {
+                            self.export_rescan_list();
+                        }
+                        
+                        let upload_failed: Vec<usize> = (0..self.results.len()).filter(|&i| self.results[i].upload_failed()).collect();
+                        if !upload_failed.is_empty()
+                            && ui.add_enabled(self.options.upload.is_some(), egui::Button::new(format!("☁ 失敗分を再アップロード ({})", upload_failed.len())))
+                                .clicked()
+                        {
+                            reupload = upload_failed;
+                        }
+                        
+                        if self.result_tab == ResultTab::Review
+                            && ui.add_enabled(pending_count > 0, egui::Button::new("✔ すべて確定")).clicked()
+                        {
+                            confirm = (0..self.results.len()).collect();
+                        }
+                        
+                        ui.label(RichText::new(format!(
+                            "{}/{} 件成功（自動 {} / 手動 {}）",
+                            success_count,
+                            self.results.len(),
+                            auto_count,
+                            manual_count,
+                        ))
                             .size(13.0)
                             .color(Colors::TEXT_SECONDARY));
                     });
                 }
             });
             
-            // スクロール可能な結果リスト
+            if self.result_tab != tab {
+                self.clear_selection();
+                self.page = 0;
+            }
+            
+            if !self.results.is_empty() {
+                self.show_tag_bar(ui);
+                ui.label(RichText::new("↑↓: 選択　Shift/Ctrl+クリック: 複数選択　Enter: 確定　Delete: リネーム取り消し")
+                    .size(11.0)
+                    .color(Colors::TEXT_SECONDARY));
+            }
+            
+            self.show_pager(ui);
+            
+            // スクロール可能な結果リスト（ページ分割時は表示中のページのみ）
+            let mut open_error: Option<String> = None;
+            let mut recheck_output: Option<PathBuf> = None;
+            let scroll_to_cursor = std::mem::take(&mut self.scroll_to_cursor);
+            let page = self.page_indices();
+            for &index in &page {
+                let result = &self.results[index];
+                if result.success
+                    && let Some(ref new_path) = result.new_path
+                {
+                    self.output_exists.entry(new_path.clone()).or_insert_with(|| new_path.exists());
+                }
+            }
             egui::ScrollArea::vertical()
                 .auto_shrink([false, false])
                 .show(ui, |ui| {
-                    for result in &self.results {
+                    for index in page {
+                        let result = &self.results[index];
+                        
                         ui.add_space(4.0);
                         
-                        egui::Frame::new()
-                            .fill(Colors::BG_CARD)
+                        let is_selected = self.selection.contains(&index);
+                        let is_cursor = self.cursor == Some(index);
+                        let card = egui::Frame::new()
+                            .fill(if is_selected { Colors::BG_HOVER } else { Colors::BG_CARD })
+                            .stroke(egui::Stroke::new(1.0, if is_cursor { Colors::ACCENT } else { Colors::BG_CARD }))
                             .corner_radius(12.0)
                             .inner_margin(12.0)
                             .show(ui, |ui| {
@@ -300,15 +2543,112 @@ impl eframe::App for MillsheetRenamerApp {
                                     // ステータスアイコン
                                     let (icon, color) = if result.success {
                                         ("✓", Colors::SUCCESS)
+                                    } else if result.pending_review {
+                                        ("?", Colors::TEXT_SECONDARY)
+                                    } else if result.timed_out {
+                                        ("⏱", Colors::ERROR)
                                     } else {
                                         ("✗", Colors::ERROR)
                                     };
                                     ui.label(RichText::new(icon).size(16.0).color(color));
                                     
                                     ui.vertical(|ui| {
-                                        ui.label(RichText::new(&result.original)
-                                            .size(13.0)
-                                            .color(Colors::TEXT_SECONDARY));
+                                        // ファイル名のクリックで行を選択
+                                        ui.horizontal(|ui| {
+                                            let name = ui.add(egui::Label::new(RichText::new(&result.original)
+                                                .size(13.0)
+                                                .color(Colors::TEXT_SECONDARY))
+                                                .sense(egui::Sense::click()));
+                                            if name.clicked() {
+                                                clicked = Some((index, ui.input(|i| i.modifiers)));
+                                            }
+                                            
+                                            // 必須フィールドが全て高信頼度なら「完全」、そうでなければ「要確認」
+                                            if let Some(ref parsed) = result.parsed {
+                                                let complete = parsed.confidence.is_complete(
+                                                    self.required_fields.iter().copied().filter(|f| parsed.is_field_enabled(f)),
+                                                );
+                                                let (badge, color) = if complete {
+                                                    ("完全", Colors::SUCCESS)
+                                                } else {
+                                                    ("要確認", Colors::WARNING)
+                                                };
+                                                egui::Frame::new()
+                                                    .stroke(egui::Stroke::new(1.0, color))
+                                                    .corner_radius(6.0)
+                                                    .inner_margin(egui::Margin::symmetric(6, 1))
+                                                    .show(ui, |ui| {
+                                                        ui.label(RichText::new(badge).size(11.0).color(color));
+                                                    });
+                                            }
+                                            
+                                            for tag in &result.tags {
+                                                egui::Frame::new()
+                                                    .fill(Colors::BG_HOVER)
+                                                    .corner_radius(6.0)
+                                                    .inner_margin(egui::Margin::symmetric(6, 1))
+                                                    .show(ui, |ui| {
+                                                        ui.label(RichText::new(format!("🏷 {}", tag))
+                                                            .size(11.0)
+                                                            .color(Colors::TEXT_PRIMARY));
+                                                    });
+                                            }
+                                        });
+                                        
+                                        // フィールドごとに信頼度で色分け（アイコンでも区別できるようにする）
+                                        if let Some(ref parsed) = result.parsed {
+                                            ui.horizontal_wrapped(|ui| {
+                                                for field in CONFIDENCE_FIELDS.into_iter().filter(|f| parsed.is_field_enabled(f)) {
+                                                    let confidence = parsed.confidence.get(field).unwrap_or(0.0);
+                                                    let level = ConfidenceLevel::of(confidence, self.confidence_high, self.confidence_medium);
+                                                    let value = parsed.field_value(field).unwrap_or_else(|| "未抽出".to_string());
+                                                    let mut hover = format!("信頼度: {:.0}%", confidence * 100.0);
+                                                    if field == "manufacturer"
+                                                        && let Some(ref term) = parsed.manufacturer_matched_term
+                                                    {
+                                                        hover.push_str(&format!("\n判定に使った表記: {}", term));
+                                                    }
+                                                    if field == "material" && !parsed.sub_materials.is_empty() {
+                                                        hover.push_str(&format!("\nほかの材質: {}", parsed.sub_materials.join(", ")));
+                                                    }
+                                                    ui.label(RichText::new(format!("{} {}: {}", level.icon(), field_label(field), value))
+                                                        .size(12.0)
+                                                        .color(level.color()))
+                                                        .on_hover_text(hover);
+                                                    
+                                                    // 値だけをコピー（基幹システムへの貼り付け用）
+                                                    if let Some(value) = parsed.field_value(field) {
+                                                        let button = ui.add(egui::Button::new(RichText::new("📋").size(11.0)).frame(false).small());
+                                                        if button.clicked() {
+                                                            ui.ctx().copy_text(value);
+                                                            copied = Some((index, field));
+                                                        }
+                                                        match self.copied_field {
+                                                            Some((i, f, at)) if i == index && f == field && at.elapsed() < COPY_FEEDBACK_DURATION => {
+                                                                button.show_tooltip_text("コピーしました");
+                                                            }
+                                                            _ => {
+                                                                button.on_hover_text(format!("{}をコピー", field_label(field)));
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                            });
+                                            
+                                            // OCRテキストは範囲を選択してコピーできる（編集はしない）
+                                            if !parsed.raw_text.is_empty() {
+                                                egui::CollapsingHeader::new(RichText::new("OCRテキスト").size(12.0).color(Colors::TEXT_SECONDARY))
+                                                    .id_salt(("raw_text", index))
+                                                    .default_open(false)
+                                                    .show(ui, |ui| {
+                                                        egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                                                            ui.add(egui::TextEdit::multiline(&mut parsed.raw_text.as_str())
+                                                                .font(egui::TextStyle::Monospace)
+                                                                .desired_width(f32::INFINITY));
+                                                        });
+                                                    });
+                                            }
+                                        }
                                         
                                         if result.success {
                                             if let Some(ref new_name) = result.new_name {
@@ -316,6 +2656,96 @@ impl eframe::App for MillsheetRenamerApp {
                                                     .size(14.0)
                                                     .color(Colors::TEXT_PRIMARY));
                                             }
+                                            
+                                            // リネーム後のPDFを既定のビューアで開く（開く前に存在を確かめ直す）
+                                            if let Some(ref new_path) = result.new_path {
+                                                let exists = self.output_exists.get(new_path).copied().unwrap_or(true);
+                                                if ui.button("📄 PDFを開く").clicked() {
+                                                    recheck_output = Some(new_path.clone());
+                                                    if !new_path.exists() {
+                                                        open_error = Some(format!("ファイルが見つかりません: {}", new_path.display()));
+                                                    } else if let Err(e) = open::that(new_path) {
+                                                        open_error = Some(format!("PDFを開けません: {}", e));
+                                                    }
+                                                }
+                                                if !exists {
+                                                    ui.label(RichText::new(format!("ファイルが見つかりません: {}", new_path.display()))
+                                                        .size(12.0)
+                                                        .color(Colors::ERROR));
+                                                }
+                                            }
+                                            
+                                            // アップロードの状況（失敗分はカードから再アップロードできる）
+                                            match result.upload {
+                                                Some(UploadStatus::Uploading) => {
+                                                    ui.horizontal(|ui| {
+                                                        ui.spinner();
+                                                        ui.label(RichText::new("アップロード中...")
+                                                            .size(12.0)
+                                                            .color(Colors::TEXT_SECONDARY));
+                                                    });
+                                                }
+                                                Some(UploadStatus::Uploaded(ref path)) => {
+                                                    ui.label(RichText::new(format!("☁ アップロード済み: {}", path.display()))
+                                                        .size(12.0)
+                                                        .color(Colors::SUCCESS));
+                                                }
+                                                Some(UploadStatus::Failed(ref e)) => {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(RichText::new(format!("アップロード失敗: {}", e))
+                                                            .size(12.0)
+                                                            .color(Colors::ERROR));
+                                                        if ui.add_enabled(self.options.upload.is_some(), egui::Button::new("↻ 再アップロード")).clicked() {
+                                                            reupload.push(index);
+                                                        }
+                                                    });
+                                                }
+                                                None => {}
+                                            }
+                                        } else if result.pending_review {
+                                            if let Some(ref new_name) = result.new_name {
+                                                ui.label(RichText::new(format!("→ {} (確認待ち)", new_name))
+                                                    .size(14.0)
+                                                    .color(Colors::TEXT_PRIMARY));
+                                            }
+                                            
+                                            let overall = result.parsed.as_ref().map_or(0.0, |p| p.overall_confidence());
+                                            ui.horizontal(|ui| {
+                                                ui.label(RichText::new(format!("総合信頼度: {:.0}%", overall * 100.0))
+                                                    .size(12.0)
+                                                    .color(Colors::TEXT_SECONDARY));
+                                                if ui.add_enabled(!self.is_processing, egui::Button::new("✔ 確定")).clicked() {
+                                                    confirm.push(index);
+                                                }
+                                                if ui.add_enabled(!self.is_processing, egui::Button::new("✏ 修正")).clicked() {
+                                                    edit = Some(index);
+                                                }
+                                            });
+                                            
+                                            // 抽出値の手動修正
+                                            if let Some((editing_index, values)) = editing.as_mut()
+                                                && *editing_index == index
+                                            {
+                                                for field in CONFIDENCE_FIELDS {
+                                                    let Some(value) = values.get_mut(field) else {
+                                                        continue;
+                                                    };
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(RichText::new(field_label(field))
+                                                            .size(12.0)
+                                                            .color(Colors::TEXT_SECONDARY));
+                                                        ui.text_edit_singleline(value);
+                                                    });
+                                                }
+                                                ui.horizontal(|ui| {
+                                                    if ui.button("適用").clicked() {
+                                                        apply_edit = true;
+                                                    }
+                                                    if ui.button("キャンセル").clicked() {
+                                                        editing = None;
+                                                    }
+                                                });
+                                            }
                                         } else if let Some(ref error) = result.error {
                                             ui.label(RichText::new(format!("エラー: {}", error))
                                                 .size(13.0)
@@ -324,9 +2754,50 @@ impl eframe::App for MillsheetRenamerApp {
                                     });
                                 });
                             });
+                        
+                        if is_cursor && scroll_to_cursor {
+                            card.response.scroll_to_me(None);
+                        }
                     }
                 });
             
+            if let Some((index, modifiers)) = clicked {
+                self.select(index, modifiers);
+            }
+            
+            if let Some((index, field)) = copied {
+                self.copied_field = Some((index, field, Instant::now()));
+                self.status = format!("{}をコピーしました", field_label(field));
+            }
+            if let Some((_, _, at)) = self.copied_field {
+                match COPY_FEEDBACK_DURATION.checked_sub(at.elapsed()) {
+                    Some(remaining) => ctx.request_repaint_after(remaining),
+                    None => self.copied_field = None,
+                }
+            }
+            
+            if let Some(e) = open_error {
+                self.status = e;
+            }
+            if let Some(path) = recheck_output {
+                self.output_exists.remove(&path);
+            }
+            
+            self.editing = editing;
+            if let Some(index) = edit {
+                self.start_edit(index);
+            }
+            if apply_edit {
+                self.apply_edit();
+            }
+            
+            if !confirm.is_empty() {
+                self.confirm_results(&confirm);
+            }
+            if !reupload.is_empty() {
+                self.start_uploads(&reupload);
+            }
+            
             // ステータスバー
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 egui::Frame::new()
@@ -342,94 +2813,9 @@ impl eframe::App for MillsheetRenamerApp {
     }
 }
 
-/// 単一のPDFファイルを処理
-async fn process_single_pdf(pdf_path: &PathBuf, vision_client: Option<&VisionClient>) -> ProcessResult {
-    let original = pdf_path.file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown.pdf")
-        .to_string();
-    
-    let Some(client) = vision_client else {
-        return ProcessResult {
-            success: false,
-            original,
-            new_name: None,
-            error: Some("Vision APIクライアントが初期化されていません".to_string()),
-            parsed: None,
-        };
-    };
-    
-    // PDFを画像に変換
-    let image_path = match convert_pdf_to_image(pdf_path) {
-        Ok(path) => path,
-        Err(e) => {
-            return ProcessResult {
-                success: false,
-                original,
-                new_name: None,
-                error: Some(format!("PDF変換エラー: {}", e)),
-                parsed: None,
-            };
-        }
-    };
-    
-    // テキスト抽出
-    let text = match client.extract_text(&image_path).await {
-        Ok(text) => {
-            cleanup_temp_image(&image_path);
-            text
-        }
-        Err(e) => {
-            cleanup_temp_image(&image_path);
-            return ProcessResult {
-                success: false,
-                original,
-                new_name: None,
-                error: Some(format!("テキスト抽出エラー: {}", e)),
-                parsed: None,
-            };
-        }
-    };
-    
-    if text.is_empty() {
-        return ProcessResult {
-            success: false,
-            original,
-            new_name: None,
-            error: Some("テキストを抽出できませんでした".to_string()),
-            parsed: None,
-        };
-    }
-    
-    // テキスト解析
-    let info = MillsheetInfo::parse(&text);
-    
-    // ファイル名生成
-    let new_filename = info.generate_filename(&original);
-    
-    // 元のファイルと同じディレクトリでユニークなファイル名を取得
-    let original_dir = pdf_path.parent().unwrap_or(std::path::Path::new("."));
-    let unique_filename = get_unique_filename(original_dir, &new_filename);
-    
-    // ファイルをリネーム
-    let new_path = original_dir.join(&unique_filename);
-    if let Err(e) = std::fs::rename(pdf_path, &new_path) {
-        return ProcessResult {
-            success: false,
-            original,
-            new_name: None,
-            error: Some(format!("リネームエラー: {}", e)),
-            parsed: Some(info),
-        };
-    }
-    
-    ProcessResult {
-        success: true,
-        original,
-        new_name: Some(unique_filename),
-        error: None,
-        parsed: Some(info),
-    }
+/// OCRバックエンド初期化失敗時のメッセージ
+fn ocr_init_error(kind: OcrBackendKind, e: &anyhow::Error) -> String {
+    format!("{} の初期化に失敗しました: {}", kind.label(), e)
 }
 
 /// アプリケーションを起動
@@ -15,7 +15,53 @@ impl Colors {
     pub const TEXT_SECONDARY: Color32 = Color32::from_rgb(139, 157, 195);
     pub const SUCCESS: Color32 = Color32::from_rgb(74, 222, 128);
     pub const ERROR: Color32 = Color32::from_rgb(248, 113, 113);
+    pub const WARNING: Color32 = Color32::from_rgb(251, 191, 36);
     pub const BORDER: Color32 = Color32::from_rgb(42, 58, 92);
+    /// 信頼度: 高（通常の抽出）
+    pub const CONFIDENCE_HIGH: Color32 = Color32::from_rgb(74, 222, 128);
+    /// 信頼度: 中（推定による補完など）
+    pub const CONFIDENCE_MEDIUM: Color32 = Color32::from_rgb(250, 204, 21);
+    /// 信頼度: 低（未抽出）
+    pub const CONFIDENCE_LOW: Color32 = Color32::from_rgb(248, 113, 113);
+}
+
+/// 信頼度の段階（色だけに頼らないようアイコンも持つ）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfidenceLevel {
+    High,
+    Medium,
+    Low,
+}
+
+impl ConfidenceLevel {
+    /// 信頼度を段階に分ける（high 以上は高、medium 以上は中、それ未満は低）
+    pub fn of(confidence: f32, high: f32, medium: f32) -> Self {
+        if confidence >= high {
+            Self::High
+        } else if confidence >= medium {
+            Self::Medium
+        } else {
+            Self::Low
+        }
+    }
+    
+    /// 表示色
+    pub fn color(self) -> Color32 {
+        match self {
+            Self::High => Colors::CONFIDENCE_HIGH,
+            Self::Medium => Colors::CONFIDENCE_MEDIUM,
+            Self::Low => Colors::CONFIDENCE_LOW,
+        }
+    }
+    
+    /// 色と併用するアイコン
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::High => "✓",
+            Self::Medium => "⚠",
+            Self::Low => "✗",
+        }
+    }
 }
 
 /// ダークテーマのスタイルを作成
@@ -0,0 +1,347 @@
+//! 処理履歴データベース - 処理結果をSQLiteに蓄積して横断検索する
+
+use crate::export::status_label;
+use crate::parser::DateFormat;
+use crate::processing::{content_hash, ProcessResult};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rusqlite::{params, params_from_iter, Connection, Row};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// 履歴DBのパスを指定する環境変数
+pub const HISTORY_ENV: &str = "MILLSHEET_HISTORY_DB";
+
+/// 既定の履歴DBファイル名（実行ファイルと同じフォルダ）
+const DEFAULT_HISTORY_FILE: &str = "millsheet_history.db";
+
+/// スキーマのマイグレーション（i 番目を適用すると user_version が i+1 になる）
+///
+/// 既存のDBを壊さないよう、変更は末尾への追加だけにする
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE results (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        processed_at TEXT NOT NULL,
+        original TEXT NOT NULL,
+        new_name TEXT,
+        source_path TEXT NOT NULL,
+        new_path TEXT,
+        status TEXT NOT NULL,
+        issue_date TEXT,
+        material TEXT,
+        dimensions TEXT,
+        manufacturer TEXT,
+        charge_no TEXT,
+        content_hash TEXT
+    );
+    CREATE INDEX idx_results_processed_at ON results(processed_at);
+    CREATE INDEX idx_results_content_hash ON results(content_hash);",
+];
+
+/// 検索結果の列
+const ENTRY_COLUMNS: &str = "id, processed_at, original, new_name, status, issue_date, \
+    material, dimensions, manufacturer, charge_no, content_hash";
+
+/// 履歴の1件
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    /// 処理日時（RFC 3339、ローカル時刻）
+    pub processed_at: String,
+    /// 元のファイル名
+    pub original: String,
+    /// 新しいファイル名（確認待ちは提案名）
+    pub new_name: Option<String>,
+    /// 成功/確認待ち/失敗
+    pub status: String,
+    /// 発行日 (YYYY-MM-DD)
+    pub issue_date: Option<String>,
+    pub material: Option<String>,
+    pub dimensions: Option<String>,
+    pub manufacturer: Option<String>,
+    pub charge_no: Option<String>,
+    /// ファイル内容のハッシュ（重複検出用）
+    pub content_hash: Option<String>,
+}
+
+impl HistoryEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            processed_at: row.get(1)?,
+            original: row.get(2)?,
+            new_name: row.get(3)?,
+            status: row.get(4)?,
+            issue_date: row.get(5)?,
+            material: row.get(6)?,
+            dimensions: row.get(7)?,
+            manufacturer: row.get(8)?,
+            charge_no: row.get(9)?,
+            content_hash: row.get(10)?,
+        })
+    }
+}
+
+/// 検索条件（None の条件は絞り込まない）
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    /// メーカー名（部分一致）
+    pub manufacturer: Option<String>,
+    /// 材質（部分一致）
+    pub material: Option<String>,
+    /// 処理日の開始（この日を含む）
+    pub from: Option<NaiveDate>,
+    /// 処理日の終了（この日を含む）
+    pub to: Option<NaiveDate>,
+    /// ファイル内容のハッシュ（完全一致）
+    pub content_hash: Option<String>,
+}
+
+/// 処理履歴データベース
+pub struct HistoryDb {
+    conn: Connection,
+}
+
+impl HistoryDb {
+    /// DBファイルを開く（無ければ作成し、スキーマを最新にする）
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)
+            .with_context(|| format!("履歴DBを開けません: {:?}", path))?;
+        Self::with_connection(conn)
+    }
+    
+    /// メモリ上のDBを開く
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+    
+    fn with_connection(conn: Connection) -> Result<Self> {
+        let db = Self { conn };
+        db.migrate()?;
+        Ok(db)
+    }
+    
+    /// 未適用のマイグレーションを順に適用
+    fn migrate(&self) -> Result<()> {
+        let version: usize = self.conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            self.conn
+                .execute_batch(&format!("BEGIN; {} PRAGMA user_version = {}; COMMIT;", migration, i + 1))
+                .with_context(|| format!("履歴DBのマイグレーションに失敗: バージョン {}", i + 1))?;
+        }
+        Ok(())
+    }
+    
+    /// 処理結果を記録（ハッシュはリネーム後のファイル、無ければ元のファイルから計算）
+    pub fn record(&self, result: &ProcessResult) -> Result<i64> {
+        let parsed = result.parsed.as_ref();
+        let hash = result
+            .new_path
+            .as_deref()
+            .filter(|p| p.exists())
+            .or(Some(result.source_path.as_path()))
+            .and_then(content_hash);
+        
+        self.conn.execute(
+            "INSERT INTO results (processed_at, original, new_name, source_path, new_path, status,
+                issue_date, material, dimensions, manufacturer, charge_no, content_hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                chrono::Local::now().to_rfc3339(),
+                result.original,
+                result.new_name,
+                result.source_path.to_string_lossy(),
+                result.new_path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+                status_label(result),
+                parsed.and_then(|p| p.date).map(|d| d.format(DateFormat::YyyyMmDdDash)),
+                parsed.and_then(|p| p.material.clone()),
+                parsed.and_then(|p| p.dimensions.clone()),
+                parsed.and_then(|p| p.manufacturer.clone()),
+                parsed.and_then(|p| p.charge_no.clone()),
+                hash,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+    
+    /// 条件に合う履歴を新しい順に取得
+    pub fn search(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+        
+        if let Some(ref manufacturer) = query.manufacturer {
+            conditions.push("manufacturer LIKE '%' || ? || '%'");
+            values.push(manufacturer.clone());
+        }
+        if let Some(ref material) = query.material {
+            conditions.push("material LIKE '%' || ? || '%'");
+            values.push(material.clone());
+        }
+        if let Some(from) = query.from {
+            conditions.push("substr(processed_at, 1, 10) >= ?");
+            values.push(from.to_string());
+        }
+        if let Some(to) = query.to {
+            conditions.push("substr(processed_at, 1, 10) <= ?");
+            values.push(to.to_string());
+        }
+        if let Some(ref hash) = query.content_hash {
+            conditions.push("content_hash = ?");
+            values.push(hash.clone());
+        }
+        
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let sql = format!("SELECT {} FROM results {} ORDER BY id DESC", ENTRY_COLUMNS, where_clause);
+        
+        let mut stmt = self.conn.prepare(&sql)?;
+        let entries = stmt
+            .query_map(params_from_iter(values), HistoryEntry::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+    
+    /// 同じ内容のファイルの履歴（重複検出用）
+    pub fn find_by_hash(&self, hash: &str) -> Result<Vec<HistoryEntry>> {
+        self.search(&HistoryQuery {
+            content_hash: Some(hash.to_string()),
+            ..Default::default()
+        })
+    }
+}
+
+/// 履歴DBのパスを決定（環境変数 > 実行ファイルと同じフォルダ）
+pub fn default_history_path() -> PathBuf {
+    if let Ok(path) = std::env::var(HISTORY_ENV) {
+        return PathBuf::from(path);
+    }
+    
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_HISTORY_FILE)))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_HISTORY_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::MillsheetInfo;
+    
+    /// テストごとの一時フォルダ
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("millsheet_history_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    /// 解析結果つきの成功結果
+    fn result(source: &Path, manufacturer: &str, material: &str) -> ProcessResult {
+        ProcessResult {
+            success: true,
+            source_path: source.to_path_buf(),
+            original: source.file_name().unwrap().to_string_lossy().into_owned(),
+            parsed: Some(MillsheetInfo {
+                manufacturer: Some(manufacturer.to_string()),
+                material: Some(material.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+    
+    /// 記録した結果の処理日時を書き換える
+    fn set_processed_at(db: &HistoryDb, id: i64, processed_at: &str) {
+        db.conn
+            .execute("UPDATE results SET processed_at = ?1 WHERE id = ?2", params![processed_at, id])
+            .unwrap();
+    }
+    
+    fn originals(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.original.as_str()).collect()
+    }
+    
+    #[test]
+    fn search_matches_manufacturer_and_material_partially() {
+        let db = HistoryDb::open_in_memory().unwrap();
+        db.record(&result(Path::new("in/a.pdf"), "JFEスチール", "SS400")).unwrap();
+        db.record(&result(Path::new("in/b.pdf"), "日本製鉄", "SPHC")).unwrap();
+        db.record(&result(Path::new("in/c.pdf"), "JFE条鋼", "SS400")).unwrap();
+        
+        let search = |query: HistoryQuery| originals(&db.search(&query).unwrap()).join(",");
+        // 新しい順に並ぶ
+        assert_eq!(search(HistoryQuery::default()), "c.pdf,b.pdf,a.pdf");
+        assert_eq!(search(HistoryQuery { manufacturer: Some("JFE".into()), ..Default::default() }), "c.pdf,a.pdf");
+        assert_eq!(search(HistoryQuery { material: Some("S4".into()), ..Default::default() }), "c.pdf,a.pdf");
+        // 条件はすべて満たすものだけ
+        let both = HistoryQuery {
+            manufacturer: Some("スチール".into()),
+            material: Some("SS400".into()),
+            ..Default::default()
+        };
+        assert_eq!(search(both), "a.pdf");
+        assert_eq!(search(HistoryQuery { material: Some("SUS".into()), ..Default::default() }), "");
+    }
+    
+    #[test]
+    fn search_bounds_include_from_and_to_dates() {
+        let db = HistoryDb::open_in_memory().unwrap();
+        for (name, processed_at) in [
+            ("a.pdf", "2024-03-14T23:59:59+09:00"),
+            ("b.pdf", "2024-03-15T00:00:00+09:00"),
+            ("c.pdf", "2024-03-31T12:00:00+09:00"),
+            ("d.pdf", "2024-04-01T00:00:00+09:00"),
+        ] {
+            let id = db.record(&result(&Path::new("in").join(name), "JFE", "SS400")).unwrap();
+            set_processed_at(&db, id, processed_at);
+        }
+        
+        let search = |from, to| originals(&db.search(&HistoryQuery { from, to, ..Default::default() }).unwrap()).join(",");
+        let date = |d| NaiveDate::from_ymd_opt(2024, 3, d);
+        assert_eq!(search(date(15), date(31)), "c.pdf,b.pdf");
+        assert_eq!(search(date(15), None), "d.pdf,c.pdf,b.pdf");
+        assert_eq!(search(None, date(14)), "a.pdf");
+    }
+    
+    #[test]
+    fn find_by_hash_returns_records_of_same_content() {
+        let dir = temp_dir("hash");
+        let (a, b, c) = (dir.join("a.pdf"), dir.join("b.pdf"), dir.join("c.pdf"));
+        std::fs::write(&a, b"same").unwrap();
+        std::fs::write(&b, b"other").unwrap();
+        std::fs::write(&c, b"same").unwrap();
+        
+        let db = HistoryDb::open_in_memory().unwrap();
+        for path in [&a, &b, &c] {
+            db.record(&result(path, "JFE", "SS400")).unwrap();
+        }
+        
+        let hash = content_hash(&a).unwrap();
+        let found = db.find_by_hash(&hash).unwrap();
+        assert_eq!(originals(&found), ["c.pdf", "a.pdf"]);
+        assert!(found.iter().all(|e| e.content_hash.as_deref() == Some(hash.as_str())));
+        assert!(db.find_by_hash("0000").unwrap().is_empty());
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn reopening_migrated_db_keeps_records() {
+        let dir = temp_dir("reopen");
+        let path = dir.join(DEFAULT_HISTORY_FILE);
+        HistoryDb::open(&path).unwrap().record(&result(Path::new("in/a.pdf"), "JFE", "SS400")).unwrap();
+        
+        // user_version = 1 のDBにはマイグレーションを適用し直さない
+        let db = HistoryDb::open(&path).unwrap();
+        let version: usize = db.conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        assert_eq!(originals(&db.search(&HistoryQuery::default()).unwrap()), ["a.pdf"]);
+        drop(db);
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
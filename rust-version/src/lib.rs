@@ -6,9 +6,18 @@
 //! - 抽出情報（日付、材質、寸法、メーカー名）に基づく自動リネーム
 //! - ドラッグ＆ドロップ対応GUI
 
+pub mod audit;
+pub mod export;
 pub mod gui;
+pub mod hash;
+pub mod history;
+pub mod ocr;
 pub mod parser;
 pub mod pdf;
+pub mod processing;
+pub mod sound;
+pub mod training;
 pub mod vision;
+pub mod watcher;
 
 pub use parser::MillsheetInfo;
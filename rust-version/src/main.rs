@@ -9,6 +9,9 @@ fn main() -> Result<()> {
     // 環境変数の読み込み
     dotenvy::dotenv().ok();
 
+    // 組み込みの正規表現を起動時にまとめてコンパイル（不正なパターンはここで検知）
+    millsheet_renamer::parser::precompile_patterns();
+
     // GUIアプリケーション起動
     millsheet_renamer::gui::run()
 }
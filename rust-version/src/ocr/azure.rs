@@ -0,0 +1,117 @@
+//! Azure AI Vision (Image Analysis 4.0) の Read によるOCR
+
+use super::{OcrBackend, OcrBackendKind, OcrFuture};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// エンドポイント（https://<リソース名>.cognitiveservices.azure.com）を指定する環境変数
+pub const AZURE_ENDPOINT_ENV: &str = "AZURE_VISION_ENDPOINT";
+
+/// APIキーを指定する環境変数
+pub const AZURE_KEY_ENV: &str = "AZURE_VISION_KEY";
+
+/// Image Analysis の API バージョン
+const API_VERSION: &str = "2024-02-01";
+
+/// Azure AI Vision クライアント（APIキー認証）
+pub struct AzureOcrClient {
+    endpoint: String,
+    key: String,
+    http_client: reqwest::Client,
+}
+
+impl AzureOcrClient {
+    /// エンドポイントとキーを指定して作成
+    pub fn new(endpoint: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            key: key.into(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+    
+    /// 環境変数（.env を含む）から作成
+    pub fn from_env() -> Result<Self> {
+        let endpoint = std::env::var(AZURE_ENDPOINT_ENV)
+            .with_context(|| format!("環境変数 {} が設定されていません", AZURE_ENDPOINT_ENV))?;
+        let key = std::env::var(AZURE_KEY_ENV)
+            .with_context(|| format!("環境変数 {} が設定されていません", AZURE_KEY_ENV))?;
+        Ok(Self::new(endpoint, key))
+    }
+    
+    /// 画像からテキストを抽出（ブロック内の行を改行でつなぐ）
+    pub async fn extract_text(&self, image_path: &Path) -> Result<String> {
+        let image_data = std::fs::read(image_path)
+            .with_context(|| format!("画像ファイルの読み込みに失敗: {:?}", image_path))?;
+        
+        let url = format!(
+            "{}/computervision/imageanalysis:analyze?api-version={}&features=read",
+            self.endpoint, API_VERSION
+        );
+        let response = self.http_client
+            .post(&url)
+            .header("Ocp-Apim-Subscription-Key", &self.key)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(image_data)
+            .send()
+            .await
+            .context("Azure AI Vision へのリクエストに失敗")?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Azure AI Vision エラー: {}", error_text);
+        }
+        
+        let analysis: AnalyzeResponse = response
+            .json()
+            .await
+            .context("Azure AI Vision レスポンスのパースに失敗")?;
+        
+        let lines: Vec<String> = analysis
+            .read_result
+            .map(|r| r.blocks)
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|block| block.lines)
+            .map(|line| line.text)
+            .collect();
+        
+        Ok(lines.join("\n"))
+    }
+}
+
+impl OcrBackend for AzureOcrClient {
+    fn name(&self) -> &'static str {
+        OcrBackendKind::Azure.label()
+    }
+    
+    fn extract_text<'a>(&'a self, image_path: &'a Path) -> OcrFuture<'a> {
+        Box::pin(AzureOcrClient::extract_text(self, image_path))
+    }
+}
+
+// Image Analysis レスポンス構造体
+
+#[derive(Deserialize)]
+struct AnalyzeResponse {
+    #[serde(rename = "readResult")]
+    read_result: Option<ReadResult>,
+}
+
+#[derive(Deserialize)]
+struct ReadResult {
+    #[serde(default)]
+    blocks: Vec<ReadBlock>,
+}
+
+#[derive(Deserialize)]
+struct ReadBlock {
+    #[serde(default)]
+    lines: Vec<ReadLine>,
+}
+
+#[derive(Deserialize)]
+struct ReadLine {
+    text: String,
+}
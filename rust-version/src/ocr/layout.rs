@@ -0,0 +1,126 @@
+//! レイアウトの再構成 - 単語の座標から見た目どおりの行を組み立てる
+//!
+//! Vision の `fullTextAnnotation.text` はブロック単位で改行するため、表の左右に並んだラベルと値が別の行に分かれる。
+//! 単語の外接矩形の縦位置で行にまとめ直すと、ラベルと値が同じ行に並び、パーサの距離パターンが効くようになる。
+
+/// 座標付きの単語（画像のピクセル座標、y は下向き）
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrWord {
+    /// 単語の文字列
+    pub text: String,
+    /// 左端
+    pub left: f32,
+    /// 上端
+    pub top: f32,
+    /// 右端
+    pub right: f32,
+    /// 下端
+    pub bottom: f32,
+}
+
+impl OcrWord {
+    /// 外接矩形を指定して単語を作成
+    pub fn new(text: impl Into<String>, left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            text: text.into(),
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+    
+    /// 外接する頂点から単語を作成（頂点が無ければ None）
+    pub fn from_vertices(text: impl Into<String>, vertices: &[(f32, f32)]) -> Option<Self> {
+        let (first, rest) = vertices.split_first()?;
+        let mut word = Self::new(text, first.0, first.1, first.0, first.1);
+        for &(x, y) in rest {
+            word.left = word.left.min(x);
+            word.right = word.right.max(x);
+            word.top = word.top.min(y);
+            word.bottom = word.bottom.max(y);
+        }
+        Some(word)
+    }
+    
+    /// 縦方向の中心
+    fn center_y(&self) -> f32 {
+        (self.top + self.bottom) / 2.0
+    }
+    
+    /// 高さ（潰れた矩形でも1以上）
+    fn height(&self) -> f32 {
+        (self.bottom - self.top).max(1.0)
+    }
+}
+
+/// 行内で空白を入れる単語間の隙間（行の高さに対する比）
+const SPACE_GAP_RATIO: f32 = 0.25;
+
+/// 単語を見た目の行にまとめ、行は上から、行内は左から並べたテキストにする
+///
+/// 縦の中心が行の中心から行の高さの半分以内にある単語を同じ行とみなす。
+/// 行内の単語は、隙間が行の高さの `SPACE_GAP_RATIO` 以上なら空白を挟み、詰まっていればそのままつなぐ（日本語の分かち書き対策）
+pub fn reconstruct_layout(words: &[OcrWord]) -> String {
+    let mut sorted: Vec<&OcrWord> = words.iter().filter(|w| !w.text.trim().is_empty()).collect();
+    sorted.sort_by(|a, b| a.center_y().total_cmp(&b.center_y()));
+    
+    // (行の中心, 行の高さ, 単語)
+    let mut rows: Vec<(f32, f32, Vec<&OcrWord>)> = Vec::new();
+    for word in sorted {
+        match rows.last_mut() {
+            Some((center, height, row)) if (word.center_y() - *center).abs() <= *height / 2.0 => {
+                row.push(word);
+                let count = row.len() as f32;
+                *center += (word.center_y() - *center) / count;
+                *height += (word.height() - *height) / count;
+            }
+            _ => rows.push((word.center_y(), word.height(), vec![word])),
+        }
+    }
+    
+    rows.into_iter()
+        .map(|(_, height, mut row)| {
+            row.sort_by(|a, b| a.left.total_cmp(&b.left));
+            let mut line = String::new();
+            let mut previous_right: Option<f32> = None;
+            for word in row {
+                if previous_right.is_some_and(|right| word.left - right >= height * SPACE_GAP_RATIO) {
+                    line.push(' ');
+                }
+                line.push_str(word.text.trim());
+                previous_right = Some(previous_right.map_or(word.right, |right| right.max(word.right)));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::MillsheetInfo;
+    
+    fn word(text: &str, left: f32, top: f32) -> OcrWord {
+        OcrWord::new(text, left, top, left + 10.0 * text.len() as f32, top + 20.0)
+    }
+    
+    #[test]
+    fn labels_and_values_share_a_line() {
+        let words = [
+            word("WEIGHT", 10.0, 100.0),
+            word("REMARKS", 10.0, 140.0),
+            word("12.3", 300.0, 102.0),
+            word("T", 350.0, 101.0),
+        ];
+        let layout = reconstruct_layout(&words);
+        assert_eq!(layout, "WEIGHT 12.3 T\nREMARKS");
+        
+        // ブロック順のテキストではラベルと値が離れて重量が取れないが、再構成したテキストでは取れる
+        let raw = MillsheetInfo::parse("WEIGHT\nREMARKS\n12.3 T");
+        assert_eq!(raw.weight, None);
+        let best = MillsheetInfo::better_of(raw, MillsheetInfo::parse(&layout));
+        assert_eq!(best.weight.as_deref(), Some("12.3t"));
+    }
+}
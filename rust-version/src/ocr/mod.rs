@@ -0,0 +1,106 @@
+//! OCRバックエンド - Google Vision / Azure を切り替えて同じパーサに渡す
+
+pub mod azure;
+pub mod layout;
+
+use crate::vision::VisionClient;
+use anyhow::Result;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub use azure::AzureOcrClient;
+
+/// OCR結果を返す Future
+pub type OcrFuture<'a> = Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+
+/// レイアウトを再構成したテキスト付きのOCR結果を返す Future
+pub type OcrTextFuture<'a> = Pin<Box<dyn Future<Output = Result<OcrText>> + Send + 'a>>;
+
+/// 準備の完了を返す Future
+pub type WarmupFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// OCR結果のテキスト
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OcrText {
+    /// バックエンドが返したままのテキスト
+    pub text: String,
+    /// 単語の座標から見た目の行を再構成したテキスト（座標を返さないバックエンドでは None）
+    pub layout_text: Option<String>,
+}
+
+/// OCRバックエンド
+///
+/// 認証方式の違いは各実装の中で吸収し、結果は行を改行でつないだプレーンテキストに揃える
+pub trait OcrBackend: Send + Sync {
+    /// 表示名
+    fn name(&self) -> &'static str;
+    
+    /// 画像からテキストを抽出
+    fn extract_text<'a>(&'a self, image_path: &'a Path) -> OcrFuture<'a>;
+    
+    /// 画像からテキストと、レイアウトを再構成したテキストを1回の呼び出しで抽出（既定では再構成しない）
+    fn extract_text_with_layout<'a>(&'a self, image_path: &'a Path) -> OcrTextFuture<'a> {
+        Box::pin(async move {
+            Ok(OcrText {
+                text: self.extract_text(image_path).await?,
+                layout_text: None,
+            })
+        })
+    }
+    
+    /// 初回のOCRの前に済ませておける準備（認証トークンの取得など、既定では何もしない）
+    fn warmup(&self) -> WarmupFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl OcrBackend for VisionClient {
+    fn name(&self) -> &'static str {
+        OcrBackendKind::GoogleVision.label()
+    }
+    
+    fn extract_text<'a>(&'a self, image_path: &'a Path) -> OcrFuture<'a> {
+        Box::pin(VisionClient::extract_text(self, image_path))
+    }
+    
+    fn extract_text_with_layout<'a>(&'a self, image_path: &'a Path) -> OcrTextFuture<'a> {
+        Box::pin(VisionClient::extract_text_with_layout(self, image_path))
+    }
+    
+    fn warmup(&self) -> WarmupFuture<'_> {
+        Box::pin(VisionClient::warmup(self))
+    }
+}
+
+/// OCRバックエンドの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OcrBackendKind {
+    /// Google Cloud Vision API（埋め込みサービスアカウント）
+    #[default]
+    GoogleVision,
+    /// Azure AI Vision（エンドポイントとキーを環境変数で指定）
+    Azure,
+}
+
+impl OcrBackendKind {
+    /// すべての種類（設定画面の選択肢用）
+    pub const ALL: [OcrBackendKind; 2] = [OcrBackendKind::GoogleVision, OcrBackendKind::Azure];
+    
+    /// 表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            OcrBackendKind::GoogleVision => "Google Vision",
+            OcrBackendKind::Azure => "Azure AI Vision",
+        }
+    }
+}
+
+/// 指定した種類のバックエンドを作成
+pub fn create_backend(kind: OcrBackendKind) -> Result<Arc<dyn OcrBackend>> {
+    Ok(match kind {
+        OcrBackendKind::GoogleVision => Arc::new(VisionClient::new()?),
+        OcrBackendKind::Azure => Arc::new(AzureOcrClient::from_env()?),
+    })
+}
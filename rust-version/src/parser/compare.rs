@@ -0,0 +1,138 @@
+//! ルール比較モジュール - 2つの解析設定で抽出結果の差分を取る
+
+use super::{MillsheetInfo, ParseOptions};
+use std::collections::BTreeMap;
+
+/// フィールドの変化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldChange {
+    /// 変化なし
+    Unchanged,
+    /// 改善（新たに抽出できた、または信頼度が上がった）
+    Improved,
+    /// 劣化（抽出できなくなった、または信頼度が下がった）
+    Degraded,
+    /// 値だけが変わった（良し悪しは目視確認が必要）
+    Changed,
+}
+
+impl FieldChange {
+    /// レポート用の名前
+    pub fn label(&self) -> &'static str {
+        match self {
+            FieldChange::Unchanged => "unchanged",
+            FieldChange::Improved => "improved",
+            FieldChange::Degraded => "degraded",
+            FieldChange::Changed => "changed",
+        }
+    }
+}
+
+/// 1フィールド分の差分
+#[derive(Debug, Clone)]
+pub struct FieldDiff {
+    /// フィールド名
+    pub field: &'static str,
+    /// 旧ルールの値
+    pub old: Option<String>,
+    /// 新ルールの値
+    pub new: Option<String>,
+    /// 変化の種類
+    pub change: FieldChange,
+}
+
+/// 同じテキストを旧ルール・新ルールで解析して差分を取る
+pub fn compare(text: &str, old: &ParseOptions, new: &ParseOptions) -> Vec<FieldDiff> {
+    let old_info = MillsheetInfo::parse_with_options(text, old);
+    let new_info = MillsheetInfo::parse_with_options(text, new);
+    
+    fields(&old_info)
+        .into_iter()
+        .zip(fields(&new_info))
+        .map(|((field, old_value, old_confidence), (_, new_value, new_confidence))| {
+            let change = match (&old_value, &new_value) {
+                (None, None) => FieldChange::Unchanged,
+                (None, Some(_)) => FieldChange::Improved,
+                (Some(_), None) => FieldChange::Degraded,
+                (Some(_), Some(_)) if new_confidence > old_confidence => FieldChange::Improved,
+                (Some(_), Some(_)) if new_confidence < old_confidence => FieldChange::Degraded,
+                (Some(a), Some(b)) if a == b => FieldChange::Unchanged,
+                (Some(_), Some(_)) => FieldChange::Changed,
+            };
+            FieldDiff {
+                field,
+                old: old_value,
+                new: new_value,
+                change,
+            }
+        })
+        .collect()
+}
+
+/// フィールド名・値・信頼度の一覧
+fn fields(info: &MillsheetInfo) -> [(&'static str, Option<String>, f32); 6] {
+    [
+        ("date", info.date.map(|d| d.to_string()), info.confidence.date),
+        ("material", info.material.clone(), info.confidence.material),
+        ("dimensions", info.dimensions.clone(), info.confidence.dimensions),
+        ("manufacturer", info.manufacturer.clone(), info.confidence.manufacturer),
+        ("charge_no", info.charge_no.clone(), info.confidence.charge_no),
+        ("weight", info.weight.clone(), info.confidence.weight),
+    ]
+}
+
+/// 変化の件数
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChangeCounts {
+    pub unchanged: usize,
+    pub improved: usize,
+    pub degraded: usize,
+    pub changed: usize,
+}
+
+/// 多数のサンプルに対するフィールド別の集計
+#[derive(Debug, Clone, Default)]
+pub struct CompareSummary {
+    /// 集計したサンプル数
+    pub samples: usize,
+    /// フィールド別の件数
+    pub fields: BTreeMap<&'static str, ChangeCounts>,
+}
+
+impl CompareSummary {
+    /// 1サンプル分の差分を加算
+    pub fn add(&mut self, diffs: &[FieldDiff]) {
+        self.samples += 1;
+        for diff in diffs {
+            let counts = self.fields.entry(diff.field).or_default();
+            match diff.change {
+                FieldChange::Unchanged => counts.unchanged += 1,
+                FieldChange::Improved => counts.improved += 1,
+                FieldChange::Degraded => counts.degraded += 1,
+                FieldChange::Changed => counts.changed += 1,
+            }
+        }
+    }
+    
+    /// 劣化したフィールドがあるか（ルール適用判断用）
+    pub fn has_regression(&self) -> bool {
+        self.fields.values().any(|c| c.degraded > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn disabling_weight_is_reported_as_degraded() {
+        let text = "材質 SS400\n寸法 9.0X1500X3000\n重量 2,450 kg";
+        let new = ParseOptions { disabled_fields: ["weight"].into(), ..Default::default() };
+        let diffs = compare(text, &ParseOptions::default(), &new);
+        
+        let weight = diffs.iter().find(|d| d.field == "weight").unwrap();
+        assert_eq!((weight.old.as_deref(), weight.new.as_deref()), (Some("2450kg"), None));
+        assert_eq!(weight.change, FieldChange::Degraded);
+        assert!(diffs.iter().filter(|d| d.field != "weight").all(|d| d.change == FieldChange::Unchanged));
+    }
+}
@@ -1,50 +1,391 @@
 //! 日付抽出モジュール
 
+use super::{compile_all, compile_builtin, FieldMatch, NormalizedText};
+use chrono::{Datelike, NaiveDate};
 use regex::Regex;
+use serde::{Serialize, Serializer};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
 
-/// テキストから発行日を抽出
-/// 優先順位: 発行日ラベル付き > 英語月名形式 > 日本語形式 > 数字形式
-pub fn extract_date(text: &str) -> Option<String> {
-    // 優先度1: 発行日ラベル付きの日付
-    if let Some(date) = extract_labeled_date(text) {
-        return Some(date);
+/// 2桁の年を読むときの既定の世紀（00〜99 → 2000〜2099年）
+pub const DEFAULT_CENTURY_START: u32 = 2000;
+
+/// 既定の年度開始月（日本の会計年度は4月始まり）
+pub const DEFAULT_FISCAL_YEAR_START_MONTH: u32 = 4;
+
+/// 発行日から導出する集計用フィールド
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedDate {
+    /// 年度（開始月の属する年）
+    pub fiscal_year: i32,
+    /// 年度内の四半期 (1〜4)
+    pub quarter: u32,
+    /// 月 (1〜12)
+    pub month: u32,
+    /// 曜日（日本語1文字）
+    pub weekday: &'static str,
+}
+
+impl DerivedDate {
+    /// 日付から導出（start_month は年度開始月 1〜12、範囲外は4月とみなす）
+    pub fn from_date(date: NaiveDate, start_month: u32) -> Self {
+        let start_month = if (1..=12).contains(&start_month) {
+            start_month
+        } else {
+            DEFAULT_FISCAL_YEAR_START_MONTH
+        };
+        
+        let month = date.month();
+        let fiscal_year = if month >= start_month { date.year() } else { date.year() - 1 };
+        let months_into_year = (month + 12 - start_month) % 12;
+        let weekday = ["月", "火", "水", "木", "金", "土", "日"][date.weekday().num_days_from_monday() as usize];
+        
+        Self {
+            fiscal_year,
+            quarter: months_into_year / 3 + 1,
+            month,
+            weekday,
+        }
     }
     
-    // 優先度2: 英語月名形式
-    if let Some(date) = extract_english_date(text) {
-        return Some(date);
+    /// テンプレートのプレースホルダ名と値
+    pub fn placeholders(&self) -> [(&'static str, String); 4] {
+        [
+            ("fiscal_year", self.fiscal_year.to_string()),
+            ("quarter", format!("Q{}", self.quarter)),
+            ("month", format!("{:02}", self.month)),
+            ("weekday", self.weekday.to_string()),
+        ]
     }
-    
-    // 優先度3: 日本語/数字形式
-    extract_japanese_date(text)
 }
 
-/// 発行日ラベル付きの日付を抽出
-fn extract_labeled_date(text: &str) -> Option<String> {
-    let patterns = [
-        r"発行日[\s\S]{0,50}?(\d{4}[./]\d{1,2}[./]\d{1,2})",
-        r"Date\s*of\s*Issue[\s\S]{0,30}?(\d{4}[./]\d{1,2}[./]\d{1,2})",
-        r"発行年月日[\s\S]{0,30}?(\d{4}[./]\d{1,2}[./]\d{1,2})",
+/// ファイル名での発行日の書式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateFormat {
+    /// 24-03-05
+    #[default]
+    YyMmDd,
+    /// 240305
+    YyMmDdCompact,
+    /// 20240305
+    YyyyMmDd,
+    /// 2024-03-05
+    YyyyMmDdDash,
+}
+
+impl DateFormat {
+    /// すべての書式（設定画面の選択肢用）
+    pub const ALL: [DateFormat; 4] = [
+        DateFormat::YyMmDd,
+        DateFormat::YyMmDdCompact,
+        DateFormat::YyyyMmDd,
+        DateFormat::YyyyMmDdDash,
     ];
     
-    for pattern in patterns {
-        if let Ok(re) = Regex::new(&format!("(?i){}", pattern)) {
-            if let Some(caps) = re.captures(text) {
-                if let Some(m) = caps.get(1) {
-                    if let Some(date) = parse_numeric_date(m.as_str()) {
-                        return Some(date);
-                    }
-                }
+    /// 書式の表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            DateFormat::YyMmDd => "YY-MM-DD",
+            DateFormat::YyMmDdCompact => "YYMMDD",
+            DateFormat::YyyyMmDd => "YYYYMMDD",
+            DateFormat::YyyyMmDdDash => "YYYY-MM-DD",
+        }
+    }
+    
+    /// 年を2桁で出す書式か
+    pub fn is_two_digit_year(&self) -> bool {
+        matches!(self, DateFormat::YyMmDd | DateFormat::YyMmDdCompact)
+    }
+    
+    /// 区切りをそのままに年を4桁にした書式
+    pub fn with_four_digit_year(&self) -> DateFormat {
+        match self {
+            DateFormat::YyMmDd => DateFormat::YyyyMmDdDash,
+            DateFormat::YyMmDdCompact => DateFormat::YyyyMmDd,
+            other => *other,
+        }
+    }
+}
+
+/// 発行日（年は西暦）
+///
+/// 表示（`Display`）とJSONは従来どおり YY-MM-DD 形式（日が無い場合は YY-MM）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IssueDate {
+    /// 年（西暦4桁）
+    pub year: u32,
+    /// 月 (1〜12)
+    pub month: u32,
+    /// 日 (1〜31、「2024年3月」のように記載が無ければ None)
+    pub day: Option<u32>,
+}
+
+impl IssueDate {
+    /// 実在する日付なら作成（月ごとの日数・うるう年を考慮）
+    pub fn new(year: u32, month: u32, day: u32) -> Option<Self> {
+        NaiveDate::from_ymd_opt(year as i32, month, day)?;
+        Some(Self { year, month, day: Some(day) })
+    }
+    
+    /// 日のない年月を作成（月が範囲外なら None）
+    pub fn year_month(year: u32, month: u32) -> Option<Self> {
+        NaiveDate::from_ymd_opt(year as i32, month, 1)?;
+        Some(Self { year, month, day: None })
+    }
+    
+    /// 指定の書式で文字列にする（日が無ければ日の部分を省く。例: YYYYMMDD → 202403）
+    pub fn format(&self, format: DateFormat) -> String {
+        let (year, month) = (self.year, self.month);
+        let Some(day) = self.day else {
+            return match format {
+                DateFormat::YyMmDd => format!("{:02}-{:02}", year % 100, month),
+                DateFormat::YyMmDdCompact => format!("{:02}{:02}", year % 100, month),
+                DateFormat::YyyyMmDd => format!("{:04}{:02}", year, month),
+                DateFormat::YyyyMmDdDash => format!("{:04}-{:02}", year, month),
+            };
+        };
+        
+        match format {
+            DateFormat::YyMmDd => format!("{:02}-{:02}-{:02}", year % 100, month, day),
+            DateFormat::YyMmDdCompact => format!("{:02}{:02}{:02}", year % 100, month, day),
+            DateFormat::YyyyMmDd => format!("{:04}{:02}{:02}", year, month, day),
+            DateFormat::YyyyMmDdDash => format!("{:04}-{:02}-{:02}", year, month, day),
+        }
+    }
+    
+    /// 2桁の年の世紀（開始年）を指定して書式化
+    ///
+    /// その世紀（例: 2000 → 2000〜2099年）の外の年は、下2桁ではソート順が崩れるので4桁で出す（None なら常に下2桁）
+    pub fn format_in_century(&self, format: DateFormat, century_start: Option<u32>) -> String {
+        match century_start {
+            Some(start) if format.is_two_digit_year() && !self.in_century(start) => {
+                self.format(format.with_four_digit_year())
             }
+            _ => self.format(format),
         }
     }
     
-    None
+    /// 指定の世紀（開始年の下2桁は切り捨て、2000 → 2000〜2099年）に含まれるか
+    pub fn in_century(&self, century_start: u32) -> bool {
+        let start = century_start - century_start % 100;
+        (start..start + 100).contains(&self.year)
+    }
+    
+    /// 2桁の年を指定の世紀（開始年）の年として読む。書式は `FromStr` と同じ
+    pub fn parse_in_century(s: &str, century_start: u32) -> anyhow::Result<Self> {
+        let parts: Vec<&str> = s.trim().split(['-', '/', '.']).collect();
+        let number = |part: &str| -> anyhow::Result<u32> {
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                anyhow::bail!("日付の形式が不正です: {}", s);
+            }
+            Ok(part.parse()?)
+        };
+        
+        let (year, month, day) = match parts.as_slice() {
+            [year, month] => (number(year)?, number(month)?, None),
+            [year, month, day] => (number(year)?, number(month)?, Some(number(day)?)),
+            _ => anyhow::bail!("日付の形式が不正です（YY-MM-DD / YY-MM）: {}", s),
+        };
+        let year = if year < 100 { century_start - century_start % 100 + year } else { year };
+        
+        let date = match day {
+            Some(day) => IssueDate::new(year, month, day),
+            None => IssueDate::year_month(year, month),
+        };
+        date.ok_or_else(|| anyhow::anyhow!("存在しない日付です: {}", s))
+    }
+    
+    /// chrono の日付に変換（日が無ければ月初、実在しない日付なら None）
+    pub fn to_naive_date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year as i32, self.month, self.day.unwrap_or(1))
+    }
+}
+
+impl fmt::Display for IssueDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(DateFormat::YyMmDd))
+    }
+}
+
+/// `Display` の形式（YY-MM-DD / YY-MM、2桁の年は2000年代）と YYYY-MM-DD / YYYY-MM を読む
+impl FromStr for IssueDate {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IssueDate::parse_in_century(s, DEFAULT_CENTURY_START)
+    }
+}
+
+impl Serialize for IssueDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// ラベルなしの日付が複数あるときの選び方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateSelection {
+    /// 形式の優先順位に従って最初に見つかったもの
+    #[default]
+    First,
+    /// 全候補のうち最も新しいもの（今日より後の日付は印刷日などとみなして除外）
+    Latest,
+}
+
+/// テキストから発行日を抽出
+/// 優先順位: 発行日ラベル付き > 試験日・検査日ラベル付き > 英語月名形式 > 日本語形式 > 数字形式
+pub fn extract_date(text: &str) -> Option<IssueDate> {
+    extract_date_with(text, DateSelection::First)
+}
+
+/// 選び方を指定して発行日を抽出（ラベル付きの日付がある場合はそちらを優先）
+pub fn extract_date_with(text: &str, selection: DateSelection) -> Option<IssueDate> {
+    extract_date_match_with(text, selection).map(|(date, _)| date)
+}
+
+/// 発行日と抽出根拠を取得（根拠の値は YY-MM-DD 形式）
+pub fn extract_date_match(text: &str) -> Option<(IssueDate, FieldMatch)> {
+    extract_date_match_with(text, DateSelection::First)
+}
+
+/// 選び方を指定して発行日と抽出根拠を取得
+pub fn extract_date_match_with(text: &str, selection: DateSelection) -> Option<(IssueDate, FieldMatch)> {
+    extract_date_match_as_of(text, selection, chrono::Local::now().date_naive())
+}
+
+/// `today` を今日として発行日と抽出根拠を取得（`DateSelection::Latest` は `today` より後の日付を除外）
+pub fn extract_date_match_as_of(
+    text: &str,
+    selection: DateSelection,
+    today: NaiveDate,
+) -> Option<(IssueDate, FieldMatch)> {
+    let normalized = NormalizedText::new(text);
+    find_date(&normalized.text, selection, today).map(|(date, m)| (date, normalized.remap(m)))
+}
+
+/// 正規化済みテキストから発行日を探す
+fn find_date(text: &str, selection: DateSelection, today: NaiveDate) -> Option<(IssueDate, FieldMatch)> {
+    // 優先度1: ラベル付きの日付（発行日 > 試験日 > 検査日）
+    if let Some(found) = extract_labeled_date(text) {
+        return Some(found);
+    }
+    
+    match selection {
+        // 優先度2: 英語月名形式、優先度3: 日本語/数字形式
+        DateSelection::First => english_date_candidates(text)
+            .into_iter()
+            .next()
+            .or_else(|| japanese_date_candidates(text).into_iter().next()),
+        DateSelection::Latest => english_date_candidates(text)
+            .into_iter()
+            .chain(japanese_date_candidates(text))
+            .filter(|(date, _)| date.to_naive_date().is_some_and(|d| d <= today))
+            .max_by_key(|(date, _)| *date),
+    }
+}
+
+/// 日付ラベル（名前と正規表現）
+const DATE_LABELS: [(&str, &str); 5] = [
+    ("発行日", r"発行年月日|発行日"),
+    ("発行日", r"Date\s*of\s*Issue"),
+    ("試験日", r"試験日|Date\s*of\s*Test|TEST\s*DATE"),
+    ("検査日", r"検査日|Inspection\s*Date"),
+    ("検査日", r"Date\s*of\s*Inspection"),
+];
+
+/// ラベル付き日付の優先順位（リネームには発行日を使い、無ければ試験日・検査日）
+pub const DATE_LABEL_PRIORITY: [&str; 3] = ["発行日", "試験日", "検査日"];
+
+/// ラベルと日付の最大距離（文字数）
+const MAX_LABEL_DISTANCE: usize = 50;
+
+/// ラベルごとの日付を抽出（キーは DATE_LABEL_PRIORITY の名前）
+pub fn extract_dates_by_label(text: &str) -> HashMap<&'static str, String> {
+    extract_labeled_date_matches(&NormalizedText::new(text).text)
+        .into_iter()
+        .map(|(label, (_, found))| (label, found.value))
+        .collect()
+}
+
+/// 優先順位リストに従ってラベル付き日付を選ぶ
+pub fn select_date_by_priority(dates: &HashMap<&str, String>, priority: &[&str]) -> Option<String> {
+    priority.iter().find_map(|label| dates.get(label).cloned())
+}
+
+/// コンパイル済みの日付ラベル（`DATE_LABELS` の順）
+static DATE_LABEL_REGEXES: LazyLock<Vec<Regex>> =
+    LazyLock::new(|| compile_all(DATE_LABELS.iter().map(|(_, pattern)| format!("(?i){}", pattern))));
+
+/// ラベルに続く数字形式の日付
+static LABELED_DATE_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(r"\d{4}[./]\d{1,2}[./]\d{1,2}"));
+
+/// ラベルごとの日付と抽出根拠
+///
+/// 各日付は直前にある最も近いラベルに割り当てるので、発行日と試験日が
+/// 同じ数字形式で並んでいても取り違えない
+fn extract_labeled_date_matches(text: &str) -> HashMap<&'static str, (IssueDate, FieldMatch)> {
+    // (ラベル終端, ラベル名, パターン)
+    let mut labels: Vec<(usize, &'static str, &'static str)> = Vec::new();
+    for (re, (name, pattern)) in DATE_LABEL_REGEXES.iter().zip(DATE_LABELS) {
+        labels.extend(re.find_iter(text).map(|m| (m.end(), name, pattern)));
+    }
+    labels.sort_by_key(|(end, _, _)| *end);
+    
+    let mut found: HashMap<&'static str, (IssueDate, FieldMatch)> = HashMap::new();
+    for m in LABELED_DATE_REGEX.find_iter(text) {
+        let Some(&(label_end, name, pattern)) = labels.iter().rev().find(|(end, _, _)| *end <= m.start()) else {
+            continue;
+        };
+        if text[label_end..m.start()].chars().count() > MAX_LABEL_DISTANCE || found.contains_key(name) {
+            continue;
+        }
+        if let Some(date) = parse_numeric_date(m.as_str()) {
+            found.insert(name, (date, FieldMatch::new(date.to_string(), m.range(), pattern)));
+        }
+    }
+    
+    found
+}
+
+/// ラベル付きの日付を優先順位に従って抽出
+fn extract_labeled_date(text: &str) -> Option<(IssueDate, FieldMatch)> {
+    let mut found = extract_labeled_date_matches(text);
+    DATE_LABEL_PRIORITY.iter().find_map(|label| found.remove(label))
+}
+
+/// 英語の曜日（Mon / Monday / Tues. など）
+const WEEKDAY_PATTERN: &str =
+    r"(?i)\b(?:MON(?:DAY)?|TUE(?:S(?:DAY)?)?|WED(?:NESDAY)?|THU(?:R(?:S(?:DAY)?)?)?|FRI(?:DAY)?|SAT(?:URDAY)?|SUN(?:DAY)?)\b\.?";
+
+/// コンパイル済みの曜日パターン
+static WEEKDAY_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(WEEKDAY_PATTERN));
+
+/// 曜日を同じバイト数の空白に置き換える（月名と取り違えないよう月名マッチの前に除去し、位置は保つ）
+fn blank_weekdays(text: &str) -> String {
+    WEEKDAY_REGEX.replace_all(text, |caps: &regex::Captures| " ".repeat(caps[0].len()))
+        .into_owned()
 }
 
-/// 英語月名形式の日付を抽出
-fn extract_english_date(text: &str) -> Option<String> {
+/// 英語月名形式の日付パターンと年月日の並び（優先順）
+///
+/// AUG . 04 . 2025 / AUG.04.2025 / Aug 4, 2025 / 04 August 2025（区切りは記号・空白の混在可）
+const ENGLISH_DATE_PATTERNS: [(&str, &str); 3] = [
+    (r"\b([A-Z]{3,9})(?:\s*[.\-/,]\s*|\s+)(\d{1,2})(?:\s*[.\-/,]\s*|\s+)(\d{4})\b", "mdy"),
+    (r"\b(\d{1,2})(?:\s*[.\-/,]\s*|\s+)([A-Z]{3,9})(?:\s*[.\-/,]\s*|\s+)(\d{4})\b", "dmy"),
+    (r"\b(\d{4})(?:\s*[.\-/,]\s*|\s+)([A-Z]{3,9})(?:\s*[.\-/,]\s*|\s+)(\d{1,2})\b", "ymd"),
+];
+
+/// コンパイル済みの英語月名形式の日付パターン（`ENGLISH_DATE_PATTERNS` の順）
+static ENGLISH_DATE_REGEXES: LazyLock<Vec<Regex>> =
+    LazyLock::new(|| compile_all(ENGLISH_DATE_PATTERNS.iter().map(|(pattern, _)| format!("(?i){}", pattern))));
+
+/// 英語月名形式の日付の候補（パターンの優先順、同じパターン内は出現順）
+fn english_date_candidates(text: &str) -> Vec<(IssueDate, FieldMatch)> {
+    // "Mon, 04 Aug 2025" の曜日は区切りと同じ扱いにする
+    let text = &blank_weekdays(text);
+    
     let month_map: HashMap<&str, u32> = [
         ("JAN", 1), ("JANUARY", 1),
         ("FEB", 2), ("FEBRUARY", 2),
@@ -60,97 +401,375 @@ fn extract_english_date(text: &str) -> Option<String> {
         ("DEC", 12), ("DECEMBER", 12),
     ].into_iter().collect();
     
-    // AUG . 04 . 2025 or AUG.04.2025
-    let patterns = [
-        (r"([A-Z]{3,9})\s*[.\-/,]\s*(\d{1,2})\s*[.\-/,]\s*(\d{4})", "mdy"),
-        (r"(\d{1,2})\s*[.\-/,]\s*([A-Z]{3,9})\s*[.\-/,]\s*(\d{4})", "dmy"),
-        (r"(\d{4})\s*[.\-/,]\s*([A-Z]{3,9})\s*[.\-/,]\s*(\d{1,2})", "ymd"),
-    ];
+    let mut candidates = Vec::new();
+    for (re, (pattern, format)) in ENGLISH_DATE_REGEXES.iter().zip(ENGLISH_DATE_PATTERNS) {
+        for caps in re.captures_iter(text) {
+            let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<u32>().ok());
+            let month_name = |i: usize| {
+                caps.get(i).and_then(|m| month_map.get(m.as_str().to_uppercase().as_str()).copied())
+            };
+            
+            let parts = match format {
+                "mdy" => month_name(1).zip(number(2)).zip(number(3)).map(|((m, d), y)| (y, m, d)),
+                "dmy" => number(1).zip(month_name(2)).zip(number(3)).map(|((d, m), y)| (y, m, d)),
+                "ymd" => number(1).zip(month_name(2)).zip(number(3)).map(|((y, m), d)| (y, m, d)),
+                _ => None,
+            };
+            
+            // 月名でない単語や範囲外の日付は候補にしない
+            if let Some((year, month, day)) = parts
+                && let Some(date) = IssueDate::new(year, month, day)
+            {
+                candidates.push((date, FieldMatch::new(date.to_string(), caps.get_match().range(), pattern)));
+            }
+        }
+    }
+    
+    candidates
+}
+
+/// 日本語/数字形式の日付パターンと元号（優先順、元号が None なら西暦）
+const JAPANESE_DATE_PATTERNS: [(&str, Option<&str>); 12] = [
+    // 2024年1月15日
+    (r"(\d{4})年(\d{1,2})月(\d{1,2})日", None),
+    // 2024/01/15 or 2024/1/15
+    (r"(\d{4})/(\d{1,2})/(\d{1,2})", None),
+    // 2024-01-15
+    (r"(\d{4})-(\d{1,2})-(\d{1,2})", None),
+    // 2024.01.15
+    (r"(\d{4})\.(\d{1,2})\.(\d{1,2})", None),
+    // 令和6年1月15日
+    (r"令和(\d{1,2})年(\d{1,2})月(\d{1,2})日", Some("reiwa")),
+    // R6.1.15 or R06.01.15
+    (r"R(\d{1,2})\.(\d{1,2})\.(\d{1,2})", Some("reiwa")),
+    // 平成31年1月15日
+    (r"平成(\d{1,2})年(\d{1,2})月(\d{1,2})日", Some("heisei")),
+    // 昭和63年5月1日
+    (r"昭和(\d{1,2})年(\d{1,2})月(\d{1,2})日", Some("showa")),
+    // S63.5.1 (S45C などの材質と区別するため語頭のみ)
+    (r"\bS(\d{1,2})\.(\d{1,2})\.(\d{1,2})", Some("showa")),
+    // 元号の1文字略記: 令6.1.15 / 平31.4.30 / 昭63年5月1日 (元号の直後は数字＋区切りに限る)
+    (r"令(\d{1,2})[./年](\d{1,2})[./月](\d{1,2})", Some("reiwa")),
+    (r"平(\d{1,2})[./年](\d{1,2})[./月](\d{1,2})", Some("heisei")),
+    (r"昭(\d{1,2})[./年](\d{1,2})[./月](\d{1,2})", Some("showa")),
+];
+
+/// 日のない年月のパターン（2024年3月 / 2024/03）
+const YEAR_MONTH_PATTERNS: [&str; 2] = [r"(\d{4})年(\d{1,2})月", r"(\d{4})/(\d{1,2})"];
+
+/// OCRで区切りが化けた日付（2024,01,15 / 2024 01 15）と8桁の数字（20240115）のパターン
+///
+/// チャージ番号や寸法と取り違えないよう、先頭の4桁が19xx/20xx年であることを必須にする。
+/// 空白区切りは数値の並び（SS400 2024 2 3）とも読めるので、月・日が2桁のときに限る
+const LENIENT_DATE_PATTERNS: [&str; 3] = [
+    r"((?:19|20)\d{2})[^\dA-Za-z\s](\d{1,2})[^\dA-Za-z\s](\d{1,2})",
+    r"((?:19|20)\d{2})[ \t](\d{2})[ \t](\d{2})",
+    r"((?:19|20)\d{2})(\d{2})(\d{2})",
+];
+
+/// コンパイル済みの日本語/数字形式の日付パターン（`JAPANESE_DATE_PATTERNS` の順）
+static JAPANESE_DATE_REGEXES: LazyLock<Vec<Regex>> =
+    LazyLock::new(|| compile_all(JAPANESE_DATE_PATTERNS.iter().map(|(pattern, _)| format!("(?i){}", pattern))));
+
+/// コンパイル済みの年月のパターン
+static YEAR_MONTH_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| compile_all(YEAR_MONTH_PATTERNS));
+
+/// コンパイル済みの区切りが化けた日付のパターン
+static LENIENT_DATE_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| compile_all(LENIENT_DATE_PATTERNS));
+
+/// 日本語/数字形式の日付の候補（パターンの優先順、同じパターン内は出現順）
+fn japanese_date_candidates(text: &str) -> Vec<(IssueDate, FieldMatch)> {
+    let mut candidates = Vec::new();
     
-    for (pattern, format) in patterns {
-        if let Ok(re) = Regex::new(&format!("(?i){}", pattern)) {
-            if let Some(caps) = re.captures(text) {
-                let (year, month, day) = match format {
-                    "mdy" => {
-                        let month_str = caps.get(1)?.as_str().to_uppercase();
-                        let month = *month_map.get(month_str.as_str())?;
-                        let day: u32 = caps.get(2)?.as_str().parse().ok()?;
-                        let year: u32 = caps.get(3)?.as_str().parse().ok()?;
-                        (year, month, day)
-                    }
-                    "dmy" => {
-                        let day: u32 = caps.get(1)?.as_str().parse().ok()?;
-                        let month_str = caps.get(2)?.as_str().to_uppercase();
-                        let month = *month_map.get(month_str.as_str())?;
-                        let year: u32 = caps.get(3)?.as_str().parse().ok()?;
-                        (year, month, day)
-                    }
-                    "ymd" => {
-                        let year: u32 = caps.get(1)?.as_str().parse().ok()?;
-                        let month_str = caps.get(2)?.as_str().to_uppercase();
-                        let month = *month_map.get(month_str.as_str())?;
-                        let day: u32 = caps.get(3)?.as_str().parse().ok()?;
-                        (year, month, day)
-                    }
-                    _ => return None,
-                };
-                
-                return Some(format!("{:02}-{:02}-{:02}", year % 100, month, day));
+    for (re, (pattern, era_type)) in JAPANESE_DATE_REGEXES.iter().zip(JAPANESE_DATE_PATTERNS) {
+        for caps in re.captures_iter(text) {
+            let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<u32>().ok());
+            let (Some(first), Some(month), Some(day)) = (number(1), number(2), number(3)) else {
+                continue;
+            };
+            
+            // 1文字略記の元号は「命令」「公平」などの語の一部を除く（「発行日令6.1.15」の「日」は許す）
+            let whole = caps.get_match();
+            let abbreviated_era = whole.as_str().chars().nth(1).is_some_and(|c| c.is_ascii_digit())
+                && !whole.as_str().starts_with(|c: char| c.is_ascii());
+            if abbreviated_era && text[..whole.start()].ends_with(|c: char| is_kanji(c) && c != '日') {
+                continue;
+            }
+            
+            let year = match era_type {
+                Some("reiwa") => 2018 + first,  // 令和1年 = 2019年
+                Some("heisei") => 1988 + first, // 平成1年 = 1989年
+                Some("showa") => 1925 + first,  // 昭和1年 = 1926年
+                _ => first,
+            };
+            
+            // 昭和64年は1月7日までしか存在しない
+            if era_type == Some("showa") && (first > 64 || (first == 64 && (month, day) > (1, 7))) {
+                continue;
+            }
+            
+            // 範囲外の日付は候補にしない
+            if let Some(date) = IssueDate::new(year, month, day) {
+                candidates.push((date, FieldMatch::new(date.to_string(), whole.range(), pattern)));
             }
         }
     }
     
-    None
-}
-
-/// 日本語/数字形式の日付を抽出
-fn extract_japanese_date(text: &str) -> Option<String> {
-    let patterns: Vec<(&str, Option<&str>)> = vec![
-        // 2024年1月15日
-        (r"(\d{4})年(\d{1,2})月(\d{1,2})日", None),
-        // 2024/01/15 or 2024/1/15
-        (r"(\d{4})/(\d{1,2})/(\d{1,2})", None),
-        // 2024-01-15
-        (r"(\d{4})-(\d{1,2})-(\d{1,2})", None),
-        // 2024.01.15
-        (r"(\d{4})\.(\d{1,2})\.(\d{1,2})", None),
-        // 令和6年1月15日
-        (r"令和(\d{1,2})年(\d{1,2})月(\d{1,2})日", Some("reiwa")),
-        // R6.1.15 or R06.01.15
-        (r"R(\d{1,2})\.(\d{1,2})\.(\d{1,2})", Some("reiwa")),
-        // 平成31年1月15日
-        (r"平成(\d{1,2})年(\d{1,2})月(\d{1,2})日", Some("heisei")),
-    ];
+    // 日のない年月は年月日の候補より後に回す
+    for re in YEAR_MONTH_REGEXES.iter() {
+        for caps in re.captures_iter(text) {
+            let whole = caps.get_match();
+            // 長い数字の一部や、年月日の先頭（2024/01/15 の 2024/01 など）は除く
+            let before_digit = text[..whole.start()].ends_with(|c: char| c.is_ascii_digit());
+            let continues = text[whole.end()..].starts_with(|c: char| c.is_ascii_digit() || c == '/');
+            if before_digit || continues {
+                continue;
+            }
+            
+            let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<u32>().ok());
+            let (Some(year), Some(month)) = (number(1), number(2)) else {
+                continue;
+            };
+            // 寸法などの数字と取り違えないよう年は1900〜2099年に限る
+            if !(1900..=2099).contains(&year) {
+                continue;
+            }
+            if let Some(date) = IssueDate::year_month(year, month) {
+                candidates.push((date, FieldMatch::new(date.to_string(), whole.range(), re.as_str())));
+            }
+        }
+    }
     
-    for (pattern, era_type) in patterns {
-        if let Ok(re) = Regex::new(&format!("(?i){}", pattern)) {
-            if let Some(caps) = re.captures(text) {
-                let first: u32 = caps.get(1)?.as_str().parse().ok()?;
-                let month: u32 = caps.get(2)?.as_str().parse().ok()?;
-                let day: u32 = caps.get(3)?.as_str().parse().ok()?;
-                
-                let year = match era_type {
-                    Some("reiwa") => 2018 + first,  // 令和1年 = 2019年
-                    Some("heisei") => 1988 + first, // 平成1年 = 1989年
-                    _ => first,
-                };
-                
-                return Some(format!("{:02}-{:02}-{:02}", year % 100, month, day));
+    // 最後のフォールバック: 区切りが化けた日付と8桁の数字
+    for re in LENIENT_DATE_REGEXES.iter() {
+        for caps in re.captures_iter(text) {
+            let whole = caps.get_match();
+            // 長い数字の一部は除く
+            let before_digit = text[..whole.start()].ends_with(|c: char| c.is_ascii_digit());
+            let after_digit = text[whole.end()..].starts_with(|c: char| c.is_ascii_digit());
+            if before_digit || after_digit {
+                continue;
+            }
+            
+            let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<u32>().ok());
+            let (Some(year), Some(month), Some(day)) = (number(1), number(2), number(3)) else {
+                continue;
+            };
+            if let Some(date) = IssueDate::new(year, month, day) {
+                // 区切りを推測しているので信頼度は下げる
+                let found = FieldMatch::new(date.to_string(), whole.range(), re.as_str());
+                candidates.push((date, FieldMatch { inferred: true, ..found }));
             }
         }
     }
     
-    None
+    candidates
+}
+
+/// CJK統合漢字か
+fn is_kanji(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c)
+}
+
+/// コンパイル済みの数字形式の日付
+static NUMERIC_DATE_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(r"(\d{4})[./\-](\d{1,2})[./\-](\d{1,2})"));
+
+/// このモジュールの組み込みパターンをすべてコンパイル
+pub(crate) fn precompile_patterns() {
+    for regexes in [&DATE_LABEL_REGEXES, &ENGLISH_DATE_REGEXES, &JAPANESE_DATE_REGEXES, &YEAR_MONTH_REGEXES, &LENIENT_DATE_REGEXES] {
+        LazyLock::force(regexes);
+    }
+    for regex in [&LABELED_DATE_REGEX, &WEEKDAY_REGEX, &NUMERIC_DATE_REGEX] {
+        LazyLock::force(regex);
+    }
 }
 
 /// 数字形式の日付をパース (YYYY.MM.DD or YYYY/MM/DD or YYYY-MM-DD)
-fn parse_numeric_date(date_str: &str) -> Option<String> {
-    let re = Regex::new(r"(\d{4})[./\-](\d{1,2})[./\-](\d{1,2})").ok()?;
-    let caps = re.captures(date_str)?;
+fn parse_numeric_date(date_str: &str) -> Option<IssueDate> {
+    let caps = NUMERIC_DATE_REGEX.captures(date_str)?;
     
     let year: u32 = caps.get(1)?.as_str().parse().ok()?;
     let month: u32 = caps.get(2)?.as_str().parse().ok()?;
     let day: u32 = caps.get(3)?.as_str().parse().ok()?;
     
-    Some(format!("{:02}-{:02}-{:02}", year % 100, month, day))
+    IssueDate::new(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn showa_dates_are_converted_from_1926() {
+        assert_eq!(extract_date("昭和63年5月1日"), IssueDate::new(1988, 5, 1));
+        assert_eq!(extract_date("昭和1年12月25日"), IssueDate::new(1926, 12, 25));
+        assert_eq!(extract_date("昭63年5月1日"), IssueDate::new(1988, 5, 1));
+    }
+    
+    #[test]
+    fn showa_abbreviation_is_converted() {
+        assert_eq!(extract_date("S63.5.1"), IssueDate::new(1988, 5, 1));
+        assert_eq!(extract_date("発行日 S63.5.1"), IssueDate::new(1988, 5, 1));
+    }
+    
+    #[test]
+    fn showa_64_ends_on_january_7() {
+        assert_eq!(extract_date("昭和64年1月7日"), IssueDate::new(1989, 1, 7));
+        assert_eq!(extract_date("S64.1.7"), IssueDate::new(1989, 1, 7));
+        assert_eq!(extract_date("昭64.1.7"), IssueDate::new(1989, 1, 7));
+        assert_eq!(extract_date("昭和64年1月8日"), None);
+        assert_eq!(extract_date("S64.1.8"), None);
+        assert_eq!(extract_date("昭64.1.8"), None);
+        assert_eq!(extract_date("昭和65年1月1日"), None);
+        // 翌日からは平成
+        assert_eq!(extract_date("平成1年1月8日"), IssueDate::new(1989, 1, 8));
+    }
+    
+    #[test]
+    fn showa_abbreviation_is_not_taken_from_materials() {
+        assert_eq!(extract_date("S45C 2.3X1219"), None);
+        assert_eq!(extract_date("SS63.5.1"), None);
+    }
+    
+    #[test]
+    fn from_str_reads_formatted_dates() {
+        assert_eq!("24-03-15".parse::<IssueDate>().ok(), IssueDate::new(2024, 3, 15));
+        assert_eq!("2024/03".parse::<IssueDate>().ok(), IssueDate::year_month(2024, 3));
+        assert!("24-02-30".parse::<IssueDate>().is_err());
+    }
+    
+    #[test]
+    fn era_abbreviations_are_converted() {
+        assert_eq!(extract_date("令和6年1月15日"), IssueDate::new(2024, 1, 15));
+        // 元号の1文字略記
+        assert_eq!(extract_date("出荷 令6.1.15"), IssueDate::new(2024, 1, 15));
+        assert_eq!(extract_date("平31/4/30"), IssueDate::new(2019, 4, 30));
+        assert_eq!(extract_date("昭63年5月1日"), IssueDate::new(1988, 5, 1));
+        // 直後が数字＋区切りでなければ元号とみなさない
+        assert_eq!(extract_date("命令6.1.15"), None);
+        assert_eq!(extract_date("平6号"), None);
+    }
+    
+    #[test]
+    fn century_picks_two_or_four_digit_year() {
+        let old = IssueDate::new(1999, 12, 31).unwrap();
+        let new = IssueDate::new(2099, 12, 31).unwrap();
+        assert_eq!(old.format(DateFormat::YyMmDd), new.format(DateFormat::YyMmDd));
+        
+        assert_eq!(old.format_in_century(DateFormat::YyMmDd, Some(2000)), "1999-12-31");
+        assert_eq!(new.format_in_century(DateFormat::YyMmDd, Some(2000)), "99-12-31");
+        assert_eq!(old.format_in_century(DateFormat::YyMmDdCompact, Some(1900)), "991231");
+        assert_eq!(new.format_in_century(DateFormat::YyMmDdCompact, Some(1900)), "20991231");
+    }
+    
+    #[test]
+    fn parse_in_century_reads_two_digit_year_in_that_century() {
+        assert_eq!(IssueDate::parse_in_century("99-12-31", 1900).ok(), IssueDate::new(1999, 12, 31));
+        assert_eq!(IssueDate::parse_in_century("2099-12-31", 1900).ok(), IssueDate::new(2099, 12, 31));
+    }
+    
+    
+    #[test]
+    fn garbled_separators_are_read_leniently() {
+        let found = extract_date_match("2024,01,15").unwrap();
+        assert_eq!((found.0, found.1.inferred), (IssueDate::new(2024, 1, 15).unwrap(), true));
+        assert_eq!(extract_date("2024 01 15"), IssueDate::new(2024, 1, 15));
+        assert_eq!(extract_date("2024,1,5"), IssueDate::new(2024, 1, 5));
+        assert_eq!(extract_date("20240115"), IssueDate::new(2024, 1, 15));
+    }
+    
+    #[test]
+    fn space_separated_numbers_need_two_digit_month_and_day() {
+        // 空白区切りの1桁の数値は日付とみなさない
+        assert_eq!(extract_date("SS400 2024 2 3"), None);
+        assert_eq!(extract_date("2024 1 15"), None);
+        assert_eq!(extract_date("2024\n01\n15"), None);
+    }
+    
+    
+    #[test]
+    fn latest_picks_newest_date_up_to_today() {
+        let text = "製造 2024年3月15日\n出荷 2024年5月1日\n印刷 2024年6月20日";
+        let as_of = |today: NaiveDate, selection| extract_date_match_as_of(text, selection, today).map(|(date, _)| date);
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        
+        assert_eq!(as_of(today, DateSelection::First), IssueDate::new(2024, 3, 15));
+        // 今日より後の日付は除外して最も新しいもの
+        assert_eq!(as_of(today, DateSelection::Latest), IssueDate::new(2024, 5, 1));
+        // 今日と同じ日付は採用する
+        assert_eq!(as_of(NaiveDate::from_ymd_opt(2024, 6, 20).unwrap(), DateSelection::Latest), IssueDate::new(2024, 6, 20));
+        // すべて未来なら見つからない
+        assert_eq!(as_of(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), DateSelection::Latest), None);
+    }
+    
+    #[test]
+    fn latest_uses_today_from_parse_options() {
+        let options = crate::parser::ParseOptions {
+            date_selection: DateSelection::Latest,
+            today: NaiveDate::from_ymd_opt(2024, 4, 30),
+            ..Default::default()
+        };
+        let info = crate::parser::MillsheetInfo::parse_with_options("2024年3月15日\n2024年5月1日", &options);
+        assert_eq!(info.date, IssueDate::new(2024, 3, 15));
+    }
+    
+    
+    #[test]
+    fn issue_and_test_dates_are_extracted_separately() {
+        // 試験日が先に書かれていても、リネームには発行日を使う
+        let text = "Date of Test 2024/03/10\n発行日 2024/03/15\n検査日: 2024.03.12";
+        let dates = extract_dates_by_label(text);
+        assert_eq!(dates.get("試験日").map(String::as_str), Some("24-03-10"));
+        assert_eq!(dates.get("発行日").map(String::as_str), Some("24-03-15"));
+        assert_eq!(dates.get("検査日").map(String::as_str), Some("24-03-12"));
+        assert_eq!(extract_date(text), IssueDate::new(2024, 3, 15));
+        
+        assert_eq!(select_date_by_priority(&dates, &["試験日", "発行日"]).as_deref(), Some("24-03-10"));
+        assert_eq!(select_date_by_priority(&dates, &["出荷日"]), None);
+    }
+    
+    
+    #[test]
+    fn out_of_range_dates_fall_through_to_next_candidate() {
+        // 13月・32日・4月31日は日付にしない
+        assert_eq!(extract_date("2024年13月1日"), None);
+        assert_eq!(extract_date("2024年3月32日"), None);
+        assert_eq!(extract_date("2024/04/31"), None);
+        assert_eq!(extract_date("Feb 30, 2024"), None);
+        // 範囲外の候補を飛ばして次の候補を使う
+        assert_eq!(extract_date("2024年13月1日\n2024年3月15日"), IssueDate::new(2024, 3, 15));
+        assert_eq!(extract_date("Feb 30, 2024 / Mar 15, 2024"), IssueDate::new(2024, 3, 15));
+    }
+    
+    #[test]
+    fn february_29_only_in_leap_years() {
+        assert_eq!(extract_date("2024年2月29日"), IssueDate::new(2024, 2, 29));
+        assert_eq!(extract_date("29 Feb 2024"), IssueDate::new(2024, 2, 29));
+        assert_eq!(extract_date("2023年2月29日"), None);
+        assert_eq!(extract_date("2100/02/29"), None);
+        assert_eq!(IssueDate::new(2000, 2, 29).map(|d| d.to_string()).as_deref(), Some("00-02-29"));
+    }
+    
+    
+    #[test]
+    fn english_dates_with_leading_weekday() {
+        assert_eq!(extract_date("Mon, 04 Aug 2025"), IssueDate::new(2025, 8, 4));
+        assert_eq!(extract_date("Monday 4 August, 2025"), IssueDate::new(2025, 8, 4));
+        assert_eq!(extract_date("Tues. Aug 5 2025"), IssueDate::new(2025, 8, 5));
+        // 曜日を除いても抽出根拠の位置は元のテキストのまま
+        let (_, found) = extract_date_match("Date: Mon, 04 Aug 2025").unwrap();
+        assert_eq!(&"Date: Mon, 04 Aug 2025"[found.span], "04 Aug 2025");
+    }
+    
+    
+    #[test]
+    fn fullwidth_dates_keep_spans_on_raw_text() {
+        let text = "発行日　２０２４／０３／１５\n";
+        assert_eq!(extract_date("２０２４年１月１５日"), IssueDate::new(2024, 1, 15));
+        assert_eq!(extract_date("令和６年１月１５日"), IssueDate::new(2024, 1, 15));
+        let (date, found) = extract_date_match(text).unwrap();
+        assert_eq!(date, IssueDate::new(2024, 3, 15).unwrap());
+        // 抽出根拠の位置は正規化前の全角のテキスト上
+        assert_eq!(&text[found.span], "２０２４／０３／１５");
+    }
 }
@@ -0,0 +1,324 @@
+//! 外部辞書モジュール - 材質パターン・メーカー表の追加定義
+
+use crate::parser::manufacturer::{normalize_corporate_form, CorporateFormStyle};
+use crate::parser::material::{material_aliases, MaterialAliasTable, MaterialPattern};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 辞書ファイルのパスを指定する環境変数
+pub const DICTIONARY_ENV: &str = "MILLSHEET_DICTIONARY";
+
+/// 既定の辞書ファイル名（実行ファイルと同じフォルダ）
+const DEFAULT_DICTIONARY_FILE: &str = "millsheet_dictionary.json";
+
+/// ヒントで書式を省略した場合のチャージ番号の書式
+const DEFAULT_CHARGE_NO_FORMAT: &str = "[A-Z0-9]{4,12}";
+
+/// メーカー定義
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManufacturerEntry {
+    /// 表示名
+    pub name: String,
+    /// 表記ゆれ
+    pub variants: Vec<String>,
+}
+
+/// メーカー別の抽出ヒント（メーカー確定後の再抽出に使う）
+///
+/// 汎用パターンで取れなかったチャージ番号を、メーカー固有のラベル表記・番号書式で探し直す
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "ManufacturerHintFile")]
+pub struct ManufacturerHint {
+    /// 対象メーカー（法人格の有無は問わない）
+    pub manufacturer: String,
+    /// チャージ番号のラベル表記（空ならラベルなしで書式だけで探す）
+    pub charge_no_labels: Vec<String>,
+    /// チャージ番号の書式（正規表現、省略時は英数字4〜12桁）
+    pub charge_no_format: Option<String>,
+    /// ラベルと書式で本文から探す正規表現（キャプチャ1が番号）
+    search: Regex,
+    /// 値が書式に完全一致するかを見る正規表現（書式の指定が無ければ None）
+    format: Option<Regex>,
+}
+
+/// 辞書ファイルのメーカー別ヒント（コンパイル前）
+#[derive(Deserialize)]
+struct ManufacturerHintFile {
+    manufacturer: String,
+    #[serde(default)]
+    charge_no_labels: Vec<String>,
+    #[serde(default)]
+    charge_no_format: Option<String>,
+}
+
+impl TryFrom<ManufacturerHintFile> for ManufacturerHint {
+    type Error = String;
+    
+    fn try_from(file: ManufacturerHintFile) -> std::result::Result<Self, Self::Error> {
+        Self::compile(file).map_err(|e| format!("{:#}", e))
+    }
+}
+
+impl ManufacturerHint {
+    /// ラベル・書式の正規表現をコンパイル
+    fn compile(file: ManufacturerHintFile) -> Result<Self> {
+        let format_pattern = file.charge_no_format.as_deref().unwrap_or(DEFAULT_CHARGE_NO_FORMAT);
+        let format = file
+            .charge_no_format
+            .as_ref()
+            .map(|format| Regex::new(&format!("^(?:{})$", format)))
+            .transpose()
+            .with_context(|| format!("チャージ番号の書式が不正です: {}", format_pattern))?;
+        let search = if file.charge_no_labels.is_empty() {
+            format!(r"\b({})\b", format_pattern)
+        } else {
+            let labels: Vec<String> = file.charge_no_labels.iter().map(|l| regex::escape(l)).collect();
+            format!(r"(?i:{})\s*[:\s]*({})", labels.join("|"), format_pattern)
+        };
+        let search = Regex::new(&search).with_context(|| format!("チャージ番号の書式が不正です: {}", format_pattern))?;
+        
+        Ok(Self {
+            manufacturer: file.manufacturer,
+            charge_no_labels: file.charge_no_labels,
+            charge_no_format: file.charge_no_format,
+            search,
+            format,
+        })
+    }
+    
+    /// ラベルと書式で本文から探す正規表現（キャプチャ1が番号）
+    pub(crate) fn search_regex(&self) -> &Regex {
+        &self.search
+    }
+    
+    /// 値が書式に完全一致するかを見る正規表現（書式の指定が無ければ None）
+    pub(crate) fn format_regex(&self) -> Option<&Regex> {
+        self.format.as_ref()
+    }
+}
+
+/// 外部辞書（読み込み時に正規表現をコンパイルし、解析のたびにはコンパイルしない）
+///
+/// ```json
+/// {
+///   "materials": ["NK\\d{3}HT"],
+///   "manufacturers": [{ "name": "JFEスチール", "variants": ["JFE STEEL", "JFEスチール"] }],
+///   "hints": [{ "manufacturer": "JFEスチール", "charge_no_labels": ["HEAT NO"], "charge_no_format": "[A-Z]\\d{5}" }],
+///   "material_aliases": { "18-8": "SUS304", "D2": "SKD11" }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(try_from = "DictionaryFile")]
+pub struct Dictionary {
+    /// 追加の材質パターン（正規表現）
+    pub materials: Vec<String>,
+    /// 追加のメーカー定義
+    pub manufacturers: Vec<ManufacturerEntry>,
+    /// メーカー別の抽出ヒント
+    pub hints: Vec<ManufacturerHint>,
+    /// 材質の略記・通称と正式な記号（組み込みの対応表に追加、同じ略記は上書き）
+    pub material_aliases: BTreeMap<String, String>,
+    /// コンパイル済みの材質パターン（`materials` と同じ順）
+    material_patterns: Vec<MaterialPattern>,
+    /// 組み込みの対応表に辞書の分を加えた略記表（辞書に略記が無ければ None で組み込みの表を使う）
+    alias_table: Option<MaterialAliasTable>,
+}
+
+/// 辞書ファイルの内容（コンパイル前）
+#[derive(Deserialize)]
+struct DictionaryFile {
+    #[serde(default)]
+    materials: Vec<String>,
+    #[serde(default)]
+    manufacturers: Vec<ManufacturerEntry>,
+    #[serde(default)]
+    hints: Vec<ManufacturerHint>,
+    #[serde(default)]
+    material_aliases: BTreeMap<String, String>,
+}
+
+impl TryFrom<DictionaryFile> for Dictionary {
+    type Error = String;
+    
+    fn try_from(file: DictionaryFile) -> std::result::Result<Self, Self::Error> {
+        Self::compile(file).map_err(|e| format!("{:#}", e))
+    }
+}
+
+impl Dictionary {
+    /// 辞書ファイルを読み込む（正規表現が不正な場合はエラー）
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("辞書ファイルの読み込みに失敗: {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("辞書ファイルのパースに失敗: {:?}", path))
+    }
+    
+    /// 辞書ファイルの正規表現をコンパイル
+    fn compile(file: DictionaryFile) -> Result<Self> {
+        let material_patterns = file
+            .materials
+            .iter()
+            .map(|pattern| MaterialPattern::new(pattern))
+            .collect::<Result<Vec<_>>>()?;
+        let alias_table = (!file.material_aliases.is_empty()).then(|| merge_material_aliases(&file.material_aliases));
+        
+        Ok(Self {
+            materials: file.materials,
+            manufacturers: file.manufacturers,
+            hints: file.hints,
+            material_aliases: file.material_aliases,
+            material_patterns,
+            alias_table,
+        })
+    }
+    
+    /// コンパイル済みの追加の材質パターン
+    pub fn material_patterns(&self) -> &[MaterialPattern] {
+        &self.material_patterns
+    }
+    
+    /// 組み込みの対応表に辞書の分を加えた材質の略記表（辞書の略記を優先）
+    pub fn material_aliases(&self) -> &MaterialAliasTable {
+        self.alias_table.as_ref().unwrap_or(MaterialAliasTable::builtin())
+    }
+    
+    /// メーカー名に対応する抽出ヒント（法人格を除いて比較）
+    pub fn hint_for(&self, manufacturer: &str) -> Option<&ManufacturerHint> {
+        let name = normalize_corporate_form(manufacturer, CorporateFormStyle::Strip);
+        self.hints
+            .iter()
+            .find(|h| normalize_corporate_form(&h.manufacturer, CorporateFormStyle::Strip) == name)
+    }
+}
+
+/// 辞書ファイルのパスを決定（環境変数 > 実行ファイルと同じフォルダ）
+pub fn default_dictionary_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(DICTIONARY_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    let path = exe_dir.join(DEFAULT_DICTIONARY_FILE);
+    path.exists().then_some(path)
+}
+
+/// 組み込みの対応表に辞書の略記を加えた略記表（同じ略記は辞書の方を使う）
+fn merge_material_aliases(dictionary: &BTreeMap<String, String>) -> MaterialAliasTable {
+    let mut table: Vec<(&str, &str)> = dictionary
+        .iter()
+        .map(|(alias, material)| (alias.as_str(), material.as_str()))
+        .collect();
+    for &(alias, material) in material_aliases() {
+        if !table.iter().any(|(a, _)| a.eq_ignore_ascii_case(alias)) {
+            table.push((alias, material));
+        }
+    }
+    MaterialAliasTable::new(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::material::extract_material_alias_match_with;
+    use crate::parser::{MillsheetInfo, ParseOptions};
+    use std::sync::Arc;
+    
+    fn parse_dictionary(json: &str) -> std::result::Result<Dictionary, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+    
+    #[test]
+    fn material_patterns_are_compiled_when_loaded() {
+        let dictionary = parse_dictionary(r#"{ "materials": ["NK\\d{3}HT", "KA36"] }"#).unwrap();
+        let patterns: Vec<&str> = dictionary.material_patterns().iter().map(MaterialPattern::as_str).collect();
+        assert_eq!(patterns, [r"NK\d{3}HT", "KA36"]);
+    }
+    
+    #[test]
+    fn invalid_material_pattern_is_rejected_when_loaded() {
+        let error = parse_dictionary(r#"{ "materials": ["NK(\\d"] }"#).unwrap_err();
+        assert!(error.to_string().contains("材質パターンが不正です"), "{}", error);
+    }
+    
+    #[test]
+    fn invalid_charge_no_format_is_rejected_when_loaded() {
+        let error = parse_dictionary(r#"{ "hints": [{ "manufacturer": "日本製鉄", "charge_no_format": "[A-Z" }] }"#).unwrap_err();
+        assert!(error.to_string().contains("チャージ番号の書式が不正です"), "{}", error);
+    }
+    
+    #[test]
+    fn hint_regexes_are_compiled_when_loaded() {
+        let dictionary = parse_dictionary(
+            r#"{ "hints": [
+                { "manufacturer": "日本製鉄", "charge_no_labels": ["製造ロット", "LOT"], "charge_no_format": "\\d[A-Z]\\d{3}" },
+                { "manufacturer": "JFEスチール" }
+            ] }"#,
+        )
+        .unwrap();
+        let labeled = &dictionary.hints[0];
+        assert_eq!(labeled.search_regex().as_str(), r"(?i:製造ロット|LOT)\s*[:\s]*(\d[A-Z]\d{3})");
+        assert!(labeled.format_regex().is_some_and(|re| re.is_match("7K123") && !re.is_match("7K1234")));
+        
+        let bare = &dictionary.hints[1];
+        assert_eq!(bare.search_regex().as_str(), r"\b([A-Z0-9]{4,12})\b");
+        assert!(bare.format_regex().is_none());
+    }
+    
+    #[test]
+    fn hint_finds_charge_no_missed_by_generic_patterns() {
+        let text = "日本製鉄株式会社\n材質 SS400\n製造ロット 7K123";
+        assert_eq!(MillsheetInfo::parse(text).charge_no, None);
+        
+        let dictionary = parse_dictionary(
+            r#"{ "hints": [{ "manufacturer": "日本製鉄", "charge_no_labels": ["製造ロット"], "charge_no_format": "\\d[A-Z]\\d{3}" }] }"#,
+        )
+        .unwrap();
+        let options = ParseOptions {
+            dictionary: Some(Arc::new(dictionary)),
+            ..Default::default()
+        };
+        assert_eq!(MillsheetInfo::parse_with_options(text, &options).charge_no.as_deref(), Some("7K123"));
+    }
+    
+    #[test]
+    fn dictionary_material_takes_priority_over_builtin() {
+        let dictionary = parse_dictionary(r#"{ "materials": ["NK\\d{3}HT"] }"#).unwrap();
+        let options = ParseOptions {
+            dictionary: Some(Arc::new(dictionary)),
+            ..Default::default()
+        };
+        let info = MillsheetInfo::parse_with_options("GRADE nk590ht / SS400", &options);
+        assert_eq!(info.material.as_deref(), Some("NK590HT"));
+        assert_eq!(info.sub_materials, ["SS400"]);
+    }
+    
+    #[test]
+    fn dictionary_aliases_override_builtin_aliases() {
+        let dictionary = parse_dictionary(r#"{ "material_aliases": { "D2": "SKD11", "304": "SUS304J1" } }"#).unwrap();
+        let found = |text| extract_material_alias_match_with(text, dictionary.material_aliases()).map(|m| m.value);
+        assert_eq!(found("材質 D2").as_deref(), Some("SKD11"));
+        assert_eq!(found("材質 304").as_deref(), Some("SUS304J1"));
+        assert_eq!(found("材質 18-8").as_deref(), Some("SUS304"));
+    }
+    
+    #[test]
+    fn dictionary_without_aliases_uses_builtin_table() {
+        let dictionary = parse_dictionary(r#"{ "materials": ["KA36"] }"#).unwrap();
+        assert!(std::ptr::eq(dictionary.material_aliases(), MaterialAliasTable::builtin()));
+        assert!(std::ptr::eq(Dictionary::default().material_aliases(), MaterialAliasTable::builtin()));
+    }
+    
+    #[test]
+    fn load_reads_and_compiles_the_file() {
+        let path = std::env::temp_dir().join(format!("millsheet_dictionary_test_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{ "materials": ["KA36"] }"#).unwrap();
+        let dictionary = Dictionary::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(dictionary.unwrap().material_patterns().len(), 1);
+    }
+}
@@ -1,136 +1,542 @@
 //! 寸法抽出モジュール
 
+use super::{compile_all, compile_builtin, mask_spans, FieldMatch, NormalizedText};
 use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// 丸棒・パイプの径寸法（φ25×4000 / Φ25.4 x L=2000、長さの "L=" は読み捨てる）
+const DIAMETER_PATTERN: &str = r"[φΦ⌀]\s*(\d+\.?\d*)\s*[xX×]\s*(?:L\s*=?\s*)?(\d+)";
+
+/// インチの単位表記（" / ” / ″ / in / inch）
+const INCH_UNIT: &str = r#"(?:"|”|″|\s*in(?:ch(?:es)?)?\b)"#;
+
+/// 1インチあたりのmm
+const MM_PER_INCH: f64 = 25.4;
+
+/// 厚さだけ読めたときに幅の候補を探す範囲（厚さの後ろのバイト数）
+const WIDTH_SEARCH_WINDOW: usize = 80;
+
+/// 補完した幅として認める厚さとの比率（幅 / 厚さ）
+const WIDTH_RATIO_RANGE: std::ops::RangeInclusive<f64> = 10.0..=10000.0;
+
+/// 寸法の妥当性ルール
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DimensionProfile {
+    /// コイル製品向け（幅・長さとも100mm以上）
+    #[default]
+    Coil,
+    /// 定尺板向け（小さな切板も通すよう幅・長さの下限を10mmに下げる）
+    Sheet,
+}
+
+impl DimensionProfile {
+    /// 全プロファイル（設定画面の選択肢順）
+    pub const ALL: [DimensionProfile; 2] = [DimensionProfile::Coil, DimensionProfile::Sheet];
+    
+    /// 表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            DimensionProfile::Coil => "コイル（幅・長さ100mm以上）",
+            DimensionProfile::Sheet => "定尺板（幅・長さ10mm以上）",
+        }
+    }
+    
+    /// 幅・数値の長さの下限（mm）
+    fn min_extent(self) -> f64 {
+        match self {
+            DimensionProfile::Coil => 100.0,
+            DimensionProfile::Sheet => 10.0,
+        }
+    }
+}
+
+/// 長さ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+pub enum LengthValue {
+    /// コイル（長さの指定なし）
+    Coil,
+    /// 長さ（mm）
+    Mm(f64),
+}
+
+/// 板の寸法（mm、単位変換できるよう数値で保持する）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+pub struct Dimensions {
+    /// 厚さ
+    pub thickness: f64,
+    /// 幅
+    pub width: f64,
+    /// 長さ（厚さ x 幅 のみの表記なら None）
+    pub length: Option<LengthValue>,
+}
+
+/// 寸法の要素（ファイル名テンプレートの `{thickness}` `{width}` `{length}`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DimensionPart {
+    /// 厚さ
+    Thickness,
+    /// 幅
+    Width,
+    /// 長さ（コイルは "C"）
+    Length,
+}
+
+impl DimensionPart {
+    /// すべての要素
+    pub const ALL: [DimensionPart; 3] = [DimensionPart::Thickness, DimensionPart::Width, DimensionPart::Length];
+    
+    /// テンプレートでの名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            DimensionPart::Thickness => "thickness",
+            DimensionPart::Width => "width",
+            DimensionPart::Length => "length",
+        }
+    }
+    
+    /// テンプレートでの名前から要素を取得
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|part| part.name() == name)
+    }
+}
+
+/// 寸法の要素を文字列にするときの書式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DimensionFormat {
+    /// 整数に丸める（1.6 → 2）
+    pub integer: bool,
+    /// 単位 mm を付ける（1.6 → 1.6mm、コイルの "C" には付けない）
+    pub unit: bool,
+}
+
+impl Dimensions {
+    /// 要素を書式に従って文字列にする（長さの記載がなければ None）
+    pub fn part(&self, part: DimensionPart, format: DimensionFormat) -> Option<String> {
+        let value = match part {
+            DimensionPart::Thickness => self.thickness,
+            DimensionPart::Width => self.width,
+            DimensionPart::Length => match self.length? {
+                LengthValue::Coil => return Some("C".to_string()),
+                LengthValue::Mm(length) => length,
+            },
+        };
+        
+        let value = if format.integer { value.round() } else { value };
+        let unit = if format.unit { "mm" } else { "" };
+        Some(format!("{}{}", format_number(value), unit))
+    }
+}
+
+/// ファイル名用の表記（1.6x1219xC / 1.6x1219x2438 / 1.6x1219）
+impl fmt::Display for Dimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", format_number(self.thickness), format_number(self.width))?;
+        match self.length {
+            Some(LengthValue::Coil) => write!(f, "xC"),
+            Some(LengthValue::Mm(length)) => write!(f, "x{}", format_number(length)),
+            None => Ok(()),
+        }
+    }
+}
 
 /// テキストから寸法を抽出
-/// フォーマット: 厚さ x 幅 x 長さ/COIL
+/// フォーマット: 厚さ x 幅 x 長さ/COIL、丸棒・パイプは D径 x 長さ
+///
+/// 各数値の後ろの単位 mm は読み飛ばす
 pub fn extract_dimensions(text: &str) -> Option<String> {
-    // 寸法セクションを探す
-    let dimension_section = find_dimension_section(text);
+    extract_dimensions_match(text).map(|m| m.value)
+}
+
+/// テキストから板の寸法を数値で抽出
+///
+/// 丸棒・パイプの径寸法や厚さしか読めなかった場合は None
+pub fn extract_dimensions_structured(text: &str) -> Option<Dimensions> {
+    extract_dimensions_spec_match(text).and_then(|(dims, _)| dims)
+}
+
+/// 寸法と抽出根拠を取得
+pub fn extract_dimensions_match(text: &str) -> Option<FieldMatch> {
+    extract_dimensions_spec_match(text).map(|(_, found)| found)
+}
+
+/// 板の寸法（板以外は None）と抽出根拠を取得
+///
+/// 厚さしか寸法の形で読めない場合は、厚さの近くにある妥当な数値を幅として補完する（`inferred` が立つ）
+pub fn extract_dimensions_spec_match(text: &str) -> Option<(Option<Dimensions>, FieldMatch)> {
+    extract_dimensions_spec_match_with(text, DimensionProfile::default())
+}
+
+/// 妥当性ルールを指定して板の寸法と抽出根拠を取得
+pub fn extract_dimensions_spec_match_with(
+    text: &str,
+    profile: DimensionProfile,
+) -> Option<(Option<Dimensions>, FieldMatch)> {
+    let normalized = NormalizedText::new(text);
+    find_dimensions(&normalized.text, profile).map(|(dims, m)| (dims, normalized.remap(m)))
+}
+
+/// 正規化済みテキストから寸法を探す
+fn find_dimensions(text: &str, profile: DimensionProfile) -> Option<(Option<Dimensions>, FieldMatch)> {
+    // 寸法・SIZE 欄の径記号は丸棒・パイプの寸法として扱う
+    let diameter = extract_diameter(text);
+    if let Some(found) = &diameter
+        && SIZE_SECTION_REGEX.find_iter(text).any(|m| m.range().contains(&found.span.start))
+    {
+        return diameter.map(|found| (None, found));
+    }
     
-    let search_texts: Vec<&str> = if let Some(ref section) = dimension_section {
-        vec![section.as_str(), text]
-    } else {
-        vec![text]
+    // 寸法セクションを優先して探す
+    if let Some((section, offset)) = find_dimension_section(text)
+        && let Some((dims, found)) = try_extract_dimensions(&section, profile)
+    {
+        // セクション内の位置を元テキストの位置に戻す
+        let matched = &section[found.span.clone()];
+        if let Some(pos) = text[offset..].find(matched) {
+            let start = offset + pos;
+            return Some((Some(dims), FieldMatch { span: start..start + matched.len(), ..found }));
+        }
+    }
+    
+    // 曲げ試験の心金径（φ25×180 など）は板の寸法があればそちらを優先する（径の部分を厚さ×幅と読まないよう伏せて探す）
+    let plate_text = match &diameter {
+        Some(found) => mask_spans(text, std::slice::from_ref(&found.span)),
+        None => text.to_string(),
     };
+    if let Some((dims, found)) = try_extract_dimensions(&plate_text, profile) {
+        return Some((Some(dims), found));
+    }
+    if diameter.is_some() {
+        return diameter.map(|found| (None, found));
+    }
     
-    for search_text in search_texts {
-        if let Some(dims) = try_extract_dimensions(search_text) {
-            return Some(dims);
-        }
+    // 輸入材のインチ表記（0.25" x 48" x COIL）
+    if let Some((dims, found)) = extract_inch_dimensions(text, profile) {
+        return Some((Some(dims), found));
     }
     
-    // フォールバック: 厚さのみ抽出
-    extract_thickness_only(text)
+    // 救済: 区切り記号が欠落して連結された寸法
+    if let Some((dims, found)) = infer_concatenated_dimensions(text, profile) {
+        return Some((Some(dims), FieldMatch { inferred: true, ..found }));
+    }
+    
+    // フォールバック: 厚さのみ抽出し、近くの数値から幅を補完
+    let thickness = extract_thickness_only(text)?;
+    if let Some((dims, found)) = complete_width(text, &thickness, profile) {
+        return Some((Some(dims), FieldMatch { inferred: true, ..found }));
+    }
+    Some((None, thickness))
 }
 
-/// 寸法セクションを探す
-fn find_dimension_section(text: &str) -> Option<String> {
-    if let Ok(re) = Regex::new(r"(?i)(?:DIMENSIONS?|寸法)[^\n]*\n?([^\n]+)") {
-        if let Some(caps) = re.captures(text) {
-            let full = caps.get(0)?.as_str();
-            let next_line = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            return Some(format!("{}{}", full, next_line));
-        }
+/// 寸法のラベル
+static DIMENSION_LABEL_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(r"(?i)DIMENSIONS?|寸法"));
+
+/// 寸法・SIZE のラベルから次の行まで（径記号がこの中にあれば丸棒・パイプの寸法）
+static SIZE_SECTION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| compile_builtin(r"(?i)(?:DIMENSIONS?|寸法|SIZE)[^\n]*\n?[^\n]*"));
+
+/// 寸法のラベルから次の行まで
+static DIMENSION_SECTION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| compile_builtin(r"(?i)(?:DIMENSIONS?|寸法)[^\n]*\n?([^\n]+)"));
+
+/// 寸法セクションを探す（セクション文字列と元テキスト中の開始位置）
+fn find_dimension_section(text: &str) -> Option<(String, usize)> {
+    if let Some(caps) = DIMENSION_SECTION_REGEX.captures(text) {
+        let full = caps.get(0)?;
+        let next_line = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+        return Some((format!("{}{}", full.as_str(), next_line), full.start()));
     }
     None
 }
 
+/// 寸法のパターンとキャプチャグループ数（具体的なものから汎用的なものへ）
+const DIMENSION_PATTERNS: [(&str, usize); 9] = [
+    // 22. 00X1, 540XCOIL (OCRで空白が入るパターン)
+    (r"(\d{1,2})\.\s*(\d{2})\s*[xX×]\s*(\d)[,.]?\s*(\d{3})\s*[xX×]\s*(COIL|コイル|C)\b", 5),
+    // 22.00X1.540XCOIL (小数点が幅に入るパターン)
+    (r"(\d{1,2}\.?\d{0,2})[xX×](\d\.\d{3})[xX×](COIL|コイル|C)\b", 3),
+    // 1.60X1,535XCOIL (カンマ区切り幅)
+    (r"(\d+\.?\d*)\s*[xX×]\s*(\d{1,2},\d{3})\s*[xX×]\s*(COIL|コイル|C)\b", 3),
+    // 1.6x1535xCOIL (標準パターン)
+    (r"(\d+\.?\d*)\s*[xX×]\s*(\d{3,4})\s*[xX×]\s*(COIL|コイル|C)\b", 3),
+    // 1.6X1219X2.438m (メートル表記の長さ)
+    (r"(\d+\.?\d*)\s*[xX×]\s*(\d{1,2},\d{3}|\d{3,4})\s*[xX×]\s*(\d{1,2}(?:\.\d{1,3})?\s*m)\b", 3),
+    // 1.6X1219X2438 / 1.6X1219X2438mm (数値長さ)
+    (r"(\d+\.?\d*)\s*[xX×]\s*(\d{3,4})\s*[xX×]\s*(\d{3,4})(?:\s*mm)?", 3),
+    // t1.6 x 1219 x COIL
+    (r"t\s*(\d+\.?\d*)\s*[xX×]\s*(\d+\.?\d*)\s*[xX×]\s*(COIL|コイル|C|\d+\.?\d*)(?:\s*mm)?", 3),
+    // 板厚1.6 幅1219
+    (r"板厚\s*(\d+\.?\d*)\s*.*?幅\s*(\d+\.?\d*)", 2),
+    // 1.6t x 1219W
+    (r"(\d+\.?\d*)\s*[tT]\s*[xX×]\s*(\d+\.?\d*)\s*[wW]?", 2),
+];
+
+/// 定尺板: 2〜3桁の小さな幅・長さ（コイル・通常の数値長さのパターンより後に試す）
+const SHEET_DIMENSION_PATTERN: (&str, usize) = (r"(\d+\.?\d*)\s*[xX×]\s*(\d{2,4})\s*[xX×]\s*(\d{2,4})(?:\s*mm)?\b", 3);
+
+/// 定尺板のパターンを差し込む位置
+const SHEET_DIMENSION_PATTERN_INDEX: usize = 6;
+
+/// コンパイル済みの寸法パターン（正規表現、抽出根拠に記録するパターン、グループ数）
+type DimensionRegexes = Vec<(Regex, String, usize)>;
+
+/// コイル・汎用の寸法パターン
+static COIL_DIMENSION_REGEXES: LazyLock<DimensionRegexes> = LazyLock::new(|| compile_dimension_patterns(DIMENSION_PATTERNS.to_vec()));
+
+/// 定尺板の寸法パターン（定尺板のパターンを差し込んだもの）
+static SHEET_DIMENSION_REGEXES: LazyLock<DimensionRegexes> = LazyLock::new(|| {
+    let mut patterns = DIMENSION_PATTERNS.to_vec();
+    patterns.insert(SHEET_DIMENSION_PATTERN_INDEX, SHEET_DIMENSION_PATTERN);
+    compile_dimension_patterns(patterns)
+});
+
+/// 寸法パターンに mm 単位の読み捨てを加えてコンパイル
+fn compile_dimension_patterns(patterns: Vec<(&str, usize)>) -> DimensionRegexes {
+    patterns
+        .into_iter()
+        .map(|(pattern, group_count)| {
+            let pattern = with_mm_units(pattern);
+            (compile_builtin(&format!("(?i){}", pattern)), pattern, group_count)
+        })
+        .collect()
+}
+
+/// プロファイルに応じたコンパイル済みの寸法パターン
+fn dimension_regexes(profile: DimensionProfile) -> &'static [(Regex, String, usize)] {
+    if profile == DimensionProfile::Sheet {
+        &SHEET_DIMENSION_REGEXES
+    } else {
+        &COIL_DIMENSION_REGEXES
+    }
+}
+
+/// 寸法候補の順位（スコア、グループ数、パターンの並び順・出現位置の早さ）
+type CandidateRank = (i32, usize, Reverse<usize>, Reverse<usize>);
+
 /// 寸法の抽出を試みる
-fn try_extract_dimensions(text: &str) -> Option<String> {
-    // パターン定義 (具体的なものから汎用的なものへ)
-    let patterns: Vec<(&str, usize)> = vec![
-        // 22. 00X1, 540XCOIL (OCRで空白が入るパターン)
-        (r"(\d{1,2})\.\s*(\d{2})\s*[xX×]\s*(\d)[,.]?\s*(\d{3})\s*[xX×]\s*(COIL|コイル|C)\b", 5),
-        // 22.00X1.540XCOIL (小数点が幅に入るパターン)
-        (r"(\d{1,2}\.?\d{0,2})[xX×](\d\.\d{3})[xX×](COIL|コイル|C)\b", 3),
-        // 1.60X1,535XCOIL (カンマ区切り幅)
-        (r"(\d+\.?\d*)\s*[xX×]\s*(\d{1,2},\d{3})\s*[xX×]\s*(COIL|コイル|C)\b", 3),
-        // 1.6x1535xCOIL (標準パターン)
-        (r"(\d+\.?\d*)\s*[xX×]\s*(\d{3,4})\s*[xX×]\s*(COIL|コイル|C)\b", 3),
-        // 1.6X1219X2438 (数値長さ)
-        (r"(\d+\.?\d*)\s*[xX×]\s*(\d{3,4})\s*[xX×]\s*(\d{3,4})", 3),
-        // t1.6 x 1219 x COIL
-        (r"t\s*(\d+\.?\d*)\s*[xX×]\s*(\d+\.?\d*)\s*[xX×]\s*(COIL|コイル|C|\d+\.?\d*)", 3),
-        // 板厚1.6 幅1219
-        (r"板厚\s*(\d+\.?\d*)\s*.*?幅\s*(\d+\.?\d*)", 2),
-        // 1.6t x 1219W
-        (r"(\d+\.?\d*)\s*[tT]\s*[xX×]\s*(\d+\.?\d*)\s*[wW]?", 2),
-    ];
-    
-    for (pattern, group_count) in patterns {
-        if let Ok(re) = Regex::new(&format!("(?i){}", pattern)) {
-            for caps in re.captures_iter(text) {
-                if let Some(dims) = parse_dimension_groups(&caps, group_count) {
-                    return Some(dims);
-                }
+///
+/// 全パターンの全マッチを候補として集め、`score_dimension` のスコアが最も高いものを返す。
+/// 同点なら具体的なパターン（キャプチャグループの多いもの）、次にパターンの並び順・出現位置の早いものを優先する
+fn try_extract_dimensions(text: &str, profile: DimensionProfile) -> Option<(Dimensions, FieldMatch)> {
+    let label_end = DIMENSION_LABEL_REGEX.find(text).map(|m| m.end());
+    
+    let mut best: Option<(CandidateRank, Dimensions, FieldMatch)> = None;
+    for (index, (re, pattern, group_count)) in dimension_regexes(profile).iter().enumerate() {
+        let group_count = *group_count;
+        for caps in re.captures_iter(text) {
+            let Some(dims) = parse_dimension_groups(&caps, group_count, profile) else {
+                continue;
+            };
+            let matched = caps.get(0)?;
+            let distance = label_end.map(|end| matched.start().abs_diff(end));
+            let score = score_dimension(&dims, matched.as_str(), distance);
+            let key = (score, group_count, Reverse(index), Reverse(matched.start()));
+            if best.as_ref().is_none_or(|(best_key, _, _)| key > *best_key) {
+                best = Some((key, dims, FieldMatch::new(dims.to_string(), matched.range(), pattern)));
             }
         }
     }
     
-    None
+    best.map(|(_, dims, found)| (dims, found))
+}
+
+/// 区切り記号の前に単位 mm を許す（"1.6mm x 1219mm x 2438mm"、値のキャプチャには含めない）
+fn with_mm_units(pattern: &str) -> String {
+    pattern.replace(r"\s*[xX×]", r"(?:\s*mm)?\s*[xX×]")
+}
+
+/// 寸法候補のもっともらしさを採点（高いほど良い）
+///
+/// 桁数の整合性（厚さは小数2桁まで、幅・長さは整数で3〜4桁、コイル表記）、区切り記号のそろい具合、
+/// "DIMENSIONS" / "寸法" の見出しからの距離（バイト数、見出しが無ければ None）で加点する
+pub fn score_dimension(dims: &Dimensions, matched: &str, section_distance: Option<usize>) -> i32 {
+    let is_integer = |v: f64| v == v.trunc();
+    let mut score = 0;
+    
+    // 厚さは小数2桁まで
+    if is_integer(dims.thickness * 100.0) {
+        score += 2;
+    }
+    // 幅は3〜4桁の整数
+    if is_integer(dims.width) && (100.0..10000.0).contains(&dims.width) {
+        score += 2;
+    }
+    match dims.length {
+        Some(LengthValue::Coil) => score += 2,
+        Some(LengthValue::Mm(length)) if is_integer(length) && (100.0..100000.0).contains(&length) => score += 2,
+        Some(LengthValue::Mm(_)) => score += 1,
+        None => {}
+    }
+    
+    // OCRノイズが無ければ区切り記号は同じ文字になる
+    let separators: Vec<char> = matched.chars().filter(|c| matches!(c, 'x' | 'X' | '×')).collect();
+    if separators.len() >= 2 && separators.windows(2).all(|w| w[0] == w[1]) {
+        score += 1;
+    }
+    
+    // 見出しに近いほど加点（200バイト以内）
+    if let Some(distance) = section_distance {
+        score += 4 - (distance / 50).min(4) as i32;
+    }
+    
+    score
 }
 
 /// キャプチャグループから寸法を解析
-fn parse_dimension_groups(caps: &regex::Captures, group_count: usize) -> Option<String> {
-    match group_count {
-        5 => {
-            // "22. 00X1, 540XCOIL" パターン
-            let thickness = format!("{}.{}", caps.get(1)?.as_str(), caps.get(2)?.as_str());
-            let width = format!("{}{}", caps.get(3)?.as_str(), caps.get(4)?.as_str());
-            let length = caps.get(5)?.as_str();
-            
-            if is_valid_dimension(&thickness, &width, Some(length)) {
-                let t = format_thickness(&thickness);
-                let l = normalize_length(length);
-                return Some(format!("{}x{}x{}", t, width, l));
+fn parse_dimension_groups(caps: &regex::Captures, group_count: usize, profile: DimensionProfile) -> Option<Dimensions> {
+    let (thickness, width, length) = match group_count {
+        // "22. 00X1, 540XCOIL" パターン
+        5 => (
+            format!("{}.{}", caps.get(1)?.as_str(), caps.get(2)?.as_str()),
+            format!("{}{}", caps.get(3)?.as_str(), caps.get(4)?.as_str()),
+            Some(normalize_length_unit(caps.get(5)?.as_str())),
+        ),
+        3 => (
+            caps.get(1)?.as_str().to_string(),
+            process_width(caps.get(2)?.as_str()),
+            Some(normalize_length_unit(caps.get(3)?.as_str())),
+        ),
+        2 => (
+            caps.get(1)?.as_str().to_string(),
+            process_width(caps.get(2)?.as_str()),
+            None,
+        ),
+        _ => return None,
+    };
+    
+    if !is_valid_dimension(&thickness, &width, length.as_deref(), profile) {
+        return None;
+    }
+    
+    let length = match length {
+        Some(length) => Some(parse_length(&length)?),
+        None => None,
+    };
+    Some(Dimensions {
+        thickness: thickness.parse().ok()?,
+        width: width.parse().ok()?,
+        length,
+    })
+}
+
+/// 区切り記号の欠落した寸法のパターン
+const CONCATENATED_DIMENSION_PATTERNS: [&str; 3] = [
+    // 1.61219C / 1.61219XCOIL (厚さと幅の間が欠落)
+    r"\b(\d{1,2}\.\d)(\d{4})\s*[xX×]?\s*(COIL|コイル|C)\b",
+    // 1.61219X2438 (厚さと幅の間が欠落、数値長さ)
+    r"\b(\d{1,2}\.\d)(\d{4})\s*[xX×]\s*(\d{3,4})\b",
+    // 1.6X1219C (幅と長さの間が欠落)
+    r"\b(\d{1,2}\.\d{1,2})\s*[xX×]\s*(\d{3,4})(COIL|コイル|C)\b",
+];
+
+/// コンパイル済みの区切り記号の欠落した寸法のパターン
+static CONCATENATED_DIMENSION_REGEXES: LazyLock<Vec<Regex>> =
+    LazyLock::new(|| compile_all(CONCATENATED_DIMENSION_PATTERNS.iter().map(|pattern| format!("(?i){}", pattern))));
+
+/// 区切り記号の欠落した寸法を桁数パターンから推定（"1.61219C" -> 1.6x1219xC）
+/// 誤補完を避けるため、厚さは小数1桁・幅は4桁の場合に限定する
+fn infer_concatenated_dimensions(text: &str, profile: DimensionProfile) -> Option<(Dimensions, FieldMatch)> {
+    for (re, pattern) in CONCATENATED_DIMENSION_REGEXES.iter().zip(CONCATENATED_DIMENSION_PATTERNS) {
+        for caps in re.captures_iter(text) {
+            if let Some(dims) = parse_dimension_groups(&caps, 3, profile) {
+                return Some((dims, FieldMatch::new(dims.to_string(), caps.get(0)?.range(), pattern)));
             }
         }
-        3 => {
-            let thickness = caps.get(1)?.as_str();
-            let width_raw = caps.get(2)?.as_str();
-            let length = caps.get(3)?.as_str();
-            
-            let width = process_width(width_raw);
-            
-            if is_valid_dimension(thickness, &width, Some(length)) {
-                let t = format_thickness(thickness);
-                let l = normalize_length(length);
-                return Some(format!("{}x{}x{}", t, width, l));
-            }
+    }
+    
+    None
+}
+
+/// インチ表記の寸法（単位の付いた厚さ・幅と、コイルまたは単位が任意の長さ）
+static INCH_DIMENSION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    let number = r"(\d+(?:\.\d+)?|\.\d+)";
+    compile_builtin(&format!(
+        r"(?i){number}{INCH_UNIT}\s*[xX×]\s*{number}{INCH_UNIT}(?:\s*[xX×]\s*(?:(COIL|コイル|C)\b|{number}({INCH_UNIT})?))?"
+    ))
+});
+
+/// インチ表記の寸法をmmに換算して抽出
+///
+/// ミリとインチの混在で取り違えないよう、単位の付いた数値だけを換算する（単位のない長さはmmのまま）
+fn extract_inch_dimensions(text: &str, profile: DimensionProfile) -> Option<(Dimensions, FieldMatch)> {
+    let re = &*INCH_DIMENSION_REGEX;
+    for caps in re.captures_iter(text) {
+        let to_mm = |inches: &str| inches.parse::<f64>().ok().map(inch_to_mm);
+        let (Some(thickness), Some(width)) = (to_mm(caps.get(1)?.as_str()), to_mm(caps.get(2)?.as_str())) else {
+            continue;
+        };
+        let length = match (caps.get(3), caps.get(4)) {
+            (Some(coil), _) => Some(coil.as_str().to_string()),
+            (None, Some(value)) if caps.get(5).is_some() => to_mm(value.as_str()).map(format_number),
+            (None, Some(value)) => Some(value.as_str().to_string()),
+            (None, None) => None,
+        };
+        
+        let thickness = format_number(thickness);
+        let width = format_number(width);
+        if !is_valid_dimension(&thickness, &width, length.as_deref(), profile) {
+            continue;
         }
-        2 => {
-            let thickness = caps.get(1)?.as_str();
-            let width_raw = caps.get(2)?.as_str();
-            
-            let width = process_width(width_raw);
-            
-            if is_valid_dimension(thickness, &width, None) {
-                let t = format_thickness(thickness);
-                return Some(format!("{}x{}", t, width));
-            }
+        
+        let length = match length {
+            Some(length) => Some(parse_length(&length)?),
+            None => None,
+        };
+        let dims = Dimensions {
+            thickness: thickness.parse().ok()?,
+            width: width.parse().ok()?,
+            length,
+        };
+        return Some((dims, FieldMatch::new(dims.to_string(), caps.get(0)?.range(), re.as_str())));
+    }
+    
+    None
+}
+
+/// インチをmmに換算（0.01mm単位に丸める）
+fn inch_to_mm(inches: f64) -> f64 {
+    (inches * MM_PER_INCH * 100.0).round() / 100.0
+}
+
+/// コンパイル済みの径寸法パターン
+static DIAMETER_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(DIAMETER_PATTERN));
+
+/// 丸棒・パイプの径寸法を抽出（"D25x4000" の形式）
+fn extract_diameter(text: &str) -> Option<FieldMatch> {
+    for caps in DIAMETER_REGEX.captures_iter(text) {
+        let diameter = caps.get(1)?.as_str();
+        let length = caps.get(2)?.as_str();
+        if is_valid_diameter(diameter, length) {
+            let dims = format!("D{}x{}", format_thickness(diameter), length);
+            return Some(FieldMatch::new(dims, caps.get(0)?.range(), DIAMETER_PATTERN));
         }
-        _ => {}
     }
     
     None
 }
 
+/// 厚さのみのパターン（フォールバック）
+const THICKNESS_ONLY_PATTERNS: [&str; 2] = [
+    r"(?:寸法|Size)[\s\S]{0,100}?(\d{1,2}\.\d{1,2})\s*[xX×]",
+    r"(\d{1,2}\.\d{2})\s*[xX×]\s*\d",
+];
+
+/// コンパイル済みの厚さのみのパターン
+static THICKNESS_ONLY_REGEXES: LazyLock<Vec<Regex>> =
+    LazyLock::new(|| compile_all(THICKNESS_ONLY_PATTERNS.iter().map(|pattern| format!("(?i){}", pattern))));
+
 /// 厚さのみを抽出（フォールバック）
-fn extract_thickness_only(text: &str) -> Option<String> {
-    let patterns = [
-        r"(?:寸法|Size)[\s\S]{0,100}?(\d{1,2}\.\d{1,2})\s*[xX×]",
-        r"(\d{1,2}\.\d{2})\s*[xX×]\s*\d",
-    ];
-    
-    for pattern in patterns {
-        if let Ok(re) = Regex::new(&format!("(?i){}", pattern)) {
-            if let Some(caps) = re.captures(text) {
-                let thickness = caps.get(1)?.as_str();
-                if let Ok(t) = thickness.parse::<f64>() {
-                    if (0.1..=100.0).contains(&t) {
-                        return Some(format_thickness(thickness));
-                    }
+fn extract_thickness_only(text: &str) -> Option<FieldMatch> {
+    for (re, pattern) in THICKNESS_ONLY_REGEXES.iter().zip(THICKNESS_ONLY_PATTERNS) {
+        if let Some(caps) = re.captures(text) {
+            let m = caps.get(1)?;
+            let thickness = m.as_str();
+            if let Ok(t) = thickness.parse::<f64>() {
+                if (0.1..=100.0).contains(&t) {
+                    return Some(FieldMatch::new(format_thickness(thickness), m.range(), pattern));
                 }
             }
         }
@@ -139,22 +545,107 @@ fn extract_thickness_only(text: &str) -> Option<String> {
     None
 }
 
+/// 幅の候補（カンマ区切り、または3〜4桁の数値）
+static WIDTH_CANDIDATE_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(r"\d{1,2},\d{3}|\d{3,4}"));
+
+/// 幅ではない数値のラベル（チャージ番号・重量・日付）が直前にあるか
+static NON_WIDTH_LABEL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    compile_builtin(
+        r"(?i)(?:CHARGE|HEAT|CH\.|LOT|溶鋼番号|チャージ|WEIGHT|MASS|重量|質量|DATE|日付|発行日|試験日)[ \t]*(?:NO\.?|#)?[ \t]*[:：.]?\s*$",
+    )
+});
+
+/// 厚さの後ろのテキストから幅の候補を探して補完（長さは不明のまま）
+///
+/// 日付・重量などの数値やラベル付きの数値を避け、妥当な幅の範囲と厚さとの比率を満たす最初の候補を使う
+fn complete_width(text: &str, thickness: &FieldMatch, profile: DimensionProfile) -> Option<(Dimensions, FieldMatch)> {
+    let start = thickness.span.end;
+    let mut end = (start + WIDTH_SEARCH_WINDOW).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let window = &text[start..end];
+    
+    let re = &*WIDTH_CANDIDATE_REGEX;
+    let t: f64 = thickness.value.parse().ok()?;
+    for m in re.find_iter(window) {
+        if !is_width_candidate(window, m.start(), m.end()) || NON_WIDTH_LABEL_REGEX.is_match(&window[..m.start()]) {
+            continue;
+        }
+        let width = process_width(m.as_str());
+        let Ok(w) = width.parse::<f64>() else {
+            continue;
+        };
+        if !is_valid_dimension(&thickness.value, &width, None, profile) || !WIDTH_RATIO_RANGE.contains(&(w / t)) {
+            continue;
+        }
+        
+        let dims = Dimensions {
+            thickness: t,
+            width: w,
+            length: None,
+        };
+        let span = thickness.span.start..start + m.end();
+        return Some((dims, FieldMatch::new(dims.to_string(), span, re.as_str())));
+    }
+    
+    None
+}
+
+/// 数値が単独の幅らしいか（長い数値・小数・日付・重量の一部でないか）
+fn is_width_candidate(text: &str, start: usize, end: usize) -> bool {
+    if text[..start].chars().next_back().is_some_and(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '/' | '-')) {
+        return false;
+    }
+    
+    let rest = text[end..].trim_start();
+    let mut next = rest.chars();
+    match next.next() {
+        Some(c) if c.is_ascii_digit() || matches!(c, '/' | '-' | '年' | '月') => false,
+        Some('.' | ',') => !next.next().is_some_and(|c| c.is_ascii_digit()),
+        _ => !rest.to_uppercase().starts_with(['K', 'T', 'ト']),
+    }
+}
+
+/// 小数点と誤認識した幅（1.540）
+static MISREAD_WIDTH_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(r"^\d{1,2}\.\d{3}$"));
+
+/// このモジュールの組み込みパターンをすべてコンパイル
+pub(crate) fn precompile_patterns() {
+    for regexes in [&COIL_DIMENSION_REGEXES, &SHEET_DIMENSION_REGEXES] {
+        LazyLock::force(regexes);
+    }
+    for regexes in [&CONCATENATED_DIMENSION_REGEXES, &THICKNESS_ONLY_REGEXES] {
+        LazyLock::force(regexes);
+    }
+    for regex in [
+        &DIMENSION_LABEL_REGEX,
+        &DIMENSION_SECTION_REGEX,
+        &SIZE_SECTION_REGEX,
+        &NON_WIDTH_LABEL_REGEX,
+        &INCH_DIMENSION_REGEX,
+        &DIAMETER_REGEX,
+        &WIDTH_CANDIDATE_REGEX,
+        &MISREAD_WIDTH_REGEX,
+    ] {
+        LazyLock::force(regex);
+    }
+}
+
 /// 幅の値を処理（カンマ除去、小数点誤認識の修正）
 fn process_width(width_raw: &str) -> String {
     let mut width = width_raw.replace(',', "");
     
     // 1.540 のような誤認識を 1540 に修正
-    if let Ok(re) = Regex::new(r"^\d{1,2}\.\d{3}$") {
-        if re.is_match(&width) {
-            width = width.replace('.', "");
-        }
+    if MISREAD_WIDTH_REGEX.is_match(&width) {
+        width = width.replace('.', "");
     }
     
     width
 }
 
-/// 寸法が妥当かチェック
-fn is_valid_dimension(thickness: &str, width: &str, length: Option<&str>) -> bool {
+/// 寸法が妥当かチェック（幅・数値の長さの下限はプロファイルに従う）
+fn is_valid_dimension(thickness: &str, width: &str, length: Option<&str>, profile: DimensionProfile) -> bool {
     let t: f64 = match thickness.replace(',', "").parse() {
         Ok(v) => v,
         Err(_) => return false,
@@ -170,8 +661,8 @@ fn is_valid_dimension(thickness: &str, width: &str, length: Option<&str>) -> boo
         return false;
     }
     
-    // 幅は 100-5000mm
-    if !(100.0..=5000.0).contains(&w) {
+    // 幅は 100-5000mm（定尺板は 10mm から）
+    if !(profile.min_extent()..=5000.0).contains(&w) {
         return false;
     }
     
@@ -185,7 +676,7 @@ fn is_valid_dimension(thickness: &str, width: &str, length: Option<&str>) -> boo
         let l_upper = l.to_uppercase();
         if !["COIL", "コイル", "C"].contains(&l_upper.as_str()) {
             if let Ok(length_val) = l.replace(',', "").parse::<f64>() {
-                if length_val < 100.0 {
+                if length_val < profile.min_extent() {
                     return false;
                 }
             }
@@ -195,26 +686,222 @@ fn is_valid_dimension(thickness: &str, width: &str, length: Option<&str>) -> boo
     true
 }
 
+/// 径寸法が妥当かチェック（径は 1-1000mm、長さは径より大きい）
+fn is_valid_diameter(diameter: &str, length: &str) -> bool {
+    let (Ok(d), Ok(l)) = (diameter.parse::<f64>(), length.parse::<f64>()) else {
+        return false;
+    };
+    (1.0..=1000.0).contains(&d) && l > d
+}
+
 /// 厚さをフォーマット（22.00 -> 22）
 fn format_thickness(thickness: &str) -> String {
-    if let Ok(t) = thickness.parse::<f64>() {
-        if t == t.trunc() {
-            return format!("{}", t as i32);
-        }
-        // 不要な末尾のゼロを削除
-        let formatted = format!("{:.2}", t);
-        formatted.trim_end_matches('0').trim_end_matches('.').to_string()
-    } else {
-        thickness.to_string()
+    match thickness.parse::<f64>() {
+        Ok(t) => format_number(t),
+        Err(_) => thickness.to_string(),
+    }
+}
+
+/// 数値をファイル名向けにフォーマット（整数なら小数点なし、小数は2桁まで）
+fn format_number(value: f64) -> String {
+    if value == value.trunc() {
+        return format!("{}", value as i64);
     }
+    // 不要な末尾のゼロを削除
+    let formatted = format!("{:.2}", value);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
 }
 
-/// 長さを正規化（COIL/コイル -> C）
-fn normalize_length(length: &str) -> String {
+/// 長さの単位をmmにそろえる（"2.438m" -> "2438"、単位なしはmmのまま、"mm" はパターン側で読み飛ばす）
+///
+/// 幅の "1.540"（カンマの誤認識）と取り違えないよう、長さの位置の値にだけ使う
+fn normalize_length_unit(length: &str) -> String {
+    let trimmed = length.trim();
+    if let Some(meters) = trimmed.strip_suffix(['m', 'M'])
+        && !meters.ends_with(['m', 'M'])
+        && let Ok(value) = meters.trim().parse::<f64>()
+    {
+        return format!("{}", (value * 1000.0).round() as i64);
+    }
+    trimmed.to_string()
+}
+
+/// 長さを解析（COIL/コイル/C はコイル）
+fn parse_length(length: &str) -> Option<LengthValue> {
     let upper = length.to_uppercase();
-    if upper == "COIL" || length == "コイル" {
-        "C".to_string()
+    if ["COIL", "コイル", "C"].contains(&upper.as_str()) {
+        Some(LengthValue::Coil)
     } else {
-        length.to_string()
+        length.replace(',', "").parse().ok().map(LengthValue::Mm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::MillsheetInfo;
+    
+    /// 寸法の表記と補完したかどうか
+    fn inferred(text: &str) -> Option<(String, bool)> {
+        extract_dimensions_spec_match(text).map(|(_, found)| (found.value, found.inferred))
+    }
+    
+    #[test]
+    fn missing_separator_between_width_and_length_is_inferred() {
+        assert_eq!(inferred("1.6X1219C"), Some(("1.6x1219xC".to_string(), true)));
+        assert_eq!(inferred("2.3 X 1219COIL"), Some(("2.3x1219xC".to_string(), true)));
+        assert_eq!(inferred("1.25X1219C"), Some(("1.25x1219xC".to_string(), true)));
+    }
+    
+    #[test]
+    fn missing_separator_between_thickness_and_width_is_inferred() {
+        assert_eq!(inferred("1.61219C"), Some(("1.6x1219xC".to_string(), true)));
+        assert_eq!(inferred("SIZE 1.61219XCOIL"), Some(("1.6x1219xC".to_string(), true)));
+        assert_eq!(inferred("1.61219X2438"), Some(("1.6x1219x2438".to_string(), true)));
+    }
+    
+    #[test]
+    fn complete_dimensions_are_not_inferred() {
+        assert_eq!(inferred("1.6X1219XC"), Some(("1.6x1219xC".to_string(), false)));
+    }
+    
+    #[test]
+    fn ambiguous_concatenations_are_not_inferred() {
+        // 小数点が無い・幅が5桁・長さもコイル表記も無いものは補完しない
+        for text in ["161219C", "1.612190C", "LOT 1.61219"] {
+            assert_eq!(inferred(text), None, "{}", text);
+        }
+    }
+    
+    #[test]
+    fn inferred_dimensions_lower_confidence() {
+        let concatenated = MillsheetInfo::parse("材質 SPHC\n寸法 1.61219C");
+        let separated = MillsheetInfo::parse("材質 SPHC\n寸法 1.6X1219XC");
+        assert_eq!(concatenated.dimensions, separated.dimensions);
+        assert!(concatenated.confidence.dimensions < separated.confidence.dimensions);
+    }
+    
+    #[test]
+    fn structured_dimensions_read_thousands_separator_and_coil() {
+        let dims = extract_dimensions_structured("寸法 1.6X1,219XCOIL").unwrap();
+        assert_eq!((dims.thickness, dims.width, dims.length), (1.6, 1219.0, Some(LengthValue::Coil)));
+        assert_eq!(dims.to_string(), "1.6x1219xC");
+    }
+    
+    #[test]
+    fn metre_length_is_converted_to_millimetres() {
+        assert_eq!(extract_dimensions("1.6 x 1219 x 2.438m").as_deref(), Some("1.6x1219x2438"));
+    }
+    
+    #[test]
+    fn spec_match_skips_labels_and_keeps_partial_dimensions() {
+        let (dims, found) = extract_dimensions_spec_match("SIZE 2.30 X (W) 1,219 X COIL").unwrap();
+        assert_eq!(dims.unwrap().to_string(), "2.3x1219");
+        assert!(found.inferred);
+        
+        let (dims, found) = extract_dimensions_spec_match("寸法 2.30 X 2024/03/15").unwrap();
+        assert_eq!((dims, found.value.as_str()), (None, "2.3"));
+    }
+    
+    #[test]
+    fn score_prefers_plausible_dimensions_near_label() {
+        let coil = Dimensions { thickness: 1.6, width: 1219.0, length: Some(LengthValue::Coil) };
+        let noisy = Dimensions { thickness: 1.605, width: 121.0, length: None };
+        assert!(score_dimension(&coil, "1.6X1219XCOIL", None) > score_dimension(&noisy, "1.605x121", None));
+        assert!(score_dimension(&coil, "1.6X1219XCOIL", Some(5)) > score_dimension(&coil, "1.6X1219XCOIL", Some(500)));
+    }
+    
+    #[test]
+    fn units_are_dropped_from_dimensions() {
+        for text in ["1.6 x 1219 x 2438", "1.6 x 1219 x 2438mm", "1.6mm x 1219mm x 2438 MM", "t1.6 x 1219mm x 2438mm"] {
+            assert_eq!(extract_dimensions(text).as_deref(), Some("1.6x1219x2438"), "{}", text);
+        }
+        assert_eq!(extract_dimensions("1.6mm X 1219mm X COIL").as_deref(), Some("1.6x1219xC"));
+    }
+    
+    #[test]
+    fn part_formats_each_dimension() {
+        let dims = extract_dimensions_structured("1.6 x 1219 x 2438").unwrap();
+        let plain = DimensionFormat::default();
+        assert_eq!(dims.part(DimensionPart::Thickness, plain).as_deref(), Some("1.6"));
+        assert_eq!(dims.part(DimensionPart::Thickness, DimensionFormat { integer: true, unit: false }).as_deref(), Some("2"));
+        assert_eq!(dims.part(DimensionPart::Length, DimensionFormat { integer: false, unit: true }).as_deref(), Some("2438mm"));
+        
+        let coil = extract_dimensions_structured("1.6mm X 1219mm X COIL").unwrap();
+        assert_eq!(coil.part(DimensionPart::Length, DimensionFormat { integer: true, unit: true }).as_deref(), Some("C"));
+    }
+    
+    #[test]
+    fn diameter_of_bars_and_pipes() {
+        assert_eq!(extract_dimensions("寸法 φ25×4000").as_deref(), Some("D25x4000"));
+        assert_eq!(extract_dimensions("SIZE Φ60.5 X L=5500").as_deref(), Some("D60.5x5500"));
+        assert_eq!(extract_dimensions("丸棒 φ32x6000").as_deref(), Some("D32x6000"));
+        // 長さが径より短いものは径寸法とみなさない
+        assert!(extract_diameter("φ25x10").is_none());
+    }
+    
+    #[test]
+    fn bend_test_diameter_does_not_override_plate_size() {
+        assert_eq!(extract_dimensions("曲げ試験 φ25×180\n寸法 1.6X1219XCOIL").as_deref(), Some("1.6x1219xC"));
+        assert_eq!(extract_dimensions("曲げ試験 φ25×180\n1.6X1219XCOIL").as_deref(), Some("1.6x1219xC"));
+        // 寸法欄の径記号は板の寸法より優先する
+        assert_eq!(extract_dimensions("寸法 φ25×4000\n曲げ 1.6X1219XCOIL").as_deref(), Some("D25x4000"));
+    }
+    
+    
+    #[test]
+    fn width_is_completed_from_nearby_number() {
+        assert_eq!(inferred("Size 2.3 X\n1219"), Some(("2.3x1219".to_string(), true)));
+        assert_eq!(inferred("Size 2.3 X W: 1,219"), Some(("2.3x1219".to_string(), true)));
+    }
+    
+    #[test]
+    fn labelled_numbers_are_not_completed_as_width() {
+        // チャージ番号・重量・日付のラベルが付いた数値は幅にしない
+        for (text, thickness) in [
+            ("Size 12.0 X\nCHARGE 1234", "12"),
+            ("Size 12.0 X\nCHARGE NO. 1234", "12"),
+            ("Size 2.3 X\nWEIGHT: 1500", "2.3"),
+            ("Size 2.3 X 重量 1500", "2.3"),
+            ("Size 2.3 X\nDATE 2024", "2.3"),
+        ] {
+            assert_eq!(inferred(text), Some((thickness.to_string(), false)), "{}", text);
+        }
+        // ラベルの後ろに幅らしい数値があればそちらを使う
+        assert_eq!(inferred("Size 2.3 X\nCHARGE 1234 1219"), Some(("2.3x1219".to_string(), true)));
+    }
+    
+    #[test]
+    fn width_out_of_ratio_or_range_is_not_completed() {
+        // 厚さの10倍未満・5000mm超の幅は補完しない
+        assert_eq!(inferred("Size 12.0 X\n110"), Some(("12".to_string(), false)));
+        assert_eq!(inferred("Size 2.3 X\n6000"), Some(("2.3".to_string(), false)));
+    }
+    
+    #[test]
+    fn completed_width_lowers_confidence() {
+        let completed = MillsheetInfo::parse("材質 SPHC\nSize 2.3 X\n1219");
+        let separated = MillsheetInfo::parse("材質 SPHC\n寸法 2.3X1219X2438");
+        assert_eq!(completed.dimensions.as_deref(), Some("2.3x1219"));
+        assert!(completed.confidence.dimensions < separated.confidence.dimensions);
+    }
+    
+    
+    #[test]
+    fn inch_dimensions_are_converted_to_millimetres() {
+        assert_eq!(extract_dimensions(r#"SIZE 0.25" x 48" x COIL"#).as_deref(), Some("6.35x1219.2xC"));
+        assert_eq!(extract_dimensions("0.25 in x 48 in x 96 in").as_deref(), Some("6.35x1219.2x2438.4"));
+        // 単位のない長さはmmのまま
+        assert_eq!(extract_dimensions("0.25” x 48” x 2438").as_deref(), Some("6.35x1219.2x2438"));
+    }
+    
+    #[test]
+    fn numbers_without_inch_units_are_not_converted() {
+        let inch = |text| extract_inch_dimensions(text, DimensionProfile::default()).map(|(dims, _)| dims.to_string());
+        assert_eq!(inch(r#"0.25" x 48" x COIL"#).as_deref(), Some("6.35x1219.2xC"));
+        // 単位の無い数値・幅だけmmの混在・換算すると範囲外のものは取らない
+        assert_eq!(inch("0.25 x 48 x COIL"), None);
+        assert_eq!(inch(r#"0.25" x 1219 x COIL"#), None);
+        assert_eq!(inch(r#"12" x 48" x COIL"#), None);
     }
 }
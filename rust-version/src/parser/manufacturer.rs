@@ -1,42 +1,338 @@
 //! メーカー名抽出モジュール
 
+use super::dictionary::ManufacturerEntry;
+use super::{compile_all, FieldMatch, NormalizedText};
 use regex::Regex;
+use std::sync::LazyLock;
 
-/// 優先メーカーリスト
+/// 優先メーカーリスト（表示名は現社名、表記ゆれには旧社名・英語表記・略称を含める）
+///
+/// "STEEL" 単独のような一般語は誤爆するので表記ゆれに入れない
 const PRIORITY_MANUFACTURERS: &[(&str, &[&str])] = &[
     ("東京製鉄", &["東京製鉄", "東京製鐵", "東京製鉄所", "東京製鐵所", "TOKYO STEEL", "TOKYOSTEEL"]),
     ("中山製鋼", &["中山製鋼", "中山製鉄", "中山製鋼所", "中山製鉄所", "NAKAYAMA STEEL", "NAKAYAMA"]),
     ("神戸製鋼", &["神戸製鋼", "神戸製鉄", "神戸製鋼所", "神戸製鉄所", "KOBE STEEL", "KOBELCO"]),
+    (
+        "日本製鉄",
+        &[
+            "日本製鉄", "日本製鐵", "新日鐵住金", "新日鉄住金", "新日本製鐵", "新日本製鉄", "新日鐵", "新日鉄",
+            "住友金属工業", "住友金属", "NIPPON STEEL", "NSSMC", "SUMITOMO METAL",
+        ],
+    ),
+    ("JFEスチール", &["JFEスチール", "JFE STEEL", "JFE", "川崎製鉄", "川崎製鐵", "日本鋼管", "KAWASAKI STEEL", "NIPPON KOKAN"]),
+    ("合同製鉄", &["合同製鐵", "合同製鉄", "GODO STEEL"]),
+    ("大和工業", &["大和工業", "YAMATO KOGYO"]),
+    ("共英製鋼", &["共英製鋼", "KYOEI STEEL"]),
+    ("愛知製鋼", &["愛知製鋼", "AICHI STEEL"]),
 ];
 
+/// 辞書にない会社名のパターン
+const COMPANY_PATTERNS: [&str; 3] = [
+    r"([^\s\n]{2,15}(?:製鉄|製鋼|製鐵))",
+    r"([^\s\n]{2,15}(?:株式会社|㈱))",
+    r"(?:製造者|メーカー)[：:]\s*([^\n]+)",
+];
+
+/// コンパイル済みの会社名のパターン（`COMPANY_PATTERNS` の順）
+static COMPANY_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| compile_all(COMPANY_PATTERNS));
+
+/// このモジュールの組み込みパターンをすべてコンパイル
+pub(crate) fn precompile_patterns() {
+    LazyLock::force(&COMPANY_REGEXES);
+}
+
+/// 表示名で新字体にそろえる旧字体（旧字体, 新字体）
+const OLD_KANJI: &[(char, char)] = &[('鐵', '鉄'), ('鑛', '鉱')];
+
+/// 工場・事業所を表す語（これより前の製鉄所/製鋼所の「所」は工場名の一部とみなす）
+const PLANT_SUFFIXES: &[&str] = &["工場", "事業所", "製造所"];
+
+/// 社名が製鉄所/製鋼所で終わりうる語（「所」を落とすと会社名の中心部分になる）
+const WORKS_SUFFIXES: &[&str] = &["製鉄所", "製鐵所", "製鋼所"];
+
+/// 法人格の表記（統一表記, 表記ゆれ）
+const CORPORATE_FORMS: &[(&str, &[&str])] = &[
+    ("株式会社", &["株式会社", "㈱", "(株)", "（株）"]),
+    ("有限会社", &["有限会社", "㈲", "(有)", "（有）"]),
+    ("合同会社", &["合同会社", "(同)", "（同）"]),
+];
+
+/// 法人格の正規化方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CorporateFormStyle {
+    /// そのまま
+    #[default]
+    Keep,
+    /// 除去（ファイル名向け）
+    Strip,
+    /// 統一表記に置換（集計向け、「㈱」「(株)」→「株式会社」）
+    Unify,
+}
+
+/// メーカー名の法人格（前株・後株）を正規化
+pub fn normalize_corporate_form(name: &str, style: CorporateFormStyle) -> String {
+    if style == CorporateFormStyle::Keep {
+        return name.to_string();
+    }
+    
+    let name = name.trim();
+    for (unified, variants) in CORPORATE_FORMS {
+        for variant in *variants {
+            // 前株
+            if let Some(core) = name.strip_prefix(variant) {
+                let core = core.trim();
+                return match style {
+                    CorporateFormStyle::Unify => format!("{}{}", unified, core),
+                    _ => core.to_string(),
+                };
+            }
+            // 後株
+            if let Some(core) = name.strip_suffix(variant) {
+                let core = core.trim();
+                return match style {
+                    CorporateFormStyle::Unify => format!("{}{}", core, unified),
+                    _ => core.to_string(),
+                };
+            }
+        }
+    }
+    
+    name.to_string()
+}
+
+/// メーカーの表示名の旧字体を新字体にそろえる（製鐵 → 製鉄）
+pub fn normalize_display_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            OLD_KANJI
+                .iter()
+                .find(|(old, _)| *old == c)
+                .map_or(c, |(_, new)| *new)
+        })
+        .collect()
+}
+
+/// 工場・製造所名を切り落として会社名の中心部分だけを残す
+///
+/// - 優先メーカー表の表記を含めば、その表示名を返す
+/// - 空白より後（「大阪製鉄所 堺工場」の「堺工場」）と、法人格より後を切り落とす
+/// - 工場名が続く「○○製鉄所」の「所」以降は切り落とす。工場名が続かなければ社名の一部として残す
+pub fn trim_company_suffix(name: &str) -> String {
+    let name = name.trim();
+    let upper = name.to_uppercase();
+    if let Some((display_name, _)) = PRIORITY_MANUFACTURERS
+        .iter()
+        .find(|(_, variants)| variants.iter().any(|variant| upper.contains(&variant.to_uppercase())))
+    {
+        return display_name.to_string();
+    }
+    
+    // 前株は残して、その後ろの社名から切り落とす位置を探す
+    let prefix_len = CORPORATE_FORMS
+        .iter()
+        .flat_map(|(_, variants)| variants.iter())
+        .find(|variant| name.starts_with(*variant))
+        .map_or(0, |variant| variant.len());
+    let (prefix, rest) = name.split_at(prefix_len);
+    let rest = rest.trim_start();
+    
+    let mut core = rest.split_whitespace().next().unwrap_or(rest);
+    // 後株の後ろ（工場名など）
+    if let Some(end) = CORPORATE_FORMS
+        .iter()
+        .flat_map(|(_, variants)| variants.iter())
+        .filter_map(|variant| core.find(variant).filter(|&pos| pos > 0).map(|pos| pos + variant.len()))
+        .min()
+    {
+        core = &core[..end];
+    }
+    // 製鉄所 + 工場名
+    if let Some(plant) = PLANT_SUFFIXES.iter().filter_map(|suffix| core.find(suffix)).min()
+        && let Some(works) = WORKS_SUFFIXES
+            .iter()
+            .filter_map(|suffix| core[..plant].find(suffix).map(|pos| pos + suffix.len() - '所'.len_utf8()))
+            .min()
+    {
+        core = &core[..works];
+    }
+    
+    if prefix.is_empty() {
+        core.to_string()
+    } else {
+        format!("{}{}", prefix, core)
+    }
+}
+
 /// テキストからメーカー名を抽出
+///
+/// 表記ゆれが完全に一致しなければ、OCRの誤認識を想定して編集距離の近い表記も探す
+/// （4〜7文字の表記は1文字、8文字以上は2文字まで。3文字以下は完全一致のみ）
 pub fn extract_manufacturer(text: &str) -> Option<String> {
-    let text_upper = text.to_uppercase();
+    extract_manufacturer_match(text, &[]).map(|m| m.value)
+}
+
+/// 優先メーカー表（表示名, 表記ゆれ）を追加してメーカー名を抽出
+///
+/// `table` は組み込みの優先メーカー表より先に評価する。組み込みと同じ表示名の行は組み込みの行を置き換える
+pub fn extract_manufacturer_with(text: &str, table: &[(&str, &[&str])]) -> Option<String> {
+    extract_manufacturer_match_with(text, &[], table).map(|m| m.value)
+}
+
+/// メーカー名と、判定に使ったテキスト中の表記（"KOBELCO" など）を取得
+pub fn extract_manufacturer_with_term(text: &str) -> Option<(String, String)> {
+    let found = extract_manufacturer_match(text, &[])?;
+    let term = matched_term(text, &found);
+    Some((found.value, term))
+}
+
+/// 抽出根拠のマッチ位置にあるテキスト（位置がずれていればマッチしたパターン）
+pub fn matched_term(text: &str, found: &FieldMatch) -> String {
+    text.get(found.span.clone())
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .unwrap_or(&found.pattern)
+        .to_string()
+}
+
+/// 辞書のメーカー定義を優先してメーカー名と抽出根拠を取得
+pub fn extract_manufacturer_match(text: &str, entries: &[ManufacturerEntry]) -> Option<FieldMatch> {
+    extract_manufacturer_match_with(text, entries, &[])
+}
+
+/// 辞書のメーカー定義 → 追加の優先メーカー表 → 組み込みの優先メーカー表の順にメーカー名と抽出根拠を取得
+pub fn extract_manufacturer_match_with(
+    text: &str,
+    entries: &[ManufacturerEntry],
+    table: &[(&str, &[&str])],
+) -> Option<FieldMatch> {
+    let normalized = NormalizedText::new(text);
+    find_manufacturer(&normalized.text, entries, table)
+        .map(|m| FieldMatch {
+            value: normalize_display_name(&m.value),
+            ..m
+        })
+        .map(|m| normalized.remap(m))
+}
+
+/// 表記の長さ（文字数）に応じたあいまい一致の許容編集距離（短い社名は誤爆しやすいので完全一致のみ）
+fn fuzzy_threshold(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// レーベンシュタイン距離
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 表記ゆれとの編集距離が許容範囲の部分文字列を探す（最も距離の小さいもの、同じなら表の順→出現順）
+///
+/// 長さが表記の ±1 文字の範囲を行内で比べ、英字は大文字小文字を区別せず単語の区切りにそろえる
+fn find_fuzzy(text: &str, known: &[(&str, &str)]) -> Option<FieldMatch> {
+    let chars: Vec<(usize, char)> = text.char_indices().map(|(i, c)| (i, c.to_ascii_uppercase())).collect();
+    let mut best: Option<(usize, FieldMatch)> = None;
     
-    // 優先メーカーを先にチェック
-    for (display_name, variants) in PRIORITY_MANUFACTURERS {
-        for variant in *variants {
-            if text_upper.contains(&variant.to_uppercase()) {
-                return Some(display_name.to_string());
+    for (name, variant) in known {
+        let target: Vec<char> = variant.chars().map(|c| c.to_ascii_uppercase()).collect();
+        let threshold = fuzzy_threshold(target.len());
+        if threshold == 0 {
+            continue;
+        }
+        
+        for start in 0..chars.len() {
+            for len in target.len() - 1..=target.len() + 1 {
+                let Some(window) = chars.get(start..start + len) else {
+                    break;
+                };
+                if window.iter().any(|(_, c)| *c == '\n') {
+                    break;
+                }
+                // 英字の表記は単語の途中から/途中までを比べない（KOBE STEEL の "OBE STEEL" と JFE STEEL など）
+                let inside_word = |i: Option<usize>| {
+                    i.and_then(|i| chars.get(i)).is_some_and(|(_, c)| c.is_ascii_alphanumeric())
+                };
+                if target[0].is_ascii_alphanumeric() && inside_word(start.checked_sub(1)) {
+                    break;
+                }
+                if target[target.len() - 1].is_ascii_alphanumeric() && inside_word(Some(start + len)) {
+                    continue;
+                }
+                let candidate: Vec<char> = window.iter().map(|(_, c)| *c).collect();
+                let distance = levenshtein(&candidate, &target);
+                if distance == 0 || distance > threshold || best.as_ref().is_some_and(|(d, _)| *d <= distance) {
+                    continue;
+                }
+                let end = chars.get(start + len).map_or(text.len(), |(i, _)| *i);
+                let span = window[0].0..end;
+                let found = FieldMatch::new(name.to_string(), span, variant);
+                best = Some((distance, FieldMatch { inferred: true, ..found }));
             }
         }
     }
     
+    let (distance, found) = best?;
+    tracing::info!(
+        "メーカー名をあいまい一致で判定: {:?} → {} (表記 {:?}, 編集距離 {})",
+        &text[found.span.clone()],
+        found.value,
+        found.pattern,
+        distance
+    );
+    Some(found)
+}
+
+/// 正規化済みテキストからメーカー名を探す
+fn find_manufacturer(text: &str, entries: &[ManufacturerEntry], table: &[(&str, &[&str])]) -> Option<FieldMatch> {
+    let text_upper = text.to_uppercase();
+    
+    // 辞書のメーカー → 追加の優先メーカー → 組み込みの優先メーカー（追加分と同じ表示名の組み込み行は使わない）
+    let overridden = |name: &str| {
+        table
+            .iter()
+            .any(|(custom, _)| normalize_display_name(custom) == normalize_display_name(name))
+    };
+    let priority = table.iter().chain(
+        PRIORITY_MANUFACTURERS
+            .iter()
+            .filter(|(display_name, _)| !overridden(display_name)),
+    );
+    let known: Vec<(&str, &str)> = entries
+        .iter()
+        .flat_map(|entry| entry.variants.iter().map(|variant| (entry.name.as_str(), variant.as_str())))
+        .chain(priority.flat_map(|(display_name, variants)| variants.iter().map(|variant| (*display_name, *variant))))
+        .collect();
+    
+    for (name, variant) in &known {
+        if let Some(pos) = text_upper.find(&variant.to_uppercase()) {
+            return Some(FieldMatch::new(name.to_string(), pos..pos + variant.len(), variant));
+        }
+    }
+    
+    // OCRの誤認識（神戸製鋼 → 神戸製銅）を編集距離で救済する
+    if let Some(found) = find_fuzzy(text, &known) {
+        return Some(found);
+    }
+    
     // その他の会社名パターン
-    let patterns = [
-        r"([^\s\n]{2,15}(?:製鉄|製鋼|製鐵))",
-        r"([^\s\n]{2,15}(?:株式会社|㈱))",
-        r"(?:製造者|メーカー)[：:]\s*([^\n]+)",
-    ];
-    
-    for pattern in patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(caps) = re.captures(text) {
-                if let Some(m) = caps.get(1) {
-                    let name = m.as_str().trim();
-                    if name.chars().count() >= 2 && name.chars().count() <= 20 {
-                        return Some(name.to_string());
-                    }
+    for (re, pattern) in COMPANY_REGEXES.iter().zip(COMPANY_PATTERNS) {
+        if let Some(caps) = re.captures(text) {
+            if let Some(m) = caps.get(1) {
+                // 工場名まで巻き込んでいれば会社名の中心部分だけにする
+                let name = trim_company_suffix(m.as_str());
+                if name.chars().count() >= 2 && name.chars().count() <= 20 {
+                    return Some(FieldMatch::new(name, m.range(), pattern));
                 }
             }
         }
@@ -44,3 +340,107 @@ pub fn extract_manufacturer(text: &str) -> Option<String> {
     
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{MillsheetInfo, ParseOptions};
+    
+    /// 前株の各表記
+    const PREFIXED: [&str; 5] = ["株式会社山陽製鋼", "㈱山陽製鋼", "(株)山陽製鋼", "（株）山陽製鋼", "㈱ 山陽製鋼"];
+    
+    /// 後株の各表記
+    const SUFFIXED: [&str; 5] = ["山陽製鋼株式会社", "山陽製鋼㈱", "山陽製鋼(株)", "山陽製鋼（株）", "山陽製鋼 株式会社"];
+    
+    #[test]
+    fn every_notation_strips_to_the_same_name() {
+        for name in PREFIXED.into_iter().chain(SUFFIXED) {
+            assert_eq!(normalize_corporate_form(name, CorporateFormStyle::Strip), "山陽製鋼", "{}", name);
+        }
+    }
+    
+    #[test]
+    fn every_notation_unifies_to_the_same_name_per_position() {
+        for name in PREFIXED {
+            assert_eq!(normalize_corporate_form(name, CorporateFormStyle::Unify), "株式会社山陽製鋼", "{}", name);
+        }
+        for name in SUFFIXED {
+            assert_eq!(normalize_corporate_form(name, CorporateFormStyle::Unify), "山陽製鋼株式会社", "{}", name);
+        }
+    }
+    
+    #[test]
+    fn keep_leaves_notation_as_is() {
+        for name in PREFIXED.into_iter().chain(SUFFIXED) {
+            assert_eq!(normalize_corporate_form(name, CorporateFormStyle::Keep), name);
+        }
+    }
+    
+    #[test]
+    fn other_corporate_forms_and_plain_names() {
+        let cases = [
+            ("有限会社大和鋼材", CorporateFormStyle::Strip, "大和鋼材"),
+            ("大和鋼材㈲", CorporateFormStyle::Unify, "大和鋼材有限会社"),
+            ("（同）大和鋼材", CorporateFormStyle::Unify, "合同会社大和鋼材"),
+            ("日本製鉄", CorporateFormStyle::Strip, "日本製鉄"),
+            ("日本製鉄", CorporateFormStyle::Unify, "日本製鉄"),
+        ];
+        for (name, style, expected) in cases {
+            assert_eq!(normalize_corporate_form(name, style), expected, "{} {:?}", name, style);
+        }
+    }
+    
+    #[test]
+    fn parse_applies_corporate_form_option() {
+        let strip = ParseOptions {
+            corporate_form: CorporateFormStyle::Strip,
+            ..Default::default()
+        };
+        for text in ["製造者: ㈱山陽製鋼", "製造者: 山陽製鋼株式会社", "製造者: (株)山陽製鋼"] {
+            let info = MillsheetInfo::parse_with_options(text, &strip);
+            assert_eq!(info.manufacturer.as_deref(), Some("山陽製鋼"), "{}", text);
+        }
+    }
+    
+    #[test]
+    fn matched_term_is_returned_as_written() {
+        let (name, term) = extract_manufacturer_with_term("MAKER: Kobelco Steel").unwrap();
+        assert_eq!(name, "神戸製鋼");
+        assert_eq!(term, "Kobelco");
+    }
+    
+    #[test]
+    fn custom_table_replaces_builtin_rows() {
+        let table: &[(&str, &[&str])] = &[
+            ("合同製鐵", &["合同製鐵", "合同製鉄", "GODO STEEL"]),
+            ("神戸製鋼", &["KOBELCO"]),
+        ];
+        assert_eq!(extract_manufacturer_with("MAKER: GODO STEEL LTD.", table).as_deref(), Some("合同製鉄"));
+        assert_eq!(extract_manufacturer_with("TOKYO STEEL", table).as_deref(), Some("東京製鉄"));
+        // 置き換えた行に無い表記は組み込みの表記ゆれでも判定しない
+        assert_eq!(extract_manufacturer_with("KOBE STEEL", table), None);
+    }
+    
+    #[test]
+    fn manufacturer_tolerates_ocr_errors() {
+        assert_eq!(extract_manufacturer("製造者 神戸製銅所").as_deref(), Some("神戸製鋼"));
+        assert_eq!(extract_manufacturer("KOBELC0 LTD").as_deref(), Some("神戸製鋼"));
+        assert_eq!(extract_manufacturer("TOKY0 STEEL CO.").as_deref(), Some("東京製鉄"));
+    }
+    
+    #[test]
+    fn former_names_map_to_current_names() {
+        assert_eq!(extract_manufacturer("新日本製鐵株式会社 君津製鐵所").as_deref(), Some("日本製鉄"));
+        assert_eq!(extract_manufacturer("MANUFACTURER: NSSMC").as_deref(), Some("日本製鉄"));
+        assert_eq!(extract_manufacturer("KAWASAKI STEEL CORP.").as_deref(), Some("JFEスチール"));
+    }
+    
+    #[test]
+    fn company_suffix_trims_plant_names() {
+        assert_eq!(trim_company_suffix("大阪製鉄所岡山工場"), "大阪製鉄");
+        assert_eq!(trim_company_suffix("日本製鋼所"), "日本製鋼所");
+        assert_eq!(trim_company_suffix("日本製鋼所 室蘭製作所"), "日本製鋼所");
+        assert_eq!(trim_company_suffix("山陽製鋼株式会社姫路工場"), "山陽製鋼株式会社");
+        assert_eq!(trim_company_suffix("東京製鐵所岡山工場"), "東京製鉄");
+    }
+}
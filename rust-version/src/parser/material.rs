@@ -1,47 +1,686 @@
 //! 材質抽出モジュール
 
+use super::{compile_builtin, FieldMatch, NormalizedText};
+use anyhow::{Context, Result};
 use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::LazyLock;
 
-/// テキストから材質/鋼種を抽出
-/// 対応: SS400, SPHC, SPCC, S45C, SUS304 など
-pub fn extract_material(text: &str) -> Option<String> {
-    let patterns = [
-        // SS系 (一般構造用鋼)
-        r"\b(SS\s*[234]\d{2})\b",
-        // SPHC, SPCC, SPCD, SPCE (熱延/冷延鋼板)
-        r"\b(SPH[CDE]|SPC[CDE])\b",
-        // SECC, SECD (電気亜鉛めっき)
-        r"\b(SEC[CD])\b",
-        // SGCC, SGHC (溶融亜鉛めっき)
-        r"\b(SG[CH]C)\b",
-        // S-C系 (機械構造用炭素鋼)
-        r"\b(S\d{2}C)\b",
-        // SCM系 (クロムモリブデン鋼)
-        r"\b(SCM\d{3})\b",
-        // SUS系 (ステンレス鋼)
-        r"\b(SUS\s*\d{3}[A-Z]?)\b",
-        // SK系 (炭素工具鋼)
-        r"\b(SK\d{1,2})\b",
-        // SM系 (溶接構造用鋼)
-        r"\b(SM\d{3}[A-C]?)\b",
-        // STK系 (炭素鋼管)
-        r"\b(STK\d{3})\b",
-        // STKR系 (角形鋼管)
-        r"\b(STKR\d{3})\b",
-        // 汎用パターン
-        r"\b(S[A-Z]{1,3}\d{2,3}[A-Z]?)\b",
-    ];
-    
-    for pattern in patterns {
-        if let Ok(re) = Regex::new(&format!("(?i){}", pattern)) {
-            if let Some(caps) = re.captures(text) {
-                if let Some(m) = caps.get(1) {
-                    let material = m.as_str().to_uppercase().replace(' ', "");
-                    return Some(material);
+/// `+` / `-` の後に続く処理記号（SUS304+BA, SGCC-Z27, SUS304-2B など）
+const TREATMENT_AFTER_SYMBOL: &str = r"(?i)^\s*([+\-])\s*([A-Z][A-Z0-9]{0,3}|\d[A-Z])\b";
+
+/// 空白区切りで認める処理記号（誤認を避けるため既知の記号に限定）
+const TREATMENT_AFTER_SPACE: &str = r"(?i)^\s+(BA|2B|2D|HL|NO\.\s?[1-4]|Z\d{2,3}|F\d{2})\b";
+
+/// 組み込みの鋼種パターン（具体的なものから汎用的なものへ、キャプチャ1が材質）
+const DEFAULT_PATTERNS: [&str; 16] = [
+    // SS系 (一般構造用鋼)
+    r"\b(SS\s*[234]\d{2})\b",
+    // SPHC, SPCC, SPCD, SPCE (熱延/冷延鋼板)
+    r"\b(SPH[CDE]|SPC[CDE])\b",
+    // SECC, SECD (電気亜鉛めっき)
+    r"\b(SEC[CD])\b",
+    // SGCC, SGHC (溶融亜鉛めっき)
+    r"\b(SG[CH]C)\b",
+    // S-C系 (機械構造用炭素鋼)
+    r"\b(S\d{2}C)\b",
+    // SCM系 (クロムモリブデン鋼)
+    r"\b(SCM\d{3})\b",
+    // SUS系 (ステンレス鋼、SUS316L / SUS304N2 / 二相の SUS329J4L など3文字までのサフィックス付き)
+    r"\b(SUS\s*\d{3}[A-Z0-9]{0,3})\b",
+    // SUH系 (耐熱鋼、SUH409L / SUH310S など)
+    r"\b(SUH\s*\d{1,3}[A-Z0-9]{0,3})\b",
+    // SK系 (炭素工具鋼)
+    r"\b(SK\d{1,2})\b",
+    // SM系 (溶接構造用鋼)
+    r"\b(SM\d{3}[A-C]?)\b",
+    // STK系 (炭素鋼管)
+    r"\b(STK\d{3})\b",
+    // STKR系 (角形鋼管)
+    r"\b(STKR\d{3})\b",
+    // 汎用パターン
+    r"\b(S[A-Z]{1,3}\d{2,3}[A-Z]?)\b",
+    // アルミニウム合金 (A1100, A5052, A6063 など、質別 -H34 / -T6 は処理記号として扱う)
+    r"\b(A[1-8]\d{3}[A-Z]?)\b",
+    // 銅・銅合金 (C1100, C2600 など)
+    r"\b(C[1-7]\d{3}[A-Z]?)\b",
+    // 海外規格 (ASTM A36, GB Q235B, EN S355JR など、JIS記号への読み替えは `normalize_material_standard`)
+    r"\b(A(?:36|283C?|653)|Q(?:195|235|275|345|355)[A-E]?|S(?:235|275|355)J[R0-2])\b",
+];
+
+/// コンパイル済みの組み込みの鋼種パターンと元のパターン文字列（`DEFAULT_PATTERNS` の順）
+static BUILTIN_REGEXES: LazyLock<Vec<(Regex, String)>> = LazyLock::new(|| {
+    DEFAULT_PATTERNS
+        .iter()
+        .map(|pattern| (compile_builtin(&format!("(?i){}", pattern)), pattern.to_string()))
+        .collect()
+});
+
+/// 海外規格の記号と相当するJIS記号の対応表（記号は大文字で空白・ハイフンなし）
+const JIS_EQUIVALENTS: [(&str, &str); 26] = [
+    // ASTM
+    ("A36", "SS400"),
+    ("A283C", "SS400"),
+    ("A653", "SGCC"),
+    ("A1008", "SPCC"),
+    ("A1011", "SPHC"),
+    // GB
+    ("Q195", "SS330"),
+    ("Q235", "SS400"),
+    ("Q275", "SS490"),
+    ("Q345", "SM490A"),
+    ("Q355", "SM490A"),
+    // EN
+    ("S235JR", "SS400"),
+    ("S275JR", "SS400"),
+    ("S355JR", "SM490A"),
+    ("S355J0", "SM490B"),
+    ("S355J2", "SM490C"),
+    ("C45", "S45C"),
+    // AISI / SAE
+    ("1020", "S20C"),
+    ("1045", "S45C"),
+    ("4135", "SCM435"),
+    ("4140", "SCM440"),
+    ("304", "SUS304"),
+    ("304L", "SUS304L"),
+    ("316", "SUS316"),
+    ("316L", "SUS316L"),
+    ("410", "SUS410"),
+    ("430", "SUS430"),
+];
+
+/// 材質の略記・通称と正式なJIS記号の対応表（材質ラベルの近くでのみ使う）
+const MATERIAL_ALIASES: [(&str, &str); 14] = [
+    ("18-8", "SUS304"),
+    ("18-8L", "SUS304L"),
+    ("18CR-8NI", "SUS304"),
+    ("18-12MO", "SUS316"),
+    ("304", "SUS304"),
+    ("304L", "SUS304L"),
+    ("316", "SUS316"),
+    ("316L", "SUS316L"),
+    ("310S", "SUS310S"),
+    ("321", "SUS321"),
+    ("410", "SUS410"),
+    ("430", "SUS430"),
+    ("18CR", "SUS430"),
+    ("13CR", "SUS410"),
+];
+
+/// 略記を読み替える材質ラベル（改行はまたがない）
+const MATERIAL_LABEL: &str = r"(?i)(?:材質|鋼種|材料|STEEL[ \t]*GRADE|GRADE|MATERIAL|QUALITY)[ \t]*[:.]?";
+
+/// コンパイル済みの材質ラベル
+static MATERIAL_LABEL_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(MATERIAL_LABEL));
+
+/// コンパイル済みの `+` / `-` の後の処理記号
+static TREATMENT_AFTER_SYMBOL_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(TREATMENT_AFTER_SYMBOL));
+
+/// コンパイル済みの空白区切りの処理記号
+static TREATMENT_AFTER_SPACE_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(TREATMENT_AFTER_SPACE));
+
+/// 組み込みの材質の略記表
+static BUILTIN_MATERIAL_ALIASES: LazyLock<MaterialAliasTable> =
+    LazyLock::new(|| MaterialAliasTable::new(MATERIAL_ALIASES.iter().copied()));
+
+/// このモジュールの組み込みパターンをすべてコンパイル
+pub(crate) fn precompile_patterns() {
+    LazyLock::force(&BUILTIN_REGEXES);
+    for regex in [&MATERIAL_LABEL_REGEX, &TREATMENT_AFTER_SYMBOL_REGEX, &TREATMENT_AFTER_SPACE_REGEX] {
+        LazyLock::force(regex);
+    }
+    LazyLock::force(&BUILTIN_MATERIAL_ALIASES);
+}
+
+/// 材質ラベルの直後で略記を探す文字数
+const MATERIAL_LABEL_REACH: usize = 24;
+
+/// 記号の前に付くことがある規格名（照合前に取り除く）
+const STANDARD_PREFIXES: [&str; 7] = ["ASTM", "AISI", "SAE", "GB/T", "GB", "EN", "TP"];
+
+/// 材質パターンの設定ファイル（`["NK\\d{3}HT"]` または `{ "patterns": [...] }`）
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaterialPatternFile {
+    List(Vec<String>),
+    Table { patterns: Vec<String> },
+}
+
+/// 材質パターンの照合器（ユーザー追加パターン → 組み込みパターンの順に評価）
+///
+/// 無効な正規表現は読み飛ばして警告ログに出す
+#[derive(Debug, Clone)]
+pub struct MaterialMatcher {
+    /// コンパイル済みのパターン（キャプチャ1が材質）と元のパターン文字列
+    patterns: Vec<(Regex, String)>,
+    /// ユーザー追加パターンの数（先頭から）
+    custom_count: usize,
+}
+
+impl Default for MaterialMatcher {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl MaterialMatcher {
+    /// ユーザー追加パターンと組み込みパターンを合成
+    pub fn new(custom_patterns: &[String]) -> Self {
+        let custom: Vec<(Regex, String)> = custom_patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(&format!(r"(?i)\b({})\b", pattern)) {
+                Ok(re) => Some((re, pattern.clone())),
+                Err(e) => {
+                    tracing::warn!("無効な材質パターンを読み飛ばします: {} ({})", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        let custom_count = custom.len();
+        
+        Self {
+            patterns: custom.into_iter().chain(BUILTIN_REGEXES.iter().cloned()).collect(),
+            custom_count,
+        }
+    }
+    
+    /// 設定ファイル（JSON の文字列配列、または `patterns` キーに配列）から読み込む
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("材質パターンの読み込みに失敗: {:?}", path))?;
+        let patterns = match serde_json::from_str(&content)
+            .with_context(|| format!("材質パターンのパースに失敗: {:?}", path))?
+        {
+            MaterialPatternFile::List(patterns) | MaterialPatternFile::Table { patterns } => patterns,
+        };
+        Ok(Self::new(&patterns))
+    }
+    
+    /// 有効なユーザー追加パターンの数
+    pub fn custom_count(&self) -> usize {
+        self.custom_count
+    }
+    
+    /// 正規化済みテキストから材質を探す（最初にマッチしたパターンを採用）
+    fn find(&self, text: &str) -> Option<FieldMatch> {
+        self.patterns.iter().find_map(|(re, pattern)| {
+            let m = re.captures(text)?.get(1)?;
+            let material = m.as_str().to_uppercase().replace(' ', "");
+            Some(FieldMatch::new(material, m.range(), pattern))
+        })
+    }
+    
+    /// 正規化済みテキストから全パターンの全マッチを探す（先のパターンと重なる位置のマッチは除く）
+    fn find_all(&self, text: &str) -> Vec<FieldMatch> {
+        let mut found: Vec<FieldMatch> = Vec::new();
+        for (re, pattern) in &self.patterns {
+            for caps in re.captures_iter(text) {
+                let Some(m) = caps.get(1) else {
+                    continue;
+                };
+                if found.iter().any(|f| f.span.start < m.end() && m.start() < f.span.end) {
+                    continue;
                 }
+                let material = m.as_str().to_uppercase().replace(' ', "");
+                found.push(FieldMatch::new(material, m.range(), pattern));
             }
         }
+        found
+    }
+}
+
+/// 追加の材質パターン（外部辞書の正規表現をコンパイルしたもの、マッチ全体が材質）
+#[derive(Debug, Clone)]
+pub struct MaterialPattern {
+    regex: Regex,
+    pattern: String,
+}
+
+impl MaterialPattern {
+    /// 材質パターンをコンパイル（前後は単語境界、大文字小文字は区別しない）
+    pub fn new(pattern: &str) -> Result<Self> {
+        let regex = Regex::new(&format!(r"(?i)\b({})\b", pattern))
+            .with_context(|| format!("材質パターンが不正です: {}", pattern))?;
+        Ok(Self {
+            regex,
+            pattern: pattern.to_string(),
+        })
+    }
+    
+    /// 元のパターン
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+}
+
+/// 材質の略記表（長い略記から並べ、略記の照合用の正規表現をコンパイル済み）
+#[derive(Debug, Clone)]
+pub struct MaterialAliasTable {
+    /// 略記と正式な記号（長い略記が先）
+    aliases: Vec<(String, String)>,
+    /// いずれかの略記に一致する正規表現（キャプチャ1が略記、略記が無ければ None）
+    regex: Option<Regex>,
+}
+
+impl MaterialAliasTable {
+    /// 略記と正式な記号の並びから略記表を作る（同じ長さの略記は並びの順）
+    pub fn new<'a>(table: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        // 長い略記を先に試す（18-8L を 18-8 より優先）
+        let mut aliases: Vec<(String, String)> = table
+            .into_iter()
+            .map(|(alias, material)| (alias.to_string(), material.to_string()))
+            .collect();
+        aliases.sort_by_key(|(alias, _)| std::cmp::Reverse(alias.len()));
+        let alternation: Vec<String> = aliases.iter().map(|(alias, _)| regex::escape(alias)).collect();
+        let regex = (!alternation.is_empty()).then(|| {
+            compile_builtin(&format!(r"(?i)(?:^|[^A-Z0-9.\-])({})(?:$|[^A-Z0-9.\-])", alternation.join("|")))
+        });
+        Self { aliases, regex }
+    }
+    
+    /// 組み込みの略記表
+    pub fn builtin() -> &'static Self {
+        &BUILTIN_MATERIAL_ALIASES
+    }
+    
+    /// 略記と正式な記号（長い略記が先）
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases.iter().map(|(alias, material)| (alias.as_str(), material.as_str()))
+    }
+    
+    /// 略記に対応する正式な記号（大文字小文字は区別しない）
+    fn lookup(&self, alias: &str) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|(a, _)| a.eq_ignore_ascii_case(alias))
+            .map(|(_, material)| material.as_str())
+    }
+}
+
+/// ファイル名での材質と処理記号の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaterialStyle {
+    /// 基本材質のみ（例: SUS304）
+    #[default]
+    Base,
+    /// 結合（例: SUS304+BA）
+    Combined,
+    /// 分離（例: SUS304_BA）
+    Separated,
+}
+
+/// 基本材質と表面処理・めっき等の処理記号
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct MaterialSpec {
+    /// 基本材質 (SUS304, SGCC など)
+    pub base: String,
+    /// 処理記号 (BA, Z27 など)
+    pub treatment: Option<String>,
+    /// 元の区切り記号（`+` / `-` / 空白）
+    pub separator: Option<char>,
+}
+
+impl MaterialSpec {
+    /// 指定の形式で文字列にする
+    pub fn format(&self, style: MaterialStyle) -> String {
+        let Some(ref treatment) = self.treatment else {
+            return self.base.clone();
+        };
+        
+        match style {
+            MaterialStyle::Base => self.base.clone(),
+            MaterialStyle::Combined => {
+                // 空白区切りはファイル名で崩れないよう '-' で結合する
+                let separator = match self.separator {
+                    Some('+') => '+',
+                    _ => '-',
+                };
+                format!("{}{}{}", self.base, separator, treatment)
+            }
+            MaterialStyle::Separated => format!("{}_{}", self.base, treatment),
+        }
+    }
+}
+
+/// 海外規格とJIS記号の対応表
+///
+/// 対応表を差し替えるときは `normalize_material_standard_with` に別の表を渡す
+pub fn jis_equivalents() -> &'static [(&'static str, &'static str)] {
+    &JIS_EQUIVALENTS
+}
+
+/// 海外規格（ASTM / GB / EN / AISI）の材質記号を相当するJIS記号に読み替える
+///
+/// 対応表に無い記号はそのまま返す。GBの品質等級（Q235B の B）は落として照合する
+pub fn normalize_material_standard(symbol: &str) -> String {
+    normalize_material_standard_with(symbol, jis_equivalents())
+}
+
+/// 対応表を指定して材質記号をJIS記号に読み替える（対応表に無ければそのまま返す）
+pub fn normalize_material_standard_with(symbol: &str, table: &[(&str, &str)]) -> String {
+    let key: String = symbol
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '.')
+        .collect::<String>()
+        .to_uppercase();
+    let key = STANDARD_PREFIXES
+        .iter()
+        .find_map(|prefix| key.strip_prefix(prefix).filter(|rest| !rest.is_empty()))
+        .unwrap_or(&key);
+    
+    let lookup = |key: &str| table.iter().find(|(foreign, _)| *foreign == key).map(|(_, jis)| jis.to_string());
+    lookup(key)
+        .or_else(|| {
+            // GBの品質等級（A〜E）は対応表に無ければ落として引き直す
+            key.strip_prefix('Q')
+                .and_then(|rest| rest.strip_suffix(['A', 'B', 'C', 'D', 'E']))
+                .and_then(|_| lookup(&key[..key.len() - 1]))
+        })
+        .unwrap_or_else(|| symbol.to_string())
+}
+
+/// 材質の略記・通称の対応表
+///
+/// 外部辞書の `material_aliases` で追加・上書きできる
+pub fn material_aliases() -> &'static [(&'static str, &'static str)] {
+    &MATERIAL_ALIASES
+}
+
+/// 材質ラベルの近くに書かれた略記・通称（18-8, 304 など）を正式な記号に読み替えて抽出
+///
+/// 略記は数字だけのことが多く誤認しやすいので、「材質」「鋼種」「GRADE」などのラベルと同じ行で、
+/// ラベルの直後 `MATERIAL_LABEL_REACH` 文字以内にあるものだけを採用する
+pub fn extract_material_alias(text: &str) -> Option<String> {
+    extract_material_alias_match_with(text, MaterialAliasTable::builtin()).map(|m| m.value)
+}
+
+/// 略記表を指定して材質の略記を読み替えて抽出（抽出根拠は推定扱い）
+pub fn extract_material_alias_match_with(text: &str, table: &MaterialAliasTable) -> Option<FieldMatch> {
+    let normalized = NormalizedText::new(text);
+    let text = normalized.text.as_str();
+    
+    let alias_re = table.regex.as_ref()?;
+    let label_re = &*MATERIAL_LABEL_REGEX;
+    
+    for label in label_re.find_iter(text) {
+        let line_end = text[label.end()..].find('\n').map_or(text.len(), |i| label.end() + i);
+        let reach = text[label.end()..line_end]
+            .char_indices()
+            .nth(MATERIAL_LABEL_REACH)
+            .map_or(line_end, |(i, _)| label.end() + i);
+        let Some(m) = alias_re.captures(&text[label.end()..reach]).and_then(|c| c.get(1)) else {
+            continue;
+        };
+        let alias = m.as_str().to_uppercase();
+        let Some(material) = table.lookup(&alias) else {
+            continue;
+        };
+        let span = label.end() + m.start()..label.end() + m.end();
+        tracing::debug!("材質の略記を読み替え: {} → {}", alias, material);
+        let found = FieldMatch {
+            inferred: true,
+            ..FieldMatch::new(material.to_string(), span, MATERIAL_LABEL)
+        };
+        return Some(normalized.remap(found));
     }
     
     None
 }
+
+/// テキストから材質/鋼種を抽出
+/// 対応: SS400, SPHC, SPCC, S45C, SUS304 など
+pub fn extract_material(text: &str) -> Option<String> {
+    extract_material_match(text, &[]).map(|m| m.value)
+}
+
+/// テキストに記載された材質をすべて抽出（出現順、重複は除く）
+///
+/// 積層品・複合部材のミルシートのように複数の材質が並ぶ場合に使う
+pub fn extract_all_materials(text: &str) -> Vec<String> {
+    extract_all_materials_with(text, &[], &MaterialMatcher::default())
+}
+
+/// 照合器を指定して材質をすべて抽出（`extra_patterns` は照合器より優先）
+pub fn extract_all_materials_with(text: &str, extra_patterns: &[MaterialPattern], matcher: &MaterialMatcher) -> Vec<String> {
+    let normalized = NormalizedText::new(text);
+    let text = normalized.text.as_str();
+    
+    let extra = MaterialMatcher {
+        patterns: extra_patterns
+            .iter()
+            .map(|p| (p.regex.clone(), p.pattern.clone()))
+            .chain(matcher.patterns.iter().cloned())
+            .collect(),
+        custom_count: 0,
+    };
+    let mut found = extra.find_all(text);
+    found.sort_by_key(|f| f.span.start);
+    
+    let mut materials: Vec<String> = Vec::new();
+    for f in found {
+        if !materials.contains(&f.value) {
+            materials.push(f.value);
+        }
+    }
+    materials
+}
+
+/// 材質を基本材質と処理記号に分けて抽出（抽出根拠のマッチ位置は処理記号まで含む）
+pub fn extract_material_spec_match(text: &str, extra_patterns: &[MaterialPattern]) -> Option<(MaterialSpec, FieldMatch)> {
+    extract_material_spec_match_with(text, extra_patterns, &MaterialMatcher::default())
+}
+
+/// 照合器を指定して材質を基本材質と処理記号に分けて抽出（`extra_patterns` は照合器より優先）
+pub fn extract_material_spec_match_with(
+    text: &str,
+    extra_patterns: &[MaterialPattern],
+    matcher: &MaterialMatcher,
+) -> Option<(MaterialSpec, FieldMatch)> {
+    let normalized = NormalizedText::new(text);
+    let text = normalized.text.as_str();
+    let found = find_material(text, extra_patterns, matcher)?;
+    let rest = &text[found.span.end..];
+    
+    let treatment = if let Some(caps) = TREATMENT_AFTER_SYMBOL_REGEX.captures(rest) {
+        let separator = caps.get(1)?.as_str().chars().next();
+        Some((caps.get(2)?.as_str(), separator, caps.get(0)?.end()))
+    } else if let Some(caps) = TREATMENT_AFTER_SPACE_REGEX.captures(rest) {
+        Some((caps.get(1)?.as_str(), Some(' '), caps.get(0)?.end()))
+    } else {
+        None
+    };
+    
+    let Some((code, separator, len)) = treatment else {
+        let spec = MaterialSpec {
+            base: found.value.clone(),
+            treatment: None,
+            separator: None,
+        };
+        return Some((spec, normalized.remap(found)));
+    };
+    
+    let spec = MaterialSpec {
+        base: found.value.clone(),
+        treatment: Some(code.to_uppercase().replace(' ', "")),
+        separator,
+    };
+    let found = FieldMatch {
+        span: found.span.start..found.span.end + len,
+        ..found
+    };
+    Some((spec, normalized.remap(found)))
+}
+
+/// 追加パターン（辞書）を優先して材質と抽出根拠を取得
+pub fn extract_material_match(text: &str, extra_patterns: &[MaterialPattern]) -> Option<FieldMatch> {
+    let normalized = NormalizedText::new(text);
+    find_material(&normalized.text, extra_patterns, &MaterialMatcher::default()).map(|m| normalized.remap(m))
+}
+
+/// 正規化済みテキストから材質を探す（辞書の追加パターン → 照合器の順）
+fn find_material(text: &str, extra_patterns: &[MaterialPattern], matcher: &MaterialMatcher) -> Option<FieldMatch> {
+    for pattern in extra_patterns {
+        if let Some(m) = pattern.regex.find(text) {
+            let material = m.as_str().to_uppercase().replace(' ', "");
+            return Some(FieldMatch::new(material, m.range(), &pattern.pattern));
+        }
+    }
+    
+    matcher.find(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{MillsheetInfo, ParseOptions};
+    
+    #[test]
+    fn alias_table_tries_longer_aliases_first() {
+        let table = MaterialAliasTable::new([("18-8", "SUS304"), ("18-8L", "SUS304L")]);
+        let aliases: Vec<&str> = table.aliases().map(|(alias, _)| alias).collect();
+        assert_eq!(aliases, ["18-8L", "18-8"]);
+        let found = extract_material_alias_match_with("材質 18-8l", &table).map(|m| m.value);
+        assert_eq!(found.as_deref(), Some("SUS304L"));
+    }
+    
+    #[test]
+    fn builtin_alias_table_is_built_once() {
+        assert!(std::ptr::eq(MaterialAliasTable::builtin(), MaterialAliasTable::builtin()));
+        assert_eq!(MaterialAliasTable::builtin().aliases().count(), MATERIAL_ALIASES.len());
+    }
+    
+    #[test]
+    fn empty_alias_table_finds_nothing() {
+        let table = MaterialAliasTable::new([]);
+        assert!(extract_material_alias_match_with("材質 18-8", &table).is_none());
+    }
+    
+    /// 基本材質・処理記号・区切り記号
+    fn spec(text: &str) -> Option<(String, Option<String>, Option<char>)> {
+        extract_material_spec_match(text, &[]).map(|(spec, _)| (spec.base, spec.treatment, spec.separator))
+    }
+    
+    fn split(base: &str, treatment: &str, separator: char) -> Option<(String, Option<String>, Option<char>)> {
+        Some((base.to_string(), Some(treatment.to_string()), Some(separator)))
+    }
+    
+    #[test]
+    fn treatment_after_plus_is_separated() {
+        assert_eq!(spec("SUS304+BA"), split("SUS304", "BA", '+'));
+        assert_eq!(spec("SUS430 + ba"), split("SUS430", "BA", '+'));
+    }
+    
+    #[test]
+    fn treatment_after_hyphen_is_separated() {
+        assert_eq!(spec("SGCC-Z27"), split("SGCC", "Z27", '-'));
+        assert_eq!(spec("SUS304-2B"), split("SUS304", "2B", '-'));
+    }
+    
+    #[test]
+    fn known_treatment_after_space_is_separated() {
+        assert_eq!(spec("SUS304 2B"), split("SUS304", "2B", ' '));
+        assert_eq!(spec("SUS304 NO.4"), split("SUS304", "NO.4", ' '));
+        assert_eq!(spec("SGCC Z12"), split("SGCC", "Z12", ' '));
+        assert_eq!(spec("SUS304 HL 1.5X1219"), split("SUS304", "HL", ' '));
+    }
+    
+    #[test]
+    fn words_and_numbers_after_material_are_not_treatments() {
+        assert_eq!(spec("SUS304 PLATE"), Some(("SUS304".to_string(), None, None)));
+        assert_eq!(spec("SS400-1"), Some(("SS400".to_string(), None, None)));
+    }
+    
+    #[test]
+    fn material_span_includes_treatment() {
+        let (_, found) = extract_material_spec_match("材質 SUS304+BA 1.5X1219", &[]).unwrap();
+        assert_eq!(found.value, "SUS304");
+        assert_eq!(&"材質 SUS304+BA 1.5X1219"[found.span], "SUS304+BA");
+    }
+    
+    #[test]
+    fn style_chooses_combined_or_separated_treatment() {
+        let spec = |separator| MaterialSpec {
+            base: "SUS304".to_string(),
+            treatment: Some("2B".to_string()),
+            separator: Some(separator),
+        };
+        assert_eq!(spec('+').format(MaterialStyle::Base), "SUS304");
+        assert_eq!(spec('+').format(MaterialStyle::Combined), "SUS304+2B");
+        assert_eq!(spec('-').format(MaterialStyle::Combined), "SUS304-2B");
+        // 空白区切りはファイル名で崩れないよう '-' で結合する
+        assert_eq!(spec(' ').format(MaterialStyle::Combined), "SUS304-2B");
+        assert_eq!(spec(' ').format(MaterialStyle::Separated), "SUS304_2B");
+    }
+    
+    #[test]
+    fn parse_keeps_both_base_and_treatment() {
+        let options = ParseOptions {
+            material_style: MaterialStyle::Combined,
+            ..Default::default()
+        };
+        let info = MillsheetInfo::parse_with_options("材質 SGCC-Z27", &options);
+        assert_eq!(info.material.as_deref(), Some("SGCC-Z27"));
+        let spec = info.material_spec.unwrap();
+        assert_eq!((spec.base.as_str(), spec.treatment.as_deref()), ("SGCC", Some("Z27")));
+        
+        let info = MillsheetInfo::parse("材質 SGCC-Z27");
+        assert_eq!(info.material.as_deref(), Some("SGCC"));
+        assert_eq!(info.material_spec.and_then(|s| s.treatment).as_deref(), Some("Z27"));
+    }
+    
+    #[test]
+    fn custom_patterns_skip_invalid_regex() {
+        let matcher = MaterialMatcher::new(&["NK\\d{3}HT".to_string(), "(broken".to_string()]);
+        assert_eq!(matcher.custom_count(), 1);
+        
+        let options = ParseOptions { material_matcher: Some(std::sync::Arc::new(matcher)), ..Default::default() };
+        let info = MillsheetInfo::parse_with_options("GRADE nk590ht / SS400", &options);
+        assert_eq!(info.material.as_deref(), Some("NK590HT"));
+    }
+    
+    #[test]
+    fn stainless_grades_keep_suffixes() {
+        assert_eq!(extract_material("GRADE sus 329j4l"), Some("SUS329J4L".to_string()));
+        assert_eq!(extract_material("SUS304N2 PLATE"), Some("SUS304N2".to_string()));
+        assert_eq!(extract_material("SUS304 STEEL"), Some("SUS304".to_string()));
+        assert_eq!(extract_material("SUH409L"), Some("SUH409L".to_string()));
+    }
+    
+    #[test]
+    fn alloy_grades_drop_tempers() {
+        assert_eq!(extract_material("ALLOY A5052-H34"), Some("A5052".to_string()));
+        assert_eq!(extract_material("C2600R-1/2H"), Some("C2600R".to_string()));
+        assert_eq!(extract_material("CHARGE A12345 / 1.6X1219XC"), None);
+    }
+    
+    #[test]
+    fn all_materials_are_deduplicated_in_order() {
+        let text = "表板 SUS304 / 芯材 SS400 / 裏板 SUS304 / 補強 A5052-H34";
+        assert_eq!(extract_all_materials(text), ["SUS304", "SS400", "A5052"]);
+    }
+    
+    #[test]
+    fn foreign_standards_map_to_jis() {
+        assert_eq!(normalize_material_standard("A36"), "SS400");
+        assert_eq!(normalize_material_standard("ASTM A36"), "SS400");
+        assert_eq!(normalize_material_standard("Q235B"), "SS400");
+        assert_eq!(normalize_material_standard("S355JR"), "SM490A");
+        assert_eq!(normalize_material_standard("AISI 316L"), "SUS316L");
+        assert_eq!(normalize_material_standard("SUS304"), "SUS304");
+    }
+    
+    #[test]
+    fn aliases_need_a_label_on_the_same_line() {
+        assert_eq!(extract_material_alias("材質 18-8 2B"), Some("SUS304".to_string()));
+        assert_eq!(extract_material_alias("GRADE: 316L"), Some("SUS316L".to_string()));
+        assert_eq!(extract_material_alias("鋼種：１８－８"), Some("SUS304".to_string()));
+        // ラベルが無い・別の行の数字は読み替えない
+        assert_eq!(extract_material_alias("ロット 304"), None);
+        assert_eq!(extract_material_alias("材質\n304"), None);
+        assert_eq!(extract_material_alias("材質 3040"), None);
+    }
+}
@@ -1,49 +1,742 @@
 //! テキスト解析モジュール - ミルシート情報の抽出
 
-mod date;
-mod dimensions;
-mod manufacturer;
-mod material;
+pub mod compare;
+pub mod date;
+pub mod dictionary;
+pub mod dimensions;
+pub mod manufacturer;
+pub mod material;
+pub mod rules;
+pub mod template;
+pub mod units;
+pub mod weight;
 
+pub use date::{DateFormat, DateSelection, IssueDate};
+pub use rules::{validate_filename, validate_generated_filename, validate_generated_filename_with, FilenameRules};
+pub use units::extract_value_with_unit;
+
+use dictionary::{Dictionary, ManufacturerHint};
+use dimensions::{DimensionProfile, Dimensions};
+use manufacturer::CorporateFormStyle;
+use material::{MaterialMatcher, MaterialSpec, MaterialStyle};
 use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::sync::{Arc, LazyLock};
+
+/// 解析オプション
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// 材質・寸法・日付として採用した部分をマスクしてからチャージ番号を抽出する
+    pub mask_used_tokens: bool,
+    /// 外部辞書（材質パターン・メーカー表の追加分）
+    pub dictionary: Option<Arc<Dictionary>>,
+    /// メーカー名の法人格（株式会社・㈱など）の扱い
+    pub corporate_form: CorporateFormStyle,
+    /// 材質の処理記号（+BA, -Z27 など）の扱い
+    pub material_style: MaterialStyle,
+    /// 材質パターンの照合器（None なら組み込みパターンのみ）
+    pub material_matcher: Option<Arc<MaterialMatcher>>,
+    /// 海外規格の材質記号（A36, Q235 など）を相当するJIS記号に読み替える
+    pub normalize_material_standard: bool,
+    /// ファイル名での発行日の書式
+    pub date_format: DateFormat,
+    /// 2桁の年が表す世紀の開始年（2000 → 2000〜2099年）。指定するとこの世紀の外の年は4桁で出す
+    pub date_century: Option<u32>,
+    /// ラベルなしの日付が複数あるときの選び方
+    pub date_selection: DateSelection,
+    /// 最も新しい日付を選ぶときの今日（これより後の日付は除外、None なら実行した日）
+    pub today: Option<chrono::NaiveDate>,
+    /// 複数のチャージ番号の並べ方
+    pub charge_no_order: ChargeNoOrder,
+    /// 寸法の妥当性ルール（コイル製品 / 定尺板）
+    pub dimension_profile: DimensionProfile,
+    /// 抽出しないフィールド（`EXTRACT_FIELDS` の名前、常に None でファイル名からも外れる）
+    pub disabled_fields: BTreeSet<&'static str>,
+}
+
+impl ParseOptions {
+    /// フィールドの抽出が有効か
+    pub fn is_enabled(&self, field: &str) -> bool {
+        !self.disabled_fields.contains(field)
+    }
+}
+
+/// OCR結果から制御文字・不正な文字を除去（改行・タブは残す）
+///
+/// 対象: 制御文字、置換文字 U+FFFD、非文字 (U+FDD0〜U+FDEF, U+xFFFE/U+xFFFF)、
+/// ゼロ幅文字・双方向制御文字・BOM。ファイル名用の `sanitize_for_filename` とは別に、パース入力段で使う
+pub fn sanitize_ocr_text(text: &str) -> String {
+    text.chars()
+        .filter(|&c| {
+            let code = c as u32;
+            let is_control = c.is_control() && !matches!(c, '\n' | '\r' | '\t');
+            let is_noncharacter = (0xFDD0..=0xFDEF).contains(&code) || code & 0xFFFE == 0xFFFE;
+            let is_invisible = matches!(
+                c,
+                '\u{FFFD}' | '\u{FEFF}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}'
+            );
+            !(is_control || is_noncharacter || is_invisible)
+        })
+        .collect()
+}
+
+/// 全角英数字・記号を半角に、全角スペースを半角スペースに、乗算記号 × を X に正規化
+pub fn normalize_fullwidth(text: &str) -> String {
+    NormalizedText::new(text).text
+}
+
+/// 正規化済みテキストと元テキストの位置対応（抽出根拠の位置を元テキストに戻すため）
+pub(crate) struct NormalizedText {
+    /// 正規化済みテキスト
+    pub text: String,
+    /// 正規化済みテキストの各バイトに対応する元テキストのバイト位置（末尾を含む）
+    offsets: Vec<usize>,
+}
+
+impl NormalizedText {
+    pub fn new(original: &str) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut offsets = Vec::with_capacity(original.len() + 1);
+        
+        for (pos, c) in original.char_indices() {
+            let normalized = match c {
+                '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+                '\u{3000}' => ' ',
+                '×' => 'X',
+                _ => c,
+            };
+            text.push(normalized);
+            offsets.extend(std::iter::repeat_n(pos, normalized.len_utf8()));
+        }
+        offsets.push(original.len());
+        
+        Self { text, offsets }
+    }
+    
+    /// 正規化済みテキスト上の位置を元テキスト上の位置に戻す
+    pub fn original_span(&self, span: Range<usize>) -> Range<usize> {
+        self.offsets[span.start]..self.offsets[span.end]
+    }
+    
+    /// 抽出根拠の位置を元テキスト上の位置に戻す
+    pub fn remap(&self, found: FieldMatch) -> FieldMatch {
+        FieldMatch {
+            span: self.original_span(found.span.clone()),
+            ..found
+        }
+    }
+}
+
+/// フィールドの抽出結果と根拠
+#[derive(Debug, Clone)]
+pub struct FieldMatch {
+    /// 正規化済みの値
+    pub value: String,
+    /// 元テキスト中のマッチ位置
+    pub span: Range<usize>,
+    /// マッチしたパターン（正規表現または表記ゆれ）
+    pub pattern: String,
+    /// 救済ロジックで推定したかどうか
+    pub inferred: bool,
+}
+
+impl FieldMatch {
+    pub fn new(value: String, span: Range<usize>, pattern: &str) -> Self {
+        Self {
+            value,
+            span,
+            pattern: pattern.to_string(),
+            inferred: false,
+        }
+    }
+}
 
-/// ミルシートから抽出された情報
+/// フィールド別の抽出根拠
 #[derive(Debug, Clone, Default)]
+pub struct FieldEvidence {
+    pub date: Option<FieldMatch>,
+    pub material: Option<FieldMatch>,
+    pub dimensions: Option<FieldMatch>,
+    pub manufacturer: Option<FieldMatch>,
+    pub charge_no: Option<FieldMatch>,
+    pub weight: Option<FieldMatch>,
+}
+
+/// 通常の抽出で得たフィールドの信頼度
+pub const CONFIDENCE_DEFAULT: f32 = 1.0;
+/// 区切り補完などの救済ロジックで推定したフィールドの信頼度
+pub const CONFIDENCE_INFERRED: f32 = 0.5;
+
+/// 総合信頼度・確認待ちの判定に使うフィールド名
+///
+/// 重量は記載の無いミルシートも多い任意の項目なので含めない（`FieldConfidence::weight` は個別に参照する）
+pub const CONFIDENCE_FIELDS: [&str; 5] = ["date", "material", "dimensions", "manufacturer", "charge_no"];
+
+/// 抽出を個別に無効化できるフィールド名
+pub const EXTRACT_FIELDS: [&str; 6] = ["date", "material", "dimensions", "manufacturer", "charge_no", "weight"];
+
+/// フィールド名を `EXTRACT_FIELDS` の要素にする（未知の名前は None）
+pub fn extract_field(name: &str) -> Option<&'static str> {
+    EXTRACT_FIELDS.into_iter().find(|field| *field == name)
+}
+
+/// フィールドの表示名
+pub fn field_label(field: &str) -> &str {
+    match field {
+        "date" => "発行日",
+        "material" => "材質",
+        "dimensions" => "寸法",
+        "manufacturer" => "メーカー",
+        "charge_no" => "チャージNo",
+        "weight" => "重量",
+        other => other,
+    }
+}
+
+/// 「完全」とみなす既定の必須フィールド（日付・材質・寸法・メーカー）
+pub const DEFAULT_REQUIRED_FIELDS: [&str; 4] = ["date", "material", "dimensions", "manufacturer"];
+
+/// フィールド別の信頼度 (0.0〜1.0、未抽出は0.0)
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct FieldConfidence {
+    /// 発行日の信頼度
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub date: f32,
+    /// 材質の信頼度
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub material: f32,
+    /// 寸法の信頼度
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub dimensions: f32,
+    /// メーカー名の信頼度
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub manufacturer: f32,
+    /// チャージ番号の信頼度
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub charge_no: f32,
+    /// 重量の信頼度（総合信頼度には含めない）
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub weight: f32,
+}
+
+impl FieldConfidence {
+    /// `CONFIDENCE_FIELDS` の総合信頼度（平均）
+    pub fn overall(&self) -> f32 {
+        (self.date + self.material + self.dimensions + self.manufacturer + self.charge_no) / 5.0
+    }
+    
+    /// フィールド名（`EXTRACT_FIELDS` のいずれか）から信頼度を取得
+    pub fn get(&self, field: &str) -> Option<f32> {
+        match field {
+            "date" => Some(self.date),
+            "material" => Some(self.material),
+            "dimensions" => Some(self.dimensions),
+            "manufacturer" => Some(self.manufacturer),
+            "charge_no" => Some(self.charge_no),
+            "weight" => Some(self.weight),
+            _ => None,
+        }
+    }
+    
+    /// 必須フィールドがすべて通常の抽出（推定でない高信頼度）で取れているか
+    pub fn is_complete<'a>(&self, required: impl IntoIterator<Item = &'a str>) -> bool {
+        required
+            .into_iter()
+            .all(|field| self.get(field).is_some_and(|c| c >= CONFIDENCE_DEFAULT))
+    }
+}
+
+/// マージした結果の各フィールドの採用元
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeSource {
+    /// `merge` の第1引数（優先ソース）
+    Primary,
+    /// `merge` の第2引数
+    Secondary,
+}
+
+/// ミルシートから抽出された情報（未抽出のフィールドは null）
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
 pub struct MillsheetInfo {
-    /// 発行日 (YY-MM-DD形式)
-    pub date: Option<String>,
-    /// 材質 (SS400, SPHC など)
+    /// 発行日 (YY-MM-DD形式、日の記載が無ければ YY-MM)
+    #[schemars(with = "Option<String>", regex(pattern = r"^\d{2}-\d{2}(-\d{2})?$"))]
+    pub date: Option<IssueDate>,
+    /// 材質 (SS400, SPHC など、処理記号の扱いは ParseOptions に従う)
     pub material: Option<String>,
+    /// 基本材質と処理記号
+    pub material_spec: Option<MaterialSpec>,
+    /// 主材質以外に記載された材質（出現順、ファイル名には使わない）
+    pub sub_materials: Vec<String>,
     /// 寸法 (厚さx幅x長さ)
     pub dimensions: Option<String>,
+    /// 板の寸法の数値（丸棒・パイプや厚さのみの場合は null）
+    pub dimensions_spec: Option<Dimensions>,
     /// メーカー名
     pub manufacturer: Option<String>,
+    /// メーカー判定に使ったテキスト中の表記（"KOBELCO" など、調査用でファイル名には使わない）
+    pub manufacturer_matched_term: Option<String>,
     /// 溶鋼番号/チャージ番号
     pub charge_no: Option<String>,
+    /// 複数ヒート混載の場合の全チャージ番号（重複なし、`charge_no` を含む）
+    pub charge_nos: Vec<String>,
+    /// 実重量 (2450kg, 12.3t など)
+    pub weight: Option<String>,
     /// 元のテキスト
     pub raw_text: String,
+    /// フィールド別の信頼度
+    pub confidence: FieldConfidence,
+    /// マージ時の各フィールドの採用元（`merge` の結果のみ、値のあるフィールドだけ）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_sources: BTreeMap<String, MergeSource>,
+    /// ファイル名での発行日の書式（JSONの `date` は常に YY-MM-DD）
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub date_format: DateFormat,
+    /// 2桁の年が表す世紀の開始年（`ParseOptions::date_century`）
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub date_century: Option<u32>,
+    /// 抽出を無効にしたフィールド（総合信頼度・テンプレートで使う）
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub disabled_fields: BTreeSet<&'static str>,
 }
 
 impl MillsheetInfo {
     /// テキストからミルシート情報を解析
+    ///
+    /// ラベル無しのチャージ番号は、材質と同じ記号や寸法の数字を含む候補を除いて探す
     pub fn parse(text: &str) -> Self {
-        Self {
-            date: date::extract_date(text),
-            material: material::extract_material(text),
-            dimensions: dimensions::extract_dimensions(text),
-            manufacturer: manufacturer::extract_manufacturer(text),
-            charge_no: extract_charge_no(text),
+        Self::parse_with_options(text, &ParseOptions::default())
+    }
+    
+    /// 同じPDFを別ソース（Vision とテキストレイヤーなど）で解析した2つの結果をフィールド単位でマージ
+    ///
+    /// 片方にしか無い値はその値、両方にあれば信頼度の高い方（同じなら優先ソースの `a`）を採用する。
+    /// 連動するフィールド（材質と基本材質・処理記号など）は同じソースからそろえて採る
+    pub fn merge(a: &MillsheetInfo, b: &MillsheetInfo) -> MillsheetInfo {
+        let mut merged = a.clone();
+        merged.field_sources.clear();
+        
+        // 値の有無・信頼度から採用元を決める
+        let choose = |field: &str, a_has: bool, b_has: bool| {
+            let a_conf = a.confidence.get(field).unwrap_or(CONFIDENCE_DEFAULT);
+            let b_conf = b.confidence.get(field).unwrap_or(CONFIDENCE_DEFAULT);
+            match (a_has, b_has) {
+                (false, false) => None,
+                (true, false) => Some(MergeSource::Primary),
+                (false, true) => Some(MergeSource::Secondary),
+                (true, true) if b_conf > a_conf => Some(MergeSource::Secondary),
+                (true, true) => Some(MergeSource::Primary),
+            }
+        };
+        
+        if let Some(source) = choose("date", a.date.is_some(), b.date.is_some()) {
+            if source == MergeSource::Secondary {
+                merged.date = b.date;
+                merged.confidence.date = b.confidence.date;
+            }
+            merged.field_sources.insert("date".to_string(), source);
+        }
+        if let Some(source) = choose("material", a.material.is_some(), b.material.is_some()) {
+            if source == MergeSource::Secondary {
+                merged.material = b.material.clone();
+                merged.material_spec = b.material_spec.clone();
+                merged.confidence.material = b.confidence.material;
+            }
+            merged.field_sources.insert("material".to_string(), source);
+        }
+        if let Some(source) = choose("dimensions", a.dimensions.is_some(), b.dimensions.is_some()) {
+            if source == MergeSource::Secondary {
+                merged.dimensions = b.dimensions.clone();
+                merged.dimensions_spec = b.dimensions_spec;
+                merged.confidence.dimensions = b.confidence.dimensions;
+            }
+            merged.field_sources.insert("dimensions".to_string(), source);
+        }
+        if let Some(source) = choose("manufacturer", a.manufacturer.is_some(), b.manufacturer.is_some()) {
+            if source == MergeSource::Secondary {
+                merged.manufacturer = b.manufacturer.clone();
+                merged.manufacturer_matched_term = b.manufacturer_matched_term.clone();
+                merged.confidence.manufacturer = b.confidence.manufacturer;
+            }
+            merged.field_sources.insert("manufacturer".to_string(), source);
+        }
+        if let Some(source) = choose("charge_no", a.charge_no.is_some(), b.charge_no.is_some()) {
+            if source == MergeSource::Secondary {
+                merged.charge_no = b.charge_no.clone();
+                merged.charge_nos = b.charge_nos.clone();
+                merged.confidence.charge_no = b.confidence.charge_no;
+            }
+            merged.field_sources.insert("charge_no".to_string(), source);
+        }
+        if let Some(source) = choose("weight", a.weight.is_some(), b.weight.is_some()) {
+            if source == MergeSource::Secondary {
+                merged.weight = b.weight.clone();
+                merged.confidence.weight = b.confidence.weight;
+            }
+            merged.field_sources.insert("weight".to_string(), source);
+        }
+        
+        for sub in &b.sub_materials {
+            if !merged.sub_materials.contains(sub) && merged.material.as_ref() != Some(sub) {
+                merged.sub_materials.push(sub.clone());
+            }
+        }
+        if let Some(material) = &merged.material {
+            merged.sub_materials.retain(|sub| sub != material);
+        }
+        if !b.raw_text.is_empty() {
+            if !merged.raw_text.is_empty() {
+                merged.raw_text.push('\n');
+            }
+            merged.raw_text.push_str(&b.raw_text);
+        }
+        
+        merged
+    }
+    
+    /// 同じ画像の生テキストとレイアウトを再構成したテキストの解析結果から、抽出できたフィールドが多い方を採用
+    ///
+    /// 同数なら総合信頼度の高い方、それも同じなら `raw`
+    pub fn better_of(raw: MillsheetInfo, layout: MillsheetInfo) -> MillsheetInfo {
+        if layout.outscores(&raw) {
+            tracing::debug!("レイアウトを再構成したテキストの解析結果を採用 ({} 項目)", layout.extracted_count());
+            layout
+        } else {
+            raw
+        }
+    }
+    
+    /// ページごとの解析結果から、抽出できたフィールドが最も多いページ（0始まりの番号と解析結果）を選ぶ
+    ///
+    /// 同数なら総合信頼度の高い方、それも同じなら前のページ
+    pub fn best_page(pages: impl IntoIterator<Item = MillsheetInfo>) -> Option<(usize, MillsheetInfo)> {
+        pages
+            .into_iter()
+            .enumerate()
+            .reduce(|best, page| if page.1.outscores(&best.1) { page } else { best })
+    }
+    
+    /// 抽出できたフィールド数、同数なら総合信頼度で `other` より優れているか
+    fn outscores(&self, other: &MillsheetInfo) -> bool {
+        let (count, other_count) = (self.extracted_count(), other.extracted_count());
+        count > other_count || (count == other_count && self.overall_confidence() > other.overall_confidence())
+    }
+    
+    /// 抽出が有効なフィールドのうち値を抽出できた数
+    pub fn extracted_count(&self) -> usize {
+        EXTRACT_FIELDS
+            .into_iter()
+            .filter(|field| self.is_field_enabled(field))
+            .filter(|field| self.field_value(field).is_some())
+            .count()
+    }
+    
+    /// JSON Schema（連携先のバリデーション用）
+    pub fn json_schema() -> serde_json::Value {
+        schemars::schema_for!(MillsheetInfo).to_value()
+    }
+    
+    /// 発行日を日付として取得
+    pub fn issue_date(&self) -> Option<chrono::NaiveDate> {
+        self.date.and_then(|d| d.to_naive_date())
+    }
+    
+    /// 発行日から年度・四半期・月・曜日を導出（発行日が無ければ None）
+    pub fn derived_date(&self, fiscal_year_start_month: u32) -> Option<date::DerivedDate> {
+        self.issue_date()
+            .map(|d| date::DerivedDate::from_date(d, fiscal_year_start_month))
+    }
+    
+    /// オプションを指定してテキストからミルシート情報を解析
+    pub fn parse_with_options(text: &str, options: &ParseOptions) -> Self {
+        Self::parse_detailed(text, options).0
+    }
+    
+    /// 解析結果と各フィールドの抽出根拠を返す
+    ///
+    /// 入力は `sanitize_ocr_text` で除去してから解析し、抽出根拠の位置は除去後の `raw_text` 上の位置
+    pub fn parse_detailed(text: &str, options: &ParseOptions) -> (Self, FieldEvidence) {
+        let sanitized = sanitize_ocr_text(text);
+        let text = sanitized.as_str();
+        let dictionary = options.dictionary.as_deref();
+        let enabled = |field: &str| options.is_enabled(field);
+        let (issue_date, date) = enabled("date")
+            .then(|| {
+                let today = options.today.unwrap_or_else(|| chrono::Local::now().date_naive());
+                date::extract_date_match_as_of(text, options.date_selection, today)
+            })
+            .flatten()
+            .unzip();
+        let default_matcher;
+        let matcher = match options.material_matcher.as_deref() {
+            Some(matcher) => matcher,
+            None => {
+                default_matcher = MaterialMatcher::default();
+                &default_matcher
+            }
+        };
+        let material_found = enabled("material")
+            .then(|| {
+                material::extract_material_spec_match_with(
+                    text,
+                    dictionary.map(|d| d.material_patterns()).unwrap_or_default(),
+                    matcher,
+                )
+            })
+            .flatten()
+            .or_else(|| {
+                // 記号が見つからなければ、材質ラベル近くの略記（18-8, 304 など）を読み替える
+                let aliases = dictionary.map_or(material::MaterialAliasTable::builtin(), |d| d.material_aliases());
+                enabled("material")
+                    .then(|| material::extract_material_alias_match_with(text, aliases))
+                    .flatten()
+                    .map(|found| (MaterialSpec { base: found.value.clone(), treatment: None, separator: None }, found))
+            });
+        let material_found = material_found.map(|(spec, found)| {
+            if options.normalize_material_standard {
+                let base = material::normalize_material_standard(&spec.base);
+                (MaterialSpec { base, ..spec }, found)
+            } else {
+                (spec, found)
+            }
+        });
+        let (material_spec, material) = match material_found {
+            Some((spec, found)) => {
+                let value = spec.format(options.material_style);
+                (Some(spec), Some(FieldMatch { value, ..found }))
+            }
+            None => (None, None),
+        };
+        let sub_materials: Vec<String> = match material_spec {
+            Some(ref spec) => material::extract_all_materials_with(
+                text,
+                dictionary.map(|d| d.material_patterns()).unwrap_or_default(),
+                matcher,
+            )
+            .into_iter()
+            .map(|m| {
+                if options.normalize_material_standard {
+                    material::normalize_material_standard(&m)
+                } else {
+                    m
+                }
+            })
+            .filter(|m| *m != spec.base)
+            .fold(Vec::new(), |mut materials, m| {
+                if !materials.contains(&m) {
+                    materials.push(m);
+                }
+                materials
+            }),
+            None => Vec::new(),
+        };
+        let (dimensions_spec, dimensions) = enabled("dimensions")
+            .then(|| dimensions::extract_dimensions_spec_match_with(text, options.dimension_profile))
+            .flatten()
+            .unzip();
+        let dimensions_spec = dimensions_spec.flatten();
+        
+        let charge_text = if options.mask_used_tokens {
+            let spans: Vec<Range<usize>> = [&date, &material, &dimensions]
+                .into_iter()
+                .filter_map(|found| found.as_ref().map(|m| m.span.clone()))
+                .collect();
+            mask_spans(text, &spans)
+        } else {
+            text.to_string()
+        };
+        // ラベル無しの候補から、材質記号（A5052, C1100 など）や寸法の数字（W1219 など）と重なるものを除く
+        let exclusions = ChargeExclusions::new(
+            material_spec.iter().map(|spec| spec.base.as_str()).chain(sub_materials.iter().map(String::as_str)),
+            dimensions.as_ref().map(|m| m.value.as_str()),
+        );
+        let mut charge_no = enabled("charge_no")
+            .then(|| extract_charge_no_match(&charge_text, &exclusions))
+            .flatten();
+        
+        let manufacturer = enabled("manufacturer")
+            .then(|| {
+                manufacturer::extract_manufacturer_match(
+                    text,
+                    dictionary.map(|d| d.manufacturers.as_slice()).unwrap_or_default(),
+                )
+            })
+            .flatten();
+        
+        // 2パス目: メーカーが確定したら、そのメーカーの書式でチャージ番号を探し直す
+        if enabled("charge_no")
+            && let Some(hint) = dictionary
+            .zip(manufacturer.as_ref())
+            .and_then(|(d, m)| d.hint_for(&m.value))
+            && !charge_no.as_ref().is_some_and(|m| matches_hint_format(&m.value, hint))
+            && let Some(found) = extract_charge_no_with_hint(&charge_text, hint)
+        {
+            charge_no = Some(found);
+        }
+        
+        // 同じ書式で並ぶ残りのチャージ番号（ヒントで見つけた番号が候補に無ければその1件のみ）
+        let charge_nos = match &charge_no {
+            Some(primary) => {
+                let normalized = NormalizedText::new(&charge_text);
+                let mut all = find_all_charge_no(&normalized.text, &exclusions);
+                if !all.contains(&primary.value) {
+                    all = vec![primary.value.clone()];
+                }
+                if options.charge_no_order == ChargeNoOrder::Ascending {
+                    all.sort();
+                }
+                all
+            }
+            None => Vec::new(),
+        };
+        
+        let manufacturer = manufacturer.map(|m| FieldMatch {
+            value: manufacturer::normalize_corporate_form(&m.value, options.corporate_form),
+            ..m
+        });
+        let weight = enabled("weight").then(|| weight::extract_weight_match(text)).flatten();
+        
+        let confidence_of = |found: &Option<FieldMatch>| match found {
+            Some(m) if m.inferred => CONFIDENCE_INFERRED,
+            Some(_) => CONFIDENCE_DEFAULT,
+            None => 0.0,
+        };
+        let confidence = FieldConfidence {
+            date: confidence_of(&date),
+            material: confidence_of(&material),
+            dimensions: confidence_of(&dimensions),
+            manufacturer: confidence_of(&manufacturer),
+            charge_no: confidence_of(&charge_no),
+            weight: confidence_of(&weight),
+        };
+        
+        let value_of = |found: &Option<FieldMatch>| found.as_ref().map(|m| m.value.clone());
+        let info = Self {
+            date: issue_date,
+            material: value_of(&material),
+            material_spec,
+            sub_materials,
+            dimensions: value_of(&dimensions),
+            dimensions_spec,
+            manufacturer: value_of(&manufacturer),
+            manufacturer_matched_term: manufacturer.as_ref().map(|m| manufacturer::matched_term(text, m)),
+            charge_no: value_of(&charge_no),
+            charge_nos,
+            weight: value_of(&weight),
             raw_text: text.to_string(),
+            confidence,
+            field_sources: BTreeMap::new(),
+            date_format: options.date_format,
+            date_century: options.date_century,
+            disabled_fields: options.disabled_fields.clone(),
+        };
+        
+        let evidence = FieldEvidence {
+            date,
+            material,
+            dimensions,
+            manufacturer,
+            charge_no,
+            weight,
+        };
+        
+        (info, evidence)
+    }
+    
+    /// フィールド名（`CONFIDENCE_FIELDS` の名前）で値を取得（発行日は `date_format` の書式）
+    pub fn field_value(&self, field: &str) -> Option<String> {
+        match field {
+            "date" => self.formatted_date(),
+            "material" => self.material.clone(),
+            "dimensions" => self.dimensions.clone(),
+            "manufacturer" => self.manufacturer.clone(),
+            "charge_no" => self.charge_no.clone(),
+            "weight" => self.weight.clone(),
+            _ => None,
+        }
+    }
+    
+    /// フィールドの抽出が有効か
+    pub fn is_field_enabled(&self, field: &str) -> bool {
+        !self.disabled_fields.contains(field)
+    }
+    
+    /// 抽出が有効なフィールドだけの総合信頼度（平均、全て無効なら1.0）
+    pub fn overall_confidence(&self) -> f32 {
+        let values: Vec<f32> = CONFIDENCE_FIELDS
+            .into_iter()
+            .filter(|field| self.is_field_enabled(field))
+            .filter_map(|field| self.confidence.get(field))
+            .collect();
+        if values.is_empty() {
+            return CONFIDENCE_DEFAULT;
         }
+        values.iter().sum::<f32>() / values.len() as f32
+    }
+    
+    /// ファイル名用のチャージ番号（複数あれば先頭から `MAX_FILENAME_CHARGE_NOS` 件までをハイフンで連結）
+    pub fn filename_charge_no(&self) -> Option<String> {
+        if self.charge_nos.is_empty() {
+            return self.charge_no.clone();
+        }
+        Some(
+            self.charge_nos
+                .iter()
+                .take(MAX_FILENAME_CHARGE_NOS)
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join("-"),
+        )
+    }
+    
+    /// リネーム後に振り分けるサブフォルダ（元のフォルダからの相対パス、振り分けない・値が無ければ None）
+    pub fn destination_subdir(&self, rule: SubdirRule) -> Option<std::path::PathBuf> {
+        let name = match rule {
+            SubdirRule::None => return None,
+            SubdirRule::YearMonth => self.date.map(|d| format!("{:04}-{:02}", d.year, d.month))?,
+            SubdirRule::Manufacturer => sanitize_for_filename(self.manufacturer.as_deref()?),
+            SubdirRule::Material => sanitize_for_filename(self.material.as_deref()?),
+        };
+        // "." / ".." だけのフォルダ名で元のフォルダの外に出さない
+        let name = name.trim_matches('.');
+        (!name.is_empty()).then(|| std::path::PathBuf::from(name))
+    }
+    
+    /// 発行日を `date_format` の書式で文字列にする（`date_century` の世紀の外の年は4桁）
+    pub fn formatted_date(&self) -> Option<String> {
+        self.date.map(|d| d.format_in_century(self.date_format, self.date_century))
+    }
+    
+    /// テンプレート文字列（`{manufacturer}_{material}_{charge_no}_{date}` など）でファイル名を生成
+    ///
+    /// 構文は `template::FilenameTemplate` と同じ。値のないフィールドは区切り文字ごと除き、末尾の `.pdf` は省略できる
+    pub fn generate_filename_with_template(&self, template: &str, original_name: &str) -> anyhow::Result<String> {
+        Ok(template::FilenameTemplate::parse(template)?.render(self, original_name))
     }
     
     /// 新しいファイル名を生成
-    /// フォーマット: [発行日]_[材質]_[寸法]_[メーカー名]_[Charge No].pdf
+    /// フォーマット: [発行日]_[材質]_[寸法]_[メーカー名]_[Charge No]_[重量].pdf
     pub fn generate_filename(&self, original_name: &str) -> String {
+        self.generate_filename_with(original_name, &FilenameOptions::default())
+    }
+    
+    /// 区切り文字・最大文字数を指定してファイル名を生成
+    ///
+    /// 何も抽出できなければ元のファイル名に `raw_text` の最初の非空行を付ける。
+    /// 最大文字数を超えた分は `options.overflow` に従って末尾のフィールドごと落とすか、そのまま切る
+    pub fn generate_filename_with(&self, original_name: &str, options: &FilenameOptions) -> String {
+        let sanitize_for_filename = |text: &str| sanitize_for_filename_with(text, options);
+        let separator = options.effective_separator().to_string();
         let mut parts: Vec<String> = Vec::new();
         
-        if let Some(ref date) = self.date {
-            parts.push(date.clone());
+        if let Some(date) = self.formatted_date() {
+            parts.push(date);
         }
         
         if let Some(ref material) = self.material {
@@ -58,58 +751,241 @@ impl MillsheetInfo {
             parts.push(sanitize_for_filename(manufacturer));
         }
         
-        if let Some(ref charge_no) = self.charge_no {
-            parts.push(sanitize_for_filename(charge_no));
+        if let Some(charge_no) = self.filename_charge_no() {
+            parts.push(sanitize_for_filename(&charge_no));
         }
         
+        if let Some(ref weight) = self.weight {
+            parts.push(sanitize_for_filename(weight));
+        }
+        
+        parts.retain(|part| !part.is_empty());
         if parts.is_empty() {
-            // 情報が抽出できなかった場合は元のファイル名をベースにする
+            // 情報が抽出できなかった場合は元のファイル名をベースに、OCRテキスト先頭の行で区別できるようにする
             let stem = std::path::Path::new(original_name)
                 .file_stem()
                 .and_then(|s| s.to_str())
                 .unwrap_or("unknown");
-            format!("{}_renamed.pdf", sanitize_for_filename(stem))
+            let mut fallback_parts = vec![sanitize_for_filename(stem)];
+            fallback_parts.extend(self.fallback_hint(options));
+            fallback_parts.retain(|part| !part.is_empty());
+            let suffix = if options.mark_unextracted { format!("{}renamed", separator) } else { String::new() };
+            let fallback_options = FilenameOptions {
+                max_len: options.max_len.saturating_sub(suffix.chars().count()),
+                ..*options
+            };
+            format!("{}{}.pdf", fit_filename_parts(&fallback_parts, &fallback_options), suffix)
         } else {
-            format!("{}.pdf", parts.join("_"))
+            format!("{}.pdf", fit_filename_parts(&parts, options))
         }
     }
+    
+    /// 抽出ゼロ時のファイル名に付ける補助トークン（OCRテキストの最初の非空行をサニタイズして `FALLBACK_HINT_MAX_LEN` 文字以内）
+    fn fallback_hint(&self, options: &FilenameOptions) -> Option<String> {
+        let separator = options.effective_separator().to_string();
+        self.raw_text
+            .lines()
+            .map(|line| sanitize_for_filename_with(line, options))
+            .find(|line| !line.is_empty())
+            .map(|line| truncate_filename_stem(&line, FALLBACK_HINT_MAX_LEN, &separator))
+    }
+}
+
+/// 複数のチャージ番号の並べ方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChargeNoOrder {
+    /// テキスト中の出現順
+    #[default]
+    Appearance,
+    /// 昇順
+    Ascending,
+}
+
+/// ファイル名に入れるチャージ番号の上限（超えた分は省略）
+pub const MAX_FILENAME_CHARGE_NOS: usize = 2;
+
+/// 溶鋼番号/チャージ番号をすべて取得（複数ヒート混載のミルシート向け、重複は除く）
+///
+/// ラベル付きの番号があれば、その直後に区切り文字や空白で同じ書式（英字・数字の並び）が続く番号までを集める。
+/// ラベルが無ければ、ラベル無しの候補のうち最初の候補と同じ書式のものを集める。
+pub fn extract_all_charge_no(text: &str, order: ChargeNoOrder) -> Vec<String> {
+    let normalized = NormalizedText::new(text);
+    let mut charge_nos = find_all_charge_no(&normalized.text, &ChargeExclusions::default());
+    if order == ChargeNoOrder::Ascending {
+        charge_nos.sort();
+    }
+    charge_nos
 }
 
-/// 溶鋼番号/チャージ番号を抽出
-fn extract_charge_no(text: &str) -> Option<String> {
+/// チャージ番号の書式（英字を A、数字を 9 に置き換えた並び）
+fn charge_no_shape(charge_no: &str) -> String {
+    charge_no
+        .chars()
+        .map(|c| if c.is_ascii_digit() { '9' } else { 'A' })
+        .collect()
+}
+
+/// 正規化済みテキストからチャージ番号をすべて探す（出現順、重複なし）
+fn find_all_charge_no(text: &str, exclusions: &ChargeExclusions) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    let mut push = |charge_no: String| {
+        if !found.contains(&charge_no) {
+            found.push(charge_no);
+        }
+    };
+    
+    // ラベル付き: ラベル直後の番号と、区切り文字で続く同じ書式の番号
+    let mut labeled_found = false;
+    for re in CHARGE_NO_LABELED_REGEXES.iter() {
+        for caps in re.captures_iter(text) {
+            let Some(m) = caps.get(1) else {
+                continue;
+            };
+            let first = m.as_str().to_uppercase();
+            let shape = charge_no_shape(&first);
+            push(first);
+            labeled_found = true;
+            let mut rest = &text[m.end()..];
+            while let Some(next) = CHARGE_NO_FOLLOWING_REGEX.captures(rest).and_then(|c| c.get(1))
+                && charge_no_shape(next.as_str()) == shape
+            {
+                push(next.as_str().to_uppercase());
+                rest = &rest[next.end()..];
+            }
+        }
+    }
+    if labeled_found {
+        return found;
+    }
+    
+    // ラベル無し: 最初の候補と同じ書式のもの
+    let mut candidates: Vec<(usize, String)> = Vec::new();
+    for re in CHARGE_NO_GENERAL_REGEXES.iter() {
+        for caps in re.captures_iter(text) {
+            let Some(m) = caps.get(1) else {
+                continue;
+            };
+            let charge_no = m.as_str().to_uppercase();
+            if !exclusions.excludes(&charge_no) && charge_no.len() >= 4 && charge_no.len() <= 12 {
+                candidates.push((m.start(), charge_no));
+            }
+        }
+        // パターンの優先順位を保つため、先のパターンで見つかれば後のパターンは見ない
+        if !candidates.is_empty() {
+            break;
+        }
+    }
+    candidates.sort_by_key(|(start, _)| *start);
+    if let Some(shape) = candidates.first().map(|(_, c)| charge_no_shape(c)) {
+        for (_, charge_no) in candidates {
+            if charge_no_shape(&charge_no) == shape {
+                push(charge_no);
+            }
+        }
+    }
+    found
+}
+
+/// ラベル無しのチャージ番号候補から除く値（抽出済みの材質・寸法）
+#[derive(Debug, Default)]
+struct ChargeExclusions {
+    /// 材質記号（これで始まる候補を除く）
+    materials: Vec<String>,
+    /// 寸法に含まれる4桁以上の数字列（候補の数字列のどれかが一致すれば除く）
+    dimension_numbers: Vec<String>,
+    /// 寸法の「厚さX幅」（大文字、候補と一致すれば除く）
+    dimension_prefix: Option<String>,
+}
+
+impl ChargeExclusions {
+    fn new<'a>(materials: impl IntoIterator<Item = &'a str>, dimensions: Option<&str>) -> Self {
+        let dimensions = dimensions.unwrap_or_default().to_uppercase();
+        let parts: Vec<&str> = dimensions.split('X').collect();
+        Self {
+            materials: materials.into_iter().map(str::to_uppercase).collect(),
+            dimension_numbers: digit_runs(&dimensions).filter(|digits| digits.len() >= 4).map(str::to_string).collect(),
+            dimension_prefix: (parts.len() >= 2).then(|| parts[..2].join("X")),
+        }
+    }
+    
+    /// 材質と同じ記号、または寸法の数字・「厚さX幅」と重なる候補か
+    fn excludes(&self, candidate: &str) -> bool {
+        self.materials.iter().any(|m| candidate.starts_with(m.as_str()))
+            || digit_runs(candidate).any(|digits| digits.len() >= 4 && self.dimension_numbers.iter().any(|n| n == digits))
+            || self.dimension_prefix.as_deref() == Some(candidate)
+    }
+}
+
+/// 文字列中の連続した数字の並び
+fn digit_runs(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_ascii_digit()).filter(|digits| !digits.is_empty())
+}
+
+/// 溶鋼番号/チャージ番号と抽出根拠を取得（ラベル無しのパターンでは `exclusions` の候補を読み飛ばす）
+///
+/// ラベルは「溶鋼番号」「ヒート番号」「炉番」「鋳造番号」「鋼番」、英語の CHARGE NO / HEAT NO. / HEAT NUMBER / CAST NO.
+fn extract_charge_no_match(text: &str, exclusions: &ChargeExclusions) -> Option<FieldMatch> {
+    let normalized = NormalizedText::new(text);
+    find_charge_no(&normalized.text, exclusions).map(|m| normalized.remap(m))
+}
+
+
+/// ラベル付きのチャージ番号パターン（全角のコロン・スペースは正規化で半角になる）
+///
+/// 英語のラベル（CHARGE NO / HEAT NO. / HEAT NUMBER / CAST NO.）は大文字小文字を区別しない
+const CHARGE_NO_LABELED_PATTERNS: [&str; 2] = [
+    r"(?:溶[鋼銅]番号|ヒート番号|鋳造番号|炉番号?|鋼番)\s*[:\s]*([A-Z0-9]{4,12})",
+    r"(?i:CHARGE\s*NO\.?|HEAT\s*(?:NO\.?|NUMBER)|CAST\s*NO\.?)\s*[:\s]*([A-Z0-9]{4,12})",
+];
+
+/// ラベル無しのチャージ番号パターン（優先順）
+const CHARGE_NO_GENERAL_PATTERNS: [&str; 2] = [
+    r"\b([A-Z]{1,2}\d{4,8})\b",
+    r"\b(\d{1,2}[A-Z]\d{4,6})\b",
+];
+
+/// コンパイル済みのラベル付きチャージ番号パターン
+static CHARGE_NO_LABELED_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| compile_all(CHARGE_NO_LABELED_PATTERNS));
+
+/// コンパイル済みのラベル無しチャージ番号パターン
+static CHARGE_NO_GENERAL_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| compile_all(CHARGE_NO_GENERAL_PATTERNS));
+
+/// ラベル付きチャージ番号の直後に区切り文字で続く番号
+static CHARGE_NO_FOLLOWING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| compile_builtin(r"^(?:\s*[,、/・]\s*|\s+)([A-Z0-9]{4,12})\b"));
+
+/// ラベル付きのコイル番号
+static COIL_NO_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    compile_builtin(r"(?i)(?:コイル\s*(?:番号|No\.?)|COIL\s*N[oO]\.?)\s*[:\s]*([A-Z0-9][A-Z0-9\-]{3,15})")
+});
+
+/// 正規化済みテキストからチャージ番号を探す
+fn find_charge_no(text: &str, exclusions: &ChargeExclusions) -> Option<FieldMatch> {
     // ラベル付きパターン
-    let labeled_patterns = [
-        r"(?:溶[鋼銅]番号|CHARGE\s*N[oO]\.?|鋼番)\s*[:\s]*([A-Z0-9]{4,12})",
-    ];
-    
-    for pattern in labeled_patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(caps) = re.captures(text) {
-                if let Some(m) = caps.get(1) {
-                    let charge_no = m.as_str().to_uppercase();
-                    if charge_no.len() >= 4 && charge_no.len() <= 12 && charge_no.chars().all(|c| c.is_alphanumeric()) {
-                        return Some(charge_no);
-                    }
+    for re in CHARGE_NO_LABELED_REGEXES.iter() {
+        if let Some(caps) = re.captures(text) {
+            if let Some(m) = caps.get(1) {
+                let charge_no = m.as_str().to_uppercase();
+                if charge_no.len() >= 4 && charge_no.len() <= 12 && charge_no.chars().all(|c| c.is_alphanumeric()) {
+                    return Some(FieldMatch::new(charge_no, m.range(), re.as_str()));
                 }
             }
         }
     }
     
     // 一般的なパターン
-    let general_patterns = [
-        r"\b([A-Z]{1,2}\d{4,8})\b",
-        r"\b(\d{1,2}[A-Z]\d{4,6})\b",
-    ];
-    
-    for pattern in general_patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(caps) = re.captures(text) {
-                if let Some(m) = caps.get(1) {
-                    let charge_no = m.as_str().to_uppercase();
-                    if charge_no.len() >= 4 && charge_no.len() <= 12 && charge_no.chars().all(|c| c.is_alphanumeric()) {
-                        return Some(charge_no);
-                    }
-                }
+    for re in CHARGE_NO_GENERAL_REGEXES.iter() {
+        for caps in re.captures_iter(text) {
+            let Some(m) = caps.get(1) else {
+                continue;
+            };
+            let charge_no = m.as_str().to_uppercase();
+            if exclusions.excludes(&charge_no) {
+                tracing::debug!("材質・寸法と重なるチャージ番号候補を除外: {}", charge_no);
+                continue;
+            }
+            if charge_no.len() >= 4 && charge_no.len() <= 12 && charge_no.chars().all(|c| c.is_alphanumeric()) {
+                return Some(FieldMatch::new(charge_no, m.range(), re.as_str()));
             }
         }
     }
@@ -117,35 +993,218 @@ fn extract_charge_no(text: &str) -> Option<String> {
     None
 }
 
-/// ファイル名に使用できない文字を置換
-fn sanitize_for_filename(text: &str) -> String {
-    // 改行をスペースに置換
-    let result = text.replace(['\r', '\n'], " ");
-    
-    // 無効な文字を置換
-    let invalid_chars = ['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
-    let mut result: String = result
-        .chars()
-        .map(|c| if invalid_chars.contains(&c) { '_' } else { c })
-        .collect();
+/// メーカー別ヒントのラベル・書式でチャージ番号を抽出
+fn extract_charge_no_with_hint(text: &str, hint: &ManufacturerHint) -> Option<FieldMatch> {
+    let normalized = NormalizedText::new(text);
+    let re = hint.search_regex();
+    let m = re.captures(&normalized.text)?.get(1)?;
+    let found = FieldMatch::new(m.as_str().to_uppercase(), m.range(), re.as_str());
+    Some(normalized.remap(found))
+}
+
+/// 値がヒントの書式に完全一致するか（書式が無ければ常に一致とみなす）
+fn matches_hint_format(value: &str, hint: &ManufacturerHint) -> bool {
+    hint.format_regex().is_none_or(|re| re.is_match(value))
+}
+
+/// コイル番号を抽出（ラベル付きのもののみ、ファイル名の重複回避に使う）
+pub fn extract_coil_no(text: &str) -> Option<String> {
+    let normalized = normalize_fullwidth(text);
+    let coil_no = COIL_NO_REGEX.captures(&normalized)?.get(1)?.as_str().to_uppercase();
+    Some(coil_no)
+}
+
+/// 組み込みの正規表現をコンパイル（定数のパターンなので、コンパイルできなければバグ）
+pub(crate) fn compile_builtin(pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap_or_else(|e| panic!("組み込みの正規表現が不正です {}: {}", pattern, e))
+}
+
+/// 組み込みのバイト列用の正規表現をコンパイル（`compile_builtin` と同じく、コンパイルできなければバグ）
+pub(crate) fn compile_builtin_bytes(pattern: &str) -> regex::bytes::Regex {
+    regex::bytes::Regex::new(pattern).unwrap_or_else(|e| panic!("組み込みの正規表現が不正です {}: {}", pattern, e))
+}
+
+/// 組み込みの正規表現の並びをコンパイル（順序はパターンの並びのまま）
+pub(crate) fn compile_all<S: AsRef<str>>(patterns: impl IntoIterator<Item = S>) -> Vec<Regex> {
+    patterns.into_iter().map(|pattern| compile_builtin(pattern.as_ref())).collect()
+}
+
+/// 全パーサの組み込みの正規表現をコンパイルする
+///
+/// 各パターンは初回の使用時にコンパイルされ、以後は使い回す。起動時に呼んでおくと、
+/// 不正なパターンを最初のファイルの処理中ではなく起動直後の panic で検知できる（解析結果は変わらない）
+pub fn precompile_patterns() {
+    for regexes in [&CHARGE_NO_LABELED_REGEXES, &CHARGE_NO_GENERAL_REGEXES] {
+        LazyLock::force(regexes);
+    }
+    for regex in [&CHARGE_NO_FOLLOWING_REGEX, &COIL_NO_REGEX] {
+        LazyLock::force(regex);
+    }
+    date::precompile_patterns();
+    dimensions::precompile_patterns();
+    manufacturer::precompile_patterns();
+    material::precompile_patterns();
+    template::precompile_patterns();
+    weight::precompile_patterns();
+}
+
+/// 指定範囲を空白に置換（バイト長を保つので他の位置はずれない）
+fn mask_spans(text: &str, spans: &[Range<usize>]) -> String {
+    let mut masked = text.to_string();
+    for span in spans {
+        if span.end <= masked.len()
+            && masked.is_char_boundary(span.start)
+            && masked.is_char_boundary(span.end)
+        {
+            masked.replace_range(span.clone(), &" ".repeat(span.len()));
+        }
+    }
+    masked
+}
+
+/// ファイル名に使えない文字
+const INVALID_FILENAME_CHARS: [char; 9] = ['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
+
+/// 既定のフィールド区切り文字
+pub const DEFAULT_FILENAME_SEPARATOR: char = '_';
+
+/// 既定のファイル名（拡張子を除く）の最大文字数
+pub const DEFAULT_FILENAME_MAX_LEN: usize = 100;
+
+/// 抽出ゼロ時にファイル名に付けるOCRテキスト先頭行の最大文字数
+pub const FALLBACK_HINT_MAX_LEN: usize = 20;
+
+/// リネーム後にファイルを振り分けるサブフォルダの決め方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubdirRule {
+    /// 振り分けない（元のフォルダでリネーム）
+    #[default]
+    None,
+    /// 発行日の年月（`2024-03`）
+    YearMonth,
+    /// メーカー名
+    Manufacturer,
+    /// 材質
+    Material,
+}
+
+impl SubdirRule {
+    /// 全ルール（設定画面の選択肢順）
+    pub const ALL: [SubdirRule; 4] = [SubdirRule::None, SubdirRule::YearMonth, SubdirRule::Manufacturer, SubdirRule::Material];
     
-    // 空白をアンダースコアに置換
-    let whitespace_re = Regex::new(r"\s+").unwrap();
-    result = whitespace_re.replace_all(&result, "_").to_string();
+    /// 表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            SubdirRule::None => "なし",
+            SubdirRule::YearMonth => "年月",
+            SubdirRule::Manufacturer => "メーカー",
+            SubdirRule::Material => "材質",
+        }
+    }
+}
+
+/// ファイル名が最大文字数を超えたときの切り詰め方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilenameOverflow {
+    /// 収まらない末尾のフィールドを区切り文字ごと落とす（先頭のフィールドだけで超える場合はそのフィールドを切る）
+    #[default]
+    DropFields,
+    /// 最大文字数で切り、末尾に残った区切り文字を除く（省略記号は付けない）
+    Truncate,
+}
+
+/// 既定の命名（`generate_filename_with`）の区切り文字と長さ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilenameOptions {
+    /// フィールドの区切り文字（値の中の空白・使えない文字もこの文字に置き換える）
+    pub separator: char,
+    /// フィールドを結合したファイル名（拡張子を除く）の最大文字数
+    pub max_len: usize,
+    /// 最大文字数を超えたときの切り詰め方
+    pub overflow: FilenameOverflow,
+    /// 情報が全く抽出できなかったファイル名の末尾に `_renamed` を付ける
+    pub mark_unextracted: bool,
+}
+
+impl Default for FilenameOptions {
+    fn default() -> Self {
+        Self {
+            separator: DEFAULT_FILENAME_SEPARATOR,
+            max_len: DEFAULT_FILENAME_MAX_LEN,
+            overflow: FilenameOverflow::default(),
+            mark_unextracted: true,
+        }
+    }
+}
+
+impl FilenameOptions {
+    /// 実際に使う区切り文字（ファイル名に使えない文字・改行なら既定の `_`）
+    pub fn effective_separator(&self) -> char {
+        if INVALID_FILENAME_CHARS.contains(&self.separator) || self.separator.is_control() {
+            DEFAULT_FILENAME_SEPARATOR
+        } else {
+            self.separator
+        }
+    }
+}
+
+/// ファイル名に使用できない文字を置換
+pub(crate) fn sanitize_for_filename(text: &str) -> String {
+    sanitize_for_filename_with(text, &FilenameOptions::default())
+}
+
+/// ファイル名に使用できない文字・空白を区切り文字に置換（長さの制限は結合後に `generate_filename_with` で行う）
+pub fn sanitize_for_filename_with(text: &str, options: &FilenameOptions) -> String {
+    let separator = options.effective_separator();
     
-    // 連続するアンダースコアを1つに
-    let underscore_re = Regex::new(r"_+").unwrap();
-    result = underscore_re.replace_all(&result, "_").to_string();
+    // 無効な文字・空白（改行を含む）を区切り文字に置換し、連続する区切り文字を1つにする
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        let c = if INVALID_FILENAME_CHARS.contains(&c) || c.is_whitespace() { separator } else { c };
+        if c == separator && result.ends_with(separator) {
+            continue;
+        }
+        result.push(c);
+    }
     
-    // 先頭と末尾のアンダースコアを削除
-    result = result.trim_matches('_').to_string();
+    // 先頭と末尾の区切り文字を削除
+    result.trim_matches(separator).to_string()
+}
+
+/// サニタイズ済みのフィールドを区切り文字で結合し、最大文字数に収める
+fn fit_filename_parts(parts: &[String], options: &FilenameOptions) -> String {
+    let separator = options.effective_separator().to_string();
+    let joined = parts.join(&separator);
+    if joined.chars().count() <= options.max_len {
+        return joined;
+    }
     
-    // 最大50文字に制限
-    if result.chars().count() > 50 {
-        result = result.chars().take(50).collect();
+    if options.overflow == FilenameOverflow::DropFields {
+        // 収まるところまで先頭からフィールド単位で足す（フィールドの途中では切らない）
+        let mut fitted = String::new();
+        for part in parts {
+            let candidate = if fitted.is_empty() {
+                part.clone()
+            } else {
+                format!("{}{}{}", fitted, separator, part)
+            };
+            if candidate.chars().count() > options.max_len {
+                break;
+            }
+            fitted = candidate;
+        }
+        if !fitted.is_empty() {
+            return fitted;
+        }
     }
     
-    result
+    truncate_filename_stem(&joined, options.max_len, &separator)
+}
+
+/// 最大文字数で切り、末尾に残った区切り文字を除く
+fn truncate_filename_stem(stem: &str, max_len: usize, separator: &str) -> String {
+    let truncated: String = stem.chars().take(max_len).collect();
+    truncated.trim_end_matches(separator).to_string()
 }
 
 /// ユニークなファイル名を取得（同名ファイルがある場合は連番を付与）
@@ -164,3 +1223,435 @@ pub fn get_unique_filename(directory: &std::path::Path, filename: &str) -> Strin
     
     final_name
 }
+
+/// 重複しないファイル名を取得（同名ファイルがある場合は識別子を付与）
+///
+/// `identifiers` を先頭から順に `{stem}_{識別子}` として試し、既にファイル名に含まれる識別子は
+/// 飛ばす。どれでも区別できなければ `get_unique_filename` と同じ連番にフォールバックする
+pub fn get_distinct_filename(directory: &std::path::Path, filename: &str, identifiers: &[String]) -> String {
+    if !directory.join(filename).exists() {
+        return filename.to_string();
+    }
+    
+    let path = std::path::Path::new(filename);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(filename);
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("pdf");
+    
+    for identifier in identifiers {
+        let identifier = sanitize_for_filename(identifier);
+        if identifier.is_empty() || stem.split('_').any(|part| part == identifier) {
+            continue;
+        }
+        let candidate = format!("{}_{}.{}", stem, identifier, ext);
+        if !directory.join(&candidate).exists() {
+            return candidate;
+        }
+    }
+    
+    get_unique_filename(directory, filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    
+    fn masked() -> ParseOptions {
+        ParseOptions {
+            mask_used_tokens: true,
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn mask_drops_charge_no_taken_from_dimensions() {
+        let text = "SIZE 9X1524 X 3048 SS400";
+        assert_eq!(MillsheetInfo::parse(text).charge_no, None);
+        assert_eq!(MillsheetInfo::parse_with_options(text, &masked()).charge_no, None);
+    }
+    
+    #[test]
+    fn mask_finds_real_charge_no_after_dimensions() {
+        let text = "SIZE 9X1524 X 3048 SS400\n8K23456";
+        assert_eq!(MillsheetInfo::parse(text).charge_no.as_deref(), Some("8K23456"));
+        assert_eq!(MillsheetInfo::parse_with_options(text, &masked()).charge_no.as_deref(), Some("8K23456"));
+    }
+    
+    #[test]
+    fn dimension_fragments_are_not_charge_nos() {
+        let info = MillsheetInfo::parse("SIZE 9X1524 X 3048 SS400");
+        assert_eq!(info.dimensions.as_deref(), Some("9x1524x3048"));
+        assert_eq!(info.charge_no, None);
+        
+        let info = MillsheetInfo::parse("寸法 12X2438X6096\nNo. 12X2438");
+        assert_eq!(info.dimensions.as_deref(), Some("12x2438x6096"));
+        assert_eq!(info.charge_no, None);
+        
+        // 寸法と重ならない候補は残す
+        assert_eq!(MillsheetInfo::parse("寸法 12X2438X6096\nNo. 2X8765").charge_no.as_deref(), Some("2X8765"));
+    }
+    
+    #[test]
+    fn exclusions_match_digit_runs_and_thickness_width() {
+        let exclusions = ChargeExclusions::new(["SS400"], Some("9x1524x3048"));
+        assert!(exclusions.excludes("9X1524"));
+        assert!(exclusions.excludes("AB3048"));
+        assert!(exclusions.excludes("SS4001"));
+        assert!(!exclusions.excludes("8K23456"));
+        assert!(ChargeExclusions::new([], Some("12x800")).excludes("12X800"));
+    }
+    
+    #[test]
+    fn mask_keeps_other_fields_and_labeled_charge_no() {
+        let text = "発行日 2024/03/15\nSIZE 9X1524 X 3048 SS400\nCHARGE NO. 8K23456";
+        let plain = MillsheetInfo::parse(text);
+        let masked = MillsheetInfo::parse_with_options(text, &masked());
+        assert_eq!(masked.charge_no.as_deref(), Some("8K23456"));
+        assert_eq!(masked.charge_no, plain.charge_no);
+        assert_eq!(masked.date, plain.date);
+        assert_eq!(masked.material, plain.material);
+        assert_eq!(masked.dimensions, plain.dimensions);
+    }
+    
+    #[test]
+    fn mask_spans_keeps_byte_offsets() {
+        let text = "材質 SS400 / 8K23456";
+        let start = text.find("SS400").unwrap();
+        let masked = mask_spans(text, &[start..start + 5, 100..120]);
+        assert_eq!(masked, "材質       / 8K23456");
+        assert_eq!(masked.len(), text.len());
+        // 文字の途中で切れる範囲はそのまま
+        assert_eq!(mask_spans(text, &[1..4, 0..1]), text);
+    }
+    
+    /// JSON Schema の `$ref` を辿る
+    fn resolve<'a>(schema: &'a serde_json::Value, root: &'a serde_json::Value) -> &'a serde_json::Value {
+        match schema["$ref"].as_str().and_then(|r| r.strip_prefix("#/$defs/")) {
+            Some(name) => resolve(&root["$defs"][name], root),
+            None => schema,
+        }
+    }
+    
+    /// 値が JSON Schema に従っているか（このクレートの Schema で使うキーワードだけを見る）
+    fn conforms(value: &serde_json::Value, schema: &serde_json::Value, root: &serde_json::Value) -> bool {
+        use serde_json::Value;
+        let schema = resolve(schema, root);
+        
+        if let Some(any_of) = schema["anyOf"].as_array() && !any_of.iter().any(|s| conforms(value, s, root)) {
+            return false;
+        }
+        if let Some(one_of) = schema["oneOf"].as_array() && one_of.iter().filter(|s| conforms(value, s, root)).count() != 1 {
+            return false;
+        }
+        if let Some(expected) = schema.get("const") && value != expected {
+            return false;
+        }
+        let type_name = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let type_matches = |t: &Value| t == type_name || (t == "number" && type_name == "integer");
+        match &schema["type"] {
+            Value::String(_) if !type_matches(&schema["type"]) => return false,
+            Value::Array(types) if !types.iter().any(type_matches) => return false,
+            _ => {}
+        }
+        
+        match value {
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                schema["minimum"].as_f64().is_none_or(|min| n >= min) && schema["maximum"].as_f64().is_none_or(|max| n <= max)
+            }
+            Value::String(s) => {
+                let len = s.chars().count() as u64;
+                schema["minLength"].as_u64().is_none_or(|min| len >= min)
+                    && schema["maxLength"].as_u64().is_none_or(|max| len <= max)
+                    && schema["pattern"].as_str().is_none_or(|p| Regex::new(p).unwrap().is_match(s))
+            }
+            Value::Array(items) => items.iter().all(|item| schema.get("items").is_none_or(|s| conforms(item, s, root))),
+            Value::Object(fields) => {
+                let required = schema["required"].as_array().cloned().unwrap_or_default();
+                required.iter().all(|name| name.as_str().is_some_and(|name| fields.contains_key(name)))
+                    && fields.iter().all(|(name, field)| match schema["properties"].get(name) {
+                        Some(property) => conforms(field, property, root),
+                        None => match schema.get("additionalProperties") {
+                            Some(Value::Bool(allowed)) => *allowed,
+                            Some(additional) => conforms(field, additional, root),
+                            None => true,
+                        },
+                    })
+            }
+            _ => true,
+        }
+    }
+    
+    /// 解析結果の JSON が Schema に従っていることを確かめる
+    fn assert_conforms(info: &MillsheetInfo) {
+        let schema = MillsheetInfo::json_schema();
+        let value = serde_json::to_value(info).unwrap();
+        assert!(conforms(&value, &schema, &schema), "{}", serde_json::to_string_pretty(&value).unwrap());
+    }
+    
+    #[test]
+    fn parsed_info_conforms_to_schema() {
+        assert_conforms(&MillsheetInfo::default());
+        assert_conforms(&MillsheetInfo::parse(
+            "発行日 2024/03/15\n日本製鉄株式会社\n材質 SUS304+BA\n寸法 1.6X1219XCOIL\nCHARGE NO. 8K23456 / 8K23457\n重量 2,450kg",
+        ));
+        assert_conforms(&MillsheetInfo::parse("発行日 2024年3月\nSIZE 1.6 X 1219 X 2438\nSS400"));
+    }
+    
+    #[test]
+    fn merged_info_conforms_to_schema() {
+        let a = MillsheetInfo::parse("材質 SS400\nCHARGE NO. 8K23456");
+        let b = MillsheetInfo::parse("発行日 2024/03/15\n寸法 1.6X1219XC");
+        let merged = MillsheetInfo::merge(&a, &b);
+        assert!(!merged.field_sources.is_empty());
+        assert_conforms(&merged);
+    }
+    
+    #[test]
+    fn schema_rejects_values_outside_the_spec() {
+        let schema = MillsheetInfo::json_schema();
+        let mut value = serde_json::to_value(MillsheetInfo::parse("発行日 2024/03/15")).unwrap();
+        assert!(conforms(&value, &schema, &schema));
+        value["date"] = "2024/03/15".into();
+        assert!(!conforms(&value, &schema, &schema));
+        value["date"] = serde_json::Value::Null;
+        value["confidence"]["date"] = 1.5.into();
+        assert!(!conforms(&value, &schema, &schema));
+    }
+    
+    #[test]
+    fn schema_marks_required_fields_and_describes_every_property() {
+        let schema = MillsheetInfo::json_schema();
+        let required: Vec<&str> = schema["required"].as_array().unwrap().iter().filter_map(|v| v.as_str()).collect();
+        for field in ["sub_materials", "charge_nos", "raw_text", "confidence"] {
+            assert!(required.contains(&field), "{}", field);
+        }
+        for field in ["date", "material", "dimensions", "manufacturer", "charge_no", "weight", "field_sources"] {
+            assert!(!required.contains(&field), "{}", field);
+        }
+        
+        let properties = schema["properties"].as_object().unwrap();
+        for (name, property) in properties {
+            assert!(property["description"].as_str().is_some_and(|d| !d.is_empty()), "{}", name);
+        }
+        // ファイル名用の設定は JSON に出さない
+        for field in ["date_format", "date_century", "disabled_fields"] {
+            assert!(!properties.contains_key(field), "{}", field);
+        }
+    }
+    
+    /// 正常なOCR結果
+    const CLEAN_TEXT: &str = "発行日 2024/03/15\n日本製鉄株式会社\n材質\tSS400\r\n寸法 9X1500X3000\nCHARGE NO. 8K23456";
+    
+    /// 制御文字・置換文字・ゼロ幅文字・双方向制御文字・BOM・非文字が混じったOCR結果
+    const NOISY_TEXT: &str = "\u{feff}発行日 2024/\u{0}03/15\n日本\u{200b}製鉄株式会社\u{7}\n材質\tSS\u{fffd}400\r\n寸法 9X1500\u{202e}X3000\u{1b}\nCHARGE NO. 8K\u{fdd0}23456\u{ffff}";
+    
+    #[test]
+    fn sanitize_removes_broken_characters_but_keeps_line_breaks_and_tabs() {
+        assert_eq!(sanitize_ocr_text(NOISY_TEXT), CLEAN_TEXT);
+        assert_eq!(sanitize_ocr_text(CLEAN_TEXT), CLEAN_TEXT);
+    }
+    
+    #[test]
+    fn sanitize_removes_replaced_lone_surrogates() {
+        let decoded = String::from_utf16_lossy(&[0x0053, 0xD800, 0x0053, 0x0034, 0x0030, 0x0030]);
+        assert_eq!(sanitize_ocr_text(&decoded), "SS400");
+    }
+    
+    #[test]
+    fn sanitize_keeps_fullwidth_and_symbols_used_by_parsers() {
+        let text = "寸法：１．６×１２１９×ＣＯＩＬ　重量 2,450kg ㈱";
+        assert_eq!(sanitize_ocr_text(text), text);
+    }
+    
+    #[test]
+    fn noisy_text_parses_like_clean_text() {
+        let clean = MillsheetInfo::parse(CLEAN_TEXT);
+        let noisy = MillsheetInfo::parse(NOISY_TEXT);
+        assert_eq!(noisy.raw_text, CLEAN_TEXT);
+        assert_eq!(noisy.date, clean.date);
+        assert_eq!(noisy.material.as_deref(), Some("SS400"));
+        assert_eq!(noisy.dimensions.as_deref(), Some("9x1500x3000"));
+        assert_eq!(noisy.manufacturer.as_deref(), Some("日本製鉄"));
+        assert_eq!(noisy.charge_no.as_deref(), Some("8K23456"));
+        assert_eq!(noisy.generate_filename("a.pdf"), clean.generate_filename("a.pdf"));
+    }
+    
+    #[test]
+    fn evidence_spans_point_into_sanitized_text() {
+        let (info, evidence) = MillsheetInfo::parse_detailed(NOISY_TEXT, &ParseOptions::default());
+        let span = evidence.charge_no.unwrap().span;
+        assert_eq!(&info.raw_text[span], "8K23456");
+        let span = evidence.material.unwrap().span;
+        assert_eq!(&info.raw_text[span], "SS400");
+    }
+    
+    #[test]
+    fn parse_reads_material_and_charge_no() {
+        let info = MillsheetInfo::parse("A5052-H34\n2.0X1250X2500\nW1250 L2500\n8K23456");
+        assert_eq!(info.material.as_deref(), Some("A5052"));
+        assert_eq!(info.charge_no.as_deref(), Some("8K23456"));
+    }
+    
+    #[test]
+    fn merge_fills_missing_fields_from_secondary() {
+        let vision = MillsheetInfo::parse("SS400\n2024年3月15日");
+        let text_layer = MillsheetInfo::parse("9.0X1500X3000\n株式会社神戸製鋼所");
+        let merged = MillsheetInfo::merge(&vision, &text_layer);
+        assert_eq!(merged.material.as_deref(), Some("SS400"));
+        assert_eq!(merged.dimensions.as_deref(), Some("9x1500x3000"));
+        assert!(merged.confidence.overall() > vision.confidence.overall());
+        assert_eq!(merged.field_sources["material"], MergeSource::Primary);
+        assert_eq!(merged.field_sources["dimensions"], MergeSource::Secondary);
+    }
+    
+    #[test]
+    fn filename_charge_no_joins_first_two() {
+        let info = MillsheetInfo::parse("SS400\n溶鋼番号 8K23456, 8K23457, 8K23458");
+        assert_eq!(info.charge_nos, ["8K23456", "8K23457", "8K23458"]);
+        assert_eq!(info.filename_charge_no().as_deref(), Some("8K23456-8K23457"));
+    }
+    
+    #[test]
+    fn all_charge_nos_follow_order() {
+        let text = "CHARGE NO. 8K23457 / 8K23456 / 8K23457";
+        assert_eq!(extract_all_charge_no(text, ChargeNoOrder::Appearance), ["8K23457", "8K23456"]);
+        assert_eq!(extract_all_charge_no(text, ChargeNoOrder::Ascending), ["8K23456", "8K23457"]);
+    }
+    
+    #[test]
+    fn heat_number_labels_are_recognized() {
+        // 海外証明書・日本語の表記ゆれ（全角のコロン・スペースも可）
+        assert_eq!(extract_all_charge_no("Heat No. 8K23456", ChargeNoOrder::Appearance), ["8K23456"]);
+        assert_eq!(extract_all_charge_no("HEAT NUMBER: K123456", ChargeNoOrder::Appearance), ["K123456"]);
+        assert_eq!(extract_all_charge_no("CAST No.：AB12345", ChargeNoOrder::Appearance), ["AB12345"]);
+        assert_eq!(extract_all_charge_no("ヒート番号　8K23456", ChargeNoOrder::Appearance), ["8K23456"]);
+        assert_eq!(extract_all_charge_no("炉番：8K23456", ChargeNoOrder::Appearance), ["8K23456"]);
+        assert_eq!(extract_all_charge_no("鋳造番号 8K23456", ChargeNoOrder::Appearance), ["8K23456"]);
+    }
+    
+    #[test]
+    fn template_filename_drops_empty_fields() {
+        let info = MillsheetInfo::parse("2024年3月15日\nSS400\nJFEスチール株式会社");
+        let name = info.generate_filename_with_template("{manufacturer}_{material}_{charge_no}_{date}", "a.pdf").unwrap();
+        assert_eq!(name, "JFEスチール_SS400_24-03-15.pdf");
+        let name = info.generate_filename_with_template("{manufacturer}_{material}_{charge_no}_{date}.PDF", "a.pdf").unwrap();
+        assert_eq!(name, "JFEスチール_SS400_24-03-15.pdf");
+    }
+    
+    #[test]
+    fn sanitize_collapses_spaces_and_slashes_into_separator() {
+        let options = FilenameOptions { separator: '-', ..Default::default() };
+        assert_eq!(sanitize_for_filename_with(" JFE  STEEL/東日本 ", &options), "JFE-STEEL-東日本");
+    }
+    
+    #[test]
+    fn filename_options_change_separator_and_overflow() {
+        let info = MillsheetInfo::parse("2024年3月15日\nSS400\n9.0X1500X3000\nJFE STEEL CORPORATION");
+        assert_eq!(info.generate_filename("a.pdf"), "24-03-15_SS400_9x1500x3000_JFEスチール.pdf");
+        
+        let options = FilenameOptions { separator: ' ', max_len: 20, ..Default::default() };
+        assert_eq!(info.generate_filename_with("a.pdf", &options), "24-03-15 SS400.pdf");
+        
+        let options = FilenameOptions { max_len: 20, overflow: FilenameOverflow::Truncate, ..Default::default() };
+        assert_eq!(info.generate_filename_with("a.pdf", &options), "24-03-15_SS400_9x150.pdf");
+    }
+    
+    #[test]
+    fn unextracted_filename_uses_first_ocr_line() {
+        // 何も抽出できなければ元のファイル名にOCRテキストの最初の非空行（20文字以内）を付ける
+        let info = MillsheetInfo::parse("\n  ミルシート 控え\n備考なし");
+        assert_eq!(info.generate_filename("scan001.pdf"), "scan001_ミルシート_控え_renamed.pdf");
+        let options = FilenameOptions { mark_unextracted: false, ..Default::default() };
+        assert_eq!(info.generate_filename_with("scan001.pdf", &options), "scan001_ミルシート_控え.pdf");
+    }
+    
+    #[test]
+    fn destination_subdir_follows_rule() {
+        let info = MillsheetInfo::parse("2024年3月15日\nSS400\nJFE STEEL CORPORATION");
+        assert_eq!(info.destination_subdir(SubdirRule::YearMonth), Some(PathBuf::from("2024-03")));
+        assert_eq!(info.destination_subdir(SubdirRule::Manufacturer), Some(PathBuf::from("JFEスチール")));
+        assert_eq!(info.destination_subdir(SubdirRule::Material), Some(PathBuf::from("SS400")));
+        assert_eq!(info.destination_subdir(SubdirRule::None), None);
+        assert_eq!(MillsheetInfo::parse("SS400").destination_subdir(SubdirRule::YearMonth), None);
+    }
+    
+    #[test]
+    fn parse_works_after_precompile() {
+        precompile_patterns();
+        let info = MillsheetInfo::parse("発行日 2024/03/15\nSIZE 1.6X1219XCOIL\n材質 SPHC");
+        assert_eq!(info.date, IssueDate::new(2024, 3, 15));
+    }
+    
+    #[test]
+    fn best_page_skips_cover_letter() {
+        // 1ページ目は送り状、2ページ目が証明書の本体
+        let pages = ["送付のご案内\nご査収ください", "発行日 2024/03/15\n材質 SS400\n9.0X1500X3000"]
+            .map(MillsheetInfo::parse);
+        let (index, best) = MillsheetInfo::best_page(pages).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(best.material.as_deref(), Some("SS400"));
+        assert!(MillsheetInfo::best_page(Vec::new()).is_none());
+    }
+    
+    
+    #[test]
+    fn distinct_filename_falls_back_through_identifiers_then_numbers() {
+        let dir = std::env::temp_dir().join(format!("millsheet_distinct_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // チャージ番号 → コイル番号 → ハッシュ → 連番の順
+        let identifiers = ["AB1234", "C-5678", "0123abcd"].map(String::from);
+        assert_eq!(get_distinct_filename(&dir, "x.pdf", &identifiers), "x.pdf");
+        
+        let mut names = Vec::new();
+        for _ in 0..5 {
+            let existing = names.last().cloned().unwrap_or_else(|| "x.pdf".to_string());
+            std::fs::write(dir.join(&existing), "").unwrap();
+            names.push(get_distinct_filename(&dir, "x.pdf", &identifiers));
+        }
+        assert_eq!(names, ["x_AB1234.pdf", "x_C-5678.pdf", "x_0123abcd.pdf", "x_1.pdf", "x_2.pdf"]);
+        
+        // ファイル名に含まれている識別子は飛ばす
+        std::fs::write(dir.join("x_AB1234_SS400.pdf"), "").unwrap();
+        assert_eq!(get_distinct_filename(&dir, "x_AB1234_SS400.pdf", &identifiers), "x_AB1234_SS400_C-5678.pdf");
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    
+    
+    #[test]
+    fn year_month_date_is_used_in_filename() {
+        let info = MillsheetInfo::parse("2024年3月\n材質 SS400\n寸法 9.0X1500X3000");
+        assert_eq!(info.date, IssueDate::year_month(2024, 3));
+        assert_eq!(info.generate_filename("scan.pdf"), "24-03_SS400_9x1500x3000.pdf");
+        
+        // 日付の書式を変えても日は付けない
+        let info = MillsheetInfo { date_format: DateFormat::YyyyMmDdDash, ..info };
+        assert_eq!(info.generate_filename("scan.pdf"), "2024-03_SS400_9x1500x3000.pdf");
+    }
+    
+    
+    #[test]
+    fn weight_has_evidence_and_confidence_outside_overall() {
+        let text = "材質 SS400\n寸法 9.0X1500X3000\n重量 2,450 kg";
+        let (info, evidence) = MillsheetInfo::parse_detailed(text, &ParseOptions::default());
+        let found = evidence.weight.unwrap();
+        assert_eq!((found.value.as_str(), &text[found.span]), ("2450kg", "2,450 kg"));
+        assert_eq!(info.confidence.weight, CONFIDENCE_DEFAULT);
+        assert_eq!(info.confidence.get("weight"), Some(CONFIDENCE_DEFAULT));
+        
+        // 重量の有無は総合信頼度を変えない
+        let without_weight = MillsheetInfo::parse("材質 SS400\n寸法 9.0X1500X3000");
+        assert_eq!(without_weight.confidence.weight, 0.0);
+        assert_eq!(without_weight.overall_confidence(), info.overall_confidence());
+    }
+}
@@ -0,0 +1,98 @@
+//! 命名規則の検証モジュール - 生成したファイル名が社内規則に合っているかを調べる
+
+use super::{field_label, sanitize_for_filename_with, FilenameOptions, MillsheetInfo};
+use regex::Regex;
+
+/// ファイル名の命名規則（None・空の項目は検証しない）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilenameRules {
+    /// 1文字ごとに照合する許可文字（正規表現の文字クラス。例: `[A-Za-z0-9_.\-]`）
+    pub allowed_chars: Option<String>,
+    /// 拡張子を含む最大文字数
+    pub max_length: Option<usize>,
+    /// ファイル名に値が含まれていなければならないフィールド（CONFIDENCE_FIELDS の名前）
+    pub required_fields: Vec<String>,
+}
+
+/// ファイル名を規則に照らして検証（許可文字・最大長）し、違反の理由をすべて返す
+///
+/// 必須フィールドは解析結果が必要なため `validate_generated_filename` で検証する。
+pub fn validate_filename(name: &str, rules: &FilenameRules) -> Result<(), Vec<String>> {
+    let mut violations = Vec::new();
+    
+    if let Some(ref allowed) = rules.allowed_chars {
+        match Regex::new(&format!("^(?:{})$", allowed)) {
+            Ok(re) => {
+                let mut invalid: Vec<char> = name
+                    .chars()
+                    .filter(|c| !re.is_match(c.encode_utf8(&mut [0; 4])))
+                    .collect();
+                invalid.dedup();
+                if !invalid.is_empty() {
+                    let shown: String = invalid.iter().take(10).collect();
+                    violations.push(format!("使用できない文字があります: {}", shown));
+                }
+            }
+            Err(_) => violations.push(format!("許可文字の指定が正規表現として不正です: {}", allowed)),
+        }
+    }
+    
+    if let Some(max_length) = rules.max_length {
+        let length = name.chars().count();
+        if length > max_length {
+            violations.push(format!("長すぎます: {} 文字（上限 {} 文字）", length, max_length));
+        }
+    }
+    
+    if violations.is_empty() { Ok(()) } else { Err(violations) }
+}
+
+/// 生成したファイル名を検証（`validate_filename` の検証に加え、必須フィールドの値が含まれているか）
+pub fn validate_generated_filename(name: &str, info: &MillsheetInfo, rules: &FilenameRules) -> Result<(), Vec<String>> {
+    validate_generated_filename_with(name, info, rules, &FilenameOptions::default())
+}
+
+/// 区切り文字・最大文字数を指定して生成したファイル名を検証（必須フィールドの値は同じ指定でサニタイズして探す）
+pub fn validate_generated_filename_with(
+    name: &str,
+    info: &MillsheetInfo,
+    rules: &FilenameRules,
+    filename: &FilenameOptions,
+) -> Result<(), Vec<String>> {
+    let mut violations = validate_filename(name, rules).err().unwrap_or_default();
+    
+    for field in &rules.required_fields {
+        let value = info
+            .field_value(field)
+            .map(|v| sanitize_for_filename_with(&v, filename))
+            .filter(|v| !v.is_empty());
+        
+        match value {
+            None => violations.push(format!("必須フィールドが抽出できていません: {}", field_label(field))),
+            Some(value) if !name.contains(&value) => {
+                violations.push(format!("必須フィールドがファイル名に含まれていません: {}", field_label(field)));
+            }
+            Some(_) => {}
+        }
+    }
+    
+    if violations.is_empty() { Ok(()) } else { Err(violations) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn each_broken_rule_is_reported() {
+        let rules = FilenameRules {
+            allowed_chars: Some(r"[A-Za-z0-9_.\-]".to_string()),
+            max_length: Some(20),
+            ..Default::default()
+        };
+        assert!(validate_filename("24-03-05_SS400.pdf", &rules).is_ok());
+        
+        let violations = validate_filename("24-03-05_SS400_新日本製鐵.pdf", &rules).unwrap_err();
+        assert_eq!(violations.len(), 2);
+    }
+}
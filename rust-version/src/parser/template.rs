@@ -0,0 +1,469 @@
+//! ファイル名テンプレート - `{date}_{material}` のようなプレースホルダ置換
+//!
+//! 構文:
+//! - `{field}`: フィールドの値（空ならなにも出力しない）
+//! - `{charge_no|coil_no|'NA'}`: 左から順に最初に値を持つものを採用し、全て空なら引用符内のリテラル
+//! - `{seq:04}`: 通し番号（`:` の後は0埋めの桁数、`{seq}` なら0埋めしない）。番号を渡さずに生成すると空
+//! - `{thickness:int,mm}`: 寸法の要素（`thickness` / `width` / `length`）。`:` の後に `int`（整数に丸める）・`mm`（単位を付ける）を `,` 区切りで指定できる
+//! - `{{` / `}}`: 波括弧そのもの
+//!
+//! 末尾の `.pdf` は書いても書かなくてもよい。値のないプレースホルダは直後の区切り文字（`_` `-` `.` 空白）ごと出力しない。
+
+use super::date::DEFAULT_FISCAL_YEAR_START_MONTH;
+use super::dimensions::{DimensionFormat, DimensionPart};
+use super::{compile_builtin, extract_coil_no, sanitize_for_filename, MillsheetInfo};
+use anyhow::{bail, Result};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// テンプレートで参照できるフィールド名
+pub const KNOWN_FIELDS: [&str; 15] = [
+    "date",
+    "material",
+    "dimensions",
+    "thickness",
+    "width",
+    "length",
+    "manufacturer",
+    "charge_no",
+    "coil_no",
+    "weight",
+    "original",
+    "fiscal_year",
+    "quarter",
+    "month",
+    "weekday",
+];
+
+/// 通し番号の0埋め桁数の上限（u64 の最大桁数）
+const MAX_SEQUENCE_WIDTH: usize = 20;
+
+/// プレースホルダ間の区切り文字（値のないプレースホルダと一緒に除く）
+const SEPARATORS: [char; 4] = ['_', '-', '.', ' '];
+
+/// 空のフィールドで重なった区切り文字
+static REPEATED_UNDERSCORES: LazyLock<Regex> = LazyLock::new(|| compile_builtin(r"_{2,}"));
+
+/// このモジュールの組み込みパターンをすべてコンパイル
+pub(crate) fn precompile_patterns() {
+    LazyLock::force(&REPEATED_UNDERSCORES);
+}
+
+/// プレースホルダ内の候補
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Alternative {
+    /// フィールド参照
+    Field(String),
+    /// 引用符で囲んだリテラル
+    Literal(String),
+    /// 通し番号（0埋めの桁数）
+    Sequence(usize),
+    /// 寸法の要素と書式
+    Dimension(DimensionPart, DimensionFormat),
+}
+
+/// テンプレートの構成要素
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// そのまま出力する文字列
+    Text(String),
+    /// フォールバックチェーン
+    Placeholder(Vec<Alternative>),
+}
+
+/// 解析済みのファイル名テンプレート
+#[derive(Debug, Clone)]
+pub struct FilenameTemplate {
+    source: String,
+    segments: Vec<Segment>,
+    fiscal_year_start_month: u32,
+}
+
+impl FilenameTemplate {
+    /// テンプレート文字列を解析
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut body = String::new();
+                    let mut closed = false;
+                    let mut quote: Option<char> = None;
+                    for c in chars.by_ref() {
+                        match (c, quote) {
+                            ('}', None) => {
+                                closed = true;
+                                break;
+                            }
+                            ('\'' | '"', None) => quote = Some(c),
+                            (c, Some(q)) if c == q => quote = None,
+                            _ => {}
+                        }
+                        body.push(c);
+                    }
+                    if !closed {
+                        bail!("テンプレートの '{{' が閉じられていません: {}", template);
+                    }
+                    
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Placeholder(parse_chain(&body)?));
+                }
+                '}' => bail!("テンプレートに対応しない '}}' があります: {}", template),
+                _ => text.push(c),
+            }
+        }
+        
+        // 拡張子は render で付けるので、テンプレートに書かれていれば除く
+        let has_extension = text
+            .get(text.len().saturating_sub(4)..)
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(".pdf"));
+        if has_extension {
+            text.truncate(text.len() - 4);
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        
+        Ok(Self {
+            source: template.to_string(),
+            segments,
+            fiscal_year_start_month: DEFAULT_FISCAL_YEAR_START_MONTH,
+        })
+    }
+    
+    /// 年度開始月を指定（{fiscal_year} / {quarter} に影響）
+    pub fn with_fiscal_year_start(mut self, month: u32) -> Self {
+        self.fiscal_year_start_month = month;
+        self
+    }
+    
+    /// 元のテンプレート文字列
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+    
+    /// KNOWN_FIELDS にないフィールド名（常に空として扱われる）
+    pub fn unknown_fields(&self) -> Vec<&str> {
+        self.segments
+            .iter()
+            .filter_map(|s| match s {
+                Segment::Placeholder(chain) => Some(chain),
+                Segment::Text(_) => None,
+            })
+            .flatten()
+            .filter_map(|a| match a {
+                Alternative::Field(name) if !KNOWN_FIELDS.contains(&name.as_str()) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+    
+    /// 通し番号 `{seq}` を参照するか（参照しなければ採番しなくてよい）
+    pub fn uses_sequence(&self) -> bool {
+        self.segments.iter().any(|s| match s {
+            Segment::Placeholder(chain) => chain.iter().any(|a| matches!(a, Alternative::Sequence(_))),
+            Segment::Text(_) => false,
+        })
+    }
+    
+    /// ファイル名を生成（全て空ならテンプレートなしの既定の命名にする）
+    ///
+    /// 抽出を無効にしたフィールドだけを参照するプレースホルダは、リテラルの代替値も含めて出力しない
+    pub fn render(&self, info: &MillsheetInfo, original_name: &str) -> String {
+        self.render_with_sequence(info, original_name, None)
+    }
+    
+    /// 通し番号を指定してファイル名を生成（`seq` が None なら `{seq}` は空）
+    pub fn render_with_sequence(&self, info: &MillsheetInfo, original_name: &str, seq: Option<u64>) -> String {
+        let mut stem = String::new();
+        // 直前のプレースホルダが空だったか（次の区切り文字を出力しない）
+        let mut skip_separator = false;
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => {
+                    let text = replace_invalid_chars(text);
+                    let text = if skip_separator && (stem.is_empty() || stem.ends_with(SEPARATORS)) {
+                        text.trim_start_matches(SEPARATORS)
+                    } else {
+                        &text
+                    };
+                    stem.push_str(text);
+                    skip_separator = false;
+                }
+                Segment::Placeholder(chain) if is_disabled_chain(chain, info) => skip_separator = true,
+                Segment::Placeholder(chain) => {
+                    let value = chain.iter().find_map(|alternative| match alternative {
+                        Alternative::Field(name) => self.field_value(info, name, original_name),
+                        Alternative::Literal(literal) => Some(replace_invalid_chars(literal)),
+                        Alternative::Sequence(width) => seq.map(|n| format!("{:0width$}", n, width = *width)),
+                        Alternative::Dimension(part, format) => info
+                            .dimensions_spec
+                            .and_then(|dims| dims.part(*part, *format))
+                            .map(|v| sanitize_for_filename(&v))
+                            .filter(|v| !v.is_empty()),
+                    });
+                    skip_separator = value.is_none();
+                    stem.push_str(&value.unwrap_or_default());
+                }
+            }
+        }
+        
+        // 空のフィールドで区切り文字が重なったり端に残ったりしないようにする
+        let stem = REPEATED_UNDERSCORES.replace_all(&stem, "_");
+        let stem = stem.trim_matches(|c: char| SEPARATORS.contains(&c) || c.is_whitespace());
+        
+        if stem.is_empty() {
+            return info.generate_filename(original_name);
+        }
+        format!("{}.pdf", stem)
+    }
+    
+    /// フィールドの値（空文字は値なしとみなす）
+    fn field_value(&self, info: &MillsheetInfo, name: &str, original_name: &str) -> Option<String> {
+        let derived = || {
+            info.derived_date(self.fiscal_year_start_month)?
+                .placeholders()
+                .into_iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value)
+        };
+        
+        let value = match name {
+            "date" => info.formatted_date(),
+            "material" => info.material.clone(),
+            "dimensions" => info.dimensions.clone(),
+            "manufacturer" => info.manufacturer.clone(),
+            "charge_no" => info.filename_charge_no(),
+            "coil_no" => extract_coil_no(&info.raw_text),
+            "weight" => info.weight.clone(),
+            "original" => std::path::Path::new(original_name)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(str::to_string),
+            // 日のない年月では曜日は決まらない
+            "weekday" if info.date.is_some_and(|d| d.day.is_none()) => None,
+            "fiscal_year" | "quarter" | "month" | "weekday" => derived(),
+            _ => None,
+        };
+        
+        value
+            .map(|v| sanitize_for_filename(&v))
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// フィールド参照がすべて抽出を無効にしたフィールドか
+fn is_disabled_chain(chain: &[Alternative], info: &MillsheetInfo) -> bool {
+    let mut fields = chain.iter().filter_map(|alternative| match alternative {
+        Alternative::Field(name) => Some(name.as_str()),
+        Alternative::Dimension(..) => Some("dimensions"),
+        Alternative::Literal(_) | Alternative::Sequence(_) => None,
+    });
+    fields.clone().next().is_some() && fields.all(|name| !info.is_field_enabled(name))
+}
+
+/// `charge_no|coil_no|'NA'` を候補の列に分解
+fn parse_chain(body: &str) -> Result<Vec<Alternative>> {
+    let mut chain = Vec::new();
+    for part in split_chain(body) {
+        let part = part.trim();
+        let quoted = part.len() >= 2
+            && ((part.starts_with('\'') && part.ends_with('\'')) || (part.starts_with('"') && part.ends_with('"')));
+        
+        if quoted {
+            chain.push(Alternative::Literal(part[1..part.len() - 1].to_string()));
+        } else if let Some(width) = parse_sequence(part) {
+            if width > MAX_SEQUENCE_WIDTH {
+                bail!("通し番号の桁数は{}桁までです: {{{}}}", MAX_SEQUENCE_WIDTH, body);
+            }
+            chain.push(Alternative::Sequence(width));
+        } else if let Some((part, format)) = parse_dimension(part, body)? {
+            chain.push(Alternative::Dimension(part, format));
+        } else if !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            chain.push(Alternative::Field(part.to_string()));
+        } else {
+            bail!("テンプレートのプレースホルダが不正です: {{{}}}", body);
+        }
+    }
+    Ok(chain)
+}
+
+/// `seq` / `seq:04` を0埋めの桁数に変換（通し番号でなければ None）
+fn parse_sequence(part: &str) -> Option<usize> {
+    let rest = part.strip_prefix("seq")?;
+    if rest.is_empty() {
+        return Some(0);
+    }
+    let digits = rest.strip_prefix(':')?;
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// `thickness` / `width:int,mm` を寸法の要素と書式に変換（寸法の要素でなければ None）
+fn parse_dimension(part: &str, body: &str) -> Result<Option<(DimensionPart, DimensionFormat)>> {
+    let (name, spec) = match part.split_once(':') {
+        Some((name, spec)) => (name, Some(spec)),
+        None => (part, None),
+    };
+    let Some(dimension) = DimensionPart::from_name(name) else {
+        return Ok(None);
+    };
+    
+    let mut format = DimensionFormat::default();
+    for flag in spec.into_iter().flat_map(|spec| spec.split(',')) {
+        match flag.trim() {
+            "int" => format.integer = true,
+            "mm" => format.unit = true,
+            _ => bail!("寸法の書式が不正です（int / mm を , 区切りで指定）: {{{}}}", body),
+        }
+    }
+    Ok(Some((dimension, format)))
+}
+
+/// 引用符の外にある '|' で分割
+fn split_chain(body: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('|', None) => {
+                parts.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&body[start..]);
+    parts
+}
+
+/// ファイル名に使えない文字を '_' に置換（リテラル用、前後の '_' は残す）
+fn replace_invalid_chars(text: &str) -> String {
+    let invalid_chars = ['\\', '/', ':', '*', '?', '"', '<', '>', '|', '\r', '\n'];
+    text.chars()
+        .map(|c| if invalid_chars.contains(&c) { '_' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn render(template: &str, text: &str) -> String {
+        FilenameTemplate::parse(template).unwrap().render(&MillsheetInfo::parse(text), "a.pdf")
+    }
+    
+    #[test]
+    fn chain_uses_first_field_with_value() {
+        let template = "{date}_{charge_no|coil_no|'NA'}";
+        assert_eq!(render(template, "2024年3月15日\nCHARGE NO. 8K23456\nCOIL NO. C-12345"), "24-03-15_8K23456.pdf");
+        assert_eq!(render(template, "2024年3月15日\nCOIL NO. C-12345"), "24-03-15_C-12345.pdf");
+    }
+    
+    #[test]
+    fn chain_falls_back_to_literal_when_all_fields_are_empty() {
+        assert_eq!(render("{date}_{charge_no|coil_no|'NA'}", "2024年3月15日"), "24-03-15_NA.pdf");
+        assert_eq!(render("{date}_{charge_no|\"不明\"}", "2024年3月15日"), "24-03-15_不明.pdf");
+    }
+    
+    #[test]
+    fn chain_without_literal_is_dropped_with_its_separator() {
+        assert_eq!(render("{charge_no|coil_no}_{date}", "2024年3月15日"), "24-03-15.pdf");
+    }
+    
+    #[test]
+    fn chain_coexists_with_plain_placeholders_and_braces() {
+        assert_eq!(
+            render("{{{material}}}_{manufacturer|'NA'}_{date}", "2024年3月15日\nSS400"),
+            "{SS400}_NA_24-03-15.pdf"
+        );
+    }
+    
+    #[test]
+    fn literal_may_contain_pipe_and_braces() {
+        let template = FilenameTemplate::parse("{charge_no|'A|B'}").unwrap();
+        assert!(template.unknown_fields().is_empty());
+        // ファイル名に使えない '|' は '_' になる
+        assert_eq!(template.render(&MillsheetInfo::parse("2024年3月15日"), "a.pdf"), "A_B.pdf");
+        assert_eq!(render("{charge_no|'}'}", "2024年3月15日"), "}.pdf");
+    }
+    
+    #[test]
+    fn unknown_fields_in_chain_are_reported() {
+        let template = FilenameTemplate::parse("{charge_no|heat_no|'NA'}").unwrap();
+        assert_eq!(template.unknown_fields(), ["heat_no"]);
+    }
+    
+    #[test]
+    fn malformed_chains_are_rejected() {
+        for template in ["{charge_no|}", "{|'NA'}", "{charge_no|'NA}", "{charge no}"] {
+            assert!(FilenameTemplate::parse(template).is_err(), "{}", template);
+        }
+    }
+    
+    #[test]
+    fn disabled_field_is_dropped_with_separator() {
+        let text = "2024年3月15日\nSS400\n1.6X1219XCOIL\nJFEスチール株式会社";
+        let template = FilenameTemplate::parse("{date}_{manufacturer|'NA'}_{material}").unwrap();
+        let info = MillsheetInfo::parse(text);
+        assert_eq!(template.render(&info, "a.pdf"), "24-03-15_JFEスチール_SS400.pdf");
+        
+        let options = crate::parser::ParseOptions { disabled_fields: ["manufacturer"].into(), ..Default::default() };
+        let info = MillsheetInfo::parse_with_options(text, &options);
+        assert_eq!(info.manufacturer, None);
+        assert_eq!(template.render(&info, "a.pdf"), "24-03-15_SS400.pdf");
+        assert_eq!(info.generate_filename("a.pdf"), "24-03-15_SS400_1.6x1219xC.pdf");
+        
+        // 値のないフィールドは区切り文字ごと除き、末尾の .pdf は省略してもよい
+        let template = FilenameTemplate::parse("{manufacturer}-{charge_no}-{date}.pdf").unwrap();
+        assert_eq!(template.render(&info, "a.pdf"), "24-03-15.pdf");
+    }
+    
+    #[test]
+    fn sequence_is_padded_and_omitted_without_number() {
+        let info = MillsheetInfo::parse("2024年3月15日\nSS400");
+        let template = FilenameTemplate::parse("{seq:04}_{date}_{material}").unwrap();
+        assert!(template.uses_sequence());
+        assert_eq!(template.render_with_sequence(&info, "a.pdf", Some(7)), "0007_24-03-15_SS400.pdf");
+        assert_eq!(template.render_with_sequence(&info, "a.pdf", Some(12345)), "12345_24-03-15_SS400.pdf");
+        assert_eq!(template.render(&info, "a.pdf"), "24-03-15_SS400.pdf");
+        
+        let template = FilenameTemplate::parse("No{seq}_{material}").unwrap();
+        assert_eq!(template.render_with_sequence(&info, "a.pdf", Some(7)), "No7_SS400.pdf");
+    }
+    
+    #[test]
+    fn dimension_parts_take_format_options() {
+        let info = MillsheetInfo::parse("2024年3月15日\nSS400\n1.6X1219XCOIL");
+        let template = FilenameTemplate::parse("{material}_{thickness}x{width}").unwrap();
+        assert_eq!(template.render(&info, "a.pdf"), "SS400_1.6x1219.pdf");
+        
+        let template = FilenameTemplate::parse("{thickness:int}_{width:mm}_{length:int,mm}").unwrap();
+        assert_eq!(template.render(&info, "a.pdf"), "2_1219mm_C.pdf");
+        
+        // 寸法が読めなければ要素は空（区切り文字ごと除く）
+        let info = MillsheetInfo::parse("2024年3月15日\nSS400");
+        let template = FilenameTemplate::parse("{material}_{thickness}_{date}").unwrap();
+        assert_eq!(template.render(&info, "a.pdf"), "SS400_24-03-15.pdf");
+        
+        assert!(FilenameTemplate::parse("{width:cm}").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! 単位付き数値の抽出モジュール - 重量・寸法・温度などの共通処理
+
+use super::normalize_fullwidth;
+use regex::Regex;
+
+/// テキストから「数値＋単位」を抽出
+///
+/// 全角英数字・全角記号を半角にそろえ、桁区切りのカンマを除いてから数値を読む。
+/// `units` は長いものから優先して照合し、返す単位は `units` に渡した表記のまま。
+pub fn extract_value_with_unit(text: &str, units: &[&str]) -> Option<(f64, String)> {
+    if units.is_empty() {
+        return None;
+    }
+    
+    let text = normalize_fullwidth(text);
+    
+    let mut sorted: Vec<&str> = units.to_vec();
+    sorted.sort_by_key(|u| std::cmp::Reverse(u.chars().count()));
+    let alternation = sorted
+        .iter()
+        .map(|u| regex::escape(&normalize_fullwidth(u)))
+        .collect::<Vec<_>>()
+        .join("|");
+    
+    let re = Regex::new(&format!(
+        r"(?i)(-?\d{{1,3}}(?:,\d{{3}})+(?:\.\d+)?|-?\d+(?:[.,]\d+)?)\s*({})",
+        alternation
+    ))
+    .ok()?;
+    
+    for caps in re.captures_iter(&text) {
+        let unit_match = caps.get(2)?;
+        
+        // "m" が "mm" や "min" の一部に当たらないよう、英字の途中で切れる一致は捨てる
+        let followed_by_letter = text[unit_match.end()..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic());
+        if followed_by_letter {
+            continue;
+        }
+        
+        let Some(value) = parse_number(caps.get(1)?.as_str()) else {
+            continue;
+        };
+        
+        let unit = sorted
+            .iter()
+            .find(|u| normalize_fullwidth(u).eq_ignore_ascii_case(unit_match.as_str()))
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| unit_match.as_str().to_string());
+        
+        return Some((value, unit));
+    }
+    
+    None
+}
+
+/// 数値文字列を読む（桁区切りのカンマは除去、それ以外のカンマは小数点とみなす）
+pub(super) fn parse_number(raw: &str) -> Option<f64> {
+    let is_grouped = raw
+        .split(',')
+        .skip(1)
+        .all(|group| group.split('.').next().is_some_and(|g| g.len() == 3));
+    
+    let normalized = if is_grouped {
+        raw.replace(',', "")
+    } else {
+        raw.replace(',', ".")
+    };
+    
+    normalized.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn value_with_unit_reads_fullwidth_and_skips_other_units() {
+        let found = extract_value_with_unit("重量 １，２３４．５ ｋｇ", &["kg", "t"]);
+        assert_eq!(found, Some((1234.5, "kg".to_string())));
+        
+        let found = extract_value_with_unit("TEMP 1,6 m / 25℃", &["℃", "mm"]);
+        assert_eq!(found, Some((25.0, "℃".to_string())));
+    }
+}
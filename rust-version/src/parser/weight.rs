@@ -0,0 +1,106 @@
+//! 重量抽出モジュール - 実重量（kg / t）
+
+use super::{compile_builtin, FieldMatch, NormalizedText};
+use super::units::parse_number;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// 数値（桁区切りのカンマ付き、または小数）
+const NUMBER: &str = r"(\d{1,3}(?:,\d{3})+(?:\.\d+)?|\d+(?:\.\d+)?)";
+
+/// 重量の単位（長いものから照合する）
+const UNIT: &str = r"(KGS|KG|TONS|TON|トン|T)";
+
+/// 重量のラベル
+const LABEL: &str = r"(?:実重量|正味重量|重量|質量|NET\s*WEIGHT|WEIGHT|NET\s*WT|N\.?W\.?)";
+
+/// ラベル付きの重量
+static LABELED_WEIGHT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| compile_builtin(&format!(r"(?i){}\s*[:：]?\s*{}\s*{}", LABEL, NUMBER, UNIT)));
+
+/// 単位付きの数値
+static UNLABELED_WEIGHT_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin(&format!(r"(?i){}\s*{}", NUMBER, UNIT)));
+
+/// このモジュールの組み込みパターンをすべてコンパイル
+pub(crate) fn precompile_patterns() {
+    LazyLock::force(&LABELED_WEIGHT_REGEX);
+    LazyLock::force(&UNLABELED_WEIGHT_REGEX);
+}
+
+/// テキストから重量を抽出（"2450kg" / "12.3t" の形式）
+///
+/// 寸法の数値（幅1540など）と取り違えないよう単位の記載を必須とし、ラベル付きの記載を優先する。
+/// 単独の "t" は板厚の表記（1.6t）と紛らわしいため、ラベルが無ければ重量とみなさない
+pub fn extract_weight(text: &str) -> Option<String> {
+    extract_weight_match(text).map(|m| m.value)
+}
+
+/// 重量と抽出根拠を取得（根拠の位置は全角を正規化する前のテキスト上）
+pub fn extract_weight_match(text: &str) -> Option<FieldMatch> {
+    let normalized = NormalizedText::new(text);
+    let text = &normalized.text;
+    
+    for (re, is_labeled) in [(&*LABELED_WEIGHT_REGEX, true), (&*UNLABELED_WEIGHT_REGEX, false)] {
+        for caps in re.captures_iter(text) {
+            let (Some(number), Some(unit)) = (caps.get(1), caps.get(2)) else {
+                continue;
+            };
+            if !is_weight_boundary(text, number.start(), unit.end(), unit.as_str())
+                || (!is_labeled && unit.as_str().eq_ignore_ascii_case("T"))
+            {
+                continue;
+            }
+            let Some(value) = parse_number(number.as_str()).filter(|v| *v > 0.0) else {
+                continue;
+            };
+            let found = FieldMatch::new(format_weight(value, unit.as_str()), number.start()..unit.end(), re.as_str());
+            return Some(normalized.remap(found));
+        }
+    }
+    
+    None
+}
+
+/// 単位が単語の途中でなく、寸法の一部でもないか
+fn is_weight_boundary(text: &str, start: usize, end: usize, unit: &str) -> bool {
+    let before = text[..start].chars().next_back();
+    if before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '.') {
+        return false;
+    }
+    
+    let rest = &text[end..];
+    if rest.chars().next().is_some_and(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    
+    // "1.6t x 1219" の厚さ表記
+    let is_ton = !unit.eq_ignore_ascii_case("KG") && !unit.eq_ignore_ascii_case("KGS");
+    !(is_ton && rest.trim_start().starts_with(['x', 'X', '×', '*']))
+}
+
+/// 単位をそろえて文字列にする（kg/KGS -> kg、t/TON/トン -> t）
+fn format_weight(value: f64, unit: &str) -> String {
+    let unit = if unit.to_uppercase().starts_with("KG") { "kg" } else { "t" };
+    format!("{}{}", value, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn weight_is_not_taken_from_thickness() {
+        assert_eq!(extract_weight("SIZE 1.6t x 1540 x C\nWEIGHT 2,450 kg"), Some("2450kg".to_string()));
+        assert_eq!(extract_weight("数量 1 重量 12.3 トン"), Some("12.3t".to_string()));
+        assert_eq!(extract_weight("1.6X1540XCOIL"), None);
+        assert_eq!(extract_weight("板厚 1.6t"), None);
+    }
+    
+    #[test]
+    fn weight_match_spans_raw_text() {
+        let text = "重量：２，４５０ｋｇ";
+        let found = extract_weight_match(text).unwrap();
+        assert_eq!(found.value, "2450kg");
+        assert_eq!(&text[found.span], "２，４５０ｋｇ");
+    }
+}
@@ -0,0 +1,172 @@
+//! PDF事前診断 - Popplerを起動せずにファイル構造だけを軽く調べる
+
+use crate::parser::compile_builtin_bytes;
+use anyhow::{Context, Result};
+use regex::bytes::Regex;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// 末尾のEOFマーカーを探す範囲（仕様上は末尾1024バイト以内）
+const EOF_SEARCH_WINDOW: usize = 1024;
+
+/// 暗号化辞書への参照
+static ENCRYPT_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin_bytes(r"/Encrypt\s*(\d+\s+\d+\s+R|<<)"));
+
+/// ページオブジェクト（/Type /Pages は含まない）
+static PAGE_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin_bytes(r"/Type\s*/Page\b"));
+
+/// フォント定義
+static FONT_REGEX: LazyLock<Regex> = LazyLock::new(|| compile_builtin_bytes(r"/Font\b"));
+
+/// 診断結果
+#[derive(Debug, Clone, Default)]
+pub struct PdfDiagnosis {
+    /// 暗号化されている（パスワードが必要な可能性がある）
+    pub encrypted: bool,
+    /// 破損している（ヘッダーまたはEOFマーカーがない）
+    pub corrupted: bool,
+    /// ページ数（オブジェクトストリーム内にしかない場合など、判定できなければ None）
+    pub page_count: Option<usize>,
+    /// テキストレイヤー（フォント定義）があるか
+    pub has_text_layer: bool,
+}
+
+impl PdfDiagnosis {
+    /// そのまま処理できるか
+    pub fn is_ok(&self) -> bool {
+        !self.encrypted && !self.corrupted
+    }
+    
+    /// 状態を表すアイコン
+    pub fn icon(&self) -> &'static str {
+        if self.corrupted {
+            "✗"
+        } else if self.encrypted {
+            "🔒"
+        } else {
+            "✓"
+        }
+    }
+    
+    /// 状態の説明
+    pub fn summary(&self) -> String {
+        if self.corrupted {
+            return "破損しています".to_string();
+        }
+        
+        let mut parts = Vec::new();
+        if self.encrypted {
+            parts.push("暗号化".to_string());
+        }
+        if let Some(pages) = self.page_count {
+            parts.push(format!("{} ページ", pages));
+        }
+        parts.push(if self.has_text_layer { "テキストあり" } else { "画像のみ" }.to_string());
+        parts.join(" / ")
+    }
+}
+
+/// PDFを診断
+pub fn diagnose(path: impl AsRef<Path>) -> Result<PdfDiagnosis> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("PDFの読み込みに失敗: {:?}", path))?;
+    Ok(diagnose_bytes(&bytes))
+}
+
+/// バイト列からPDFを診断
+pub fn diagnose_bytes(bytes: &[u8]) -> PdfDiagnosis {
+    let has_header = bytes.windows(5).take(1024).any(|w| w == b"%PDF-");
+    let tail = &bytes[bytes.len().saturating_sub(EOF_SEARCH_WINDOW)..];
+    let has_eof = tail.windows(5).any(|w| w == b"%%EOF");
+    
+    if !has_header || !has_eof {
+        return PdfDiagnosis {
+            corrupted: true,
+            ..Default::default()
+        };
+    }
+    
+    let page_count = PAGE_REGEX.find_iter(bytes).count();
+    
+    PdfDiagnosis {
+        encrypted: ENCRYPT_REGEX.is_match(bytes),
+        corrupted: false,
+        page_count: (page_count > 0).then_some(page_count),
+        has_text_layer: FONT_REGEX.is_match(bytes),
+    }
+}
+
+/// 診断の組み込みの正規表現をすべてコンパイル
+pub(crate) fn precompile_patterns() {
+    for regex in [&ENCRYPT_REGEX, &PAGE_REGEX, &FONT_REGEX] {
+        LazyLock::force(regex);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 最小限のPDF（`objects` をオブジェクトとして並べ、`trailer` を付ける）
+    fn pdf(objects: &[&str], trailer: &str) -> Vec<u8> {
+        let mut bytes = b"%PDF-1.4\n".to_vec();
+        for (i, object) in objects.iter().enumerate() {
+            bytes.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+        }
+        bytes.extend(format!("trailer\n<< /Root 1 0 R {}>>\n%%EOF\n", trailer).bytes());
+        bytes
+    }
+    
+    const CATALOG: &str = "<< /Type /Catalog /Pages 2 0 R >>";
+    const PAGES: &str = "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>";
+    
+    #[test]
+    fn counts_pages_and_detects_text_layer() {
+        let text = pdf(&[CATALOG, PAGES, "<< /Type /Page /Resources << /Font << /F1 5 0 R >> >> >>", "<< /Type/Page >>"], "");
+        let diagnosis = diagnose_bytes(&text);
+        assert!(diagnosis.is_ok());
+        // /Type /Pages はページに数えない
+        assert_eq!(diagnosis.page_count, Some(2));
+        assert!(diagnosis.has_text_layer);
+        assert_eq!(diagnosis.summary(), "2 ページ / テキストあり");
+        
+        let scanned = pdf(&[CATALOG, PAGES, "<< /Type /Page /Resources << /XObject << /Im1 5 0 R >> >> >>"], "");
+        let diagnosis = diagnose_bytes(&scanned);
+        assert_eq!((diagnosis.page_count, diagnosis.has_text_layer), (Some(1), false));
+        assert_eq!(diagnosis.summary(), "1 ページ / 画像のみ");
+        
+        // ページがオブジェクトストリームの中にしかなければ判定しない
+        let compressed = pdf(&[CATALOG, "<< /Type /ObjStm /N 2 /Length 0 >>"], "");
+        assert_eq!(diagnose_bytes(&compressed).page_count, None);
+    }
+    
+    #[test]
+    fn detects_encryption_by_reference_or_inline_dictionary() {
+        for trailer in ["/Encrypt 6 0 R ", "/Encrypt << /Filter /Standard >> "] {
+            let diagnosis = diagnose_bytes(&pdf(&[CATALOG, PAGES], trailer));
+            assert!(diagnosis.encrypted && !diagnosis.corrupted, "{}", trailer);
+            assert!(!diagnosis.is_ok());
+            assert_eq!(diagnosis.icon(), "🔒");
+        }
+        // /Encrypt の後ろが参照でも辞書でもなければ暗号化とみなさない
+        assert!(!diagnose_bytes(&pdf(&[CATALOG, "<< /EncryptMetadata false >>"], "")).encrypted);
+    }
+    
+    #[test]
+    fn detects_missing_header_or_eof_marker() {
+        let valid = pdf(&[CATALOG, PAGES], "");
+        assert!(!diagnose_bytes(&valid).corrupted);
+        
+        let no_header = valid[9..].to_vec();
+        let truncated = valid[..valid.len() / 2].to_vec();
+        // 末尾1024バイトより前のEOFマーカーは数えない
+        let mut trailing_garbage = valid.clone();
+        trailing_garbage.extend(std::iter::repeat_n(b' ', EOF_SEARCH_WINDOW));
+        for bytes in [no_header, truncated, trailing_garbage, Vec::new()] {
+            let diagnosis = diagnose_bytes(&bytes);
+            assert!(diagnosis.corrupted);
+            assert_eq!((diagnosis.icon(), diagnosis.summary().as_str()), ("✗", "破損しています"));
+        }
+    }
+}
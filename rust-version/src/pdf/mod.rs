@@ -1,10 +1,20 @@
 //! PDF処理モジュール - PDFから画像への変換
 
+pub mod diagnose;
+pub mod stitch;
+pub mod thumbnail;
+
+pub use diagnose::{diagnose, PdfDiagnosis};
+pub use stitch::{stitch_pages, MAX_STITCHED_PAGES};
+pub use thumbnail::{render_thumbnail, Thumbnail};
+
+use crate::hash::fnv1a_hex;
 use anyhow::{Context, Result};
 use std::io::{Cursor, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
 
 /// 埋め込みPoppler（zipファイル）
 const EMBEDDED_POPPLER: &[u8] = include_bytes!("../poppler.zip");
@@ -12,24 +22,91 @@ const EMBEDDED_POPPLER: &[u8] = include_bytes!("../poppler.zip");
 /// 展開済みPopplerのパス（一度だけ展開）
 static POPPLER_DIR: OnceLock<PathBuf> = OnceLock::new();
 
+/// Popplerの展開を1スレッドずつに制限する（並列処理の初回に同時に展開しないため）
+static POPPLER_EXTRACT_LOCK: Mutex<()> = Mutex::new(());
+
+/// 展開完了マーカーのファイル名（中身は埋め込みzipのハッシュ）
+const POPPLER_MARKER: &str = ".extracted";
+
+/// OCR用に変換するときの既定の解像度
+pub const DEFAULT_CONVERT_DPI: u32 = 300;
+
+/// 設定画面で選べる変換の解像度（大判図面は低め、細かい文字は高めにする）
+pub const CONVERT_DPI_CHOICES: [u32; 3] = [150, 300, 600];
+
+/// 全ページを変換するときのページ数の上限（OCRのコストを抑えるため先頭から）
+pub const MAX_OCR_PAGES: usize = 5;
+
+/// 一時ディレクトリ名の連番（並列処理時の衝突防止）
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
 /// Popplerを一時フォルダに展開
+///
+/// 展開は全体をロックして1回だけ行う。完了マーカーが無い・埋め込みzipと合わないフォルダ
+/// （展開途中で落ちた、古いバージョンが残っている）は消して展開し直す
 fn extract_poppler() -> Result<PathBuf> {
     // 既に初期化済みならそのパスを返す
     if let Some(dir) = POPPLER_DIR.get() {
         return Ok(dir.clone());
     }
     
+    let _guard = POPPLER_EXTRACT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    // ロックを待つ間に他のスレッドが展開を済ませていればそれを使う
+    if let Some(dir) = POPPLER_DIR.get() {
+        return Ok(dir.clone());
+    }
+    
     let temp_dir = std::env::temp_dir().join("millsheet_poppler");
+    let fingerprint = fnv1a_hex(EMBEDDED_POPPLER);
     
     // 既に展開済みならそのまま使う
-    let pdftoppm_path = temp_dir.join("pdftoppm.exe");
-    if pdftoppm_path.exists() {
+    if is_extracted(&temp_dir, &fingerprint) {
         let _ = POPPLER_DIR.set(temp_dir.clone());
         return Ok(temp_dir);
     }
     
-    // 展開
-    std::fs::create_dir_all(&temp_dir)?;
+    // 別プロセスと衝突しないよう作業用フォルダに展開し、完了マーカーを書いてから置き換える
+    let staging_dir = std::env::temp_dir().join(format!("millsheet_poppler_extracting_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&staging_dir);
+    if let Err(e) = unpack_poppler(&staging_dir, &fingerprint) {
+        let _ = std::fs::remove_dir_all(&staging_dir);
+        return Err(e);
+    }
+    
+    if is_extracted(&temp_dir, &fingerprint) {
+        // 展開している間に別プロセスが先に展開を終えた
+        let _ = std::fs::remove_dir_all(&staging_dir);
+        let _ = POPPLER_DIR.set(temp_dir.clone());
+        return Ok(temp_dir);
+    }
+    if temp_dir.exists() {
+        tracing::warn!("展開が不完全なPopplerを削除して展開し直します: {}", temp_dir.display());
+        if let Err(e) = std::fs::remove_dir_all(&temp_dir) {
+            let _ = std::fs::remove_dir_all(&staging_dir);
+            return Err(e).with_context(|| format!("展開が不完全なPopplerを削除できません: {}", temp_dir.display()));
+        }
+    }
+    if let Err(e) = std::fs::rename(&staging_dir, &temp_dir) {
+        let _ = std::fs::remove_dir_all(&staging_dir);
+        // 同時に起動した別プロセスが先に展開を終えていればそれを使う
+        if !is_extracted(&temp_dir, &fingerprint) {
+            return Err(e).with_context(|| format!("Popplerの展開先を作成できません: {}", temp_dir.display()));
+        }
+    }
+    
+    let _ = POPPLER_DIR.set(temp_dir.clone());
+    Ok(temp_dir)
+}
+
+/// 展開を最後まで終えたPopplerのフォルダか（完了マーカーが埋め込みzipのハッシュと一致する）
+fn is_extracted(dir: &Path, fingerprint: &str) -> bool {
+    dir.join("pdftoppm.exe").exists()
+        && std::fs::read_to_string(dir.join(POPPLER_MARKER)).is_ok_and(|marker| marker.trim() == fingerprint)
+}
+
+/// 埋め込みPopplerを `dir` に展開し、最後に完了マーカーを書く
+fn unpack_poppler(dir: &Path, fingerprint: &str) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
     
     let cursor = Cursor::new(EMBEDDED_POPPLER);
     let mut archive = zip::ZipArchive::new(cursor)
@@ -37,7 +114,7 @@ fn extract_poppler() -> Result<PathBuf> {
     
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
-        let outpath = temp_dir.join(file.name());
+        let outpath = dir.join(file.name());
         
         if file.is_dir() {
             std::fs::create_dir_all(&outpath)?;
@@ -52,24 +129,168 @@ fn extract_poppler() -> Result<PathBuf> {
         }
     }
     
-    let _ = POPPLER_DIR.set(temp_dir.clone());
-    Ok(temp_dir)
+    std::fs::write(dir.join(POPPLER_MARKER), fingerprint).context("Popplerの展開完了マーカーを書き込めません")?;
+    Ok(())
+}
+
+/// 埋め込みPopplerを展開しておく（初回変換の待ち時間を前倒しする、展開済みなら何もしない）
+pub fn prepare_poppler() -> Result<()> {
+    extract_poppler().map(|_| ())
 }
 
 /// PDFの1ページ目を画像に変換
 pub fn convert_pdf_to_image(pdf_path: impl AsRef<Path>) -> Result<PathBuf> {
+    convert_pdf_to_image_with_password(pdf_path, None)
+}
+
+/// PDFの1ページ目を指定の解像度で画像に変換
+pub fn convert_pdf_to_image_with_dpi(pdf_path: impl AsRef<Path>, dpi: u32) -> Result<PathBuf> {
+    convert_pdf_to_image_with(pdf_path, None, dpi)
+}
+
+/// パスワード付きPDFの1ページ目を画像に変換
+pub fn convert_pdf_to_image_with_password(pdf_path: impl AsRef<Path>, password: Option<&str>) -> Result<PathBuf> {
+    convert_pdf_to_image_with(pdf_path, password, DEFAULT_CONVERT_DPI)
+}
+
+/// パスワード・解像度を指定してPDFの1ページ目を画像に変換
+pub fn convert_pdf_to_image_with(pdf_path: impl AsRef<Path>, password: Option<&str>, dpi: u32) -> Result<PathBuf> {
+    let temp_dir = run_pdftoppm(pdf_path.as_ref(), password, 1, 1, dpi)?;
+    first_page_image(&temp_dir).inspect_err(|_| remove_temp_dir(&temp_dir))
+}
+
+/// PDFの指定ページ（1始まり）を画像に変換
+pub fn convert_pdf_page(pdf_path: impl AsRef<Path>, page: usize) -> Result<PathBuf> {
+    convert_pdf_page_with(pdf_path, None, page, DEFAULT_CONVERT_DPI)
+}
+
+/// パスワード・解像度を指定してPDFの指定ページ（1始まり）を画像に変換
+///
+/// 構造からページ数が分かるPDFで範囲外のページを指定したら、pdftoppmを起動せずにエラーにする
+pub fn convert_pdf_page_with(pdf_path: impl AsRef<Path>, password: Option<&str>, page: usize, dpi: u32) -> Result<PathBuf> {
     let pdf_path = pdf_path.as_ref();
+    check_page_in_range(pdf_path, page)?;
+    
+    let temp_dir = run_pdftoppm(pdf_path, password, page, page, dpi)
+        .with_context(|| format!("{} ページ目を変換できません（ページ番号が範囲外の可能性があります）", page))?;
+    // 出力名の桁数は総ページ数で変わる（page-2.png / page-02.png）ので、出力された1枚を探す
+    page_images(&temp_dir)
+        .map(|pages| pages[0].clone())
+        .with_context(|| format!("{} ページ目がありません: {}", page, pdf_path.display()))
+        .inspect_err(|_| remove_temp_dir(&temp_dir))
+}
+
+/// ページ番号が1以上で、構造から分かるページ数を超えていないか（ページ数が分からなければ通す）
+fn check_page_in_range(pdf_path: &Path, page: usize) -> Result<()> {
+    if page == 0 {
+        anyhow::bail!("ページ番号は1から指定してください");
+    }
+    if let Ok(diagnosis) = diagnose(pdf_path)
+        && let Some(count) = diagnosis.page_count
+        && page > count
+    {
+        anyhow::bail!("{} ページ目はありません（{} は全 {} ページ）", page, pdf_path.display(), count);
+    }
+    Ok(())
+}
+
+/// ページを `dpi` で変換したときの画像の大きさ（ピクセル、pdftoppm と同じく切り上げ。ページの寸法はポイント = 1/72インチ）
+///
+/// 画素数は解像度の2乗に比例するので、150DPI → 300DPI → 600DPI で4倍ずつ増え、
+/// 転送量やVision APIの画像サイズの上限に効いてくる
+pub fn rendered_pixels(width_pt: f32, height_pt: f32, dpi: u32) -> (u32, u32) {
+    let scale = dpi as f64 / 72.0;
+    ((width_pt as f64 * scale).ceil() as u32, (height_pt as f64 * scale).ceil() as u32)
+}
+
+/// 変換した一時フォルダから1ページ目の画像を探す
+fn first_page_image(temp_dir: &Path) -> Result<PathBuf> {
+    // 生成されたファイルを探す
+    let image_path = temp_dir.join("page-1.png");
+    if image_path.exists() {
+        return Ok(image_path);
+    }
+    
+    // page-01.png のパターンも試す
+    let image_path = temp_dir.join("page-01.png");
+    if image_path.exists() {
+        return Ok(image_path);
+    }
+    
+    anyhow::bail!("変換された画像ファイルが見つかりません")
+}
+
+/// 先頭から最大 `max_pages` ページを `dpi` で画像に変換（ページ順、すべて同じ一時フォルダに出力）
+pub fn convert_pdf_pages(pdf_path: impl AsRef<Path>, password: Option<&str>, max_pages: usize, dpi: u32) -> Result<Vec<PathBuf>> {
+    let temp_dir = run_pdftoppm(pdf_path.as_ref(), password, 1, max_pages.max(1), dpi)?;
+    page_images(&temp_dir).inspect_err(|_| remove_temp_dir(&temp_dir))
+}
+
+/// 先頭から最大 `MAX_OCR_PAGES` ページを `dpi` で画像に変換（ページ順）
+///
+/// 画像はすべて同じ一時フォルダに出力されるので、使い終わったら1枚目を `cleanup_temp_image` に渡せばまとめて消える
+pub fn convert_pdf_all_pages(pdf_path: impl AsRef<Path>, password: Option<&str>, dpi: u32) -> Result<Vec<PathBuf>> {
+    convert_pdf_pages(pdf_path, password, MAX_OCR_PAGES, dpi)
+}
+
+/// 変換した一時フォルダのページ画像（ページ順）
+fn page_images(temp_dir: &Path) -> Result<Vec<PathBuf>> {
+    // page-1.png / page-01.png など桁数はページ数で変わるが、1回の出力内では揃うので名前順でページ順になる
+    let mut pages: Vec<PathBuf> = std::fs::read_dir(temp_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("page-") && n.ends_with(".png"))
+        })
+        .collect();
+    pages.sort();
+    
+    if pages.is_empty() {
+        anyhow::bail!("変換された画像ファイルが見つかりません");
+    }
+    Ok(pages)
+}
+
+/// 変換に失敗した一時フォルダを消す（途中まで出力されたページ画像を残さない）
+fn remove_temp_dir(temp_dir: &Path) {
+    if let Err(e) = std::fs::remove_dir_all(temp_dir) {
+        tracing::warn!("一時フォルダを削除できません {}: {}", temp_dir.display(), e);
+    }
+}
+
+/// pdftoppmで `first_page` ページ目から `last_page` ページ目までを `dpi` でPNGに変換し、出力先の一時フォルダを返す
+fn run_pdftoppm(pdf_path: &Path, password: Option<&str>, first_page: usize, last_page: usize, dpi: u32) -> Result<PathBuf> {
+    let first_page = first_page.to_string();
+    let last_page = last_page.to_string();
+    let dpi = dpi.to_string();
     
     // 一時ディレクトリを作成
     let temp_dir = std::env::temp_dir().join(format!(
-        "millsheet_{}",
+        "millsheet_{}_{}_{}",
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
-            .as_millis()
+            .as_millis(),
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
     ));
     std::fs::create_dir_all(&temp_dir)?;
     
+    pdftoppm_into(&temp_dir, pdf_path, password, &first_page, &last_page, &dpi)
+        .inspect_err(|_| remove_temp_dir(&temp_dir))?;
+    Ok(temp_dir)
+}
+
+/// pdftoppmを実行して `temp_dir` にページ画像を出力
+fn pdftoppm_into(
+    temp_dir: &Path,
+    pdf_path: &Path,
+    password: Option<&str>,
+    first_page: &str,
+    last_page: &str,
+    dpi: &str,
+) -> Result<()> {
     let output_base = temp_dir.join("page");
     
     // pdftoppmのパスを取得（埋め込みを展開）
@@ -81,10 +302,11 @@ pub fn convert_pdf_to_image(pdf_path: impl AsRef<Path>) -> Result<PathBuf> {
     let output = Command::new(&pdftoppm)
         .args([
             "-png",
-            "-f", "1",
-            "-l", "1",
-            "-r", "300",
+            "-f", first_page,
+            "-l", last_page,
+            "-r", dpi,
         ])
+        .args(password.map(|p| ["-upw", p]).into_iter().flatten())
         .arg(pdf_path)
         .arg(&output_base)
         .creation_flags(0x08000000) // CREATE_NO_WINDOW
@@ -95,10 +317,11 @@ pub fn convert_pdf_to_image(pdf_path: impl AsRef<Path>) -> Result<PathBuf> {
     let output = Command::new(&pdftoppm)
         .args([
             "-png",
-            "-f", "1",
-            "-l", "1",
-            "-r", "300",
+            "-f", first_page,
+            "-l", last_page,
+            "-r", dpi,
         ])
+        .args(password.map(|p| ["-upw", p]).into_iter().flatten())
         .arg(pdf_path)
         .arg(&output_base)
         .output()
@@ -109,19 +332,7 @@ pub fn convert_pdf_to_image(pdf_path: impl AsRef<Path>) -> Result<PathBuf> {
         anyhow::bail!("PDF変換に失敗: {}", stderr);
     }
     
-    // 生成されたファイルを探す
-    let image_path = temp_dir.join("page-1.png");
-    if image_path.exists() {
-        return Ok(image_path);
-    }
-    
-    // page-01.png のパターンも試す
-    let image_path = temp_dir.join("page-01.png");
-    if image_path.exists() {
-        return Ok(image_path);
-    }
-    
-    anyhow::bail!("変換された画像ファイルが見つかりません")
+    Ok(())
 }
 
 /// 一時ファイルをクリーンアップ
@@ -150,3 +361,85 @@ impl CommandExt for Command {
         WinCommandExt::creation_flags(self, flags)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const FINGERPRINT: &str = "0123456789abcdef";
+    
+    /// テストごとの空の作業フォルダ
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("millsheet_pdf_test_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    #[test]
+    fn folder_without_marker_is_not_extracted() {
+        let dir = scratch_dir("no_marker");
+        std::fs::write(dir.join("pdftoppm.exe"), b"").unwrap();
+        let extracted = is_extracted(&dir, FINGERPRINT);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(!extracted);
+    }
+    
+    #[test]
+    fn folder_with_stale_marker_is_not_extracted() {
+        let dir = scratch_dir("stale_marker");
+        std::fs::write(dir.join("pdftoppm.exe"), b"").unwrap();
+        std::fs::write(dir.join(POPPLER_MARKER), "fedcba9876543210").unwrap();
+        let extracted = is_extracted(&dir, FINGERPRINT);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(!extracted);
+    }
+    
+    #[test]
+    fn marker_without_pdftoppm_is_not_extracted() {
+        let dir = scratch_dir("no_pdftoppm");
+        std::fs::write(dir.join(POPPLER_MARKER), FINGERPRINT).unwrap();
+        let extracted = is_extracted(&dir, FINGERPRINT);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(!extracted);
+    }
+    
+    #[test]
+    fn folder_with_matching_marker_is_extracted() {
+        let dir = scratch_dir("matching_marker");
+        std::fs::write(dir.join("pdftoppm.exe"), b"").unwrap();
+        std::fs::write(dir.join(POPPLER_MARKER), format!("{}\n", FINGERPRINT)).unwrap();
+        let extracted = is_extracted(&dir, FINGERPRINT);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(extracted);
+    }
+    
+    #[test]
+    fn unpack_writes_marker_with_fingerprint() {
+        let dir = scratch_dir("unpack").join("poppler");
+        unpack_poppler(&dir, FINGERPRINT).unwrap();
+        let marker = std::fs::read_to_string(dir.join(POPPLER_MARKER));
+        let _ = std::fs::remove_dir_all(dir.parent().unwrap());
+        assert_eq!(marker.unwrap(), FINGERPRINT);
+    }
+    
+    #[test]
+    fn rendered_pixels_scale_with_dpi() {
+        // A4 (595×842pt)
+        assert_eq!(rendered_pixels(595.0, 842.0, 300), (2480, 3509));
+        assert_eq!(rendered_pixels(595.0, 842.0, 150), (1240, 1755));
+        
+        let pixels = |dpi| {
+            let (w, h) = rendered_pixels(595.0, 842.0, dpi);
+            w as u64 * h as u64
+        };
+        assert!(pixels(600) > pixels(300) * 39 / 10);
+        assert!(pixels(300) > pixels(150) * 39 / 10);
+    }
+    
+    #[test]
+    fn page_numbers_start_at_one() {
+        let error = convert_pdf_page("scan.pdf", 0).unwrap_err();
+        assert_eq!(error.to_string(), "ページ番号は1から指定してください");
+    }
+}
@@ -0,0 +1,152 @@
+//! ページ画像の縦連結 - 複数ページを1回のOCRで読むため
+
+use anyhow::{bail, Context, Result};
+use image::{imageops, GrayImage, Luma};
+use std::path::{Path, PathBuf};
+
+/// 結合できる最大ページ数（300DPIのA4で縦約14000px）
+pub const MAX_STITCHED_PAGES: usize = 4;
+
+/// 結合画像の最大バイト数（base64化しても Vision API のリクエスト上限10MBに収まる大きさ）
+pub const MAX_STITCHED_BYTES: u64 = 7 * 1024 * 1024;
+
+/// 結合画像のファイル名（先頭ページと同じ一時フォルダに作る）
+const STITCHED_FILE: &str = "stitched.png";
+
+/// ページ画像を上から順に縦に連結して1枚のPNGにする
+///
+/// OCRに色は不要なのでグレースケールにしてサイズを抑える。幅の狭いページは左寄せにし、余白は白で埋める。
+/// ページ数が `MAX_STITCHED_PAGES` を、結合後のサイズが `MAX_STITCHED_BYTES` を超える場合はエラー
+pub fn stitch_pages(paths: &[PathBuf]) -> Result<PathBuf> {
+    stitch_pages_within(paths, MAX_STITCHED_BYTES)
+}
+
+/// 結合後のサイズ上限を `max_bytes` にして縦に連結
+fn stitch_pages_within(paths: &[PathBuf], max_bytes: u64) -> Result<PathBuf> {
+    if paths.is_empty() {
+        bail!("結合するページがありません");
+    }
+    if paths.len() > MAX_STITCHED_PAGES {
+        bail!("結合できるのは {} ページまでです ({} ページ)", MAX_STITCHED_PAGES, paths.len());
+    }
+    
+    let pages = paths
+        .iter()
+        .map(|path| {
+            image::open(path)
+                .map(|img| img.to_luma8())
+                .with_context(|| format!("ページ画像の読み込みに失敗: {}", path.display()))
+        })
+        .collect::<Result<Vec<GrayImage>>>()?;
+    
+    let width = pages.iter().map(|p| p.width()).max().unwrap_or(0);
+    let height = pages.iter().map(|p| p.height()).sum();
+    let mut stitched = GrayImage::from_pixel(width, height, Luma([255]));
+    let mut top = 0;
+    for page in &pages {
+        imageops::replace(&mut stitched, page, 0, top);
+        top += i64::from(page.height());
+    }
+    
+    let output = paths[0].parent().unwrap_or(Path::new(".")).join(STITCHED_FILE);
+    stitched
+        .save(&output)
+        .with_context(|| format!("結合画像の保存に失敗: {}", output.display()))?;
+    
+    let size = std::fs::metadata(&output)?.len();
+    if size > max_bytes {
+        let _ = std::fs::remove_file(&output);
+        bail!(
+            "結合画像が大きすぎます ({:.1}MB、上限 {:.1}MB)",
+            size as f64 / 1024.0 / 1024.0,
+            max_bytes as f64 / 1024.0 / 1024.0
+        );
+    }
+    
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// テストごとの一時フォルダ
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("millsheet_stitch_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    /// 一色で塗ったページ画像を保存
+    fn write_page(dir: &Path, name: &str, width: u32, height: u32, value: u8) -> PathBuf {
+        let path = dir.join(name);
+        GrayImage::from_pixel(width, height, Luma([value])).save(&path).unwrap();
+        path
+    }
+    
+    #[test]
+    fn stitches_pages_in_order_and_pads_narrow_pages_with_white() {
+        let dir = temp_dir("order");
+        let pages = vec![
+            write_page(&dir, "page-1.png", 4, 2, 10),
+            write_page(&dir, "page-2.png", 6, 3, 20),
+            write_page(&dir, "page-3.png", 2, 1, 30),
+        ];
+        
+        let output = stitch_pages(&pages).unwrap();
+        assert_eq!(output, dir.join(STITCHED_FILE));
+        let stitched = image::open(&output).unwrap().to_luma8();
+        // 幅は最も広いページ、高さは全ページの合計
+        assert_eq!(stitched.dimensions(), (6, 6));
+        
+        // ページは上から順に並ぶ
+        let left_column: Vec<u8> = (0..6).map(|y| stitched.get_pixel(0, y)[0]).collect();
+        assert_eq!(left_column, [10, 10, 20, 20, 20, 30]);
+        // 狭いページは左寄せで、右側の余白は白
+        assert_eq!(stitched.get_pixel(3, 0)[0], 10);
+        assert_eq!(stitched.get_pixel(4, 0)[0], 255);
+        assert_eq!(stitched.get_pixel(5, 1)[0], 255);
+        assert_eq!(stitched.get_pixel(5, 4)[0], 20);
+        assert_eq!(stitched.get_pixel(2, 5)[0], 255);
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn rejects_empty_and_too_many_pages() {
+        let dir = temp_dir("count");
+        assert!(stitch_pages(&[]).is_err());
+        
+        let pages: Vec<PathBuf> = (0..=MAX_STITCHED_PAGES)
+            .map(|i| write_page(&dir, &format!("page-{}.png", i + 1), 2, 2, 0))
+            .collect();
+        let err = stitch_pages(&pages).unwrap_err();
+        assert!(err.to_string().contains("ページまで"));
+        assert!(!dir.join(STITCHED_FILE).exists());
+        
+        // 上限ちょうどのページ数なら結合できる
+        assert!(stitch_pages(&pages[..MAX_STITCHED_PAGES]).is_ok());
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn rejects_and_removes_stitched_image_over_size_limit() {
+        let dir = temp_dir("size");
+        let pages = vec![
+            write_page(&dir, "page-1.png", 8, 8, 0),
+            write_page(&dir, "page-2.png", 8, 8, 255),
+        ];
+        
+        let err = stitch_pages_within(&pages, 16).unwrap_err();
+        assert!(err.to_string().contains("大きすぎます"));
+        // 上限を超えた結合画像は残さない
+        assert!(!dir.join(STITCHED_FILE).exists());
+        
+        // 既定の上限（MAX_STITCHED_BYTES）には収まる
+        assert!(stitch_pages(&pages).is_ok());
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
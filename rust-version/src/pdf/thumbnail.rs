@@ -0,0 +1,53 @@
+//! サムネイル生成 - 処理待ちファイルの1ページ目を低解像度で確認用に描く
+
+use super::{first_page_image, run_pdftoppm};
+use crate::processing::is_image_file;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// サムネイル用に変換するときの解像度（OCR用の300DPIよりずっと軽い）
+pub const THUMBNAIL_DPI: u32 = 24;
+
+/// サムネイルの長辺の最大ピクセル数
+pub const THUMBNAIL_MAX_SIZE: u32 = 160;
+
+/// 1ページ目のサムネイル（RGBA、非乗算アルファ）
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    /// 幅（ピクセル）
+    pub width: u32,
+    /// 高さ（ピクセル）
+    pub height: u32,
+    /// 左上から行順に並んだ RGBA の画素
+    pub rgba: Vec<u8>,
+}
+
+/// PDFの1ページ目を低解像度で変換し、長辺が `THUMBNAIL_MAX_SIZE` 以内のサムネイルにする
+///
+/// 本処理の変換とは別に一時フォルダを作り、読み込んだら消す。画像ファイルは変換せずにそのまま縮小する
+pub fn render_thumbnail(pdf_path: impl AsRef<Path>, password: Option<&str>) -> Result<Thumbnail> {
+    let pdf_path = pdf_path.as_ref();
+    if is_image_file(pdf_path) {
+        let image = image::open(pdf_path).with_context(|| format!("サムネイルの読み込みに失敗: {}", pdf_path.display()))?;
+        return Ok(Thumbnail::from_image(image));
+    }
+    let temp_dir = run_pdftoppm(pdf_path, password, 1, 1, THUMBNAIL_DPI)?;
+    let loaded = first_page_image(&temp_dir).and_then(|path| {
+        image::open(&path).with_context(|| format!("サムネイルの読み込みに失敗: {}", pdf_path.display()))
+    });
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    
+    Ok(Thumbnail::from_image(loaded?))
+}
+
+impl Thumbnail {
+    /// 長辺が `THUMBNAIL_MAX_SIZE` 以内になるよう縮小して作成
+    fn from_image(image: image::DynamicImage) -> Self {
+        let image = image.thumbnail(THUMBNAIL_MAX_SIZE, THUMBNAIL_MAX_SIZE).to_rgba8();
+        Self {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.into_raw(),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! OCR結果のキャッシュ - 同じファイルを再処理するときにAPIを呼び直さない
+//!
+//! ファイル内容のハッシュとページの読み方で引くので、リネーム後や別フォルダに移したファイルにも効く
+
+use crate::ocr::OcrText;
+use crate::processing::{content_hash, PageMode};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// OCRテキスト（レイアウトを再構成したテキストを含む）のキャッシュ（複製しても中身は共有する）
+#[derive(Debug, Clone, Default)]
+pub struct OcrCache {
+    entries: Arc<Mutex<HashMap<(String, PageMode), OcrText>>>,
+}
+
+impl OcrCache {
+    /// キャッシュ済みのOCRテキスト
+    pub fn get(&self, pdf_path: &Path, page_mode: PageMode) -> Option<OcrText> {
+        let hash = content_hash(pdf_path)?;
+        self.entries.lock().ok()?.get(&(hash, page_mode)).cloned()
+    }
+    
+    /// OCRテキストを記録
+    pub fn insert(&self, pdf_path: &Path, page_mode: PageMode, text: &OcrText) {
+        let Some(hash) = content_hash(pdf_path) else {
+            return;
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert((hash, page_mode), text.clone());
+        }
+    }
+    
+    /// ファイルがキャッシュ済みか
+    pub fn contains(&self, pdf_path: &Path, page_mode: PageMode) -> bool {
+        self.get(pdf_path, page_mode).is_some()
+    }
+    
+    /// キャッシュ済みの件数
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|entries| entries.len()).unwrap_or(0)
+    }
+    
+    /// キャッシュが空か
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// すべて破棄
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! 処理のキャンセル - 未処理分を破棄し、処理済みの結果は残す
+//!
+//! 処理中だった1件は、リネーム前にキャンセルを検知すれば破棄（未処理として数える）、
+//! リネーム済みなら結果に含める。
+
+use crate::processing::ProcessResult;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// キャンセルにより破棄した結果のエラー文言
+pub const CANCELLED_ERROR: &str = "キャンセルにより未処理";
+
+/// 処理のキャンセル要求（複製したものは同じ要求を共有する）
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// キャンセルされていない要求を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// キャンセルを要求
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+    
+    /// キャンセルが要求されたか
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl ProcessResult {
+    /// リネーム前にキャンセルされて破棄する結果か
+    pub fn is_cancelled(&self) -> bool {
+        !self.success && self.error.as_deref() == Some(CANCELLED_ERROR)
+    }
+}
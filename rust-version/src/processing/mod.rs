@@ -0,0 +1,459 @@
+//! 処理パイプライン - PDF変換 → OCR → 解析 → リネーム
+
+pub mod cache;
+pub mod cancel;
+pub mod pipeline;
+pub mod plan;
+pub mod queue;
+pub mod rescan;
+pub mod sequence;
+pub mod tags;
+pub mod temp_images;
+pub mod upload;
+pub mod warmup;
+
+pub use crate::hash::fnv1a_hex;
+pub use cache::OcrCache;
+pub use cancel::{CancelToken, CANCELLED_ERROR};
+pub use pipeline::Pipeline;
+pub use plan::{execute_rename, plan_rename, plan_rename_as, plan_rename_into, validate_plans, RenamePlan};
+pub use sequence::{SequenceCounter, SequenceMode};
+pub use temp_images::TempImages;
+pub use upload::{upload_processed, UploadMode, UploadOptions, UploadStatus};
+pub use warmup::{is_warmed_up, warmup, warmup_with};
+
+use crate::parser::template::FilenameTemplate;
+use crate::parser::{FilenameOptions, FilenameRules, MillsheetInfo, ParseOptions, SubdirRule};
+use crate::ocr::OcrBackend;
+use anyhow::{bail, Context, Result};
+use pipeline::{FsRenamer, Renamer};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 処理段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStage {
+    /// PDF→画像変換
+    Convert,
+    /// テキスト抽出 (OCR)
+    Ocr,
+    /// テキスト解析
+    Parse,
+    /// リネーム
+    Rename,
+}
+
+impl fmt::Display for ProcessStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProcessStage::Convert => "PDF変換",
+            ProcessStage::Ocr => "テキスト抽出",
+            ProcessStage::Parse => "テキスト解析",
+            ProcessStage::Rename => "リネーム",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 段階別タイムアウト
+#[derive(Debug, Clone)]
+pub struct StageTimeouts {
+    /// PDF→画像変換
+    pub convert: Duration,
+    /// OCR (ネットワーク往復を含むため長め)
+    pub ocr: Duration,
+    /// テキスト解析
+    pub parse: Duration,
+}
+
+impl Default for StageTimeouts {
+    fn default() -> Self {
+        Self {
+            convert: Duration::from_secs(30),
+            ocr: Duration::from_secs(90),
+            parse: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Vision APIのレート制限を考慮した同時実行数の上限
+pub const MAX_CONCURRENCY: usize = 8;
+
+/// 1ファイルの処理で見込むメモリ量（300DPI画像 + base64エンコード分）
+const MEMORY_PER_TASK: u64 = 256 * 1024 * 1024;
+
+/// OCRで文字を読み取れなかったときのエラー（再スキャン推奨の判定に使う）
+pub const NO_TEXT_ERROR: &str = "テキストを抽出できませんでした";
+
+/// 複数ページのPDFのOCRの仕方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PageMode {
+    /// 1ページ目のみ
+    #[default]
+    FirstPage,
+    /// 各ページを個別にOCRしてテキストを連結（ページ数分APIを呼ぶ）
+    EachPage,
+    /// 縦に結合した1枚の画像を1回でOCR（結合できなければ個別にOCR）
+    Stitched,
+    /// 先頭から `MAX_OCR_PAGES` ページまでを個別にOCR・解析し、最も多くのフィールドを抽出できたページを採用
+    BestPage,
+    /// 指定の1ページのみ（1始まり、材料情報が決まったページにあるフォーマット向け）
+    Page(usize),
+}
+
+impl PageMode {
+    /// 全モード（設定画面の選択肢順、ページ番号を別に入力する `Page` は含まない）
+    pub const ALL: [PageMode; 4] = [PageMode::FirstPage, PageMode::EachPage, PageMode::Stitched, PageMode::BestPage];
+    
+    /// 表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            PageMode::FirstPage => "1ページ目のみ",
+            PageMode::EachPage => "全ページを個別に",
+            PageMode::Stitched => "全ページを結合して1回で",
+            PageMode::BestPage => "全ページから最良のページを採用",
+            PageMode::Page(_) => "指定ページのみ",
+        }
+    }
+}
+
+/// 処理オプション
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingOptions {
+    /// 解析オプション
+    pub parse: ParseOptions,
+    /// 段階別タイムアウト
+    pub timeouts: StageTimeouts,
+    /// 同時実行数（未指定ならシステムリソースから自動決定）
+    pub concurrency: Option<usize>,
+    /// 総合信頼度がこの値未満なら自動リネームせず確認待ちにする（None なら常に自動リネーム）
+    pub review_threshold: Option<f32>,
+    /// ファイル名テンプレート（None なら既定の命名）
+    pub filename_template: Option<FilenameTemplate>,
+    /// テンプレートの `{seq}` に埋め込む通し番号のカウンタ（None なら `{seq}` は空）
+    pub sequence: Option<SequenceCounter>,
+    /// 既定の命名の区切り文字・1フィールドの最大文字数（テンプレート使用時は使わない）
+    pub filename: FilenameOptions,
+    /// 命名規則（None なら検証しない）
+    pub filename_rules: Option<FilenameRules>,
+    /// 命名規則に違反してもリネームする（違反はログに残す）
+    pub allow_rule_violations: bool,
+    /// リネーム後に振り分けるサブフォルダの決め方（振り分けに失敗したら元のフォルダでリネーム）
+    pub subdir_rule: SubdirRule,
+    /// 複数ページのOCRの仕方（1ページ目以外は先頭から `MAX_STITCHED_PAGES`、最良ページの採用は `MAX_OCR_PAGES` ページまで）
+    pub page_mode: PageMode,
+    /// PDFを画像に変換するときの解像度（None なら `DEFAULT_CONVERT_DPI`）
+    pub convert_dpi: Option<u32>,
+    /// 単語の座標から見た目の行を再構成したテキストでも解析し、抽出できた項目が多い方を採用（座標を返す Google Vision のみ）
+    pub preserve_layout: bool,
+    /// OCR結果のキャッシュ（None ならキャッシュしない）
+    pub ocr_cache: Option<OcrCache>,
+    /// 1バッチのファイル数（None なら分割しない）。バッチごとに一時ファイルを片付けてから次へ進む
+    pub batch_size: Option<usize>,
+    /// 変換してまだ片付けていない画像の記録（None なら記録しない）。バッチの区切りで片付け漏れを消すのに使う
+    pub temp_images: Option<TempImages>,
+    /// キャンセル要求（None ならキャンセルしない）。リネーム前に検知した1件は破棄する
+    pub cancel: Option<CancelToken>,
+    /// リネーム後のアップロード先（None ならアップロードしない）
+    pub upload: Option<UploadOptions>,
+}
+
+impl ProcessingOptions {
+    /// CPUコア数と利用可能メモリから既定の同時実行数を決定
+    pub fn auto_concurrency() -> usize {
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        let by_memory = match system.available_memory() {
+            0 => cores,
+            available => (available / MEMORY_PER_TASK) as usize,
+        };
+        
+        cores.min(by_memory).clamp(1, MAX_CONCURRENCY)
+    }
+    
+    /// 実際に使う同時実行数（手動設定を優先）
+    pub fn effective_concurrency(&self) -> usize {
+        match self.concurrency {
+            Some(n) => {
+                let n = n.clamp(1, MAX_CONCURRENCY);
+                tracing::info!("同時実行数: {} (手動設定)", n);
+                n
+            }
+            None => {
+                let n = Self::auto_concurrency();
+                tracing::info!("同時実行数: {} (自動決定)", n);
+                n
+            }
+        }
+    }
+}
+
+/// 処理結果
+#[derive(Clone, Default)]
+pub struct ProcessResult {
+    pub success: bool,
+    /// 処理対象のパス
+    pub source_path: PathBuf,
+    pub original: String,
+    pub new_name: Option<String>,
+    /// リネーム後のパス
+    pub new_path: Option<PathBuf>,
+    pub error: Option<String>,
+    pub parsed: Option<MillsheetInfo>,
+    /// 失敗した段階
+    pub failed_stage: Option<ProcessStage>,
+    /// タイムアウトによる失敗かどうか
+    pub timed_out: bool,
+    /// 確認待ち（信頼度不足で未リネーム、new_name は提案名）
+    pub pending_review: bool,
+    /// 手動確認を経たかどうか（確認待ち・確定済みの両方）
+    pub manual_review: bool,
+    /// ユーザーが付けたタグ（「要再検査」「保留」など）
+    pub tags: Vec<String>,
+    /// リネーム後のアップロードの状況（アップロードしていなければ None）
+    pub upload: Option<UploadStatus>,
+}
+
+impl ProcessResult {
+    /// 失敗結果を作成
+    fn failure(source: &Path, stage: ProcessStage, error: String, parsed: Option<MillsheetInfo>) -> Self {
+        Self {
+            success: false,
+            source_path: source.to_path_buf(),
+            original: file_name_of(source),
+            new_name: None,
+            new_path: None,
+            error: Some(error),
+            parsed,
+            failed_stage: Some(stage),
+            timed_out: false,
+            pending_review: false,
+            manual_review: false,
+            tags: Vec::new(),
+            upload: None,
+        }
+    }
+    
+    /// タイムアウト結果を作成
+    fn timeout(source: &Path, stage: ProcessStage, limit: Duration) -> Self {
+        Self {
+            timed_out: true,
+            ..Self::failure(
+                source,
+                stage,
+                format!("{}がタイムアウトしました ({}秒)", stage, limit.as_secs()),
+                None,
+            )
+        }
+    }
+    
+    /// 計画を実行してリネーム結果を作成
+    fn from_plan(plan: RenamePlan) -> Self {
+        Self::from_plan_with(plan, &FsRenamer)
+    }
+    
+    /// 計画を指定のリネーム段階で実行してリネーム結果を作成
+    ///
+    /// サブフォルダへの振り分けに失敗したら、元のフォルダでリネームし直す
+    fn from_plan_with(plan: RenamePlan, renamer: &dyn Renamer) -> Self {
+        let plan = match renamer.rename(&plan) {
+            Ok(()) => plan,
+            Err(e) => {
+                let Some(fallback) = plan.in_source_dir() else {
+                    return Self::failure(&plan.source_path, ProcessStage::Rename, format!("{:#}", e), Some(plan.info));
+                };
+                tracing::warn!("振り分けに失敗したため元のフォルダでリネームします: {:#}", e);
+                if let Err(e) = renamer.rename(&fallback) {
+                    return Self::failure(&fallback.source_path, ProcessStage::Rename, format!("{:#}", e), Some(fallback.info));
+                }
+                fallback
+            }
+        };
+        
+        Self {
+            success: true,
+            original: plan.original(),
+            source_path: plan.source_path,
+            new_name: Some(plan.new_name),
+            new_path: Some(plan.new_path),
+            error: None,
+            parsed: Some(plan.info),
+            failed_stage: None,
+            timed_out: false,
+            pending_review: false,
+            manual_review: false,
+            tags: Vec::new(),
+            upload: None,
+        }
+    }
+    
+    /// リネーム前にキャンセルされた結果を作成（呼び出し側で破棄する）
+    fn cancelled(source: &Path) -> Self {
+        Self::failure(source, ProcessStage::Rename, CANCELLED_ERROR.to_string(), None)
+    }
+    
+    /// 確認待ち結果を作成
+    fn pending(source: &Path, proposed_name: String, parsed: MillsheetInfo) -> Self {
+        Self {
+            success: false,
+            source_path: source.to_path_buf(),
+            original: file_name_of(source),
+            new_name: Some(proposed_name),
+            new_path: None,
+            error: None,
+            parsed: Some(parsed),
+            failed_stage: None,
+            timed_out: false,
+            pending_review: true,
+            manual_review: true,
+            tags: Vec::new(),
+            upload: None,
+        }
+    }
+}
+
+/// パスからファイル名を取得
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown.pdf")
+        .to_string()
+}
+
+/// PDFに変換せずそのままOCRする画像の拡張子
+pub const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// 処理できる入力ファイルの拡張子（ファイル選択ダイアログ用）
+pub const INPUT_EXTENSIONS: [&str; 4] = ["pdf", "png", "jpg", "jpeg"];
+
+/// 処理できる入力ファイル（PDFまたは `IMAGE_EXTENSIONS` の画像）か（拡張子の大文字小文字は問わない）
+pub fn is_supported_input(path: &Path) -> bool {
+    has_extension_in(path, &INPUT_EXTENSIONS)
+}
+
+/// PDFに変換せずそのままOCRする画像ファイルか
+pub fn is_image_file(path: &Path) -> bool {
+    has_extension_in(path, &IMAGE_EXTENSIONS)
+}
+
+/// 拡張子が `extensions` のいずれかか（大文字小文字は問わない）
+fn has_extension_in(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.iter().any(|ext| e.eq_ignore_ascii_case(ext)))
+}
+
+/// 生成したファイル名（`.pdf`）の拡張子を元ファイルに合わせる（PDFならそのまま）
+pub fn with_source_extension(filename: &str, source: &Path) -> String {
+    if !is_image_file(source) {
+        return filename.to_string();
+    }
+    let ext = source.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let stem = Path::new(filename)
+        .extension()
+        .filter(|e| e.eq_ignore_ascii_case("pdf") || e.eq_ignore_ascii_case(ext))
+        .map_or(filename, |e| &filename[..filename.len() - e.len() - 1]);
+    format!("{}.{}", stem, ext)
+}
+
+/// 単一のPDFファイルを処理（暗号化PDFは password を指定、既定の段階で `Pipeline::process` を実行）
+///
+/// PNG/JPEG の画像はPDF変換を飛ばしてOCRし、元の拡張子を保つ。`options.upload` があればリネーム後にアップロードする
+pub async fn process_single_pdf(
+    pdf_path: &Path,
+    password: Option<String>,
+    ocr: Option<&dyn OcrBackend>,
+    options: &ProcessingOptions,
+) -> ProcessResult {
+    let result = Pipeline::new(ocr).process(pdf_path, password, options).await;
+    upload_processed(result, options).await
+}
+
+/// リネームせずにOCR・解析までを行い、リネーム計画を返す（試し処理用、失敗時は失敗結果）
+///
+/// `options.ocr_cache` があればOCRテキストを記録するので、後の本処理ではAPIを呼び直さない
+pub async fn preview_single_pdf(
+    pdf_path: &Path,
+    password: Option<String>,
+    ocr: Option<&dyn OcrBackend>,
+    options: &ProcessingOptions,
+) -> Result<RenamePlan, ProcessResult> {
+    Pipeline::new(ocr).preview(pdf_path, password, options).await
+}
+
+/// 確認待ちの結果を確定してリネーム（`options.subdir_rule` に従って振り分ける）
+pub fn confirm_pending(result: &ProcessResult, options: &ProcessingOptions) -> ProcessResult {
+    let (Some(new_filename), Some(info)) = (&result.new_name, &result.parsed) else {
+        return result.clone();
+    };
+    
+    let subdir = info.destination_subdir(options.subdir_rule);
+    let plan = plan_rename_into(&result.source_path, subdir.as_deref(), new_filename, info.clone());
+    let mut confirmed = ProcessResult::from_plan(plan);
+    confirmed.manual_review = true;
+    confirmed.tags = result.tags.clone();
+    confirmed
+}
+
+/// リネームを取り消して確認待ちに戻す
+pub fn revert_rename(result: &ProcessResult) -> Result<ProcessResult> {
+    let (Some(new_path), Some(info)) = (&result.new_path, &result.parsed) else {
+        bail!("リネームされていません: {}", result.original);
+    };
+    if result.source_path.exists() {
+        bail!("元のファイル名が既に存在します: {}", result.source_path.display());
+    }
+    
+    std::fs::rename(new_path, &result.source_path)
+        .with_context(|| format!("リネームの取り消しに失敗: {}", new_path.display()))?;
+    
+    let proposed_name = result
+        .new_name
+        .clone()
+        .unwrap_or_else(|| info.generate_filename(&result.original));
+    let mut pending = ProcessResult::pending(&result.source_path, proposed_name, info.clone());
+    pending.tags = result.tags.clone();
+    Ok(pending)
+}
+
+/// ファイル内容のハッシュ（FNV-1a 64bit の16進16桁、実行環境によらず同じ値になる）
+pub fn content_hash(path: &Path) -> Option<String> {
+    std::fs::read(path).ok().map(|bytes| fnv1a_hex(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn cancelled_processing_leaves_file_alone() {
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let options = ProcessingOptions { cancel: Some(cancel), ..Default::default() };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(process_single_pdf(Path::new("sample.pdf"), None, None, &options));
+        assert!(result.is_cancelled());
+        assert!(result.new_path.is_none());
+    }
+    
+    #[test]
+    fn supported_inputs_ignore_extension_case() {
+        assert!(is_supported_input(Path::new("scan.PDF")));
+        assert!(is_supported_input(Path::new("scan.Jpeg")));
+        assert!(!is_supported_input(Path::new("scan.tiff")));
+        assert!(is_image_file(Path::new("scan.png")));
+        assert!(!is_image_file(Path::new("scan.pdf")));
+    }
+    
+    #[test]
+    fn source_extension_is_kept_for_images() {
+        assert_eq!(with_source_extension("24-03-15_SS400.pdf", Path::new("scan.JPG")), "24-03-15_SS400.JPG");
+        assert_eq!(with_source_extension("24-03-15_SS400.pdf", Path::new("scan.pdf")), "24-03-15_SS400.pdf");
+        assert_eq!(with_source_extension("24-03-15_SS400", Path::new("scan.png")), "24-03-15_SS400.png");
+    }
+}
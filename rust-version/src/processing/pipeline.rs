@@ -0,0 +1,788 @@
+//! 処理パイプラインの段階 - 変換 → OCR → 解析 → 命名 → リネームを差し替え可能にする
+//!
+//! 各段階の責務:
+//! - `Converter`: PDFをOCRする画像に変換し、処理後に一時ファイルを片付ける
+//! - `OcrBackend`: 画像からテキストを抽出する（Google Vision / Azure）
+//! - `Parser`: テキストからミルシート情報を解析する
+//! - `Namer`: 解析結果から新しいファイル名・パスを決める（ファイルは変更しない）
+//! - `Renamer`: 決めたとおりにファイルをリネームする
+//!
+//! タイムアウト・OCRキャッシュ・キャンセル・命名規則・確認待ちの判定は `Pipeline` が段階の外側で行う。
+
+use crate::ocr::{OcrBackend, OcrText};
+use crate::parser::{MillsheetInfo, ParseOptions};
+use crate::pdf::{
+    cleanup_temp_image, convert_pdf_all_pages, convert_pdf_page_with, convert_pdf_pages, convert_pdf_to_image_with,
+    stitch_pages, DEFAULT_CONVERT_DPI, MAX_STITCHED_PAGES,
+};
+use crate::processing::{
+    execute_rename, is_image_file, plan_rename, CancelToken, PageMode, ProcessResult, ProcessStage, ProcessingOptions,
+    RenamePlan, TempImages, NO_TEXT_ERROR,
+};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 最良ページの採用で、ページごとのOCRテキストを区切る改ページ文字（キャッシュにもこの形で残す）
+const PAGE_BREAK: &str = "\u{000C}";
+
+/// PDF→画像変換の段階（別スレッドで実行する）
+pub trait Converter: Send + Sync {
+    /// PDFをOCRする画像に `dpi` の解像度で変換（暗号化PDFは password を指定、成功時は空でない）
+    fn convert(&self, pdf_path: &Path, password: Option<&str>, page_mode: PageMode, dpi: u32) -> Result<Vec<PathBuf>>;
+    
+    /// OCRが終わった画像を片付ける（既定では何もしない）
+    fn cleanup(&self, _images: &[PathBuf]) {}
+}
+
+/// テキスト解析の段階（別スレッドで実行する）
+pub trait Parser: Send + Sync {
+    /// テキストからミルシート情報を解析
+    fn parse(&self, text: &str, options: &ParseOptions) -> MillsheetInfo;
+}
+
+/// 命名の段階（ファイルは変更しない）
+pub trait Namer: Send + Sync {
+    /// 解析結果から新しいファイル名・パスを決め、命名規則の違反を記録した計画を返す
+    fn plan(&self, pdf_path: &Path, info: MillsheetInfo, options: &ProcessingOptions) -> RenamePlan;
+}
+
+/// リネームの段階
+pub trait Renamer: Send + Sync {
+    /// 計画どおりにリネーム
+    fn rename(&self, plan: &RenamePlan) -> Result<()>;
+}
+
+/// pdftoppm で変換する既定の実装（結合モードでは結合画像まで作る）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PdfConverter;
+
+impl Converter for PdfConverter {
+    fn convert(&self, pdf_path: &Path, password: Option<&str>, page_mode: PageMode, dpi: u32) -> Result<Vec<PathBuf>> {
+        match page_mode {
+            PageMode::FirstPage => return convert_pdf_to_image_with(pdf_path, password, dpi).map(|path| vec![path]),
+            PageMode::BestPage => return convert_pdf_all_pages(pdf_path, password, dpi),
+            PageMode::Page(page) => return convert_pdf_page_with(pdf_path, password, page, dpi).map(|path| vec![path]),
+            PageMode::EachPage | PageMode::Stitched => {}
+        }
+        
+        let pages = convert_pdf_pages(pdf_path, password, MAX_STITCHED_PAGES, dpi)?;
+        Ok(stitch_if_requested(page_mode, pages))
+    }
+    
+    fn cleanup(&self, images: &[PathBuf]) {
+        // ページ画像はすべて同じ一時フォルダに置かれる
+        if let Some(first) = images.first() {
+            cleanup_temp_image(first);
+        }
+    }
+}
+
+/// 結合モードで複数ページなら1枚に結合する（結合できなければページごとのまま）
+fn stitch_if_requested(page_mode: PageMode, pages: Vec<PathBuf>) -> Vec<PathBuf> {
+    if page_mode != PageMode::Stitched || pages.len() == 1 {
+        return pages;
+    }
+    
+    match stitch_pages(&pages) {
+        Ok(stitched) => vec![stitched],
+        Err(e) => {
+            tracing::warn!("ページを結合できないため個別にOCRします: {:#}", e);
+            pages
+        }
+    }
+}
+
+/// 組み込みのルールで解析する既定の実装
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultParser;
+
+impl Parser for DefaultParser {
+    fn parse(&self, text: &str, options: &ParseOptions) -> MillsheetInfo {
+        MillsheetInfo::parse_with_options(text, options)
+    }
+}
+
+/// テンプレート・命名規則に従う既定の実装（`plan_rename`）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultNamer;
+
+impl Namer for DefaultNamer {
+    fn plan(&self, pdf_path: &Path, info: MillsheetInfo, options: &ProcessingOptions) -> RenamePlan {
+        plan_rename(pdf_path, info, options)
+    }
+}
+
+/// ファイルシステム上でリネームする既定の実装（`execute_rename`）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsRenamer;
+
+impl Renamer for FsRenamer {
+    fn rename(&self, plan: &RenamePlan) -> Result<()> {
+        execute_rename(plan)
+    }
+}
+
+/// 各段階を組み合わせて1ファイルを処理する
+///
+/// 変換・解析は別スレッドで実行するため `Arc` で持つ。OCRは呼び出し側のバックエンドを借りる
+pub struct Pipeline<'a> {
+    converter: Arc<dyn Converter>,
+    ocr: Option<&'a dyn OcrBackend>,
+    parser: Arc<dyn Parser>,
+    namer: Arc<dyn Namer>,
+    renamer: Arc<dyn Renamer>,
+}
+
+impl<'a> Pipeline<'a> {
+    /// 既定の実装でパイプラインを作成（OCRバックエンドが None なら OCR 段階で失敗する）
+    pub fn new(ocr: Option<&'a dyn OcrBackend>) -> Self {
+        Self {
+            converter: Arc::new(PdfConverter),
+            ocr,
+            parser: Arc::new(DefaultParser),
+            namer: Arc::new(DefaultNamer),
+            renamer: Arc::new(FsRenamer),
+        }
+    }
+    
+    /// 変換の段階を差し替え
+    pub fn with_converter(mut self, converter: Arc<dyn Converter>) -> Self {
+        self.converter = converter;
+        self
+    }
+    
+    /// 解析の段階を差し替え
+    pub fn with_parser(mut self, parser: Arc<dyn Parser>) -> Self {
+        self.parser = parser;
+        self
+    }
+    
+    /// 命名の段階を差し替え
+    pub fn with_namer(mut self, namer: Arc<dyn Namer>) -> Self {
+        self.namer = namer;
+        self
+    }
+    
+    /// リネームの段階を差し替え
+    pub fn with_renamer(mut self, renamer: Arc<dyn Renamer>) -> Self {
+        self.renamer = renamer;
+        self
+    }
+    
+    /// 1ファイルを処理（暗号化PDFは password を指定）
+    ///
+    /// キャンセルされていればファイルには触れない。アップロードはしない（枠を空けてから `upload_processed` で行う）
+    pub async fn process(&self, pdf_path: &Path, password: Option<String>, options: &ProcessingOptions) -> ProcessResult {
+        let is_cancelled = || options.cancel.as_ref().is_some_and(CancelToken::is_cancelled);
+        if is_cancelled() {
+            return ProcessResult::cancelled(pdf_path);
+        }
+        
+        let plan = match self.preview(pdf_path, password, options).await {
+            Ok(plan) => plan,
+            Err(failure) => return failure,
+        };
+        
+        // ここより後はリネームまで進むので、キャンセルはこの時点で検知する
+        if is_cancelled() {
+            tracing::info!("リネーム前にキャンセルされたため破棄: {}", pdf_path.display());
+            return ProcessResult::cancelled(pdf_path);
+        }
+        
+        // 命名規則に違反していれば、違反を許容しない設定ならリネームを中止
+        if !plan.violations.is_empty() {
+            let reason = format!("命名規則違反 ({}): {}", plan.proposed_name, plan.violations.join(" / "));
+            if !options.allow_rule_violations {
+                return ProcessResult::failure(pdf_path, ProcessStage::Rename, reason, Some(plan.info));
+            }
+            tracing::warn!("{}", reason);
+        }
+        
+        // 総合信頼度が閾値未満なら確認待ちにする
+        if let Some(threshold) = options.review_threshold
+            && plan.info.overall_confidence() < threshold
+        {
+            return ProcessResult::pending(pdf_path, plan.proposed_name, plan.info);
+        }
+        
+        ProcessResult::from_plan_with(plan, self.renamer.as_ref())
+    }
+    
+    /// リネームせずにOCR・解析・命名までを行い、リネーム計画を返す（失敗時は失敗結果）
+    ///
+    /// `options.ocr_cache` があればOCRテキストを記録するので、後の本処理ではAPIを呼び直さない
+    pub async fn preview(
+        &self,
+        pdf_path: &Path,
+        password: Option<String>,
+        options: &ProcessingOptions,
+    ) -> Result<RenamePlan, ProcessResult> {
+        // レイアウトを再構成したテキストが必要なのに記録されていなければ、OCRし直す
+        let cached = options
+            .ocr_cache
+            .as_ref()
+            .and_then(|cache| cache.get(pdf_path, options.page_mode))
+            .filter(|cached| !options.preserve_layout || cached.layout_text.is_some());
+        let ocr_text = match cached {
+            Some(ocr_text) => {
+                tracing::info!("OCRキャッシュを使用: {}", pdf_path.display());
+                ocr_text
+            }
+            None => {
+                let ocr_text = self.ocr_pdf(pdf_path, password, options).await?;
+                if let Some(ref cache) = options.ocr_cache {
+                    cache.insert(pdf_path, options.page_mode, &ocr_text);
+                }
+                ocr_text
+            }
+        };
+        
+        // テキスト解析（レイアウト保持なら再構成したテキストでも解析し、抽出できた項目が多い方を採用）
+        // 最良ページの採用ではページごとに解析し、抽出できた項目が最も多いページを採用する
+        let parser = self.parser.clone();
+        let parse_options = options.parse.clone();
+        let layout_text = ocr_text.layout_text.filter(|_| options.preserve_layout);
+        let best_page = options.page_mode == PageMode::BestPage;
+        let source = pdf_path.to_path_buf();
+        let parsed = tokio::time::timeout(
+            options.timeouts.parse,
+            tokio::task::spawn_blocking(move || {
+                let parse_page = |text: &str, layout_text: Option<&str>| {
+                    let info = parser.parse(text, &parse_options);
+                    match layout_text {
+                        Some(layout_text) => MillsheetInfo::better_of(info, parser.parse(layout_text, &parse_options)),
+                        None => info,
+                    }
+                };
+                if !best_page {
+                    return parse_page(&ocr_text.text, layout_text.as_deref());
+                }
+                
+                // 再構成したテキストは元のテキストと同じページ数で連結されている
+                let mut layout_pages = layout_text.as_deref().map(|text| text.split(PAGE_BREAK));
+                let pages = ocr_text
+                    .text
+                    .split(PAGE_BREAK)
+                    .map(|text| parse_page(text, layout_pages.as_mut().and_then(Iterator::next)))
+                    .collect::<Vec<_>>();
+                let page_count = pages.len();
+                let (index, info) = MillsheetInfo::best_page(pages).unwrap_or_default();
+                tracing::info!("{} ページ中 {} ページ目の解析結果を採用: {}", page_count, index + 1, source.display());
+                info
+            }),
+        )
+        .await;
+        let info = match parsed {
+            Ok(Ok(info)) => info,
+            Ok(Err(e)) => {
+                return Err(ProcessResult::failure(pdf_path, ProcessStage::Parse, format!("解析エラー: {}", e), None));
+            }
+            Err(_) => return Err(ProcessResult::timeout(pdf_path, ProcessStage::Parse, options.timeouts.parse)),
+        };
+        
+        // ファイル名生成と命名規則の検証
+        Ok(self.namer.plan(pdf_path, info, options))
+    }
+    
+    /// PDFを画像に変換してOCR（画像ファイルは変換せずにOCR、空のテキストは失敗として扱う）
+    async fn ocr_pdf(
+        &self,
+        pdf_path: &Path,
+        password: Option<String>,
+        options: &ProcessingOptions,
+    ) -> Result<OcrText, ProcessResult> {
+        let Some(ocr) = self.ocr else {
+            return Err(ProcessResult::failure(
+                pdf_path,
+                ProcessStage::Ocr,
+                "OCRバックエンドが初期化されていません".to_string(),
+                None,
+            ));
+        };
+        
+        let timeouts = &options.timeouts;
+        let page_mode = options.page_mode;
+        
+        // 画像ファイルはそのままOCRする（元ファイルなので片付けない）
+        let images = if is_image_file(pdf_path) {
+            ConvertedImages {
+                converter: None,
+                temp_images: None,
+                paths: vec![pdf_path.to_path_buf()],
+            }
+        } else {
+            let paths = self.convert_pdf(pdf_path, password, options).await?;
+            if let Some(ref temp_images) = options.temp_images {
+                temp_images.record(&paths);
+            }
+            ConvertedImages {
+                converter: Some(self.converter.as_ref()),
+                temp_images: options.temp_images.as_ref(),
+                paths,
+            }
+        };
+        
+        // テキスト抽出（途中で失敗・タイムアウト・キャンセルしてもページ画像は片付ける）
+        let separator = if page_mode == PageMode::BestPage { PAGE_BREAK } else { "\n" };
+        let extracted = tokio::time::timeout(
+            timeouts.ocr,
+            extract_pages_text(ocr, &images.paths, options.preserve_layout, separator),
+        )
+        .await;
+        drop(images);
+        let text = match extracted {
+            Ok(Ok(text)) => text,
+            Ok(Err(e)) => {
+                return Err(ProcessResult::failure(pdf_path, ProcessStage::Ocr, format!("テキスト抽出エラー: {}", e), None));
+            }
+            Err(_) => return Err(ProcessResult::timeout(pdf_path, ProcessStage::Ocr, timeouts.ocr)),
+        };
+        
+        if text.text.is_empty() {
+            return Err(ProcessResult::failure(
+                pdf_path,
+                ProcessStage::Ocr,
+                NO_TEXT_ERROR.to_string(),
+                None,
+            ));
+        }
+        
+        Ok(text)
+    }
+    
+    /// PDFを画像に変換（タイムアウトで待つのをやめた場合も、変換された画像は片付ける）
+    async fn convert_pdf(
+        &self,
+        pdf_path: &Path,
+        password: Option<String>,
+        options: &ProcessingOptions,
+    ) -> Result<Vec<PathBuf>, ProcessResult> {
+        let timeouts = &options.timeouts;
+        let converter = self.converter.clone();
+        let convert_path = pdf_path.to_path_buf();
+        let page_mode = options.page_mode;
+        let dpi = options.convert_dpi.unwrap_or(DEFAULT_CONVERT_DPI);
+        let handoff = Arc::new(Mutex::new(Handoff::default()));
+        let converter_handoff = handoff.clone();
+        let converted = tokio::time::timeout(
+            timeouts.convert,
+            tokio::task::spawn_blocking(move || {
+                let converted = converter.convert(&convert_path, password.as_deref(), page_mode, dpi);
+                if let Ok(ref images) = converted {
+                    converter_handoff.lock().unwrap_or_else(|e| e.into_inner()).deliver(converter.as_ref(), images);
+                }
+                converted
+            }),
+        )
+        .await;
+        if converted.is_err() {
+            handoff.lock().unwrap_or_else(|e| e.into_inner()).abandon(self.converter.as_ref());
+        }
+        match converted {
+            Ok(Ok(Ok(paths))) if !paths.is_empty() => Ok(paths),
+            Ok(Ok(Ok(_))) => Err(ProcessResult::failure(
+                pdf_path,
+                ProcessStage::Convert,
+                "PDF変換エラー: 画像がありません".to_string(),
+                None,
+            )),
+            Ok(Ok(Err(e))) => Err(ProcessResult::failure(pdf_path, ProcessStage::Convert, format!("PDF変換エラー: {}", e), None)),
+            Ok(Err(e)) => Err(ProcessResult::failure(pdf_path, ProcessStage::Convert, format!("PDF変換エラー: {}", e), None)),
+            Err(_) => Err(ProcessResult::timeout(pdf_path, ProcessStage::Convert, timeouts.convert)),
+        }
+    }
+}
+
+/// 変換した画像を待たずにタイムアウトしたとき、変換側と待つ側のうち後から来た方が画像を片付けるための受け渡し
+#[derive(Debug, Default)]
+struct Handoff {
+    /// 待つ側がタイムアウトであきらめたか
+    abandoned: bool,
+    /// あきらめる前に変換が終わっていれば、その画像
+    images: Option<Vec<PathBuf>>,
+}
+
+impl Handoff {
+    /// 変換側: 変換した画像を渡す（待つ側があきらめていれば片付ける）
+    fn deliver(&mut self, converter: &dyn Converter, images: &[PathBuf]) {
+        if self.abandoned {
+            converter.cleanup(images);
+        } else {
+            self.images = Some(images.to_vec());
+        }
+    }
+    
+    /// 待つ側: タイムアウトであきらめる（画像が届いていれば片付ける）
+    fn abandon(&mut self, converter: &dyn Converter) {
+        self.abandoned = true;
+        if let Some(images) = self.images.take() {
+            converter.cleanup(&images);
+        }
+    }
+}
+
+/// OCR中のページ画像（捨てるときに変換の段階で片付ける、変換していない入力画像はそのまま）
+struct ConvertedImages<'a> {
+    converter: Option<&'a dyn Converter>,
+    /// 片付けたら記録から外す
+    temp_images: Option<&'a TempImages>,
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for ConvertedImages<'_> {
+    fn drop(&mut self) {
+        if let Some(converter) = self.converter {
+            converter.cleanup(&self.paths);
+        }
+        if let Some(temp_images) = self.temp_images {
+            temp_images.release(&self.paths);
+        }
+    }
+}
+
+/// 画像を順にOCRしてテキストを `separator` で連結（`layout` ならレイアウトを再構成したテキストも連結）
+async fn extract_pages_text(ocr: &dyn OcrBackend, image_paths: &[PathBuf], layout: bool, separator: &str) -> Result<OcrText> {
+    let mut pages = Vec::with_capacity(image_paths.len());
+    for path in image_paths {
+        pages.push(if layout {
+            ocr.extract_text_with_layout(path).await?
+        } else {
+            OcrText {
+                text: ocr.extract_text(path).await?,
+                layout_text: None,
+            }
+        });
+    }
+    
+    // 再構成できなかったページは元のテキストで埋める
+    let layout_text = pages.iter().any(|page| page.layout_text.is_some()).then(|| {
+        pages
+            .iter()
+            .map(|page| page.layout_text.as_deref().unwrap_or(&page.text))
+            .collect::<Vec<_>>()
+            .join(separator)
+    });
+    let text = pages.into_iter().map(|page| page.text).collect::<Vec<_>>().join(separator);
+    Ok(OcrText { text, layout_text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::OcrFuture;
+    use crate::processing::CANCELLED_ERROR;
+    
+    /// 変換のたびに1枚の画像パスを返し、片付けた画像を数える
+    #[derive(Default)]
+    struct FakeConverter(Mutex<Vec<PathBuf>>);
+    
+    impl Converter for FakeConverter {
+        fn convert(&self, pdf_path: &Path, _: Option<&str>, _: PageMode, _: u32) -> Result<Vec<PathBuf>> {
+            Ok(vec![pdf_path.with_extension("png")])
+        }
+        
+        fn cleanup(&self, images: &[PathBuf]) {
+            self.0.lock().unwrap().extend_from_slice(images);
+        }
+    }
+    
+    /// 決まったテキストを返す（`fail` なら失敗する）
+    struct FakeOcr {
+        fail: bool,
+    }
+    
+    impl OcrBackend for FakeOcr {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+        
+        fn extract_text<'a>(&'a self, _: &'a Path) -> OcrFuture<'a> {
+            let fail = self.fail;
+            Box::pin(async move {
+                if fail {
+                    anyhow::bail!("OCRエラー");
+                }
+                Ok("2024年3月15日\nSS400\n9.0X1500X3000".to_string())
+            })
+        }
+    }
+    
+    /// リネームした名前を記録するだけ
+    #[derive(Default)]
+    struct RecordingRenamer(Mutex<Vec<String>>);
+    
+    impl Renamer for RecordingRenamer {
+        fn rename(&self, plan: &RenamePlan) -> Result<()> {
+            self.0.lock().unwrap().push(plan.new_name.clone());
+            Ok(())
+        }
+    }
+    
+    fn run(ocr: &FakeOcr, converter: Arc<FakeConverter>, options: &ProcessingOptions, files: &[&str]) -> Vec<ProcessResult> {
+        let pipeline = Pipeline::new(Some(ocr))
+            .with_converter(converter)
+            .with_renamer(Arc::new(RecordingRenamer::default()));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        files
+            .iter()
+            .map(|file| runtime.block_on(pipeline.process(Path::new(file), None, options)))
+            .collect()
+    }
+    
+    /// 変換に時間がかかり、片付けた画像を記録する
+    #[derive(Default)]
+    struct SlowConverter(Mutex<Vec<PathBuf>>);
+    
+    impl Converter for SlowConverter {
+        fn convert(&self, pdf_path: &Path, _: Option<&str>, _: PageMode, _: u32) -> Result<Vec<PathBuf>> {
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            Ok(vec![pdf_path.with_extension("png")])
+        }
+        
+        fn cleanup(&self, images: &[PathBuf]) {
+            self.0.lock().unwrap().extend_from_slice(images);
+        }
+    }
+    
+    #[test]
+    fn timed_out_conversion_cleans_up_after_it_finishes() {
+        let converter = Arc::new(SlowConverter::default());
+        let pipeline = Pipeline::new(Some(&FakeOcr { fail: false })).with_converter(converter.clone());
+        let mut options = ProcessingOptions::default();
+        options.timeouts.convert = std::time::Duration::from_millis(50);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        
+        let result = runtime.block_on(pipeline.process(Path::new("in/a.pdf"), None, &options));
+        assert!(result.timed_out);
+        assert_eq!(result.failed_stage, Some(ProcessStage::Convert));
+        assert!(converter.0.lock().unwrap().is_empty());
+        
+        // タイムアウト後も走っていた変換が終われば、変換側が画像を片付ける
+        std::thread::sleep(std::time::Duration::from_millis(600));
+        assert_eq!(*converter.0.lock().unwrap(), [PathBuf::from("in/a.png")]);
+    }
+    
+    #[test]
+    fn handoff_cleans_up_images_delivered_before_abandon() {
+        let converter = FakeConverter::default();
+        let mut handoff = Handoff::default();
+        handoff.deliver(&converter, &[PathBuf::from("t/page-1.png")]);
+        assert!(converter.0.lock().unwrap().is_empty());
+        handoff.abandon(&converter);
+        assert_eq!(*converter.0.lock().unwrap(), [PathBuf::from("t/page-1.png")]);
+    }
+    
+    #[test]
+    fn handoff_cleans_up_images_delivered_after_abandon() {
+        let converter = FakeConverter::default();
+        let mut handoff = Handoff::default();
+        handoff.abandon(&converter);
+        assert!(converter.0.lock().unwrap().is_empty());
+        handoff.deliver(&converter, &[PathBuf::from("t/page-1.png")]);
+        assert_eq!(*converter.0.lock().unwrap(), [PathBuf::from("t/page-1.png")]);
+    }
+    
+    #[test]
+    fn converted_images_do_not_accumulate_across_files() {
+        let temp_images = TempImages::default();
+        let options = ProcessingOptions {
+            temp_images: Some(temp_images.clone()),
+            ..Default::default()
+        };
+        let converter = Arc::new(FakeConverter::default());
+        let results = run(&FakeOcr { fail: false }, converter.clone(), &options, &["in/a.pdf", "in/b.pdf", "in/c.pdf"]);
+        
+        assert!(results.iter().all(|r| r.success));
+        assert_eq!(converter.0.lock().unwrap().len(), 3);
+        assert!(temp_images.is_empty());
+    }
+    
+    #[test]
+    fn converted_images_are_released_when_ocr_fails() {
+        let temp_images = TempImages::default();
+        let options = ProcessingOptions {
+            temp_images: Some(temp_images.clone()),
+            ..Default::default()
+        };
+        let converter = Arc::new(FakeConverter::default());
+        let results = run(&FakeOcr { fail: true }, converter.clone(), &options, &["in/a.pdf"]);
+        
+        assert_eq!(results[0].failed_stage, Some(ProcessStage::Ocr));
+        assert_eq!(*converter.0.lock().unwrap(), [PathBuf::from("in/a.png")]);
+        assert!(temp_images.is_empty());
+    }
+    
+    #[test]
+    fn input_images_are_neither_converted_nor_cleaned_up() {
+        let temp_images = TempImages::default();
+        let options = ProcessingOptions {
+            temp_images: Some(temp_images.clone()),
+            ..Default::default()
+        };
+        let converter = Arc::new(FakeConverter::default());
+        let results = run(&FakeOcr { fail: false }, converter.clone(), &options, &["in/scan.jpg"]);
+        
+        assert!(results[0].success);
+        assert_eq!(results[0].new_name.as_deref(), Some("24-03-15_SS400_9x1500x3000.jpg"));
+        assert!(converter.0.lock().unwrap().is_empty());
+        assert!(temp_images.is_empty());
+    }
+    
+    #[test]
+    fn pipeline_renames_with_injected_parts() {
+        let renamer = Arc::new(RecordingRenamer::default());
+        let pipeline = Pipeline::new(Some(&FakeOcr { fail: false }))
+            .with_converter(Arc::new(FakeConverter::default()))
+            .with_renamer(renamer.clone());
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(pipeline.process(Path::new("in/scan.pdf"), None, &ProcessingOptions::default()));
+        assert!(result.success);
+        assert_eq!(result.new_name.as_deref(), Some("24-03-15_SS400_9x1500x3000.pdf"));
+        assert_eq!(*renamer.0.lock().unwrap(), ["24-03-15_SS400_9x1500x3000.pdf"]);
+    }
+    
+    /// OCRの途中でキャンセルを要求する
+    struct CancellingOcr(CancelToken);
+    
+    impl OcrBackend for CancellingOcr {
+        fn name(&self) -> &'static str {
+            "cancelling"
+        }
+        
+        fn extract_text<'a>(&'a self, _: &'a Path) -> OcrFuture<'a> {
+            self.0.cancel();
+            Box::pin(async { Ok("2024年3月15日\nSS400\n9.0X1500X3000".to_string()) })
+        }
+    }
+    
+    /// リネームした直後にキャンセルを要求する
+    struct CancellingRenamer(CancelToken, RecordingRenamer);
+    
+    impl Renamer for CancellingRenamer {
+        fn rename(&self, plan: &RenamePlan) -> Result<()> {
+            self.1.rename(plan)?;
+            self.0.cancel();
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn cancel_after_preview_discards_result_before_rename() {
+        let cancel = CancelToken::new();
+        let renamer = Arc::new(RecordingRenamer::default());
+        let ocr = CancellingOcr(cancel.clone());
+        let pipeline = Pipeline::new(Some(&ocr))
+            .with_converter(Arc::new(FakeConverter::default()))
+            .with_renamer(renamer.clone());
+        let options = ProcessingOptions { cancel: Some(cancel), ..Default::default() };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(pipeline.process(Path::new("in/scan.pdf"), None, &options));
+        
+        // 未処理として数える結果で、リネームはしない
+        assert!(result.is_cancelled());
+        assert_eq!(result.error.as_deref(), Some(CANCELLED_ERROR));
+        assert!(result.new_path.is_none() && result.parsed.is_none());
+        assert!(renamer.0.lock().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn cancel_after_rename_keeps_result() {
+        let cancel = CancelToken::new();
+        let renamer = Arc::new(CancellingRenamer(cancel.clone(), RecordingRenamer::default()));
+        let pipeline = Pipeline::new(Some(&FakeOcr { fail: false }))
+            .with_converter(Arc::new(FakeConverter::default()))
+            .with_renamer(renamer.clone());
+        let options = ProcessingOptions { cancel: Some(cancel.clone()), ..Default::default() };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(pipeline.process(Path::new("in/scan.pdf"), None, &options));
+        
+        assert!(cancel.is_cancelled());
+        assert!(result.success && !result.is_cancelled());
+        assert_eq!(result.new_name.as_deref(), Some("24-03-15_SS400_9x1500x3000.pdf"));
+        assert_eq!(*renamer.1.0.lock().unwrap(), ["24-03-15_SS400_9x1500x3000.pdf"]);
+    }
+    
+    
+    /// ページごとの色と、そのページに書かれているテキスト
+    const PAGE_TEXTS: [(u8, &str); 2] = [(10, "2024年3月15日\nSS400"), (20, "9.0X1500X3000")];
+    
+    /// 一色で塗ったページ画像を書き出し、既定の実装と同じく結合モードでは結合する
+    struct PageImageConverter(PathBuf);
+    
+    impl Converter for PageImageConverter {
+        fn convert(&self, _: &Path, _: Option<&str>, page_mode: PageMode, _: u32) -> Result<Vec<PathBuf>> {
+            std::fs::create_dir_all(&self.0)?;
+            let pages = PAGE_TEXTS
+                .iter()
+                .enumerate()
+                .map(|(i, &(value, _))| {
+                    let path = self.0.join(format!("page-{}.png", i + 1));
+                    image::GrayImage::from_pixel(4 + i as u32, 3, image::Luma([value])).save(&path)?;
+                    Ok(path)
+                })
+                .collect::<Result<Vec<PathBuf>>>()?;
+            Ok(stitch_if_requested(page_mode, pages))
+        }
+    }
+    
+    /// 画像の左端を上から読み、ページの色ごとにそのページのテキストを返す（呼び出し回数を数える）
+    #[derive(Default)]
+    struct PageImageOcr(std::sync::atomic::AtomicUsize);
+    
+    impl OcrBackend for PageImageOcr {
+        fn name(&self) -> &'static str {
+            "page-image"
+        }
+        
+        fn extract_text<'a>(&'a self, image_path: &'a Path) -> OcrFuture<'a> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Box::pin(async move {
+                let image = image::open(image_path)?.to_luma8();
+                let mut lines = Vec::new();
+                let mut previous = None;
+                for y in 0..image.height() {
+                    let value = image.get_pixel(0, y)[0];
+                    if previous != Some(value) {
+                        previous = Some(value);
+                        if let Some(&(_, text)) = PAGE_TEXTS.iter().find(|(v, _)| *v == value) {
+                            lines.push(text);
+                        }
+                    }
+                }
+                Ok(lines.join("\n"))
+            })
+        }
+    }
+    
+    #[test]
+    fn stitched_pages_parse_like_each_page() {
+        let dir = std::env::temp_dir().join(format!("millsheet_pipeline_stitch_{}", std::process::id()));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut outcomes = Vec::new();
+        for page_mode in [PageMode::EachPage, PageMode::Stitched] {
+            let ocr = PageImageOcr::default();
+            let pipeline = Pipeline::new(Some(&ocr))
+                .with_converter(Arc::new(PageImageConverter(dir.join(format!("{:?}", page_mode)))))
+                .with_renamer(Arc::new(RecordingRenamer::default()));
+            let options = ProcessingOptions { page_mode, ..Default::default() };
+            let result = runtime.block_on(pipeline.process(Path::new("in/scan.pdf"), None, &options));
+            outcomes.push((result, ocr.0.into_inner()));
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+        
+        let (each_page, each_page_calls) = &outcomes[0];
+        let (stitched, stitched_calls) = &outcomes[1];
+        // 結合すればOCRは1回で済み、ページごとに読んだときと同じ結果になる
+        assert_eq!((*each_page_calls, *stitched_calls), (2, 1));
+        assert!(each_page.success && stitched.success);
+        assert_eq!(each_page.new_name.as_deref(), Some("24-03-15_SS400_9x1500x3000.pdf"));
+        assert_eq!(stitched.new_name, each_page.new_name);
+        let (each_page, stitched) = (each_page.parsed.as_ref().unwrap(), stitched.parsed.as_ref().unwrap());
+        assert_eq!(stitched.raw_text, each_page.raw_text);
+        assert_eq!(
+            (&stitched.material, &stitched.dimensions),
+            (&each_page.material, &each_page.dimensions)
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! リネーム計画 - 新しいパスの決定（プレビュー）と実行を分離する
+
+use crate::parser::{extract_coil_no, get_distinct_filename, validate_generated_filename_with, FilenameOptions, MillsheetInfo};
+use crate::processing::{content_hash, file_name_of, with_source_extension, ProcessingOptions};
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// リネームの計画（実行前に内容を検査できる）
+#[derive(Debug, Clone)]
+pub struct RenamePlan {
+    /// リネーム元のパス
+    pub source_path: PathBuf,
+    /// 生成したファイル名（重複回避前）
+    pub proposed_name: String,
+    /// 実際に使うファイル名（重複回避後）
+    pub new_name: String,
+    /// リネーム後のパス
+    pub new_path: PathBuf,
+    /// 提案名と衝突した既存ファイル（衝突しなければ None）
+    pub collision: Option<PathBuf>,
+    /// 命名規則の違反内容（規則未設定または違反なしなら空）
+    pub violations: Vec<String>,
+    /// 振り分け先のサブフォルダ（元のフォルダからの相対パス、振り分けなければ None）
+    pub subdir: Option<PathBuf>,
+    /// 解析結果
+    pub info: MillsheetInfo,
+}
+
+impl RenamePlan {
+    /// 提案名が既存ファイルと衝突したか
+    pub fn has_collision(&self) -> bool {
+        self.collision.is_some()
+    }
+    
+    /// 元のファイル名
+    pub fn original(&self) -> String {
+        file_name_of(&self.source_path)
+    }
+    
+    /// 振り分けずに元のフォルダでリネームする計画（振り分けに失敗したときのフォールバック、振り分けなければ None）
+    pub fn in_source_dir(&self) -> Option<RenamePlan> {
+        self.subdir.as_ref()?;
+        Some(RenamePlan {
+            violations: self.violations.clone(),
+            ..plan_rename_as(&self.source_path, &self.proposed_name, self.info.clone())
+        })
+    }
+}
+
+/// テンプレートと命名規則に従ってリネームを計画（ファイルは変更しない）
+///
+/// `options.subdir_rule` で振り分ける場合は、元のフォルダの下のサブフォルダをリネーム先にする
+pub fn plan_rename(pdf_path: &Path, info: MillsheetInfo, options: &ProcessingOptions) -> RenamePlan {
+    let original = file_name_of(pdf_path);
+    // テンプレートは区切り文字を自分で書くので、区切り文字・最大文字数の指定は既定の命名にだけ使う
+    let (new_filename, filename_options) = match options.filename_template {
+        Some(ref template) => {
+            let seq = if template.uses_sequence() { next_sequence(options) } else { None };
+            (template.render_with_sequence(&info, &original, seq), FilenameOptions::default())
+        }
+        None => (info.generate_filename_with(&original, &options.filename), options.filename),
+    };
+    let new_filename = with_source_extension(&new_filename, pdf_path);
+    
+    let violations = options
+        .filename_rules
+        .as_ref()
+        .and_then(|rules| validate_generated_filename_with(&new_filename, &info, rules, &filename_options).err())
+        .unwrap_or_default();
+    
+    let subdir = info.destination_subdir(options.subdir_rule);
+    RenamePlan {
+        violations,
+        ..plan_rename_into(pdf_path, subdir.as_deref(), &new_filename, info)
+    }
+}
+
+/// テンプレートの `{seq}` に埋め込む通し番号を採番（カウンタ未設定・採番失敗なら None）
+pub(crate) fn next_sequence(options: &ProcessingOptions) -> Option<u64> {
+    let counter = options.sequence.as_ref()?;
+    match counter.next() {
+        Ok(seq) => Some(seq),
+        Err(e) => {
+            tracing::warn!("通し番号を採番できません: {:#}", e);
+            None
+        }
+    }
+}
+
+/// 指定のファイル名で元のフォルダにリネームを計画（手動で修正した名前の確定用、命名規則は検証しない）
+pub fn plan_rename_as(pdf_path: &Path, new_filename: &str, info: MillsheetInfo) -> RenamePlan {
+    plan_rename_into(pdf_path, None, new_filename, info)
+}
+
+/// 元のフォルダの下のサブフォルダ（None なら元のフォルダ）へ指定のファイル名でリネームを計画
+///
+/// 同名ファイルがあれば連番より先にチャージ番号・コイル番号・内容ハッシュで区別を試みる。
+/// 画像ファイルのリネームでは、ファイル名の拡張子を元の画像の拡張子にそろえる
+pub fn plan_rename_into(pdf_path: &Path, subdir: Option<&Path>, new_filename: &str, info: MillsheetInfo) -> RenamePlan {
+    let new_filename = &with_source_extension(new_filename, pdf_path);
+    let source_dir = pdf_path.parent().unwrap_or(Path::new("."));
+    let target_dir = match subdir {
+        Some(subdir) => source_dir.join(subdir),
+        None => source_dir.to_path_buf(),
+    };
+    let collision = Some(target_dir.join(new_filename)).filter(|path| path.exists());
+    // 識別子（ファイル内容のハッシュは読み込みが要る）は同名ファイルがあるときだけ求める
+    let unique_filename = match collision {
+        Some(_) => {
+            let identifiers: Vec<String> = [
+                info.charge_no.clone(),
+                extract_coil_no(&info.raw_text),
+                content_hash(pdf_path).map(|hash| hash[..8].to_string()),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            get_distinct_filename(&target_dir, new_filename, &identifiers)
+        }
+        None => new_filename.to_string(),
+    };
+    
+    RenamePlan {
+        source_path: pdf_path.to_path_buf(),
+        proposed_name: new_filename.to_string(),
+        new_path: target_dir.join(&unique_filename),
+        new_name: unique_filename,
+        collision,
+        violations: Vec::new(),
+        subdir: subdir.map(Path::to_path_buf),
+        info,
+    }
+}
+
+/// 計画どおりにリネーム（計画後にリネーム先が作られていた場合は上書きせずエラー）
+///
+/// サブフォルダに振り分ける計画では、フォルダが無ければ作成してから移動する
+pub fn execute_rename(plan: &RenamePlan) -> Result<()> {
+    if !plan.source_path.exists() {
+        bail!("リネーム元が見つかりません: {}", plan.source_path.display());
+    }
+    if plan.new_path.exists() {
+        bail!("リネーム先が既に存在します: {}", plan.new_path.display());
+    }
+    if plan.subdir.is_some()
+        && let Some(dir) = plan.new_path.parent()
+    {
+        std::fs::create_dir_all(dir).with_context(|| format!("振り分け先のフォルダを作成できません: {}", dir.display()))?;
+    }
+    
+    std::fs::rename(&plan.source_path, &plan.new_path)
+        .with_context(|| format!("リネームエラー: {}", plan.source_path.display()))
+}
+
+/// 複数の計画をまとめて検証（一括実行の前に使う）
+///
+/// リネーム元の欠落、リネーム先の既存ファイル、計画どうしのリネーム先の重複を問題として返す
+pub fn validate_plans(plans: &[RenamePlan]) -> Result<(), Vec<String>> {
+    let mut problems = Vec::new();
+    let mut targets: HashMap<&Path, &Path> = HashMap::new();
+    
+    for plan in plans {
+        if !plan.source_path.exists() {
+            problems.push(format!("リネーム元が見つかりません: {}", plan.source_path.display()));
+        }
+        if plan.new_path.exists() {
+            problems.push(format!("リネーム先が既に存在します: {}", plan.new_path.display()));
+        }
+        if let Some(other) = targets.insert(&plan.new_path, &plan.source_path) {
+            problems.push(format!(
+                "リネーム先が重複しています: {} ({} と {})",
+                plan.new_path.display(),
+                other.display(),
+                plan.source_path.display()
+            ));
+        }
+    }
+    
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// テストごとの作業フォルダ（中身の異なるPDFを2つ置く）
+    fn setup(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("millsheet_plan_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.pdf"), dir.join("b.pdf"));
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(&b, "b").unwrap();
+        (dir, a, b)
+    }
+    
+    #[test]
+    fn validated_plans_are_executed() {
+        let (dir, a, b) = setup("execute");
+        let options = ProcessingOptions::default();
+        let plans = [
+            plan_rename(&a, MillsheetInfo::parse("2024年3月15日\nSS400"), &options),
+            plan_rename(&b, MillsheetInfo::parse("2024年3月16日\nSS400"), &options),
+        ];
+        assert!(validate_plans(&plans).is_ok());
+        for plan in &plans {
+            execute_rename(plan).unwrap();
+        }
+        assert!(dir.join("24-03-15_SS400.pdf").exists());
+        assert!(dir.join("24-03-16_SS400.pdf").exists());
+        assert!(!a.exists() && !b.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn duplicate_targets_are_reported() {
+        let (dir, a, b) = setup("duplicate");
+        let options = ProcessingOptions::default();
+        let plans = [a, b].map(|path| plan_rename(&path, MillsheetInfo::parse("2024年3月15日\nSS400"), &options));
+        let problems = validate_plans(&plans).unwrap_err();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("リネーム先が重複しています"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    
+    #[test]
+    fn collision_is_resolved_with_coil_no_when_charge_no_is_in_name() {
+        let (dir, a, _) = setup("collision");
+        let options = ProcessingOptions::default();
+        let info = || MillsheetInfo {
+            charge_no: Some("AB1234".to_string()),
+            ..MillsheetInfo::parse("2024年3月15日\nSS400\nCOIL NO. C5678")
+        };
+        
+        let plan = plan_rename(&a, info(), &options);
+        assert_eq!(plan.new_name, "24-03-15_SS400_AB1234.pdf");
+        assert!(plan.collision.is_none());
+        
+        // ファイル名に入っているチャージ番号の次はコイル番号で区別する
+        std::fs::write(dir.join("24-03-15_SS400_AB1234.pdf"), "other").unwrap();
+        let plan = plan_rename(&a, info(), &options);
+        assert_eq!(plan.new_name, "24-03-15_SS400_AB1234_C5678.pdf");
+        assert_eq!(plan.collision, Some(dir.join("24-03-15_SS400_AB1234.pdf")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+//! 優先度付きの処理キュー - バックグラウンド処理が1件ずつ取り出す
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// 取り出し順の決め方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PriorityMode {
+    /// 手動で設定した優先度の高い順
+    #[default]
+    Manual,
+    /// ファイルサイズの小さい順（小さいほど速く終わる）
+    FileSize,
+}
+
+/// キュー内の1件
+#[derive(Debug, Clone)]
+pub struct QueuedJob {
+    /// ファイルパス
+    pub path: PathBuf,
+    /// 暗号化PDFのパスワード
+    pub password: Option<String>,
+    /// 手動の優先度（大きいほど先）
+    pub priority: i32,
+    /// ファイルサイズ（バイト）
+    pub size: u64,
+}
+
+impl QueuedJob {
+    /// ジョブを作成（サイズはファイルから取得し、読めなければ0）
+    pub fn new(path: PathBuf, password: Option<String>, priority: i32) -> Self {
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Self {
+            path,
+            password,
+            priority,
+            size,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    mode: PriorityMode,
+    /// 追加順に並んだ待機中のジョブ
+    jobs: Vec<QueuedJob>,
+}
+
+/// 複数スレッドから共有する優先度付きキュー
+///
+/// 優先度や取り出し順の変更は次の取り出しから反映され、処理中のものには影響しない
+#[derive(Debug, Clone, Default)]
+pub struct JobQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+impl JobQueue {
+    /// 空のキューを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// 末尾に追加
+    pub fn push(&self, job: QueuedJob) {
+        self.lock().jobs.push(job);
+    }
+    
+    /// 最も優先度の高いジョブを取り出す（同順位なら追加順）
+    pub fn pop(&self) -> Option<QueuedJob> {
+        let mut state = self.lock();
+        let index = next_index(&state.jobs, state.mode)?;
+        Some(state.jobs.remove(index))
+    }
+    
+    /// 取り出し順の決め方
+    pub fn mode(&self) -> PriorityMode {
+        self.lock().mode
+    }
+    
+    /// 取り出し順の決め方を変更
+    pub fn set_mode(&self, mode: PriorityMode) {
+        self.lock().mode = mode;
+    }
+    
+    /// 待機中のジョブの手動優先度を変更（取り出し済みなら何もしない）
+    pub fn set_priority(&self, path: &Path, priority: i32) {
+        if let Some(job) = self.lock().jobs.iter_mut().find(|j| j.path == path) {
+            job.priority = priority;
+        }
+    }
+    
+    /// 待機中のジョブを取り出し順に並べた一覧
+    pub fn snapshot(&self) -> Vec<QueuedJob> {
+        let state = self.lock();
+        let mut jobs = state.jobs.clone();
+        match state.mode {
+            PriorityMode::Manual => jobs.sort_by_key(|j| std::cmp::Reverse(j.priority)),
+            PriorityMode::FileSize => jobs.sort_by_key(|j| j.size),
+        }
+        jobs
+    }
+    
+    /// 待機中の件数
+    pub fn len(&self) -> usize {
+        self.lock().jobs.len()
+    }
+    
+    /// 待機中のジョブがないか
+    pub fn is_empty(&self) -> bool {
+        self.lock().jobs.is_empty()
+    }
+    
+    /// 待機中のジョブをすべて破棄し、破棄した件数を返す
+    pub fn clear(&self) -> usize {
+        let mut state = self.lock();
+        let count = state.jobs.len();
+        state.jobs.clear();
+        count
+    }
+}
+
+/// 次に取り出すジョブの位置（安定ソートと同じく同順位は先頭側を選ぶ）
+fn next_index(jobs: &[QueuedJob], mode: PriorityMode) -> Option<usize> {
+    let (index, _) = match mode {
+        PriorityMode::Manual => jobs
+            .iter()
+            .enumerate()
+            .min_by_key(|(i, j)| (std::cmp::Reverse(j.priority), *i))?,
+        PriorityMode::FileSize => jobs
+            .iter()
+            .enumerate()
+            .min_by_key(|(i, j)| (j.size, *i))?,
+    };
+    Some(index)
+}
@@ -0,0 +1,77 @@
+//! 再スキャン推奨リスト - OCR品質が原因で抽出できなかったファイルを現場に再スキャンしてもらう
+
+use crate::processing::{ProcessResult, ProcessStage, NO_TEXT_ERROR};
+use std::fmt;
+
+/// これ未満の総合信頼度はOCR品質が低いとみなす
+pub const LOW_OCR_CONFIDENCE: f32 = 0.5;
+
+/// 再スキャンを勧める理由
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RescanReason {
+    /// OCRで文字をまったく読み取れなかった（白紙・極端に薄いスキャンなど）
+    NoText,
+    /// 読み取れたが総合信頼度が低い
+    LowConfidence(f32),
+}
+
+impl fmt::Display for RescanReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RescanReason::NoText => write!(f, "文字を読み取れませんでした"),
+            RescanReason::LowConfidence(confidence) => write!(f, "OCR信頼度 {:.0}%", confidence * 100.0),
+        }
+    }
+}
+
+impl ProcessResult {
+    /// PDF変換で失敗したか（破損・非対応のPDFで、再スキャンでは直らない）
+    pub fn is_conversion_failure(&self) -> bool {
+        !self.success && self.failed_stage == Some(ProcessStage::Convert)
+    }
+    
+    /// 再スキャンで改善が見込めるなら、その理由
+    ///
+    /// 成功した結果・変換失敗・タイムアウト・API エラーなどOCR品質と関係ない失敗は対象外。
+    /// 確認待ちは信頼度が低ければ対象にする
+    pub fn rescan_reason(&self) -> Option<RescanReason> {
+        if self.success || self.timed_out || self.is_conversion_failure() {
+            return None;
+        }
+        if self.failed_stage == Some(ProcessStage::Ocr) {
+            return (self.error.as_deref() == Some(NO_TEXT_ERROR)).then_some(RescanReason::NoText);
+        }
+        
+        let confidence = self.parsed.as_ref()?.overall_confidence();
+        (confidence < LOW_OCR_CONFIDENCE).then_some(RescanReason::LowConfidence(confidence))
+    }
+}
+
+/// 再スキャンを勧める結果と理由（結果の順）
+pub fn rescan_candidates(results: &[ProcessResult]) -> Vec<(&ProcessResult, RescanReason)> {
+    results
+        .iter()
+        .filter_map(|result| result.rescan_reason().map(|reason| (result, reason)))
+        .collect()
+}
+
+/// 再スキャン推奨リストのテキスト（1行1ファイル、ファイル名と理由をタブ区切り）
+///
+/// 変換に失敗したファイルは件数だけを末尾に添える
+pub fn format_rescan_list(results: &[ProcessResult]) -> String {
+    let candidates = rescan_candidates(results);
+    let mut lines = vec![format!("再スキャン推奨リスト（{} 件）", candidates.len())];
+    lines.extend(
+        candidates
+            .iter()
+            .map(|(result, reason)| format!("{}\t{}", result.current_path().display(), reason)),
+    );
+    
+    let corrupted = results.iter().filter(|r| r.is_conversion_failure()).count();
+    if corrupted > 0 {
+        lines.push(String::new());
+        lines.push(format!("※ PDF変換に失敗した {} 件は破損の可能性があるため対象外（元データの再送を依頼してください）", corrupted));
+    }
+    
+    lines.join("\n") + "\n"
+}
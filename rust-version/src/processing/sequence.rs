@@ -0,0 +1,148 @@
+//! 通し番号の採番 - 受領順の管理番号をファイル名テンプレートの `{seq:04}` に埋め込む
+//!
+//! セッションモードは作成するたびに1から数え直し、永続モードはファイルに記録した前回の番号の続きから採番する。
+//! 複製したカウンタは同じ番号列を共有するので、並列に処理しても番号は重複しない。
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 永続カウンタのパスを指定する環境変数
+pub const SEQUENCE_ENV: &str = "MILLSHEET_SEQUENCE_FILE";
+
+/// 既定の永続カウンタのファイル名
+const DEFAULT_SEQUENCE_FILE: &str = "millsheet_sequence.txt";
+
+/// 通し番号の採番方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SequenceMode {
+    /// 起動（カウンタの作成）ごとに1から
+    #[default]
+    Session,
+    /// ファイルに記録した前回の番号の続きから
+    Persistent,
+}
+
+impl SequenceMode {
+    /// 表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            SequenceMode::Session => "起動ごとに1から",
+            SequenceMode::Persistent => "前回の続きから",
+        }
+    }
+}
+
+/// 通し番号のカウンタ（複製しても番号列は共有する）
+#[derive(Debug, Clone, Default)]
+pub struct SequenceCounter {
+    /// 最後に採番した番号（未採番なら0）
+    last: Arc<Mutex<u64>>,
+    /// 永続モードの記録先（セッションモードなら None）
+    path: Option<PathBuf>,
+}
+
+impl SequenceCounter {
+    /// 1から数えるセッションモードのカウンタを作成
+    pub fn session() -> Self {
+        Self::default()
+    }
+    
+    /// ファイルに記録した番号の続きから数える永続モードのカウンタを作成（ファイルがなければ1から）
+    pub fn persistent(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let last = match std::fs::read_to_string(&path) {
+            Ok(content) => content
+                .trim()
+                .parse()
+                .with_context(|| format!("通し番号ファイルの内容が不正です: {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => {
+                return Err(e).with_context(|| format!("通し番号ファイルを読み込めません: {}", path.display()));
+            }
+        };
+        
+        Ok(Self {
+            last: Arc::new(Mutex::new(last)),
+            path: Some(path),
+        })
+    }
+    
+    /// 採番方法に応じてカウンタを作成（永続モードは `path` に記録）
+    pub fn open(mode: SequenceMode, path: &Path) -> Result<Self> {
+        match mode {
+            SequenceMode::Session => Ok(Self::session()),
+            SequenceMode::Persistent => Self::persistent(path),
+        }
+    }
+    
+    /// 次の番号を採番
+    ///
+    /// 永続モードでは記録してから返すので、記録に失敗した番号は使われず、次回も同じ番号から採番する
+    pub fn next(&self) -> Result<u64> {
+        let mut last = self
+            .last
+            .lock()
+            .map_err(|_| anyhow::anyhow!("通し番号のカウンタが壊れています"))?;
+        let next = *last + 1;
+        if let Some(ref path) = self.path {
+            std::fs::write(path, next.to_string())
+                .with_context(|| format!("通し番号を記録できません: {}", path.display()))?;
+        }
+        *last = next;
+        Ok(next)
+    }
+    
+    /// 最後に採番した番号（未採番なら0）
+    pub fn last(&self) -> u64 {
+        self.last.lock().map(|last| *last).unwrap_or(0)
+    }
+    
+    /// 永続モードの記録先
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+/// 永続カウンタのパスを決定（環境変数 > 実行ファイルと同じフォルダ）
+pub fn default_sequence_path() -> PathBuf {
+    if let Ok(path) = std::env::var(SEQUENCE_ENV) {
+        return PathBuf::from(path);
+    }
+    
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_SEQUENCE_FILE)))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SEQUENCE_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    
+    #[test]
+    fn session_numbers_are_unique_across_threads() {
+        let counter = SequenceCounter::session();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                std::thread::spawn(move || (0..25).map(|_| counter.next().unwrap()).collect::<Vec<_>>())
+            })
+            .collect();
+        let numbers: HashSet<u64> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        assert_eq!(numbers, (1..=100).collect());
+        assert_eq!(SequenceCounter::session().next().unwrap(), 1);
+    }
+    
+    #[test]
+    fn persistent_counter_continues_after_reopen() {
+        let path = std::env::temp_dir().join(format!("millsheet_seq_{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let counter = SequenceCounter::persistent(&path).unwrap();
+        assert_eq!((counter.next().unwrap(), counter.next().unwrap()), (1, 2));
+        let reopened = SequenceCounter::persistent(&path).unwrap();
+        assert_eq!(reopened.next().unwrap(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+//! 処理結果のタグ - 手で付けたタグごとに結果をまとめて操作する
+
+use crate::export::{export_results, ExportFormat};
+use crate::parser::{get_unique_filename, sanitize_for_filename};
+use crate::processing::ProcessResult;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// よく使うタグ（GUIの候補）
+pub const PRESET_TAGS: [&str; 3] = ["要再検査", "保留", "返品"];
+
+impl ProcessResult {
+    /// タグが付いているか
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+    
+    /// タグを付ける（前後の空白は除き、空・付与済みなら何もしない）
+    pub fn add_tag(&mut self, tag: &str) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !self.has_tag(tag) {
+            self.tags.push(tag.to_string());
+        }
+    }
+    
+    /// タグを外す
+    pub fn remove_tag(&mut self, tag: &str) {
+        self.tags.retain(|t| t != tag);
+    }
+    
+    /// ファイルの現在の場所（リネーム済みならリネーム後のパス）
+    pub fn current_path(&self) -> &Path {
+        self.new_path.as_deref().unwrap_or(&self.source_path)
+    }
+}
+
+/// 結果に付いている全タグ（名前順）
+pub fn collect_tags(results: &[ProcessResult]) -> BTreeSet<String> {
+    results.iter().flat_map(|r| r.tags.iter().cloned()).collect()
+}
+
+/// タグの付いた結果だけを取り出す
+pub fn results_with_tag(results: &[ProcessResult], tag: &str) -> Vec<ProcessResult> {
+    results.iter().filter(|r| r.has_tag(tag)).cloned().collect()
+}
+
+/// タグごとにフォルダへ書き出し、書き出したファイルのパスを返す
+///
+/// ファイル名は `millsheet_results_<タグ>.<拡張子>`。複数のタグが付いた結果は各タグのファイルに入る
+pub fn export_by_tag(results: &[ProcessResult], format: ExportFormat, dir: &Path) -> Result<Vec<PathBuf>> {
+    let tags = collect_tags(results);
+    if tags.is_empty() {
+        bail!("タグの付いた結果がありません");
+    }
+    
+    tags.iter()
+        .map(|tag| {
+            let path = dir.join(format!("millsheet_results_{}.{}", sanitize_for_filename(tag), format.extension()));
+            export_results(&results_with_tag(results, tag), format, &path)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// タグの付いた結果のファイルを `<移動先>/<タグ>/` へ移動し、移動した件数を返す
+///
+/// 移動先に同名ファイルがあれば連番を付ける。移動後は結果のパスを更新するので、リネームの取り消しも使える
+pub fn move_tagged(results: &mut [ProcessResult], tag: &str, dest_root: &Path) -> Result<usize> {
+    let dir = dest_root.join(sanitize_for_filename(tag));
+    std::fs::create_dir_all(&dir).with_context(|| format!("フォルダを作成できません: {}", dir.display()))?;
+    
+    let mut moved = 0;
+    for result in results.iter_mut().filter(|r| r.has_tag(tag)) {
+        let from = result.current_path().to_path_buf();
+        if !from.exists() {
+            tracing::warn!("移動元が見つかりません: {}", from.display());
+            continue;
+        }
+        let Some(name) = from.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let to = dir.join(get_unique_filename(&dir, name));
+        move_file(&from, &to)?;
+        
+        match result.new_path {
+            Some(_) => result.new_path = Some(to),
+            None => result.source_path = to,
+        }
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+/// ファイルを移動（別ドライブへはコピーしてから削除）
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to).with_context(|| format!("ファイルを移動できません: {}", from.display()))?;
+    std::fs::remove_file(from).with_context(|| format!("移動元を削除できません: {}", from.display()))
+}
@@ -0,0 +1,124 @@
+//! 変換画像の記録 - 処理中に変換してまだ片付けていない画像を覚えておき、片付け漏れだけを後から消す
+//!
+//! 一時フォルダを名前でまとめて消すと、試し処理・サムネイル・タイムアウト後も走っている変換のフォルダまで消えるため、
+//! バッチの区切りではこの記録に残っている分だけを片付ける
+
+use crate::processing::pipeline::Converter;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// 変換してまだ片付けていない画像の記録（複製しても中身は共有する）
+#[derive(Debug, Clone, Default)]
+pub struct TempImages {
+    /// 先頭の画像のパス → 1回の変換で作った画像
+    entries: Arc<Mutex<HashMap<PathBuf, Vec<PathBuf>>>>,
+}
+
+impl TempImages {
+    /// 変換した画像を記録
+    pub fn record(&self, images: &[PathBuf]) {
+        let Some(first) = images.first() else {
+            return;
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(first.clone(), images.to_vec());
+        }
+    }
+    
+    /// 片付けた画像を記録から外す
+    pub fn release(&self, images: &[PathBuf]) {
+        let Some(first) = images.first() else {
+            return;
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(first);
+        }
+    }
+    
+    /// 片付けていない変換の件数
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|entries| entries.len()).unwrap_or(0)
+    }
+    
+    /// 片付けていない変換がないか
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// 記録に残っている画像を `converter` で片付けて記録を空にし、片付けた変換の件数を返す
+    pub fn cleanup(&self, converter: &dyn Converter) -> usize {
+        let leftovers: Vec<Vec<PathBuf>> = match self.entries.lock() {
+            Ok(mut entries) => entries.drain().map(|(_, images)| images).collect(),
+            Err(_) => return 0,
+        };
+        for images in &leftovers {
+            converter.cleanup(images);
+        }
+        leftovers.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::PageMode;
+    use anyhow::Result;
+    use std::path::Path;
+    
+    /// 片付けた画像を記録するだけの変換
+    #[derive(Default)]
+    struct RecordingConverter(Mutex<Vec<PathBuf>>);
+    
+    impl Converter for RecordingConverter {
+        fn convert(&self, _: &Path, _: Option<&str>, _: PageMode, _: u32) -> Result<Vec<PathBuf>> {
+            Ok(Vec::new())
+        }
+        
+        fn cleanup(&self, images: &[PathBuf]) {
+            self.0.lock().unwrap().extend_from_slice(images);
+        }
+    }
+    
+    fn images(dir: &str) -> Vec<PathBuf> {
+        vec![PathBuf::from(dir).join("page-1.png"), PathBuf::from(dir).join("page-2.png")]
+    }
+    
+    #[test]
+    fn released_images_are_not_cleaned_up_again() {
+        let temp_images = TempImages::default();
+        temp_images.record(&images("a"));
+        temp_images.record(&images("b"));
+        temp_images.release(&images("a"));
+        assert_eq!(temp_images.len(), 1);
+        
+        let converter = RecordingConverter::default();
+        assert_eq!(temp_images.cleanup(&converter), 1);
+        assert_eq!(*converter.0.lock().unwrap(), images("b"));
+        assert!(temp_images.is_empty());
+    }
+    
+    #[test]
+    fn cleanup_leaves_unrecorded_images_alone() {
+        let temp_images = TempImages::default();
+        let converter = RecordingConverter::default();
+        assert_eq!(temp_images.cleanup(&converter), 0);
+        assert!(converter.0.lock().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn clones_share_the_record() {
+        let temp_images = TempImages::default();
+        temp_images.clone().record(&images("a"));
+        assert_eq!(temp_images.len(), 1);
+        temp_images.clone().release(&images("a"));
+        assert!(temp_images.is_empty());
+    }
+    
+    #[test]
+    fn empty_conversion_is_not_recorded() {
+        let temp_images = TempImages::default();
+        temp_images.record(&[]);
+        assert!(temp_images.is_empty());
+    }
+}
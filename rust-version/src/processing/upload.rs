@@ -0,0 +1,261 @@
+//! リネーム後のアップロード - 処理結果のファイルを共有フォルダ（ネットワークパス）へ配置する
+
+use crate::parser::get_unique_filename;
+use crate::processing::{ProcessResult, ProcessingOptions};
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 既定のリトライ回数（初回の試行を含まない）
+pub const DEFAULT_UPLOAD_RETRIES: u32 = 3;
+
+/// リトライの間隔（n 回目のリトライは n 倍待つ）
+pub const UPLOAD_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// アップロード後の元ファイルの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UploadMode {
+    /// コピーして元ファイルを残す
+    #[default]
+    Copy,
+    /// コピーできたら元ファイルを削除する（移動）
+    Move,
+}
+
+impl UploadMode {
+    /// すべての扱い（設定画面の選択肢用）
+    pub const ALL: [UploadMode; 2] = [UploadMode::Copy, UploadMode::Move];
+    
+    /// 表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            UploadMode::Copy => "コピー（元ファイルを残す）",
+            UploadMode::Move => "移動",
+        }
+    }
+}
+
+/// アップロード先の設定
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    /// 配置先のフォルダ（`\\server\share\millsheets` などのネットワークパス可、無ければ作成）
+    pub dest_dir: PathBuf,
+    /// 元ファイルを残すか移動するか
+    pub mode: UploadMode,
+    /// 失敗時のリトライ回数
+    pub retries: u32,
+    /// リトライの間隔
+    pub retry_interval: Duration,
+}
+
+impl UploadOptions {
+    /// 既定のリトライ設定でコピーする設定を作成
+    pub fn new(dest_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dest_dir: dest_dir.into(),
+            mode: UploadMode::default(),
+            retries: DEFAULT_UPLOAD_RETRIES,
+            retry_interval: UPLOAD_RETRY_INTERVAL,
+        }
+    }
+}
+
+/// アップロードの状況（結果カードの表示用）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadStatus {
+    /// アップロード中
+    Uploading,
+    /// 配置済み（配置先のパス）
+    Uploaded(PathBuf),
+    /// リトライしても失敗（最後のエラー）
+    Failed(String),
+}
+
+impl ProcessResult {
+    /// アップロードに失敗していて再アップロードできるか
+    pub fn upload_failed(&self) -> bool {
+        matches!(self.upload, Some(UploadStatus::Failed(_)))
+    }
+}
+
+/// 結果のファイル（リネーム済みならリネーム後）をアップロードし、状況を `result.upload` に記録する
+///
+/// 失敗時は `options.retries` 回まで再試行する。待つ間はスレッドを止めるので、非同期処理からは `spawn_blocking` で呼ぶ
+pub fn upload_result(result: &mut ProcessResult, options: &UploadOptions) {
+    let from = result.current_path().to_path_buf();
+    let status = match upload_file(&from, options) {
+        Ok(to) => {
+            tracing::info!("アップロード: {} → {}", from.display(), to.display());
+            if options.mode == UploadMode::Move {
+                match std::fs::remove_file(&from) {
+                    Ok(()) => match result.new_path {
+                        Some(_) => result.new_path = Some(to.clone()),
+                        None => result.source_path = to.clone(),
+                    },
+                    Err(e) => tracing::warn!(
+                        "コピーしましたが元ファイルを削除できません（コピーとして扱います）: {}: {}",
+                        from.display(),
+                        e
+                    ),
+                }
+            }
+            UploadStatus::Uploaded(to)
+        }
+        Err(e) => {
+            tracing::warn!("アップロードに失敗: {}: {:#}", from.display(), e);
+            UploadStatus::Failed(format!("{:#}", e))
+        }
+    };
+    result.upload = Some(status);
+}
+
+/// 別スレッドで `upload_result` を実行して結果を返す（リトライの待ち時間で非同期処理を止めない）
+pub async fn upload_in_background(mut result: ProcessResult, options: UploadOptions) -> ProcessResult {
+    let fallback = result.clone();
+    tokio::task::spawn_blocking(move || {
+        upload_result(&mut result, &options);
+        result
+    })
+    .await
+    .unwrap_or_else(|e| ProcessResult {
+        upload: Some(UploadStatus::Failed(format!("アップロード処理が異常終了しました: {}", e))),
+        ..fallback
+    })
+}
+
+/// 成功した結果を `options.upload` の設定でアップロード（設定がないか失敗した結果はそのまま返す）
+///
+/// リトライの待ち時間があるので、同時実行数の枠などは手放してから呼ぶ
+pub async fn upload_processed(result: ProcessResult, options: &ProcessingOptions) -> ProcessResult {
+    match &options.upload {
+        Some(upload) if result.success => upload_in_background(result, upload.clone()).await,
+        _ => result,
+    }
+}
+
+/// ファイルを配置先へコピーし（失敗したらリトライ）、配置先のパスを返す
+fn upload_file(from: &Path, options: &UploadOptions) -> Result<PathBuf> {
+    if !from.exists() {
+        bail!("アップロード元が見つかりません: {}", from.display());
+    }
+    let Some(name) = from.file_name().and_then(|n| n.to_str()) else {
+        bail!("ファイル名を取得できません: {}", from.display());
+    };
+    
+    let mut attempt = 0;
+    loop {
+        match copy_into(from, &options.dest_dir, name) {
+            Ok(to) => return Ok(to),
+            Err(e) if attempt < options.retries => {
+                attempt += 1;
+                tracing::warn!("アップロードを再試行します ({}/{}): {:#}", attempt, options.retries, e);
+                std::thread::sleep(options.retry_interval * attempt);
+            }
+            Err(e) => return Err(e.context(format!("{}回試行しても失敗しました", attempt + 1))),
+        }
+    }
+}
+
+/// フォルダへ1回コピーを試みる（同名ファイルがあれば連番を付け、途中で失敗したら書きかけを消す）
+fn copy_into(from: &Path, dir: &Path, name: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir).with_context(|| format!("アップロード先を作成できません: {}", dir.display()))?;
+    let to = dir.join(get_unique_filename(dir, name));
+    if let Err(e) = std::fs::copy(from, &to) {
+        let _ = std::fs::remove_file(&to);
+        return Err(e).with_context(|| format!("コピーできません: {}", to.display()));
+    }
+    Ok(to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// テストごとの作業フォルダ（元ファイル1つと空のアップロード先）
+    fn setup(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("millsheet_upload_test_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("24-03-15_SS400.pdf");
+        std::fs::write(&source, b"%PDF-1.4").unwrap();
+        (source, dir.join("share"))
+    }
+    
+    fn renamed(path: &Path) -> ProcessResult {
+        ProcessResult {
+            success: true,
+            new_path: Some(path.to_path_buf()),
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn copy_keeps_the_source() {
+        let (source, share) = setup("copy");
+        let mut result = renamed(&source);
+        upload_result(&mut result, &UploadOptions::new(&share));
+        
+        let uploaded = share.join("24-03-15_SS400.pdf");
+        assert_eq!(result.upload, Some(UploadStatus::Uploaded(uploaded.clone())));
+        assert!(uploaded.exists());
+        assert!(source.exists());
+        assert_eq!(result.new_path.as_deref(), Some(source.as_path()));
+        let _ = std::fs::remove_dir_all(source.parent().unwrap());
+    }
+    
+    #[test]
+    fn move_removes_the_source_and_points_to_the_share() {
+        let (source, share) = setup("move");
+        let mut result = renamed(&source);
+        let options = UploadOptions {
+            mode: UploadMode::Move,
+            ..UploadOptions::new(&share)
+        };
+        upload_result(&mut result, &options);
+        
+        let uploaded = share.join("24-03-15_SS400.pdf");
+        assert_eq!(result.upload, Some(UploadStatus::Uploaded(uploaded.clone())));
+        assert!(!source.exists());
+        assert_eq!(result.new_path, Some(uploaded));
+        let _ = std::fs::remove_dir_all(source.parent().unwrap());
+    }
+    
+    #[test]
+    fn existing_file_on_the_share_gets_a_numbered_name() {
+        let (source, share) = setup("numbered");
+        std::fs::create_dir_all(&share).unwrap();
+        std::fs::write(share.join("24-03-15_SS400.pdf"), b"old").unwrap();
+        let mut result = renamed(&source);
+        upload_result(&mut result, &UploadOptions::new(&share));
+        
+        let Some(UploadStatus::Uploaded(to)) = result.upload else {
+            panic!("アップロードに失敗: {:?}", result.upload);
+        };
+        assert_ne!(to, share.join("24-03-15_SS400.pdf"));
+        assert_eq!(std::fs::read(share.join("24-03-15_SS400.pdf")).unwrap(), b"old");
+        let _ = std::fs::remove_dir_all(source.parent().unwrap());
+    }
+    
+    #[test]
+    fn failed_results_are_not_uploaded() {
+        let options = ProcessingOptions {
+            upload: Some(UploadOptions::new("share")),
+            ..Default::default()
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(upload_processed(ProcessResult::default(), &options));
+        assert_eq!(result.upload, None);
+    }
+    
+    #[test]
+    fn missing_source_marks_upload_failed() {
+        let mut result = ProcessResult {
+            source_path: "missing/sample.pdf".into(),
+            ..Default::default()
+        };
+        let options = UploadOptions { retries: 0, ..UploadOptions::new("share") };
+        upload_result(&mut result, &options);
+        assert!(matches!(result.upload, Some(UploadStatus::Failed(_))));
+        assert!(result.upload_failed());
+    }
+}
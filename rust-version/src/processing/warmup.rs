@@ -0,0 +1,128 @@
+//! ウォームアップ - 初回処理で一度に走る準備（Poppler展開・正規表現コンパイル・認証トークン取得）を前倒しする
+
+use crate::ocr::OcrBackend;
+use crate::parser::precompile_patterns;
+use crate::pdf::prepare_poppler;
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// すべての準備が済んだか
+static WARMED_UP: AtomicBool = AtomicBool::new(false);
+
+/// ウォームアップ済みか（`warmup_with` がOCRバックエンドの準備まで成功したら true）
+pub fn is_warmed_up() -> bool {
+    WARMED_UP.load(Ordering::SeqCst)
+}
+
+/// OCRバックエンドを使わない準備（正規表現のコンパイル・Popplerの展開）を実行
+///
+/// どちらも一度済めば2回目以降はすぐ返る。失敗しても処理時に同じ準備をやり直すので、呼び出し側はログに残すだけでよい
+pub fn warmup() -> Result<()> {
+    warmup_using(prepare_poppler)
+}
+
+/// 正規表現をコンパイルし、`prepare_pdf` でPDF変換の準備をする
+fn warmup_using(prepare_pdf: fn() -> Result<()>) -> Result<()> {
+    precompile_patterns();
+    crate::pdf::diagnose::precompile_patterns();
+    prepare_pdf().context("Popplerの展開に失敗")
+}
+
+/// `warmup` に加えてOCRバックエンドの準備（Vision のアクセストークン取得など）まで済ませる
+///
+/// 展開などの待ちは別スレッドで行うので、GUI起動直後などにバックグラウンドで呼べる
+pub async fn warmup_with(ocr: Option<&dyn OcrBackend>) -> Result<()> {
+    prepare_all(ocr, prepare_poppler).await?;
+    
+    WARMED_UP.store(true, Ordering::SeqCst);
+    tracing::info!("ウォームアップ完了");
+    Ok(())
+}
+
+/// `prepare_pdf` を使った準備とOCRバックエンドの準備を順に実行
+async fn prepare_all(ocr: Option<&dyn OcrBackend>, prepare_pdf: fn() -> Result<()>) -> Result<()> {
+    tokio::task::spawn_blocking(move || warmup_using(prepare_pdf))
+        .await
+        .context("ウォームアップが中断されました")??;
+    if let Some(ocr) = ocr {
+        ocr.warmup()
+            .await
+            .with_context(|| format!("{} の準備に失敗", ocr.name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::{OcrFuture, WarmupFuture};
+    use std::path::Path;
+    use std::sync::atomic::AtomicUsize;
+    
+    /// 準備した回数を数える（`fail` なら準備に失敗する）
+    struct FakeOcr {
+        fail: bool,
+        warmups: AtomicUsize,
+    }
+    
+    impl FakeOcr {
+        fn new(fail: bool) -> Self {
+            Self { fail, warmups: AtomicUsize::new(0) }
+        }
+    }
+    
+    impl OcrBackend for FakeOcr {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+        
+        fn extract_text<'a>(&'a self, _: &'a Path) -> OcrFuture<'a> {
+            Box::pin(async { Ok(String::new()) })
+        }
+        
+        fn warmup(&self) -> WarmupFuture<'_> {
+            self.warmups.fetch_add(1, Ordering::SeqCst);
+            let fail = self.fail;
+            Box::pin(async move {
+                if fail {
+                    anyhow::bail!("認証エラー");
+                }
+                Ok(())
+            })
+        }
+    }
+    
+    fn prepared() -> Result<()> {
+        Ok(())
+    }
+    
+    fn broken() -> Result<()> {
+        anyhow::bail!("展開先に書き込めません")
+    }
+    
+    fn run(ocr: &FakeOcr, prepare_pdf: fn() -> Result<()>) -> Result<()> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(prepare_all(Some(ocr), prepare_pdf))
+    }
+    
+    #[test]
+    fn ocr_is_prepared_after_pdf_preparation() {
+        let ocr = FakeOcr::new(false);
+        run(&ocr, prepared).unwrap();
+        assert_eq!(ocr.warmups.load(Ordering::SeqCst), 1);
+    }
+    
+    #[test]
+    fn failed_pdf_preparation_skips_ocr() {
+        let ocr = FakeOcr::new(false);
+        let error = run(&ocr, broken).unwrap_err();
+        assert_eq!(error.to_string(), "Popplerの展開に失敗");
+        assert_eq!(ocr.warmups.load(Ordering::SeqCst), 0);
+    }
+    
+    #[test]
+    fn failed_ocr_preparation_names_the_backend() {
+        let error = run(&FakeOcr::new(true), prepared).unwrap_err();
+        assert_eq!(error.to_string(), "fake の準備に失敗");
+    }
+}
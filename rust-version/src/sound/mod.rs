@@ -0,0 +1,143 @@
+//! 処理結果の通知音 - 成功・失敗で異なる短い音を鳴らす
+//!
+//! 音声ライブラリを使わず、各OSに標準で備わる手段で鳴らす
+//! （Windows は Beep、macOS は afplay、Linux は paplay、鳴らせなければ端末のベル）
+
+use std::time::{Duration, Instant};
+
+/// 通知音の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationSound {
+    /// 成功
+    Success,
+    /// 失敗・確認待ち
+    Failure,
+}
+
+/// 通知音の設定
+#[derive(Debug, Clone, Copy)]
+pub struct SoundSettings {
+    /// 通知音を鳴らすか
+    pub enabled: bool,
+    /// 成功時も鳴らすか（false なら失敗時のみ）
+    pub on_success: bool,
+    /// 同じ種類の音を鳴らす最短間隔（連続処理で鳴りすぎないようにする）
+    pub min_interval: Duration,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            on_success: true,
+            min_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// 設定とレート制限に従って通知音を鳴らす
+#[derive(Debug, Default)]
+pub struct SoundNotifier {
+    /// 通知音の設定
+    pub settings: SoundSettings,
+    /// 最後に成功音を鳴らした時刻
+    last_success: Option<Instant>,
+    /// 最後に失敗音を鳴らした時刻
+    last_failure: Option<Instant>,
+}
+
+impl SoundNotifier {
+    /// 設定を指定して作成
+    pub fn new(settings: SoundSettings) -> Self {
+        Self {
+            settings,
+            ..Self::default()
+        }
+    }
+    
+    /// 通知音を鳴らす（無効・成功音なし・間隔不足の場合は鳴らさず false）
+    ///
+    /// 再生は別スレッドで行うので呼び出し元は待たない。成功音と失敗音は別々に間隔を数えるため、
+    /// 成功が続いた直後の失敗も聞き逃さない
+    pub fn notify(&mut self, sound: NotificationSound) -> bool {
+        if !self.settings.enabled || (sound == NotificationSound::Success && !self.settings.on_success) {
+            return false;
+        }
+        
+        let last = match sound {
+            NotificationSound::Success => &mut self.last_success,
+            NotificationSound::Failure => &mut self.last_failure,
+        };
+        let now = Instant::now();
+        if last.is_some_and(|t| now.duration_since(t) < self.settings.min_interval) {
+            return false;
+        }
+        *last = Some(now);
+        
+        std::thread::spawn(move || play(sound));
+        true
+    }
+}
+
+/// 通知音を再生（再生が終わるまで戻らない）
+#[cfg(windows)]
+pub fn play(sound: NotificationSound) {
+    use winapi::um::utilapiset::Beep;
+    
+    let tones: &[(u32, u32)] = match sound {
+        NotificationSound::Success => &[(1320, 120)],
+        NotificationSound::Failure => &[(330, 180), (220, 260)],
+    };
+    for &(frequency, millis) in tones {
+        // SAFETY: Beep は引数の値だけを使う
+        unsafe {
+            Beep(frequency, millis);
+        }
+    }
+}
+
+/// 通知音を再生（再生が終わるまで戻らない）
+#[cfg(target_os = "macos")]
+pub fn play(sound: NotificationSound) {
+    let file = match sound {
+        NotificationSound::Success => "/System/Library/Sounds/Glass.aiff",
+        NotificationSound::Failure => "/System/Library/Sounds/Basso.aiff",
+    };
+    if !run_player("afplay", file) {
+        ring_bell();
+    }
+}
+
+/// 通知音を再生（再生が終わるまで戻らない）
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn play(sound: NotificationSound) {
+    let file = match sound {
+        NotificationSound::Success => "/usr/share/sounds/freedesktop/stereo/complete.oga",
+        NotificationSound::Failure => "/usr/share/sounds/freedesktop/stereo/dialog-error.oga",
+    };
+    if !run_player("paplay", file) {
+        ring_bell();
+    }
+}
+
+/// 外部の再生コマンドで音声ファイルを鳴らす（コマンドやファイルが無ければ false）
+#[cfg(unix)]
+fn run_player(command: &str, file: &str) -> bool {
+    std::path::Path::new(file).exists()
+        && std::process::Command::new(command)
+            .arg(file)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+}
+
+/// 端末のベルを鳴らす（再生手段が無い環境向けのフォールバック）
+#[cfg(unix)]
+fn ring_bell() {
+    use std::io::Write;
+    
+    let mut stderr = std::io::stderr();
+    let _ = stderr.write_all(b"\x07");
+    let _ = stderr.flush();
+}
@@ -0,0 +1,208 @@
+//! 手動修正の学習データ - OCRテキスト・自動抽出結果・修正後の値を蓄積してパーサ改善に使う
+//!
+//! 蓄積はオプトイン。1件1行のJSON Lines で追記し、`export_cases` でCLIの `parse` に
+//! そのまま渡せるテストケース（テキスト＋期待値JSON）に書き出す
+
+use crate::parser::{MillsheetInfo, CONFIDENCE_FIELDS};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// 学習データのパスを指定する環境変数
+pub const TRAINING_ENV: &str = "MILLSHEET_TRAINING_DATA";
+
+/// 既定の学習データファイル名（実行ファイルと同じフォルダ）
+const DEFAULT_TRAINING_FILE: &str = "millsheet_corrections.jsonl";
+
+/// 匿名化で個人情報を置き換える文字列
+const REDACTED: &str = "***";
+
+/// 手動修正の1件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrectionRecord {
+    /// 記録日時（RFC 3339、ローカル時刻）
+    pub recorded_at: String,
+    /// 元のファイル名（匿名化時は記録しない）
+    pub original: Option<String>,
+    /// OCRテキスト
+    pub raw_text: String,
+    /// 自動抽出の結果（フィールド名 → 値）
+    pub extracted: BTreeMap<String, Option<String>>,
+    /// 修正後の値（フィールド名 → 値）
+    pub corrected: BTreeMap<String, Option<String>>,
+    /// 修正されたフィールド
+    pub changed_fields: Vec<String>,
+    /// 匿名化済みか
+    pub anonymized: bool,
+}
+
+impl CorrectionRecord {
+    /// 自動抽出と修正後の解析結果から作成（修正が無ければ None）
+    pub fn new(original: &str, extracted: &MillsheetInfo, corrected: &MillsheetInfo, anonymize: bool) -> Option<Self> {
+        let extracted_values = field_values(extracted);
+        let corrected_values = field_values(corrected);
+        let changed_fields: Vec<String> = CONFIDENCE_FIELDS
+            .iter()
+            .filter(|field| extracted_values.get(**field) != corrected_values.get(**field))
+            .map(|field| field.to_string())
+            .collect();
+        if changed_fields.is_empty() {
+            return None;
+        }
+        
+        let raw_text = if anonymize {
+            anonymize_text(&extracted.raw_text)
+        } else {
+            extracted.raw_text.clone()
+        };
+        Some(Self {
+            recorded_at: chrono::Local::now().to_rfc3339(),
+            original: (!anonymize).then(|| original.to_string()),
+            raw_text,
+            extracted: extracted_values,
+            corrected: corrected_values,
+            changed_fields,
+            anonymized: anonymize,
+        })
+    }
+}
+
+/// 抽出対象フィールドの値（発行日は YY-MM-DD）
+fn field_values(info: &MillsheetInfo) -> BTreeMap<String, Option<String>> {
+    CONFIDENCE_FIELDS
+        .iter()
+        .map(|field| {
+            let value = match *field {
+                "date" => info.date.map(|d| d.to_string()),
+                _ => info.field_value(field),
+            };
+            (field.to_string(), value)
+        })
+        .collect()
+}
+
+/// OCRテキストから個人情報を伏せる
+///
+/// メールアドレス・電話番号と、担当者・検査員などのラベルに続く氏名を置き換える。
+/// 材質・寸法・チャージ番号などパーサ改善に必要な値は残す
+pub fn anonymize_text(text: &str) -> String {
+    let patterns = [
+        // メールアドレス
+        r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}",
+        // 電話・FAX番号（市外局番から、ハイフン・括弧区切り）
+        r"\(?0\d{1,4}\)?[\-\s]\d{1,4}[\-\s]\d{3,4}",
+    ];
+    let mut anonymized = text.to_string();
+    for pattern in patterns {
+        if let Ok(re) = Regex::new(pattern) {
+            anonymized = re.replace_all(&anonymized, REDACTED).into_owned();
+        }
+    }
+    
+    // ラベルに続く氏名（行末まで）
+    let person = r"(?im)^(\s*(?:担当者?|検査員|検査者|承認者?|作成者|氏名|INSPECTOR|APPROVED\s*BY|INSPECTED\s*BY|CHECKED\s*BY)\s*[:：]?\s*)\S.*$";
+    if let Ok(re) = Regex::new(person) {
+        anonymized = re.replace_all(&anonymized, format!("${{1}}{}", REDACTED)).into_owned();
+    }
+    anonymized
+}
+
+/// 学習データ（JSON Lines）
+pub struct TrainingStore {
+    path: PathBuf,
+}
+
+impl TrainingStore {
+    /// 学習データファイルを指定（ファイルは最初の記録時に作成）
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+    
+    /// 学習データファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// 1件追記
+    pub fn append(&self, record: &CorrectionRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("学習データを開けません: {:?}", self.path))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)
+            .with_context(|| format!("学習データの書き込みに失敗: {:?}", self.path))
+    }
+    
+    /// 全件読み込む（壊れた行は読み飛ばす、ファイルが無ければ空）
+    pub fn load(&self) -> Result<Vec<CorrectionRecord>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("学習データを開けません: {:?}", self.path)),
+        };
+        
+        let mut records = Vec::new();
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("学習データの {} 行目を読み飛ばします: {}", i + 1, e),
+            }
+        }
+        Ok(records)
+    }
+    
+    /// テストケースとしてフォルダに書き出し、件数を返す
+    ///
+    /// `case_0001.txt`（OCRテキスト）と `case_0001.json`（修正後の期待値と修正されたフィールド）の組で出力する
+    pub fn export_cases(&self, dir: impl AsRef<Path>) -> Result<usize> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        
+        let records = self.load()?;
+        for (i, record) in records.iter().enumerate() {
+            let stem = format!("case_{:04}", i + 1);
+            std::fs::write(dir.join(format!("{}.txt", stem)), &record.raw_text)?;
+            let expected = serde_json::json!({
+                "expected": record.corrected,
+                "extracted": record.extracted,
+                "changed_fields": record.changed_fields,
+            });
+            std::fs::write(dir.join(format!("{}.json", stem)), serde_json::to_string_pretty(&expected)?)?;
+        }
+        Ok(records.len())
+    }
+}
+
+/// 学習データのパスを決定（環境変数 > 実行ファイルと同じフォルダ）
+pub fn default_training_path() -> PathBuf {
+    if let Ok(path) = std::env::var(TRAINING_ENV) {
+        return PathBuf::from(path);
+    }
+    
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_TRAINING_FILE)))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_TRAINING_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn personal_information_is_masked() {
+        let text = "検査員: 山田 太郎\nTEL 03-1234-5678\nMAIL qa@example.com\nSS400 1.6X1219XCOIL";
+        assert_eq!(anonymize_text(text), "検査員: ***\nTEL ***\nMAIL ***\nSS400 1.6X1219XCOIL");
+    }
+}
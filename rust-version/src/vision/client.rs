@@ -1,6 +1,9 @@
 //! Vision API クライアント
 
 use super::auth::{find_credentials, get_access_token, ServiceAccountCredentials};
+use super::usage::record_usage;
+use crate::ocr::layout::{reconstruct_layout, OcrWord};
+use crate::ocr::OcrText;
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::{Deserialize, Serialize};
@@ -50,10 +53,47 @@ impl VisionClient {
         Ok(new_token)
     }
     
+    /// アクセストークンを先に取得しておく（初回のOCRで認証を待たない）
+    pub async fn warmup(&self) -> Result<()> {
+        self.get_token().await.map(|_| ())
+    }
+    
     /// 画像からテキストを抽出
     pub async fn extract_text(&self, image_path: impl AsRef<Path>) -> Result<String> {
-        let image_data = std::fs::read(image_path.as_ref())
-            .with_context(|| format!("画像ファイルの読み込みに失敗: {:?}", image_path.as_ref()))?;
+        let annotation = self.annotate(image_path.as_ref()).await?;
+        Ok(annotation.map(|a| a.text).unwrap_or_default())
+    }
+    
+    /// 画像からテキストと、単語の座標から見た目の行を再構成したテキストを抽出（APIの呼び出しは1回）
+    pub async fn extract_text_with_layout(&self, image_path: impl AsRef<Path>) -> Result<OcrText> {
+        let Some(annotation) = self.annotate(image_path.as_ref()).await? else {
+            return Ok(OcrText::default());
+        };
+        
+        let words: Vec<OcrWord> = annotation
+            .pages
+            .iter()
+            .flat_map(|page| &page.blocks)
+            .flat_map(|block| &block.paragraphs)
+            .flat_map(|paragraph| &paragraph.words)
+            .filter_map(|word| {
+                let text: String = word.symbols.iter().map(|symbol| symbol.text.as_str()).collect();
+                let vertices: Vec<(f32, f32)> = word.bounding_box.vertices.iter().map(|v| (v.x, v.y)).collect();
+                OcrWord::from_vertices(text, &vertices)
+            })
+            .collect();
+        let layout_text = (!words.is_empty()).then(|| reconstruct_layout(&words));
+        
+        Ok(OcrText {
+            text: annotation.text,
+            layout_text,
+        })
+    }
+    
+    /// 画像を DOCUMENT_TEXT_DETECTION で解析（文字が無ければ None）
+    async fn annotate(&self, image_path: &Path) -> Result<Option<TextAnnotation>> {
+        let image_data = std::fs::read(image_path)
+            .with_context(|| format!("画像ファイルの読み込みに失敗: {:?}", image_path))?;
         
         let base64_image = STANDARD.encode(&image_data);
         
@@ -82,6 +122,9 @@ impl VisionClient {
             .await
             .context("Vision APIリクエストに失敗")?;
         
+        // 応答が返った呼び出しは課金対象になりうるため、成否にかかわらず記録する
+        record_usage(1, request.requests.len() as u64);
+        
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
             anyhow::bail!("Vision API エラー: {}", error_text);
@@ -92,15 +135,11 @@ impl VisionClient {
             .await
             .context("Vision APIレスポンスのパースに失敗")?;
         
-        // テキストを抽出
-        let text = vision_response
+        Ok(vision_response
             .responses
-            .first()
-            .and_then(|r| r.full_text_annotation.as_ref())
-            .map(|a| a.text.clone())
-            .unwrap_or_default();
-        
-        Ok(text)
+            .into_iter()
+            .next()
+            .and_then(|r| r.full_text_annotation))
     }
 }
 
@@ -152,4 +191,52 @@ struct AnnotateImageResponse {
 #[derive(Deserialize)]
 struct TextAnnotation {
     text: String,
+    #[serde(default)]
+    pages: Vec<Page>,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    #[serde(default)]
+    blocks: Vec<Block>,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    #[serde(default)]
+    paragraphs: Vec<Paragraph>,
+}
+
+#[derive(Deserialize)]
+struct Paragraph {
+    #[serde(default)]
+    words: Vec<Word>,
+}
+
+#[derive(Deserialize)]
+struct Word {
+    #[serde(rename = "boundingBox", default)]
+    bounding_box: BoundingPoly,
+    #[serde(default)]
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Deserialize, Default)]
+struct BoundingPoly {
+    #[serde(default)]
+    vertices: Vec<Vertex>,
+}
+
+// 座標が0の軸は省略される
+#[derive(Deserialize)]
+struct Vertex {
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct Symbol {
+    text: String,
 }
@@ -2,5 +2,6 @@
 
 mod auth;
 mod client;
+pub mod usage;
 
 pub use client::VisionClient;
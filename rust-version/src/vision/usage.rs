@@ -0,0 +1,116 @@
+//! Vision API 使用量の記録 - 月別の呼び出し回数・ページ数
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 使用量ファイルのパスを指定する環境変数
+pub const USAGE_ENV: &str = "MILLSHEET_USAGE_FILE";
+
+/// 既定の使用量ファイル名（実行ファイルと同じフォルダ）
+const DEFAULT_USAGE_FILE: &str = "millsheet_usage.json";
+
+/// 並列処理中の読み書きが交差しないようにするロック
+static USAGE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 1か月分の使用量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonthlyUsage {
+    /// API呼び出し回数（リトライを含む）
+    pub calls: u64,
+    /// 処理ページ数（1リクエストに複数画像を含む場合はその数）
+    pub pages: u64,
+}
+
+/// 月別の使用量履歴（キーは "YYYY-MM"）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageLog {
+    #[serde(default)]
+    pub months: BTreeMap<String, MonthlyUsage>,
+}
+
+impl UsageLog {
+    /// 使用量ファイルを読み込む（ファイルが無ければ空）
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("使用量ファイルの読み込みに失敗: {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("使用量ファイルのパースに失敗: {:?}", path))
+    }
+    
+    /// 使用量ファイルに保存
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("使用量ファイルの保存に失敗: {:?}", path))
+    }
+    
+    /// 指定月の使用量を加算
+    pub fn add(&mut self, month: &str, calls: u64, pages: u64) {
+        let usage = self.months.entry(month.to_string()).or_default();
+        usage.calls += calls;
+        usage.pages += pages;
+    }
+    
+    /// 今月の使用量（月が替われば自然に0から数え直す）
+    pub fn current_month(&self) -> MonthlyUsage {
+        self.months.get(&current_month_key()).copied().unwrap_or_default()
+    }
+    
+    /// 月別の履歴をCSVに整形
+    pub fn to_csv(&self) -> String {
+        let mut lines = vec!["月,呼び出し回数,ページ数".to_string()];
+        for (month, usage) in &self.months {
+            lines.push(format!("{},{},{}", month, usage.calls, usage.pages));
+        }
+        lines.join("\r\n") + "\r\n"
+    }
+}
+
+/// 今月のキー ("YYYY-MM")
+pub fn current_month_key() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
+
+/// 使用量ファイルのパスを決定（環境変数 > 実行ファイルと同じフォルダ）
+pub fn default_usage_path() -> PathBuf {
+    if let Ok(path) = std::env::var(USAGE_ENV) {
+        return PathBuf::from(path);
+    }
+    
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_USAGE_FILE)))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_USAGE_FILE))
+}
+
+/// 今月の使用量を記録（記録の失敗は処理を止めずにログだけ残す）
+pub fn record_usage(calls: u64, pages: u64) {
+    let _guard = USAGE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = default_usage_path();
+    
+    let result = UsageLog::load(&path).and_then(|mut log| {
+        log.add(&current_month_key(), calls, pages);
+        log.save(&path)
+    });
+    if let Err(e) = result {
+        tracing::warn!("Vision API使用量の記録に失敗: {:#}", e);
+    }
+}
+
+/// 使用量履歴を読み込む（読み込めなければ空）
+pub fn load_usage() -> UsageLog {
+    let _guard = USAGE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    UsageLog::load(default_usage_path()).unwrap_or_else(|e| {
+        tracing::warn!("Vision API使用量の読み込みに失敗: {:#}", e);
+        UsageLog::default()
+    })
+}
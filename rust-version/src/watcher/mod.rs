@@ -0,0 +1,174 @@
+//! ファイル監視モジュール - 辞書ファイルの変更検知・フォルダの新規PDF検知
+
+use crate::parser::dictionary::Dictionary;
+use crate::processing::is_supported_input;
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// この時間サイズが変わらなければ書き込み完了とみなす
+pub const STABLE_DURATION: Duration = Duration::from_secs(2);
+
+/// 書き込み中ファイルのサイズ確認間隔
+const STABLE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 辞書監視イベント
+pub enum DictionaryEvent {
+    /// 再読み込みに成功
+    Reloaded(Arc<Dictionary>),
+    /// 再読み込みに失敗（以前の辞書を保持する）
+    Failed(String),
+}
+
+/// 辞書ファイルの変更を監視し、変更時に再読み込みする
+pub struct DictionaryWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<DictionaryEvent>,
+    path: PathBuf,
+}
+
+impl DictionaryWatcher {
+    /// 監視を開始
+    pub fn start(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let (tx, rx) = channel();
+        
+        // エディタは一時ファイル経由で置き換えることが多いので親フォルダを監視する
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let file_name = path.file_name().map(|n| n.to_os_string());
+        let target = path.clone();
+        
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                return;
+            }
+            if !event.paths.iter().any(|p| p.file_name().map(|n| n.to_os_string()) == file_name) {
+                return;
+            }
+            
+            let message = match Dictionary::load(&target) {
+                Ok(dictionary) => {
+                    tracing::info!("辞書を再読み込みしました: {:?}", target);
+                    DictionaryEvent::Reloaded(Arc::new(dictionary))
+                }
+                Err(e) => {
+                    tracing::error!("辞書の再読み込みに失敗（以前の辞書を保持）: {:#}", e);
+                    DictionaryEvent::Failed(format!("{:#}", e))
+                }
+            };
+            let _ = tx.send(message);
+        })
+        .context("ファイル監視の初期化に失敗")?;
+        
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("フォルダの監視に失敗: {:?}", dir))?;
+        
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            path,
+        })
+    }
+    
+    /// 監視中のファイル
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// 溜まったイベントのうち最新のものを取得
+    pub fn poll(&self) -> Option<DictionaryEvent> {
+        self.rx.try_iter().last()
+    }
+}
+
+/// フォルダに追加されたPDF（PNG/JPEG の画像を含む）を監視し、書き込みが終わったものを通知する
+pub struct FolderWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<PathBuf>,
+    folder: PathBuf,
+}
+
+impl FolderWatcher {
+    /// 監視を開始（既存のファイルは対象外）
+    pub fn start(folder: impl AsRef<Path>) -> Result<Self> {
+        let folder = folder.as_ref().to_path_buf();
+        let (candidate_tx, candidate_rx) = channel::<PathBuf>();
+        let (stable_tx, stable_rx) = channel();
+        
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                return;
+            }
+            for path in event.paths {
+                if is_supported_input(&path) {
+                    let _ = candidate_tx.send(path);
+                }
+            }
+        })
+        .context("ファイル監視の初期化に失敗")?;
+        
+        watcher
+            .watch(&folder, RecursiveMode::NonRecursive)
+            .with_context(|| format!("フォルダの監視に失敗: {:?}", folder))?;
+        
+        // 監視を止めると送信側が破棄され、このスレッドも終了する
+        std::thread::spawn(move || {
+            let mut pending: HashMap<PathBuf, (u64, Instant)> = HashMap::new();
+            loop {
+                match candidate_rx.recv_timeout(STABLE_POLL_INTERVAL) {
+                    Ok(path) => {
+                        pending.insert(path, (u64::MAX, Instant::now()));
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                
+                pending.retain(|path, (size, changed_at)| {
+                    let Ok(metadata) = std::fs::metadata(path) else {
+                        // 移動・削除されたファイルは対象外
+                        return false;
+                    };
+                    if metadata.len() != *size {
+                        *size = metadata.len();
+                        *changed_at = Instant::now();
+                        return true;
+                    }
+                    if metadata.len() == 0 || changed_at.elapsed() < STABLE_DURATION {
+                        return true;
+                    }
+                    
+                    tracing::info!("新しいPDFを検出: {:?}", path);
+                    let _ = stable_tx.send(path.clone());
+                    false
+                });
+            }
+        });
+        
+        Ok(Self {
+            _watcher: watcher,
+            rx: stable_rx,
+            folder,
+        })
+    }
+    
+    /// 監視中のフォルダ
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+    
+    /// 書き込みが終わった新規PDFを取得
+    pub fn poll(&self) -> Vec<PathBuf> {
+        self.rx.try_iter().collect()
+    }
+}